@@ -0,0 +1,379 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use sjf_api::currency::Currency;
+use sjf_config::Config;
+use sjf_db::auth::Uuid;
+use sjf_db::jobs::Retried;
+
+/// Operations on the shop, configured the same way as the server.
+#[derive(Parser)]
+#[command(name = "sjf-admin", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Writes the catalogue and its images to a tar archive.
+    Backup { archive: PathBuf },
+    /// Restores a backup into a shop with an empty catalogue and bucket.
+    Restore { archive: PathBuf },
+    /// Creates or updates the database tables and views.
+    Migrate,
+    /// Prints a code that lets an admin register a passkey, in place of the
+    /// code written to the server log.
+    CreateInvite {
+        name: String,
+        /// How long the code can be used.
+        #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..=24 * 365))]
+        hours: u64,
+    },
+    /// Admin and customer accounts.
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Stock reserved by checkouts that haven't completed.
+    #[command(subcommand)]
+    Reservations(ReservationsCommand),
+    /// Background jobs that haven't succeeded yet.
+    #[command(subcommand)]
+    Jobs(JobsCommand),
+    /// Refreshes the materialized views of category paths and product images.
+    RefreshViews,
+    /// Encodes the smaller variants of images again from the largest one.
+    RegenerateImages {
+        /// Only this image, otherwise every image.
+        #[arg(long)]
+        image: Option<u32>,
+    },
+    /// Checks that object storage has a file for every image variant.
+    VerifyImages,
+    /// Writes a report as CSV to standard output.
+    #[command(subcommand)]
+    Report(ReportCommand),
+}
+
+#[derive(Subcommand)]
+enum UsersCommand {
+    List,
+    /// Ends the sessions of a user and stops them from logging in.
+    Disable {
+        id: Uuid,
+    },
+    Enable {
+        id: Uuid,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReservationsCommand {
+    List,
+    /// Releases the stock of a reservation and removes it.
+    Undo {
+        id: Uuid,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobsCommand {
+    List {
+        /// Only jobs that failed too many times.
+        #[arg(long)]
+        dead: bool,
+    },
+    /// Runs a dead job again.
+    Retry { id: i64 },
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Sales declared through OSS in a quarter.
+    Oss {
+        year: i32,
+        #[arg(value_parser = clap::value_parser!(u32).range(1..=4))]
+        quarter: u32,
+        /// ECB rate of the quarter's last day for a currency other than euro,
+        /// `DKK=7.4609`, fills in the euro columns of its rows.
+        #[arg(long, value_parser = parse_ecb_rate)]
+        ecb: Vec<(Currency, u32)>,
+    },
+    /// Every product, in the format read by the product import.
+    Products,
+    /// Tracked products that are sold out or below their reorder threshold.
+    LowStock,
+}
+
+async fn connect_db(config: &Config) -> Result<(), String> {
+    sjf_db::connect(&config.database, &config.checkout)
+        .await
+        .map_err(|e| format!("Failed to connect to the database: {e}"))
+}
+
+async fn connect(config: &Config) -> Result<(), String> {
+    connect_db(config).await?;
+    sjf_image::init(&config.storage)
+        .await
+        .map_err(|e| format!("Failed to connect to object storage: {e}"))
+}
+
+fn parse_ecb_rate(value: &str) -> Result<(Currency, u32), String> {
+    sjf_api::tax::parse_ecb_rate(value)
+        .ok_or_else(|| format!("Expected a rate like DKK=7.4609, got {value}"))
+}
+
+fn db_error(e: sjf_db::Error) -> String {
+    format!("Database error: {e}")
+}
+
+fn date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d %H:%M").to_string()
+}
+
+async fn users(command: UsersCommand) -> Result<(), String> {
+    let (id, disabled) = match command {
+        UsersCommand::List => {
+            for user in sjf_db::auth::users().await.map_err(db_error)? {
+                let disabled = user
+                    .disabled
+                    .map(|d| format!(" disabled {}", date(d)))
+                    .unwrap_or_default();
+                println!(
+                    "{} {:<8} {:<30} {} passkeys, last login {}{}",
+                    user.id,
+                    user.role.as_str(),
+                    user.name,
+                    user.passkeys,
+                    date(user.last_login),
+                    disabled
+                );
+            }
+            return Ok(());
+        }
+        UsersCommand::Disable { id } => (id, true),
+        UsersCommand::Enable { id } => (id, false),
+    };
+    match sjf_db::auth::set_disabled(&id, disabled)
+        .await
+        .map_err(db_error)?
+    {
+        true if disabled => println!("Disabled {id}"),
+        true => println!("Enabled {id}"),
+        false => return Err(format!("No user {id}")),
+    }
+    Ok(())
+}
+
+async fn reservations(command: ReservationsCommand) -> Result<(), String> {
+    let pending = sjf_db::reservation::pending().await.map_err(db_error)?;
+    match command {
+        ReservationsCommand::List => {
+            for reservation in pending {
+                let expires = reservation
+                    .expires_at
+                    .map(date)
+                    .unwrap_or_else(|| "-".into());
+                println!(
+                    "{} created {} expires {} {}",
+                    reservation.id,
+                    date(reservation.created),
+                    expires,
+                    reservation.currency
+                );
+                for (product, name, quantity) in reservation.lines {
+                    println!("    {quantity} x {name} ({product})");
+                }
+            }
+        }
+        ReservationsCommand::Undo { id } => {
+            if !pending.iter().any(|r| r.id == id) {
+                return Err(format!("No pending reservation {id}"));
+            }
+            sjf_db::checkout::undo_reservation(id.to_string())
+                .await
+                .map_err(|e| format!("Failed to undo {id}: {e}"))?;
+            println!("Undid {id}");
+        }
+    }
+    Ok(())
+}
+
+async fn jobs(command: JobsCommand) -> Result<(), String> {
+    match command {
+        JobsCommand::List { dead } => {
+            for job in sjf_db::jobs::unfinished(dead).await.map_err(db_error)? {
+                println!(
+                    "{:<8} {:<28} {:<8} attempt {}/{} at {} {}",
+                    job.id,
+                    job.kind,
+                    job.status,
+                    job.attempts,
+                    job.max_attempts,
+                    date(job.run_at),
+                    job.last_error.unwrap_or_default()
+                );
+            }
+        }
+        JobsCommand::Retry { id } => match sjf_db::jobs::retry(id).await.map_err(db_error)? {
+            Retried::Queued => println!("Queued job {id} again"),
+            Retried::Merged(into) => {
+                println!("Job {id} merged into job {into} of the same kind, which runs it")
+            }
+            Retried::NotDead => return Err(format!("No dead job {id}")),
+        },
+    }
+    Ok(())
+}
+
+async fn regenerate_images(image: Option<u32>) -> Result<(), String> {
+    let images = match image {
+        Some(image) => vec![image],
+        None => sjf_db::image::all_images().await.map_err(db_error)?,
+    };
+    let mut failed = 0;
+    for image in images.iter() {
+        if let Err(e) = sjf_image::regenerate(*image).await {
+            eprintln!("Failed to regenerate image {image}: {e:?}");
+            failed += 1;
+        }
+    }
+    println!(
+        "Regenerated {} of {} images",
+        images.len() - failed,
+        images.len()
+    );
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{failed} images failed")),
+    }
+}
+
+async fn verify_images() -> Result<(), String> {
+    let missing = sjf_image::missing_objects()
+        .await
+        .map_err(|e| format!("Failed to verify images: {e:?}"))?;
+    for id in missing.iter() {
+        let (image, variant): (u32, u32) = id.clone().into();
+        println!("Missing image {image} variant {variant}");
+    }
+    match missing.len() {
+        0 => {
+            println!("Every image variant is stored");
+            Ok(())
+        }
+        n => Err(format!("{n} image variants are missing")),
+    }
+}
+
+async fn report(command: ReportCommand) -> Result<(), String> {
+    let csv = match command {
+        ReportCommand::Oss { year, quarter, ecb } => {
+            let rows = sjf_db::tax::oss_report(year, quarter)
+                .await
+                .map_err(db_error)?;
+            sjf_api::tax::oss_report_csv(&rows, &ecb.into_iter().collect())
+        }
+        ReportCommand::Products => sjf_db::import::export().await.map_err(db_error)?,
+        ReportCommand::LowStock => {
+            let header = ["product_id", "name", "quantity", "reorder_threshold"];
+            let alerts = sjf_db::stock::low_stock().await.map_err(db_error)?;
+            sjf_api::import::write_csv(std::iter::once(header.map(String::from).to_vec()).chain(
+                alerts.into_iter().map(|a| {
+                    vec![
+                        a.product_id.to_string(),
+                        a.name,
+                        a.quantity.to_string(),
+                        a.reorder_threshold
+                            .map(|t| t.to_string())
+                            .unwrap_or_default(),
+                    ]
+                }),
+            ))
+        }
+    };
+    print!("{csv}");
+    Ok(())
+}
+
+async fn run(command: Command) -> Result<(), String> {
+    let config = Config::load().map_err(|e| e.to_string())?;
+    match command {
+        Command::Backup { archive } => {
+            connect(&config).await?;
+            let file = File::create(&archive)
+                .map_err(|e| format!("Failed to create {}: {e}", archive.display()))?;
+            let summary = sjf_image::backup::create(BufWriter::new(file))
+                .await
+                .map_err(|e| format!("Backup failed: {e:?}"))?;
+            println!("Backed up {summary} to {}", archive.display());
+        }
+        Command::Restore { archive } => {
+            connect(&config).await?;
+            let file = File::open(&archive)
+                .map_err(|e| format!("Failed to open {}: {e}", archive.display()))?;
+            let summary = sjf_image::backup::restore(BufReader::new(file))
+                .await
+                .map_err(|e| format!("Restore failed: {e:?}"))?;
+            println!("Restored {summary} from {}", archive.display());
+        }
+        Command::Migrate => {
+            connect_db(&config).await?;
+            println!("The database is up to date");
+        }
+        Command::CreateInvite { name, hours } => {
+            connect_db(&config).await?;
+            let code = sjf_auth::endpoints::create_invite(&name, Duration::from_secs(hours * 3600))
+                .await
+                .map_err(|e| format!("Failed to create invite: {e}"))?;
+            println!("Register '{name}' with the code {code} within {hours} hours");
+        }
+        Command::Users(command) => {
+            connect_db(&config).await?;
+            users(command).await?;
+        }
+        Command::Reservations(command) => {
+            connect_db(&config).await?;
+            reservations(command).await?;
+        }
+        Command::Jobs(command) => {
+            connect_db(&config).await?;
+            jobs(command).await?;
+        }
+        Command::RefreshViews => {
+            connect_db(&config).await?;
+            sjf_db::refresh_views().await.map_err(db_error)?;
+            println!("Refreshed the views");
+        }
+        Command::RegenerateImages { image } => {
+            connect(&config).await?;
+            regenerate_images(image).await?;
+        }
+        Command::VerifyImages => {
+            connect(&config).await?;
+            verify_images().await?;
+        }
+        Command::Report(command) => {
+            connect_db(&config).await?;
+            report(command).await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let _ = dotenvy::dotenv();
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
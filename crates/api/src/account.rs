@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::currency::Currency;
+
+/// What a logged in user is allowed to do. Admins and customers are registered
+/// through separate flows and never share an account.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    Customer,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Customer => "customer",
+        }
+    }
+}
+
+impl From<&str> for Role {
+    /// Anything but `"admin"` is a customer, so an unknown role never grants
+    /// access to the admin pages.
+    fn from(s: &str) -> Self {
+        match s {
+            "admin" => Role::Admin,
+            _ => Role::Customer,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OrderLine {
+    pub description: String,
+    pub quantity: u32,
+    /// In minor units of the order's currency.
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PastOrder {
+    pub reference: String,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub currency: Currency,
+    /// In minor units of `currency`.
+    pub total: u64,
+    pub lines: Vec<OrderLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Address {
+    pub name: Option<String>,
+    pub line1: Option<String>,
+    pub line2: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Account {
+    pub email: String,
+    pub orders: Vec<PastOrder>,
+    pub addresses: Vec<Address>,
+}
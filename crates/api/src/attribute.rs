@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AttributeKind {
+    Text,
+    Number { unit: String },
+    Enum { options: Vec<String> },
+}
+
+impl AttributeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeKind::Text => "text",
+            AttributeKind::Number { .. } => "number",
+            AttributeKind::Enum { .. } => "enum",
+        }
+    }
+}
+
+/// A typed product attribute defined on a category. Products in the category
+/// and all its subcategories can have a value for it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AttributeDefinition {
+    pub id: u32,
+    /// The category the attribute is defined on.
+    pub category: u32,
+    pub name: String,
+    pub kind: AttributeKind,
+}
+
+impl AttributeDefinition {
+    /// The value as stored, `None` if it isn't valid for the attribute.
+    /// Numbers are stored in a canonical form so equal values match when filtering.
+    pub fn normalize(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        match &self.kind {
+            AttributeKind::Text => Some(value.to_owned()),
+            AttributeKind::Number { .. } => value
+                .replace(',', ".")
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(|v| v.to_string()),
+            AttributeKind::Enum { options } => options.iter().find(|o| *o == value).cloned(),
+        }
+    }
+
+    /// A stored value as shown to customers.
+    pub fn display(&self, value: &str) -> String {
+        match &self.kind {
+            AttributeKind::Number { unit } if !unit.is_empty() => {
+                format!("{} {}", value.replace('.', ","), unit)
+            }
+            AttributeKind::Number { .. } => value.replace('.', ","),
+            _ => value.to_owned(),
+        }
+    }
+}
+
+/// A row of the specification table of a product.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Spec {
+    pub name: String,
+    pub value: String,
+}
+
+/// Values of an attribute among the products of a category listing, with the
+/// number of products having each value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Facet {
+    pub attribute: AttributeDefinition,
+    pub values: Vec<(String, u32)>,
+}
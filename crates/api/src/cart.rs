@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Locale;
+use crate::product::{Product, ProductId, ProductTranslation};
+
+pub use u32 as ProductQuantity;
+
+/// Name of the signed cookie identifying an anonymous visitor's server-side cart.
+pub const CART_COOKIE: &str = "sjf_cart";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncCartRequest {
+    /// The cart as known by the browser.
+    pub items: Vec<(ProductId, ProductQuantity)>,
+    /// Combine with the items already stored on the server instead of replacing them.
+    pub merge: bool,
+    /// Language of the returned names.
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+/// Something about the cart changed since the customer last saw it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum CartNotice {
+    PriceChanged {
+        product_id: ProductId,
+        name: String,
+        old_price: u32,
+        new_price: u32,
+    },
+    QuantityReduced {
+        product_id: ProductId,
+        name: String,
+        requested: ProductQuantity,
+        available: ProductQuantity,
+    },
+    Removed {
+        product_id: ProductId,
+        name: Option<String>,
+    },
+}
+
+impl CartNotice {
+    pub fn product_id(&self) -> ProductId {
+        match self {
+            CartNotice::PriceChanged { product_id, .. }
+            | CartNotice::QuantityReduced { product_id, .. }
+            | CartNotice::Removed { product_id, .. } => *product_id,
+        }
+    }
+
+    pub fn translate(&mut self, translation: &ProductTranslation) {
+        match self {
+            CartNotice::PriceChanged { name, .. } | CartNotice::QuantityReduced { name, .. } => {
+                *name = translation.name.clone()
+            }
+            CartNotice::Removed { name, .. } => *name = Some(translation.name.clone()),
+        }
+    }
+
+    pub fn message(&self, locale: Locale) -> String {
+        match (locale, self) {
+            (
+                Locale::Sv,
+                CartNotice::PriceChanged {
+                    name,
+                    old_price,
+                    new_price,
+                    ..
+                },
+            ) => format!("Priset på {name} har ändrats från {old_price}kr till {new_price}kr"),
+            (
+                Locale::En,
+                CartNotice::PriceChanged {
+                    name,
+                    old_price,
+                    new_price,
+                    ..
+                },
+            ) => format!("The price of {name} has changed from {old_price} kr to {new_price} kr"),
+            (
+                Locale::Sv,
+                CartNotice::QuantityReduced {
+                    name,
+                    requested,
+                    available,
+                    ..
+                },
+            ) => format!(
+                "Det finns bara {available} st av {name} kvar, antalet har minskats från {requested}"
+            ),
+            (
+                Locale::En,
+                CartNotice::QuantityReduced {
+                    name,
+                    requested,
+                    available,
+                    ..
+                },
+            ) => format!(
+                "There are only {available} of {name} left, the quantity has been reduced from {requested}"
+            ),
+            (
+                Locale::Sv,
+                CartNotice::Removed {
+                    name: Some(name), ..
+                },
+            ) => format!("{name} finns inte längre i lager och har tagits bort från varukorgen"),
+            (
+                Locale::En,
+                CartNotice::Removed {
+                    name: Some(name), ..
+                },
+            ) => format!("{name} is no longer in stock and has been removed from the cart"),
+            (Locale::Sv, CartNotice::Removed { name: None, .. }) => {
+                String::from("En produkt finns inte längre och har tagits bort från varukorgen")
+            }
+            (Locale::En, CartNotice::Removed { name: None, .. }) => {
+                String::from("A product no longer exists and has been removed from the cart")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncCartResponse {
+    pub items: Vec<(Product, ProductQuantity)>,
+    pub notices: Vec<CartNotice>,
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -19,5 +21,16 @@ pub struct DeleteReq {
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct GetChildrenRsp {
+    /// Id and name of each child, the name is also its path segment.
     pub children: Vec<(u32, String)>,
+    /// Names translated to the visitor's language, where there is a translation.
+    #[serde(default)]
+    pub labels: BTreeMap<u32, String>,
+}
+
+impl GetChildrenRsp {
+    /// The name to show for a child.
+    pub fn label<'a>(&'a self, id: u32, name: &'a str) -> &'a str {
+        self.labels.get(&id).map(|l| l.as_str()).unwrap_or(name)
+    }
 }
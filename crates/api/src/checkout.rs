@@ -1,10 +1,68 @@
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+
+use crate::currency::Currency;
+use crate::i18n::Locale;
 pub use u32 as ProductId;
 pub use u32 as ProductQuantity;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CheckoutRequest {
     pub order: BTreeMap<ProductId, ProductQuantity>,
+    /// Set when the cart was restored from an abandoned checkout reminder.
+    #[serde(default)]
+    pub recovery: Option<String>,
+    /// Language of the payment page and the pages returned to afterwards.
+    #[serde(default)]
+    pub locale: Locale,
+    /// Currency to pay in, the rate is looked up when the order is reserved.
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+/// Orders above this many kr ship for free.
+pub const FREE_SHIPPING_OVER: u32 = 999;
+
+/// Shipping cost in kr of an order of `quantity` items worth `total` kr.
+pub fn shipping_price(quantity: u32, total: u32) -> u32 {
+    if total > FREE_SHIPPING_OVER {
+        return 0;
+    }
+    match quantity {
+        0..=2 => 89,
+        3..=4 => 99,
+        5.. => 129,
+    }
+}
+
+pub const RECOVER_CHECKOUT_PATH: &str = "/varukorg/aterstall";
+pub const REMINDER_OPT_OUT_PATH: &str = "/varukorg/inga-paminnelser";
+
+/// Where the link in a checkout reminder puts the cart back.
+pub fn recover_checkout_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => RECOVER_CHECKOUT_PATH,
+        Locale::En => "/en/cart/restore",
+    }
+}
+
+/// Where the link in a checkout reminder stops further reminders.
+pub fn reminder_opt_out_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => REMINDER_OPT_OUT_PATH,
+        Locale::En => "/en/cart/no-reminders",
+    }
+}
+
+/// How abandoned checkouts turned out, counted from when they were abandoned.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RecoveryStats {
+    pub days: u32,
+    pub abandoned: u32,
+    pub reminded: u32,
+    pub clicked: u32,
+    pub recovered: u32,
+    /// Value of the recovered carts in kr, as they were when abandoned.
+    pub recovered_value: u32,
 }
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+
+/// Cookie remembering the currency picked by the visitor.
+pub const CURRENCY_COOKIE: &str = "currency";
+
+/// Prices are kept in kr and shown and charged in the visitor's currency,
+/// converted with the rates set by staff.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub enum Currency {
+    #[default]
+    Sek,
+    Eur,
+    Nok,
+    Dkk,
+}
+
+/// Member states of the EU, where orders paid in euro can be shipped.
+pub const EU_COUNTRIES: [&str; 27] = [
+    "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IE", "IT",
+    "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Sek, Currency::Eur, Currency::Nok, Currency::Dkk];
+
+    /// ISO 4217 code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Sek => "SEK",
+            Currency::Eur => "EUR",
+            Currency::Nok => "NOK",
+            Currency::Dkk => "DKK",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Currency> {
+        Self::ALL
+            .into_iter()
+            .find(|c| code.eq_ignore_ascii_case(c.code()))
+    }
+
+    /// Prices are rounded to whole cents in euro and to whole kronor/kroner
+    /// otherwise, as that is how they are written in the Nordics.
+    fn rounding(self) -> u64 {
+        match self {
+            Currency::Eur => 1,
+            _ => 100,
+        }
+    }
+
+    /// Countries orders in this currency are shipped to, as ISO 3166 codes.
+    pub fn shipping_countries(self) -> &'static [&'static str] {
+        match self {
+            Currency::Sek => &["SE"],
+            Currency::Nok => &["NO"],
+            Currency::Dkk => &["DK"],
+            Currency::Eur => &EU_COUNTRIES,
+        }
+    }
+
+    /// Whether orders in this currency leave the EU, and so are sold without
+    /// Swedish VAT as exports.
+    pub fn is_export(self) -> bool {
+        self.shipping_countries()
+            .iter()
+            .all(|c| !EU_COUNTRIES.contains(c))
+    }
+
+    /// An amount in minor units (öre, cents, øre) written for customers.
+    pub fn format(self, minor: u64) -> String {
+        match self {
+            Currency::Sek => format!("{}kr", minor / 100),
+            Currency::Eur => format!("€{}.{:02}", minor / 100, minor % 100),
+            Currency::Nok | Currency::Dkk => format!("{} {}", minor / 100, self.code()),
+        }
+    }
+}
+
+/// What one unit of a currency costs in kr, in millionths of a krona.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ExchangeRate {
+    pub currency: Currency,
+    pub sek_micros: u32,
+}
+
+impl Default for ExchangeRate {
+    fn default() -> Self {
+        Self::SEK
+    }
+}
+
+impl ExchangeRate {
+    pub const SEK: ExchangeRate = ExchangeRate {
+        currency: Currency::Sek,
+        sek_micros: 1_000_000,
+    };
+
+    /// A price in kr converted to minor units of the currency, rounded half up
+    /// to what the currency is priced in. Totals are summed from these so that
+    /// they match what the payment provider charges line by line.
+    pub fn minor_units(&self, sek: u32) -> u64 {
+        let step = self.currency.rounding();
+        let numerator = sek as u64 * 100 * 1_000_000;
+        let denominator = self.sek_micros.max(1) as u64 * step;
+        (numerator + denominator / 2) / denominator * step
+    }
+
+    /// A price in kr written in the currency.
+    pub fn format(&self, sek: u32) -> String {
+        self.currency.format(self.minor_units(sek))
+    }
+
+    /// Total of `(price in kr, quantity)` lines in minor units.
+    pub fn total(&self, lines: impl IntoIterator<Item = (u32, u32)>) -> u64 {
+        lines
+            .into_iter()
+            .map(|(price, quantity)| self.minor_units(price) * quantity as u64)
+            .sum()
+    }
+
+    /// The rate as kr per unit, the way staff enter it.
+    pub fn sek_per_unit(&self) -> f64 {
+        self.sek_micros as f64 / 1_000_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(currency: Currency, sek_micros: u32) -> ExchangeRate {
+        ExchangeRate {
+            currency,
+            sek_micros,
+        }
+    }
+
+    /// `minor_units` computed with wider integers, for comparison.
+    fn reference(rate: ExchangeRate, sek: u32) -> u128 {
+        let step = rate.currency.rounding() as u128;
+        let exact = sek as u128 * 100 * 1_000_000;
+        let unit = rate.sek_micros as u128 * step;
+        (exact + unit / 2) / unit * step
+    }
+
+    #[test]
+    fn kronor_are_converted_to_ore() {
+        assert_eq!(ExchangeRate::SEK.minor_units(1), 100);
+        assert_eq!(ExchangeRate::SEK.minor_units(1234), 123_400);
+        assert_eq!(ExchangeRate::SEK.format(1234), "1234kr");
+    }
+
+    #[test]
+    fn halves_round_up() {
+        // 200 kr per euro makes 1 kr half a cent
+        let eur = rate(Currency::Eur, 200_000_000);
+        assert_eq!(eur.minor_units(1), 1);
+        assert_eq!(eur.minor_units(3), 2);
+        // Just below half a cent
+        assert_eq!(rate(Currency::Eur, 200_000_001).minor_units(1), 0);
+
+        // 2 kr per krone makes odd amounts half a krone, charged in whole kroner
+        let nok = rate(Currency::Nok, 2_000_000);
+        assert_eq!(nok.minor_units(1), 100);
+        assert_eq!(nok.minor_units(2), 100);
+        assert_eq!(nok.minor_units(5), 300);
+        assert_eq!(nok.format(5), "3 NOK");
+        assert_eq!(rate(Currency::Dkk, 2_000_001).minor_units(1), 0);
+    }
+
+    #[test]
+    fn cents_are_kept_in_euro() {
+        // 11.50 kr per euro
+        let eur = rate(Currency::Eur, 11_500_000);
+        assert_eq!(eur.minor_units(100), 870);
+        assert_eq!(eur.format(100), "€8.70");
+        assert_eq!(eur.minor_units(23), 200);
+    }
+
+    #[test]
+    fn zero_is_zero() {
+        for currency in Currency::ALL {
+            assert_eq!(rate(currency, 1_000_000).minor_units(0), 0);
+        }
+        // A missing rate doesn't divide by zero
+        assert_eq!(rate(Currency::Eur, 0).minor_units(0), 0);
+        assert_eq!(rate(Currency::Eur, 0).minor_units(1), 100_000_000);
+    }
+
+    #[test]
+    fn large_amounts_stay_exact_within_the_rate_limits() {
+        // sek_micros is stored as a positive integer
+        for sek_micros in [1, 1_000_000, 11_500_000, i32::MAX as u32, u32::MAX] {
+            for currency in Currency::ALL {
+                let rate = rate(currency, sek_micros);
+                for sek in [1, 999, 1_000_000, u32::MAX] {
+                    assert_eq!(
+                        rate.minor_units(sek) as u128,
+                        reference(rate, sek),
+                        "{sek} kr at {sek_micros} micros in {currency:?}"
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            rate(Currency::Eur, 1).minor_units(u32::MAX),
+            u32::MAX as u64 * 100_000_000
+        );
+        assert_eq!(
+            ExchangeRate::SEK.minor_units(u32::MAX),
+            u32::MAX as u64 * 100
+        );
+    }
+
+    #[test]
+    fn totals_add_up_rounded_lines() {
+        let eur = rate(Currency::Eur, 200_000_000);
+        // Each line of 1 kr rounds up to a cent
+        assert_eq!(eur.total([(1, 3), (3, 1)]), 3 + 2);
+        assert_eq!(ExchangeRate::SEK.total([(100, 2), (50, 1)]), 25_000);
+    }
+}
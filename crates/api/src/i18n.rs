@@ -0,0 +1,479 @@
+use serde::{Deserialize, Serialize};
+
+/// Cookie remembering the language of the last page the visitor loaded.
+pub const LOCALE_COOKIE: &str = "locale";
+
+/// Languages of the storefront. Swedish pages live at the root and the other
+/// languages under a path prefix, `/en` for English.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub enum Locale {
+    #[default]
+    Sv,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Sv, Locale::En];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Sv => "sv",
+            Locale::En => "en",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Locale> {
+        Self::ALL
+            .into_iter()
+            .find(|l| code.eq_ignore_ascii_case(l.code()))
+    }
+
+    /// The path every page in this language starts with.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Locale::Sv => "",
+            Locale::En => "/en",
+        }
+    }
+
+    pub fn from_path(path: &str) -> Locale {
+        Self::ALL
+            .into_iter()
+            .filter(|l| !l.prefix().is_empty())
+            .find(|l| {
+                path.strip_prefix(l.prefix())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .unwrap_or_default()
+    }
+
+    /// The preferred language of an `Accept-Language` header. Danish and
+    /// Norwegian visitors get Swedish, everyone else outside Sweden English.
+    pub fn from_accept_language(header: &str) -> Locale {
+        let mut languages: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut parts = part.trim().split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty()).then_some((tag, quality))
+            })
+            .collect();
+        languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        match languages.first() {
+            Some((tag, _)) => {
+                let language = tag.split('-').next().unwrap_or_default();
+                match language.to_ascii_lowercase().as_str() {
+                    "sv" | "da" | "no" | "nb" | "nn" => Locale::Sv,
+                    _ => Locale::En,
+                }
+            }
+            None => Locale::Sv,
+        }
+    }
+
+    /// The name of the language in itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::Sv => "Svenska",
+            Locale::En => "English",
+        }
+    }
+
+    /// OpenGraph locale.
+    pub fn territory(self) -> &'static str {
+        match self {
+            Locale::Sv => "sv_SE",
+            Locale::En => "en_GB",
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Locale::Sv => &SV,
+            Locale::En => &EN,
+        }
+    }
+
+    pub fn only_left(self, quantity: u32) -> String {
+        match self {
+            Locale::Sv => format!("Endast {quantity} kvar i lager"),
+            Locale::En => format!("Only {quantity} left in stock"),
+        }
+    }
+
+    pub fn preorder_ships(self, date: chrono::NaiveDate) -> String {
+        match self {
+            Locale::Sv => format!(
+                "Förbeställning, beräknas skickas {}",
+                date.format("%Y-%m-%d")
+            ),
+            Locale::En => format!("Pre-order, expected to ship {}", date.format("%Y-%m-%d")),
+        }
+    }
+
+    pub fn rating(self, average: f32) -> String {
+        match self {
+            Locale::Sv => format!("{average:.1} av 5"),
+            Locale::En => format!("{average:.1} out of 5"),
+        }
+    }
+
+    pub fn review_heading(self, product: &str) -> String {
+        match self {
+            Locale::Sv => format!("Recensera {product}"),
+            Locale::En => format!("Review {product}"),
+        }
+    }
+
+    pub fn already_reviewed(self, product: &str) -> String {
+        match self {
+            Locale::Sv => format!("Du har redan recenserat {product}."),
+            Locale::En => format!("You have already reviewed {product}."),
+        }
+    }
+
+    pub fn page_of(self, page: u32, pages: u32) -> String {
+        match self {
+            Locale::Sv => format!("Sida {page} av {pages}"),
+            Locale::En => format!("Page {page} of {pages}"),
+        }
+    }
+
+    pub fn category_description(self, category: &str) -> String {
+        match self {
+            Locale::Sv => format!(
+                "{category} hos SJF Concept. Kläder, lasergraverade produkter, 3D-printade föremål och personligt anpassad design."
+            ),
+            Locale::En => format!(
+                "{category} at SJF Concept. Clothing, laser engraved products, 3D printed items and personalised design."
+            ),
+        }
+    }
+}
+
+/// The fixed texts of the storefront in one language.
+pub struct Messages {
+    pub home: &'static str,
+    pub favourites: &'static str,
+    pub about: &'static str,
+    pub loading: &'static str,
+    pub loading_failed: &'static str,
+    /// Followed by the amount in the visitor's currency.
+    pub free_shipping: &'static str,
+    pub currency: &'static str,
+    pub demo_mode: &'static str,
+    pub shipping_and_terms: &'static str,
+    pub my_account: &'static str,
+    pub staff_login: &'static str,
+    pub not_found: &'static str,
+    pub loading_product: &'static str,
+    pub product_missing: &'static str,
+    pub sold_out: &'static str,
+    pub added: &'static str,
+    pub add_to_cart: &'static str,
+    pub backordered: &'static str,
+    pub you_may_also_like: &'static str,
+    pub others_also_bought: &'static str,
+    pub reviews: &'static str,
+    pub staff_reply: &'static str,
+    pub notify_in_stock: &'static str,
+    pub your_email: &'static str,
+    pub subscribe: &'static str,
+    pub confirm_subscription: &'static str,
+    pub invalid_email: &'static str,
+    pub rate_limited: &'static str,
+    pub something_went_wrong: &'static str,
+    pub cart_updated: &'static str,
+    pub to_checkout: &'static str,
+    pub processing: &'static str,
+    pub failed: &'static str,
+    pub redirecting: &'static str,
+    pub total: &'static str,
+    pub remove_favourite: &'static str,
+    pub save_favourite: &'static str,
+    pub no_favourites: &'static str,
+    pub favourites_failed: &'static str,
+    pub popular: &'static str,
+    pub explore: &'static str,
+    pub no_image: &'static str,
+    pub shop_description: &'static str,
+    pub sort: &'static str,
+    pub price: &'static str,
+    pub price_from: &'static str,
+    pub price_to: &'static str,
+    pub in_stock_only: &'static str,
+    pub no_matches: &'static str,
+    pub previous: &'static str,
+    pub next: &'static str,
+    pub empty_category: &'static str,
+    pub back_to_start: &'static str,
+    pub subcategories_failed: &'static str,
+    pub order_created: &'static str,
+    pub order_thanks: &'static str,
+    pub order_canceled: &'static str,
+    pub order_canceled_text: &'static str,
+    pub contact_us: &'static str,
+    pub reference: &'static str,
+    pub account_intro: &'static str,
+    pub email_placeholder: &'static str,
+    pub log_in: &'static str,
+    pub create_account: &'static str,
+    pub login_failed: &'static str,
+    pub registration_failed: &'static str,
+    pub waiting_for_passkey: &'static str,
+    pub past_orders: &'static str,
+    pub no_orders: &'static str,
+    pub pieces: &'static str,
+    pub logged_in_as: &'static str,
+    pub log_out: &'static str,
+    pub saved_addresses: &'static str,
+    pub no_addresses: &'static str,
+    pub account_failed: &'static str,
+    pub error_heading: &'static str,
+    pub try_again_later: &'static str,
+    pub welcome_back: &'static str,
+    pub cart_restored: &'static str,
+    pub cart_not_restored: &'static str,
+    pub cart_link_expired: &'static str,
+    pub cart_restore_failed: &'static str,
+    pub restoring_cart: &'static str,
+    pub stop_reminders_question: &'static str,
+    pub stop_reminders_text: &'static str,
+    pub stop_reminders: &'static str,
+    pub reminders_stopped: &'static str,
+    pub reminders_stopped_text: &'static str,
+    pub reminders_stop_failed: &'static str,
+    pub thanks: &'static str,
+    pub review_submitted: &'static str,
+    pub already_reviewed: &'static str,
+    pub invalid_review: &'static str,
+    pub too_many_reviews: &'static str,
+    pub review_name: &'static str,
+    pub review_text: &'static str,
+    pub send: &'static str,
+    pub invalid_link: &'static str,
+    pub check_link: &'static str,
+    pub subscription_confirmed: &'static str,
+    pub subscription_confirmed_text: &'static str,
+    pub subscription_canceled: &'static str,
+    pub subscription_canceled_text: &'static str,
+}
+
+pub const SV: Messages = Messages {
+    home: "Hem",
+    favourites: "Favoriter",
+    about: "Om SJF",
+    loading: "Laddar...",
+    loading_failed: "Laddning misslyckades!",
+    free_shipping: "Fri frakt över",
+    currency: "Valuta",
+    demo_mode: "SIDAN ÄR I DEMOLÄGE!",
+    shipping_and_terms: "Frakt & Villkor",
+    my_account: "Mina sidor",
+    staff_login: "Inloggning",
+    not_found: "Oops! Sidan hittades inte :/",
+    loading_product: "Laddar produkt...",
+    product_missing: "Ooops här var det tomt, möjligen kan produkten plockats bort",
+    sold_out: "Slutsåld!",
+    added: "Tillagd!",
+    add_to_cart: "Lägg i varukorg",
+    backordered: "Restnoterad, skickas när den finns i lager igen",
+    you_may_also_like: "Du kanske också gillar",
+    others_also_bought: "Andra köpte också",
+    reviews: "Recensioner",
+    staff_reply: "Svar från SJF Concept:",
+    notify_in_stock: "Meddela mig när den finns i lager igen",
+    your_email: "Din email",
+    subscribe: "Bevaka",
+    confirm_subscription: "Kolla din inkorg och bekräfta bevakningen!",
+    invalid_email: "Ogiltig emailadress.",
+    rate_limited: "För många förfrågningar, försök igen senare.",
+    something_went_wrong: "Något gick fel, försök igen senare.",
+    cart_updated: "Varukorgen har uppdaterats",
+    to_checkout: "Till kassa",
+    processing: "Processerar...",
+    failed: "Misslyckades",
+    redirecting: "Omdirigerar till stripe...",
+    total: "Totalt",
+    remove_favourite: "Ta bort från favoriter",
+    save_favourite: "Spara som favorit",
+    no_favourites: "Du har inga favoriter ännu. Tryck på hjärtat vid en produkt för att spara den här.",
+    favourites_failed: "Kunde inte ladda favoriterna.",
+    popular: "Populärt!",
+    explore: "Utforska",
+    no_image: "Ingen bild",
+    shop_description: "Välkommen till SJF Concept – kläder, lasergraverade produkter, 3D-printade föremål, dekaler och personligt anpassad design.",
+    sort: "Sortera",
+    price: "Pris",
+    price_from: "Från",
+    price_to: "Till",
+    in_stock_only: "Endast i lager",
+    no_matches: "Inga produkter matchar filtret.",
+    previous: "Föregående",
+    next: "Nästa",
+    empty_category: "Ooops här var det tomt för tillfället, men kika gärna tillbaka senare.",
+    back_to_start: "Tillbaka till början",
+    subcategories_failed: "Kunde inte ladda underkategorier",
+    order_created: "Order skapad!",
+    order_thanks: "Tack för dit köp! Inom kort får du en orderbekräftelse via email!",
+    order_canceled: "Order avbruten!",
+    order_canceled_text: "Köpet har avbrutits och du har inte blivit debiterad. Om du skulle ångra dig så finns dina varor kvar i varukorgen redo att beställas.",
+    contact_us: "Har du några tankar eller funderingar så kontakta oss på: sjfconcept@hotmail.com",
+    reference: "Referens:",
+    account_intro: "Logga in med en passkey för att se dina tidigare beställningar och få din adress ifylld i kassan. Har du inget konto skapar du ett med din emailadress.",
+    email_placeholder: "namn@exempel.se",
+    log_in: "Logga in",
+    create_account: "Skapa konto",
+    login_failed: "Inloggningen misslyckades.",
+    registration_failed: "Kontot kunde inte skapas, kanske finns det redan?",
+    waiting_for_passkey: "Väntar på passkey...",
+    past_orders: "Tidigare beställningar",
+    no_orders: "Du har inte gjort några beställningar ännu.",
+    pieces: "st",
+    logged_in_as: "Inloggad som",
+    log_out: "Logga ut",
+    saved_addresses: "Sparade adresser",
+    no_addresses: "Adresser du anger i kassan sparas här till nästa gång.",
+    account_failed: "Kunde inte ladda kontot, försök igen senare.",
+    error_heading: "Något gick fel",
+    try_again_later: "Försök igen senare.",
+    welcome_back: "Välkommen tillbaka!",
+    cart_restored: "Varorna från ditt tidigare köp ligger nu i varukorgen. Gå till kassan när du är redo att slutföra beställningen.",
+    cart_not_restored: "Varukorgen kunde inte återställas",
+    cart_link_expired: "Länken har gått ut eller så finns produkterna inte längre kvar.",
+    cart_restore_failed: "Varukorgen kunde inte återställas just nu, försök igen senare.",
+    restoring_cart: "Återställer varukorgen...",
+    stop_reminders_question: "Avsluta påminnelser?",
+    stop_reminders_text: "Vi påminner om köp som inte har slutförts. Vill du inte få fler sådana påminnelser?",
+    stop_reminders: "Avsluta påminnelser",
+    reminders_stopped: "Påminnelser avslutade!",
+    reminders_stopped_text: "Du kommer inte att få fler påminnelser om köp som inte har slutförts.",
+    reminders_stop_failed: "Påminnelserna kunde inte avslutas just nu, försök igen senare.",
+    thanks: "Tack!",
+    review_submitted: "Tack för din recension! Den publiceras efter granskning.",
+    already_reviewed: "Du har redan recenserat den här produkten.",
+    invalid_review: "Fyll i betyg, namn och en recension utan för många länkar.",
+    too_many_reviews: "För många recensioner, försök igen senare.",
+    review_name: "Namn",
+    review_text: "Recension",
+    send: "Skicka",
+    invalid_link: "Länken är ogiltig",
+    check_link: "Kontrollera att du har kopierat hela länken från mailet.",
+    subscription_confirmed: "Bevakning bekräftad!",
+    subscription_confirmed_text: "Vi skickar ett mail till dig så fort produkten finns i lager igen. Därefter avslutas bevakningen automatiskt.",
+    subscription_canceled: "Bevakning avslutad!",
+    subscription_canceled_text: "Du kommer inte att få några fler mail om den här produkten.",
+};
+
+pub const EN: Messages = Messages {
+    home: "Home",
+    favourites: "Favourites",
+    about: "About SJF",
+    loading: "Loading...",
+    loading_failed: "Loading failed!",
+    free_shipping: "Free shipping over",
+    currency: "Currency",
+    demo_mode: "THE SITE IS IN DEMO MODE!",
+    shipping_and_terms: "Shipping & Terms",
+    my_account: "My account",
+    staff_login: "Staff login",
+    not_found: "Oops! Page not found :/",
+    loading_product: "Loading product...",
+    product_missing: "Oops, nothing here. The product may have been removed.",
+    sold_out: "Sold out!",
+    added: "Added!",
+    add_to_cart: "Add to cart",
+    backordered: "Backordered, ships as soon as it is back in stock",
+    you_may_also_like: "You may also like",
+    others_also_bought: "Others also bought",
+    reviews: "Reviews",
+    staff_reply: "Reply from SJF Concept:",
+    notify_in_stock: "Let me know when it is back in stock",
+    your_email: "Your email",
+    subscribe: "Notify me",
+    confirm_subscription: "Check your inbox and confirm the subscription!",
+    invalid_email: "Invalid email address.",
+    rate_limited: "Too many requests, please try again later.",
+    something_went_wrong: "Something went wrong, please try again later.",
+    cart_updated: "Your cart has been updated",
+    to_checkout: "Checkout",
+    processing: "Processing...",
+    failed: "Failed",
+    redirecting: "Redirecting to Stripe...",
+    total: "Total",
+    remove_favourite: "Remove from favourites",
+    save_favourite: "Save as favourite",
+    no_favourites: "You have no favourites yet. Tap the heart on a product to save it here.",
+    favourites_failed: "Could not load your favourites.",
+    popular: "Popular!",
+    explore: "Explore",
+    no_image: "No image",
+    shop_description: "Welcome to SJF Concept – clothing, laser engraved products, 3D printed items, decals and personalised design.",
+    sort: "Sort",
+    price: "Price",
+    price_from: "From",
+    price_to: "To",
+    in_stock_only: "In stock only",
+    no_matches: "No products match the filter.",
+    previous: "Previous",
+    next: "Next",
+    empty_category: "Oops, nothing here at the moment, but do check back later.",
+    back_to_start: "Back to the start",
+    subcategories_failed: "Could not load the subcategories",
+    order_created: "Order placed!",
+    order_thanks: "Thank you for your purchase! You will shortly receive an order confirmation by email.",
+    order_canceled: "Order cancelled!",
+    order_canceled_text: "The purchase was cancelled and you have not been charged. Should you change your mind, your items are still in the cart ready to be ordered.",
+    contact_us: "If you have any thoughts or questions, contact us at: sjfconcept@hotmail.com",
+    reference: "Reference:",
+    account_intro: "Log in with a passkey to see your previous orders and get your address filled in at checkout. If you don't have an account, create one with your email address.",
+    email_placeholder: "name@example.com",
+    log_in: "Log in",
+    create_account: "Create account",
+    login_failed: "Login failed.",
+    registration_failed: "The account could not be created, maybe it already exists?",
+    waiting_for_passkey: "Waiting for passkey...",
+    past_orders: "Previous orders",
+    no_orders: "You haven't placed any orders yet.",
+    pieces: "pcs",
+    logged_in_as: "Logged in as",
+    log_out: "Log out",
+    saved_addresses: "Saved addresses",
+    no_addresses: "Addresses you enter at checkout are saved here for next time.",
+    account_failed: "Could not load your account, please try again later.",
+    error_heading: "Something went wrong",
+    try_again_later: "Please try again later.",
+    welcome_back: "Welcome back!",
+    cart_restored: "The items from your earlier purchase are back in your cart. Go to checkout when you are ready to complete the order.",
+    cart_not_restored: "Your cart could not be restored",
+    cart_link_expired: "The link has expired or the products are no longer available.",
+    cart_restore_failed: "Your cart could not be restored right now, please try again later.",
+    restoring_cart: "Restoring your cart...",
+    stop_reminders_question: "Stop reminders?",
+    stop_reminders_text: "We send reminders about purchases that weren't completed. Would you rather not get any more of them?",
+    stop_reminders: "Stop reminders",
+    reminders_stopped: "Reminders stopped!",
+    reminders_stopped_text: "You won't get any more reminders about purchases that weren't completed.",
+    reminders_stop_failed: "The reminders could not be stopped right now, please try again later.",
+    thanks: "Thank you!",
+    review_submitted: "Thank you for your review! It will be published once it has been checked.",
+    already_reviewed: "You have already reviewed this product.",
+    invalid_review: "Fill in a rating, your name and a review without too many links.",
+    too_many_reviews: "Too many reviews, please try again later.",
+    review_name: "Name",
+    review_text: "Review",
+    send: "Send",
+    invalid_link: "The link is invalid",
+    check_link: "Check that you copied the whole link from the email.",
+    subscription_confirmed: "Alert confirmed!",
+    subscription_confirmed_text: "We will email you as soon as the product is back in stock. The alert ends after that.",
+    subscription_canceled: "Alert cancelled!",
+    subscription_canceled_text: "You won't get any more emails about this product.",
+};
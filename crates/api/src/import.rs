@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::product::ARTICLE_PREFIX;
+
+/// Columns of the product import and export, in the order they are exported.
+pub const COLUMNS: [&str; 8] = [
+    "sku",
+    "name",
+    "description",
+    "price",
+    "quantity",
+    "tax_rate",
+    "category",
+    "images",
+];
+
+/// Separates the segments of the category column, `Kläder/Tröjor`.
+pub const CATEGORY_SEPARATOR: char = '/';
+/// Separates the images of the images column.
+pub const IMAGE_SEPARATOR: char = '|';
+
+/// An image of an imported product.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ImageRef {
+    /// An image already in the shop, exported as `/images/<id>`.
+    Existing(u32),
+    /// Downloaded when the import is applied.
+    Url(String),
+    /// A file uploaded together with the import.
+    File(String),
+}
+
+impl ImageRef {
+    pub fn parse(value: &str) -> ImageRef {
+        let value = value.trim();
+        let path = value
+            .strip_prefix("https://")
+            .or_else(|| value.strip_prefix("http://"))
+            .and_then(|v| v.find('/').map(|i| &v[i..]))
+            .unwrap_or(value);
+        if let Some(id) = path
+            .strip_prefix("/images/")
+            .and_then(|rest| rest.split('/').next())
+            .and_then(|id| id.parse().ok())
+        {
+            return ImageRef::Existing(id);
+        }
+        match value.starts_with("https://") || value.starts_with("http://") {
+            true => ImageRef::Url(value.to_owned()),
+            false => ImageRef::File(value.to_owned()),
+        }
+    }
+
+    pub fn export(&self) -> String {
+        match self {
+            ImageRef::Existing(id) => format!("/images/{}", id),
+            ImageRef::Url(url) => url.clone(),
+            ImageRef::File(name) => name.clone(),
+        }
+    }
+}
+
+/// A row of an import file that passed validation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportRow {
+    /// Row in the file as numbered by a spreadsheet, the header is row 1.
+    pub line: usize,
+    /// Matches the SKU of an existing product, or its article number
+    /// `artikel-<id>` for products that don't have one.
+    pub sku: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub price: u32,
+    /// Empty when the stock isn't tracked.
+    pub quantity: Option<u32>,
+    pub tax_rate: u32,
+    pub category: Vec<String>,
+    pub images: Vec<ImageRef>,
+}
+
+impl ImportRow {
+    /// The id of the product an article number refers to.
+    pub fn article_id(&self) -> Option<u32> {
+        self.sku
+            .as_deref()
+            .and_then(|s| s.strip_prefix(ARTICLE_PREFIX))
+            .and_then(|id| id.parse().ok())
+    }
+
+    pub fn to_record(&self) -> Vec<String> {
+        vec![
+            self.sku.clone().unwrap_or_default(),
+            self.name.clone(),
+            self.description.clone(),
+            self.price.to_string(),
+            self.quantity.map(|q| q.to_string()).unwrap_or_default(),
+            self.tax_rate.to_string(),
+            self.category.join(&CATEGORY_SEPARATOR.to_string()),
+            self.images
+                .iter()
+                .map(|i| i.export())
+                .collect::<Vec<_>>()
+                .join(&IMAGE_SEPARATOR.to_string()),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ImportAction {
+    Create,
+    Update(u32),
+    Unchanged(u32),
+}
+
+/// What importing a row does, shown before the import is applied.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportChange {
+    pub line: usize,
+    pub sku: Option<String>,
+    pub name: String,
+    pub action: ImportAction,
+    /// The fields that change, as `price: 199 → 249`.
+    pub changes: Vec<String>,
+}
+
+/// An import file, as CSV text or an Excel workbook of which the first sheet
+/// is read.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ImportFile {
+    Csv(String),
+    Xlsx(Vec<u8>),
+}
+
+impl Default for ImportFile {
+    fn default() -> Self {
+        ImportFile::Csv(String::new())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ImportRequest {
+    pub file: ImportFile,
+    /// Image files referred to by name in the file. Only the names are needed
+    /// for a dry run, the contents may be left empty.
+    pub images: BTreeMap<String, Vec<u8>>,
+    /// A dry run when false.
+    pub apply: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ImportReport {
+    pub changes: Vec<ImportChange>,
+    pub errors: Vec<RowError>,
+    /// Nothing is applied when there are errors.
+    pub applied: bool,
+}
+
+/// Splits CSV text into records. Fields are separated by `;` or `,`, whichever
+/// the header uses, so that files saved by a Swedish Excel work too.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let header = text.lines().next().unwrap_or_default();
+    let separator = match header.contains(';') {
+        true => ';',
+        false => ',',
+    };
+
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (c, false) if c == separator => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Writes records as CSV separated by `;`, with a byte order mark for Excel.
+pub fn write_csv(records: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut csv = String::from("\u{feff}");
+    for record in records {
+        csv += &record
+            .iter()
+            .map(|f| format!("\"{}\"", f.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(";");
+        csv += "\n";
+    }
+    csv
+}
+
+/// Validates the records of an import file, the first being the header. Rows
+/// with errors are left out of the result.
+pub fn parse_rows(records: Vec<Vec<String>>) -> (Vec<ImportRow>, Vec<RowError>) {
+    let mut records = records.into_iter();
+    let mut errors = vec![];
+    let Some(header) = records.next() else {
+        errors.push(RowError {
+            line: 1,
+            message: "Filen är tom".into(),
+        });
+        return (vec![], errors);
+    };
+
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let mut columns = BTreeMap::new();
+    for column in COLUMNS {
+        match header.iter().position(|h| h == column) {
+            Some(i) => {
+                columns.insert(column, i);
+            }
+            None if column == "sku" || column == "images" => {}
+            None => errors.push(RowError {
+                line: 1,
+                message: format!("Kolumnen {} saknas", column),
+            }),
+        }
+    }
+    if !errors.is_empty() {
+        return (vec![], errors);
+    }
+
+    let mut rows = vec![];
+    let mut skus = BTreeSet::new();
+    for (i, record) in records.enumerate() {
+        let line = i + 2;
+        let field = |column: &str| {
+            columns
+                .get(column)
+                .and_then(|i| record.get(*i))
+                .map(|f| f.trim())
+                .unwrap_or_default()
+        };
+        let mut error = |message: String| errors.push(RowError { line, message });
+
+        let sku = Some(field("sku").to_owned()).filter(|s| !s.is_empty());
+        if let Some(sku) = &sku {
+            if sku.chars().count() > 64 {
+                error("SKU får vara högst 64 tecken".into());
+                continue;
+            }
+            if !skus.insert(sku.clone()) {
+                error(format!("SKU {} finns på flera rader", sku));
+                continue;
+            }
+        }
+
+        let name = field("name");
+        if name.is_empty() || name.chars().count() > 100 {
+            error("Namnet måste vara 1-100 tecken".into());
+            continue;
+        }
+        // The admin edits prices as u16.
+        let Some(price) = field("price")
+            .parse::<u32>()
+            .ok()
+            .filter(|p| *p > 0 && *p <= u16::MAX as u32)
+        else {
+            error(format!(
+                "Ogiltigt pris {}, ange hela kronor",
+                field("price")
+            ));
+            continue;
+        };
+        let quantity = match field("quantity") {
+            "" => None,
+            q => match q.parse::<u32>() {
+                Ok(q) => Some(q),
+                Err(_) => {
+                    error(format!("Ogiltigt antal {}", q));
+                    continue;
+                }
+            },
+        };
+        let Some(tax_rate) = field("tax_rate")
+            .trim_end_matches('%')
+            .parse::<u32>()
+            .ok()
+            .filter(|t| [0, 6, 12, 25].contains(t))
+        else {
+            error(format!(
+                "Ogiltig momssats {}, ange 25, 12, 6 eller 0",
+                field("tax_rate")
+            ));
+            continue;
+        };
+        let category: Vec<String> = field("category")
+            .split(CATEGORY_SEPARATOR)
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if category.is_empty() {
+            error("Kategori saknas".into());
+            continue;
+        }
+        let images = field("images")
+            .split(IMAGE_SEPARATOR)
+            .map(str::trim)
+            .filter(|i| !i.is_empty())
+            .map(ImageRef::parse)
+            .collect();
+
+        rows.push(ImportRow {
+            line,
+            sku,
+            name: name.to_owned(),
+            description: field("description").to_owned(),
+            price,
+            quantity,
+            tax_rate,
+            category,
+            images,
+        });
+    }
+    (rows, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|r| r.iter().map(|f| f.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn csv_separator_follows_the_header() {
+        assert_eq!(
+            parse_csv("sku;name\n1;Tröja, blå\n"),
+            records(&[&["sku", "name"], &["1", "Tröja, blå"]])
+        );
+        assert_eq!(
+            parse_csv("sku,name\n1,Tröja; blå\n"),
+            records(&[&["sku", "name"], &["1", "Tröja; blå"]])
+        );
+    }
+
+    #[test]
+    fn csv_quoted_fields() {
+        assert_eq!(
+            parse_csv("a;b;c\n\"x;y\";\"säg \"\"hej\"\"\";\"\"\n"),
+            records(&[&["a", "b", "c"], &["x;y", "säg \"hej\"", ""]])
+        );
+        assert_eq!(
+            parse_csv("a,b\n\"rad ett\nrad två, tre\",z"),
+            records(&[&["a", "b"], &["rad ett\nrad två, tre", "z"]])
+        );
+    }
+
+    #[test]
+    fn csv_from_excel() {
+        assert_eq!(
+            parse_csv("\u{feff}a;b\r\n1;\"två\r\nrader\"\r\n\r\n;\r\n2;3\r\n"),
+            records(&[&["a", "b"], &["1", "två\r\nrader"], &["2", "3"]])
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let written = records(&[
+            &["sku", "name", "description"],
+            &["a-1", "Mössa \"Vinter\"", "Varm; mjuk,\nstickad"],
+        ]);
+        assert_eq!(parse_csv(&write_csv(written.clone())), written);
+    }
+
+    #[test]
+    fn rows_are_validated() {
+        let (rows, errors) = parse_rows(records(&[
+            &[
+                "Name",
+                "price",
+                "quantity",
+                "tax_rate",
+                "category",
+                "description",
+            ],
+            &["Mössa", "199", "", "25%", "Kläder/ Mössor", ""],
+            &["Halsduk", "0", "3", "25", "Kläder", ""],
+            &["Vante", "99", "3", "7", "Kläder", ""],
+        ]));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].quantity, None);
+        assert_eq!(rows[0].category, ["Kläder", "Mössor"]);
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [3, 4]);
+
+        let (rows, errors) = parse_rows(records(&[&["name", "price"]]));
+        assert!(rows.is_empty());
+        assert_eq!(errors.len(), 4);
+    }
+}
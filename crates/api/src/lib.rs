@@ -1,4 +1,15 @@
+pub mod account;
+pub mod attribute;
+pub mod cart;
 pub mod category;
 pub mod checkout;
+pub mod currency;
+pub mod i18n;
+pub mod import;
+pub mod order;
 pub mod payment;
 pub mod product;
+pub mod review;
+pub mod stock;
+pub mod tax;
+pub mod wishlist;
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::currency::ExchangeRate;
+use crate::product::ProductId;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OrderedProduct {
+    /// `None` once the product has been deleted.
+    pub product_id: Option<ProductId>,
+    pub name: String,
+    pub quantity: u32,
+    pub price: u32,
+}
+
+/// A paid order as recorded when its reservation was committed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Order {
+    pub id: String,
+    pub email: Option<String>,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub delivered: Option<chrono::DateTime<chrono::Utc>>,
+    /// The currency the order was paid in and its rate at the time, line prices are in kr.
+    pub exchange_rate: ExchangeRate,
+    pub lines: Vec<OrderedProduct>,
+}
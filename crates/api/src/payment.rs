@@ -1,2 +1,20 @@
+use crate::i18n::Locale;
+
 pub const CANCLE_PATH: &str = "/order/avbruten";
 pub const SUCCESS_PATH: &str = "/order/klar";
+
+/// Where Stripe sends the customer back to after a cancelled payment.
+pub fn cancel_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => CANCLE_PATH,
+        Locale::En => "/en/order/canceled",
+    }
+}
+
+/// Where Stripe sends the customer back to after a completed payment.
+pub fn success_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => SUCCESS_PATH,
+        Locale::En => "/en/order/done",
+    }
+}
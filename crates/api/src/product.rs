@@ -1,8 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use crate::i18n::Locale;
+use crate::stock::StockPolicy;
+
 pub const PRODUCTS_PATH: &str = "/produkter";
 pub const ARTICLE_PREFIX: &str = "artikel-";
 
+/// Where the catalogue starts in each language.
+pub fn products_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => PRODUCTS_PATH,
+        Locale::En => "/en/products",
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ImageVariant {
     pub width: u32,
@@ -26,17 +37,112 @@ impl Image {
 
 pub use u32 as ProductId;
 
+/// Whether a product can be bought, as told to search engines and product feeds.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Availability {
+    InStock,
+    OutOfStock,
+    PreOrder,
+    BackOrder,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Product {
     pub id: ProductId,
     pub name: String,
+    /// Units that can be ordered, including backorders. `None` when the quantity isn't tracked
+    /// or the product is pre-ordered.
     pub stock: Option<u32>,
+    pub stock_policy: StockPolicy,
     pub description: String,
     pub category_name: Vec<String>,
     pub price: u32,
     pub images: Vec<Image>,
 }
 
+impl Product {
+    /// Units that can ship directly from stock.
+    pub fn on_hand(&self) -> Option<u32> {
+        match &self.stock_policy {
+            StockPolicy::Stop => self.stock,
+            StockPolicy::Backorder { limit } => self.stock.map(|s| s.saturating_sub(*limit)),
+            StockPolicy::Preorder { .. } => None,
+        }
+    }
+
+    pub fn availability(&self) -> Availability {
+        if self.stock == Some(0) {
+            return Availability::OutOfStock;
+        }
+        match &self.stock_policy {
+            StockPolicy::Preorder { .. } => Availability::PreOrder,
+            StockPolicy::Backorder { .. } if self.on_hand() == Some(0) => Availability::BackOrder,
+            _ => Availability::InStock,
+        }
+    }
+
+    pub fn preview(&self) -> Preview {
+        Preview {
+            id: self.id,
+            name: self.name.clone(),
+            category_name: self.category_name.clone(),
+            price: self.price,
+            images: self.images.clone(),
+        }
+    }
+
+    /// Tells the customer when `quantity` units will ship, if not right away.
+    pub fn delivery_note(&self, quantity: u32, locale: Locale) -> Option<String> {
+        match &self.stock_policy {
+            StockPolicy::Stop => None,
+            StockPolicy::Preorder { ship_date } => Some(locale.preorder_ships(*ship_date)),
+            StockPolicy::Backorder { .. } => {
+                let on_hand = self.on_hand()?;
+                (quantity > on_hand).then(|| locale.messages().backordered.to_owned())
+            }
+        }
+    }
+}
+
+/// Relative URL of a category page given the names of its path.
+pub fn category_url(category_name: &[String]) -> String {
+    category_url_in(Locale::Sv, category_name)
+}
+
+pub fn category_url_in(locale: Locale, category_name: &[String]) -> String {
+    category_name
+        .iter()
+        .map(|x| urlencoding::encode(x))
+        .fold(String::from(products_path(locale)), |acc, s| acc + "/" + &s)
+}
+
+/// Relative URL of a product page given the names of its category path.
+pub fn product_url(category_name: &[String], id: ProductId) -> String {
+    product_url_in(Locale::Sv, category_name, id)
+}
+
+pub fn product_url_in(locale: Locale, category_name: &[String], id: ProductId) -> String {
+    assert!(!category_name.is_empty());
+    category_url_in(locale, category_name) + "/" + ARTICLE_PREFIX + &id.to_string()
+}
+
+/// The name and description of a product in a language other than Swedish.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ProductTranslation {
+    pub name: String,
+    pub description: String,
+}
+
+impl Product {
+    /// Shows the product in another language, an empty description keeps the Swedish one.
+    pub fn translate(&mut self, translation: &ProductTranslation) {
+        self.name = translation.name.clone();
+        if !translation.description.trim().is_empty() {
+            self.description = translation.description.clone();
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Preview {
     pub id: u32,
@@ -52,14 +158,15 @@ impl Preview {
     }
 
     pub fn product_url(&self) -> String {
-        assert!(!self.category_name.is_empty());
-        let cat = self
-            .category_name
-            .iter()
-            .map(|x| urlencoding::encode(x))
-            .fold(String::from(PRODUCTS_PATH), |acc, s| acc + "/" + &s);
+        product_url(&self.category_name, self.id)
+    }
 
-        cat + "/" + &self.article_name()
+    pub fn product_url_in(&self, locale: Locale) -> String {
+        product_url_in(locale, &self.category_name, self.id)
+    }
+
+    pub fn translate(&mut self, translation: &ProductTranslation) {
+        self.name = translation.name.clone();
     }
 
     pub fn product_path(&self) -> Vec<String> {
@@ -69,17 +176,188 @@ impl Preview {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum PreviewOrder {
+    #[default]
+    Newest,
+    PriceAscending,
+    PriceDescending,
+    Name,
+    /// Most units sold recently first.
+    Popularity,
+    Random,
+}
+
+impl PreviewOrder {
+    /// The orders a customer can choose between.
+    pub const SELECTABLE: [PreviewOrder; 5] = [
+        PreviewOrder::Newest,
+        PreviewOrder::PriceAscending,
+        PreviewOrder::PriceDescending,
+        PreviewOrder::Name,
+        PreviewOrder::Popularity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreviewOrder::Newest => "newest",
+            PreviewOrder::PriceAscending => "price_asc",
+            PreviewOrder::PriceDescending => "price_desc",
+            PreviewOrder::Name => "name",
+            PreviewOrder::Popularity => "popularity",
+            PreviewOrder::Random => "random",
+        }
+    }
+
+    /// Name used in the URL query.
+    pub fn slug(&self) -> &'static str {
+        match self {
+            PreviewOrder::Newest => "nyast",
+            PreviewOrder::PriceAscending => "pris-stigande",
+            PreviewOrder::PriceDescending => "pris-fallande",
+            PreviewOrder::Name => "namn",
+            PreviewOrder::Popularity => "populart",
+            PreviewOrder::Random => "slumpad",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::SELECTABLE
+            .into_iter()
+            .chain([PreviewOrder::Random])
+            .find(|o| o.slug() == slug)
+    }
+
+    pub fn label(&self, locale: Locale) -> &'static str {
+        match (locale, self) {
+            (Locale::Sv, PreviewOrder::Newest) => "Nyast",
+            (Locale::Sv, PreviewOrder::PriceAscending) => "Lägsta pris",
+            (Locale::Sv, PreviewOrder::PriceDescending) => "Högsta pris",
+            (Locale::Sv, PreviewOrder::Name) => "Namn",
+            (Locale::Sv, PreviewOrder::Popularity) => "Populärast",
+            (Locale::Sv, PreviewOrder::Random) => "Slumpad",
+            (Locale::En, PreviewOrder::Newest) => "Newest",
+            (Locale::En, PreviewOrder::PriceAscending) => "Lowest price",
+            (Locale::En, PreviewOrder::PriceDescending) => "Highest price",
+            (Locale::En, PreviewOrder::Name) => "Name",
+            (Locale::En, PreviewOrder::Popularity) => "Most popular",
+            (Locale::En, PreviewOrder::Random) => "Random",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct PreviewFilter {
+    pub min_price: Option<u32>,
+    pub max_price: Option<u32>,
+    /// Only products that can ship directly from stock.
+    pub in_stock: bool,
+    /// Selected attribute values. Products match any of the values selected
+    /// for an attribute, and all attributes that have a selection.
+    pub attributes: Vec<(u32, String)>,
+}
+
+impl PreviewFilter {
+    pub fn is_selected(&self, attribute: u32, value: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|(a, v)| *a == attribute && v == value)
+    }
+
+    /// Selects `value` if it isn't, otherwise removes it from the selection.
+    pub fn toggle(&mut self, attribute: u32, value: &str) {
+        if self.is_selected(attribute, value) {
+            self.attributes
+                .retain(|(a, v)| !(*a == attribute && v == value));
+        } else {
+            self.attributes.push((attribute, value.to_owned()));
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct GetPreviewsRequest {
     pub category: Option<u32>,
     pub recursive: bool,
     pub limit: u32,
-    pub random: bool,
+    /// Zero based page of `limit` previews.
+    pub page: u32,
+    pub order: PreviewOrder,
+    pub filter: PreviewFilter,
+    /// Language of the returned names.
+    #[serde(default)]
+    pub locale: Locale,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct GetPreviewsResp {
     pub previews: Vec<Preview>,
+    /// Previews matching the filter on all pages.
+    pub total: u32,
+}
+
+/// Sorting, filtering and page of a category listing as kept in the URL query,
+/// e.g. `sortera=pris-stigande&min=100&max=500&i-lager=1&e4=Ek&sida=2` where
+/// `e4` is a selected value of attribute 4.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ListingQuery {
+    pub order: PreviewOrder,
+    pub filter: PreviewFilter,
+    /// Zero based, shown one based in the URL.
+    pub page: u32,
+}
+
+impl ListingQuery {
+    pub fn with_page(self, page: u32) -> Self {
+        ListingQuery { page, ..self }
+    }
+}
+
+impl From<&str> for ListingQuery {
+    fn from(query: &str) -> Self {
+        let mut res = ListingQuery::default();
+        for (key, value) in query.split('&').filter_map(|s| s.split_once('=')) {
+            match key {
+                "sortera" => res.order = PreviewOrder::from_slug(value).unwrap_or_default(),
+                "min" => res.filter.min_price = value.parse().ok(),
+                "max" => res.filter.max_price = value.parse().ok(),
+                "i-lager" => res.filter.in_stock = value == "1",
+                "sida" => res.page = value.parse::<u32>().unwrap_or(1).saturating_sub(1),
+                key => {
+                    if let Some(Ok(attribute)) = key.strip_prefix('e').map(str::parse) {
+                        let value = urlencoding::decode(value).unwrap_or(value.into());
+                        res.filter.attributes.push((attribute, value.into_owned()));
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl std::fmt::Display for ListingQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut args = Vec::new();
+        if self.order != PreviewOrder::default() {
+            args.push(format!("sortera={}", self.order.slug()));
+        }
+        if let Some(min) = self.filter.min_price {
+            args.push(format!("min={min}"));
+        }
+        if let Some(max) = self.filter.max_price {
+            args.push(format!("max={max}"));
+        }
+        if self.filter.in_stock {
+            args.push("i-lager=1".into());
+        }
+        for (attribute, value) in &self.filter.attributes {
+            args.push(format!("e{attribute}={}", urlencoding::encode(value)));
+        }
+        if self.page > 0 {
+            args.push(format!("sida={}", self.page + 1));
+        }
+        write!(f, "{}", args.join("&"))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -95,3 +373,46 @@ pub struct GetProductsRequest {
 }
 
 pub type GetProductsResponse = Vec<Product>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listing_queries_print_as_they_are_parsed() {
+        for query in [
+            "",
+            "sortera=pris-stigande",
+            "min=100&max=500",
+            "i-lager=1&sida=3",
+            "sortera=populart&min=0&i-lager=1&e4=Ek&e4=Bj%C3%B6rk&e7=2%20m%20%26%20mer&sida=2",
+        ] {
+            assert_eq!(ListingQuery::from(query).to_string(), query);
+        }
+    }
+
+    #[test]
+    fn listing_queries_are_parsed_as_they_are_printed() {
+        let query = ListingQuery {
+            order: PreviewOrder::Name,
+            filter: PreviewFilter {
+                min_price: Some(50),
+                max_price: None,
+                in_stock: true,
+                attributes: vec![(4, "Ek & björk".into()), (9, "100%".into())],
+            },
+            page: 4,
+        };
+        assert_eq!(ListingQuery::from(query.to_string().as_str()), query);
+        assert_eq!(
+            ListingQuery::from(ListingQuery::default().to_string().as_str()),
+            ListingQuery::default()
+        );
+    }
+
+    #[test]
+    fn invalid_listing_queries_fall_back_to_the_defaults() {
+        let query = ListingQuery::from("sortera=okand&min=mycket&sida=0&e=x&ex=y&utan-varde");
+        assert_eq!(query, ListingQuery::default());
+    }
+}
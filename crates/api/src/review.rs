@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Locale;
+use crate::product::ProductId;
+
+pub const REVIEW_PATH: &str = "/recension";
+pub const MAX_REVIEW_LENGTH: usize = 2000;
+pub const MAX_AUTHOR_LENGTH: usize = 100;
+
+/// Where the links in a review request lead.
+pub fn review_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => REVIEW_PATH,
+        Locale::En => "/en/review",
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RatingSummary {
+    pub average: f32,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Review {
+    pub id: String,
+    /// 1 to 5
+    pub rating: u8,
+    pub text: String,
+    pub author: String,
+    pub created: chrono::DateTime<chrono::Utc>,
+    /// Public answer from staff.
+    pub reply: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProductReviews {
+    pub summary: Option<RatingSummary>,
+    pub reviews: Vec<Review>,
+}
+
+/// A review waiting for or having passed moderation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ModeratedReview {
+    pub review: Review,
+    pub product_id: ProductId,
+    pub product_name: String,
+    pub status: ReviewStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "pending",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl From<&str> for ReviewStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "approved" => ReviewStatus::Approved,
+            "rejected" => ReviewStatus::Rejected,
+            _ => ReviewStatus::Pending,
+        }
+    }
+}
+
+/// What a review link is for.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReviewInvitation {
+    pub product_name: String,
+    pub already_reviewed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubmitReviewRequest {
+    pub token: String,
+    pub rating: u8,
+    pub text: String,
+    pub author: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum SubmitReviewStatus {
+    Submitted,
+    AlreadyReviewed,
+    InvalidToken,
+    Invalid,
+    RateLimited,
+}
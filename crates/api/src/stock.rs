@@ -0,0 +1,117 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Locale;
+use crate::product::ProductId;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StockLevel {
+    Low,
+    SoldOut,
+}
+
+impl StockLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockLevel::Low => "low",
+            StockLevel::SoldOut => "sold_out",
+        }
+    }
+
+    pub fn from_quantity(quantity: i32) -> Self {
+        if quantity <= 0 {
+            StockLevel::SoldOut
+        } else {
+            StockLevel::Low
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StockAlert {
+    pub product_id: ProductId,
+    pub name: String,
+    /// Negative when products have been backordered.
+    pub quantity: i32,
+    pub reorder_threshold: Option<u32>,
+    pub level: StockLevel,
+}
+
+pub const CONFIRM_SUBSCRIPTION_PATH: &str = "/bevakning/bekrafta";
+pub const UNSUBSCRIBE_PATH: &str = "/bevakning/avsluta";
+
+/// Where the link in the email sent on subscribing confirms the subscription.
+pub fn confirm_subscription_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => CONFIRM_SUBSCRIPTION_PATH,
+        Locale::En => "/en/alerts/confirm",
+    }
+}
+
+/// Where the link in the email sent on subscribing cancels the subscription.
+pub fn unsubscribe_path(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Sv => UNSUBSCRIBE_PATH,
+        Locale::En => "/en/alerts/cancel",
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SubscribeStatus {
+    /// A confirmation email has been sent, unless the address already had a
+    /// subscription for the product.
+    ConfirmationSent,
+    InvalidEmail,
+    RateLimited,
+}
+
+/// How a tracked product behaves when its quantity runs out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub enum StockPolicy {
+    /// Sales stop when the quantity reaches zero.
+    #[default]
+    Stop,
+    /// Sales continue until the quantity reaches `-limit`, the extra units
+    /// ship once restocked.
+    Backorder { limit: u32 },
+    /// The product is made to order and ships at the expected date.
+    Preorder { ship_date: NaiveDate },
+}
+
+impl StockPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockPolicy::Stop => "stop",
+            StockPolicy::Backorder { .. } => "backorder",
+            StockPolicy::Preorder { .. } => "preorder",
+        }
+    }
+}
+
+/// How an ordered line will be fulfilled, decided when the stock is reserved.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Fulfilment {
+    InStock,
+    Backorder,
+    Preorder,
+}
+
+impl Fulfilment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fulfilment::InStock => "stock",
+            Fulfilment::Backorder => "backorder",
+            Fulfilment::Preorder => "preorder",
+        }
+    }
+}
+
+impl From<&str> for Fulfilment {
+    fn from(s: &str) -> Self {
+        match s {
+            "backorder" => Fulfilment::Backorder,
+            "preorder" => Fulfilment::Preorder,
+            _ => Fulfilment::InStock,
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::currency::{Currency, EU_COUNTRIES};
+
+pub use u32 as BasisPoints;
+
+/// The kind of goods a VAT rate depends on. Products keep their Swedish rate,
+/// which tells the category apart as Sweden has a rate for each.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaxCategory {
+    Standard,
+    /// Foodstuffs, 12% in Sweden.
+    Food,
+    /// Books and newspapers, 6% in Sweden.
+    Books,
+    Zero,
+}
+
+/// VAT rates of the EU member states in basis points, as standard, food and
+/// books. Last checked against the EU Commission's rate database for 2025,
+/// where a country has several reduced rates for food the common one is used.
+const RATES: [(&str, [BasisPoints; 3]); 27] = [
+    ("AT", [2000, 1000, 1000]),
+    ("BE", [2100, 600, 600]),
+    ("BG", [2000, 2000, 900]),
+    ("CY", [1900, 500, 500]),
+    ("CZ", [2100, 1200, 0]),
+    ("DE", [1900, 700, 700]),
+    ("DK", [2500, 2500, 2500]),
+    ("EE", [2400, 2400, 1300]),
+    ("ES", [2100, 1000, 400]),
+    ("FI", [2550, 1400, 1400]),
+    ("FR", [2000, 550, 550]),
+    ("GR", [2400, 1300, 600]),
+    ("HR", [2500, 1300, 500]),
+    ("HU", [2700, 1800, 500]),
+    ("IE", [2300, 0, 0]),
+    ("IT", [2200, 1000, 400]),
+    ("LT", [2100, 2100, 900]),
+    ("LU", [1700, 300, 300]),
+    ("LV", [2100, 2100, 1200]),
+    ("MT", [1800, 0, 500]),
+    ("NL", [2100, 900, 900]),
+    ("PL", [2300, 500, 500]),
+    ("PT", [2300, 600, 600]),
+    ("RO", [2100, 1100, 1100]),
+    ("SE", [2500, 1200, 600]),
+    ("SI", [2200, 950, 500]),
+    ("SK", [2300, 1900, 500]),
+];
+
+impl TaxCategory {
+    pub const ALL: [TaxCategory; 4] = [
+        TaxCategory::Standard,
+        TaxCategory::Food,
+        TaxCategory::Books,
+        TaxCategory::Zero,
+    ];
+
+    pub fn from_swedish_rate(percent: u32) -> TaxCategory {
+        match percent {
+            12 => TaxCategory::Food,
+            6 => TaxCategory::Books,
+            0 => TaxCategory::Zero,
+            _ => TaxCategory::Standard,
+        }
+    }
+
+    /// The rate of this category in an EU member state, 0 outside the EU.
+    pub fn rate(self, country: &str) -> BasisPoints {
+        let Some((_, rates)) = RATES.iter().find(|(c, _)| *c == country) else {
+            return 0;
+        };
+        match self {
+            TaxCategory::Standard => rates[0],
+            TaxCategory::Food => rates[1],
+            TaxCategory::Books => rates[2],
+            TaxCategory::Zero => 0,
+        }
+    }
+}
+
+/// The country whose VAT applies to an order shipped to `destination`, `None`
+/// for exports outside the EU which are sold without VAT. Sales to consumers
+/// in other member states carry Swedish VAT until we're registered for the
+/// One Stop Shop (OSS), after which the destination's VAT applies and is
+/// declared quarterly through OSS.
+pub fn vat_country(destination: &str, oss: bool) -> Option<&str> {
+    match destination {
+        "SE" => Some("SE"),
+        c if !EU_COUNTRIES.contains(&c) => None,
+        c if oss => Some(c),
+        _ => Some("SE"),
+    }
+}
+
+/// The rate applied to a product with the given Swedish rate shipped to `destination`.
+pub fn applied_rate(swedish_percent: u32, destination: &str, oss: bool) -> BasisPoints {
+    match vat_country(destination, oss) {
+        Some(country) => TaxCategory::from_swedish_rate(swedish_percent).rate(country),
+        None => 0,
+    }
+}
+
+/// The Swedish rate shipping is taxed at. Shipping follows the goods it
+/// carries, so it gets the rate of the `(Swedish rate, value)` lines making up
+/// most of the order, the higher rate on a tie and 25% for an empty order.
+/// Like a product's rate it is then applied for the destination.
+pub fn shipping_rate(lines: impl IntoIterator<Item = (u32, u32)>) -> u32 {
+    let mut values = std::collections::BTreeMap::<u32, u64>::new();
+    for (rate, value) in lines {
+        *values.entry(rate).or_default() += value as u64;
+    }
+    values
+        .into_iter()
+        .max_by_key(|(rate, value)| (*value, *rate))
+        .map(|(rate, _)| rate)
+        .unwrap_or(25)
+}
+
+/// Writes basis points as a percentage, `25.5%`.
+pub fn format_rate(rate: BasisPoints) -> String {
+    match rate % 100 {
+        0 => format!("{}%", rate / 100),
+        r if r % 10 == 0 => format!("{}.{}%", rate / 100, r / 10),
+        r => format!("{}.{:02}%", rate / 100, r),
+    }
+}
+
+/// Sales declared through OSS in a quarter, per member state of consumption,
+/// rate and the currency the orders were charged in. Amounts are what the
+/// customers paid including VAT, in minor units of that currency. The return
+/// itself is filed in euro, converted at the ECB rate of the quarter's last
+/// day.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OssReportRow {
+    pub country: String,
+    pub rate: BasisPoints,
+    pub currency: Currency,
+    pub orders: u32,
+    pub gross: u64,
+}
+
+/// ECB reference rates as units of each currency per euro, in millionths,
+/// `7.4609` DKK as `7_460_900`.
+pub type EcbRates = BTreeMap<Currency, u32>;
+
+impl OssReportRow {
+    /// The VAT included in `gross`, rounded to whole minor units.
+    pub fn vat(&self) -> u64 {
+        let rate = self.rate as u64;
+        (self.gross * rate + (10_000 + rate) / 2) / (10_000 + rate)
+    }
+
+    pub fn taxable(&self) -> u64 {
+        self.gross - self.vat()
+    }
+
+    /// `minor` units of the row's currency in euro cents, `None` if the ECB
+    /// rate of the currency isn't known.
+    pub fn to_eur(&self, minor: u64, ecb: &EcbRates) -> Option<u64> {
+        if self.currency == Currency::Eur {
+            return Some(minor);
+        }
+        let micros = *ecb.get(&self.currency).filter(|m| **m > 0)? as u64;
+        Some((minor * 1_000_000 + micros / 2) / micros)
+    }
+}
+
+/// Parses an ECB rate written as `DKK=7.4609`, units of the currency per euro.
+pub fn parse_ecb_rate(value: &str) -> Option<(Currency, u32)> {
+    let (code, rate) = value.split_once('=')?;
+    let micros = rate
+        .trim()
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .map(|v| (v * 1_000_000.0).round())
+        .filter(|v| *v >= 1.0 && *v <= u32::MAX as f64)?;
+    Some((Currency::from_code(code.trim())?, micros as u32))
+}
+
+/// Formats minor units with two decimals, the way amounts are entered in returns.
+pub fn format_minor(minor: u64) -> String {
+    format!("{}.{:02}", minor / 100, minor % 100)
+}
+
+/// The OSS report as CSV, one row per member state, rate and currency. The
+/// euro columns are empty for currencies without a rate in `ecb`.
+pub fn oss_report_csv(rows: &[OssReportRow], ecb: &EcbRates) -> String {
+    let mut csv = String::from(
+        "country;vat_rate;currency;orders;sales_incl_vat;taxable_amount;vat;sales_incl_vat_eur;taxable_amount_eur;vat_eur\n",
+    );
+    for row in rows {
+        let eur = |minor| row.to_eur(minor, ecb).map(format_minor).unwrap_or_default();
+        csv += &format!(
+            "{};{};{};{};{};{};{};{};{};{}\n",
+            row.country,
+            format_rate(row.rate).trim_end_matches('%'),
+            row.currency.code(),
+            row.orders,
+            format_minor(row.gross),
+            format_minor(row.taxable()),
+            format_minor(row.vat()),
+            eur(row.gross),
+            eur(row.taxable()),
+            eur(row.vat())
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipping_follows_the_goods() {
+        assert_eq!(shipping_rate([]), 25);
+        assert_eq!(shipping_rate([(6, 300)]), 6);
+        assert_eq!(shipping_rate([(6, 200), (6, 200), (25, 300)]), 6);
+        assert_eq!(shipping_rate([(12, 100), (25, 100)]), 25);
+        assert_eq!(applied_rate(shipping_rate([(6, 300)]), "DE", true), 700);
+        assert_eq!(applied_rate(shipping_rate([(6, 300)]), "DE", false), 600);
+        assert_eq!(applied_rate(shipping_rate([(25, 300)]), "NO", true), 0);
+    }
+
+    fn row(country: &str, rate: BasisPoints, currency: Currency, gross: u64) -> OssReportRow {
+        OssReportRow {
+            country: country.into(),
+            rate,
+            currency,
+            orders: 2,
+            gross,
+        }
+    }
+
+    #[test]
+    fn vat_is_taken_out_of_the_gross_amount() {
+        let de = row("DE", 1900, Currency::Eur, 11_900);
+        assert_eq!(de.vat(), 1_900);
+        assert_eq!(de.taxable(), 10_000);
+        // 100 * 2550 / 12550 = 20.3 rounds down, 1000 * 2550 / 12550 = 203.2 too
+        assert_eq!(row("FI", 2550, Currency::Eur, 100).vat(), 20);
+        assert_eq!(row("FI", 2550, Currency::Eur, 1_000).vat(), 203);
+        // 5 * 1000 / 11000 = 0.45 rounds down, 6 * 1000 / 11000 = 0.55 up
+        assert_eq!(row("AT", 1000, Currency::Eur, 5).vat(), 0);
+        assert_eq!(row("AT", 1000, Currency::Eur, 6).vat(), 1);
+        assert_eq!(row("IE", 0, Currency::Eur, 500).vat(), 0);
+    }
+
+    #[test]
+    fn vat_follows_the_destination_once_registered_for_oss() {
+        assert_eq!(vat_country("SE", false), Some("SE"));
+        assert_eq!(vat_country("SE", true), Some("SE"));
+        assert_eq!(vat_country("DE", false), Some("SE"));
+        assert_eq!(vat_country("DE", true), Some("DE"));
+        assert_eq!(vat_country("NO", true), None);
+        assert_eq!(vat_country("US", false), None);
+
+        assert_eq!(applied_rate(12, "FR", true), 550);
+        assert_eq!(applied_rate(12, "FR", false), 1200);
+        assert_eq!(applied_rate(6, "IE", true), 0);
+        assert_eq!(applied_rate(25, "NO", false), 0);
+        assert_eq!(applied_rate(0, "DE", true), 0);
+    }
+
+    #[test]
+    fn rates_are_written_as_percentages() {
+        assert_eq!(format_rate(2500), "25%");
+        assert_eq!(format_rate(2550), "25.5%");
+        assert_eq!(format_rate(950), "9.5%");
+        assert_eq!(format_rate(1205), "12.05%");
+        assert_eq!(format_rate(0), "0%");
+    }
+
+    #[test]
+    fn other_currencies_are_converted_at_the_ecb_rate() {
+        let ecb = EcbRates::from([(Currency::Dkk, 7_460_900)]);
+        assert_eq!(
+            row("DE", 1900, Currency::Eur, 1234).to_eur(1234, &ecb),
+            Some(1234)
+        );
+        let dk = row("DK", 2500, Currency::Dkk, 74_609);
+        assert_eq!(dk.to_eur(74_609, &ecb), Some(10_000));
+        assert_eq!(dk.to_eur(74_609, &EcbRates::new()), None);
+        let broken = EcbRates::from([(Currency::Dkk, 0)]);
+        assert_eq!(dk.to_eur(74_609, &broken), None);
+
+        assert_eq!(
+            parse_ecb_rate("DKK=7.4609"),
+            Some((Currency::Dkk, 7_460_900))
+        );
+        assert_eq!(
+            parse_ecb_rate("dkk = 7,4609"),
+            Some((Currency::Dkk, 7_460_900))
+        );
+        assert_eq!(parse_ecb_rate("DKK"), None);
+        assert_eq!(parse_ecb_rate("XYZ=1"), None);
+        assert_eq!(parse_ecb_rate("DKK=0"), None);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_country_rate_and_currency() {
+        let ecb = EcbRates::from([(Currency::Dkk, 7_460_900)]);
+        let rows = [
+            row("DE", 1900, Currency::Eur, 11_900),
+            row("DK", 2500, Currency::Dkk, 93_261),
+            row("DK", 2500, Currency::Nok, 12_500),
+        ];
+        assert_eq!(
+            oss_report_csv(&rows, &ecb),
+            "country;vat_rate;currency;orders;sales_incl_vat;taxable_amount;vat;sales_incl_vat_eur;taxable_amount_eur;vat_eur\n\
+            DE;19;EUR;2;119.00;100.00;19.00;119.00;100.00;19.00\n\
+            DK;25;DKK;2;932.61;746.09;186.52;125.00;100.00;25.00\n\
+            DK;25;NOK;2;125.00;100.00;25.00;;;\n"
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::product::ProductId;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncWishlistRequest {
+    /// The wishlist as known by the browser.
+    pub items: Vec<ProductId>,
+    /// Combine with the account's stored wishlist instead of replacing it.
+    pub merge: bool,
+}
+
+/// A product and the number of customer accounts that have it on their wishlist.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WishedProduct {
+    pub product_id: ProductId,
+    pub name: String,
+    pub wishes: u32,
+}
@@ -0,0 +1,59 @@
+//! Signed cookies identifying visitors that haven't logged in.
+
+use std::sync::OnceLock;
+
+use axum::http::{HeaderMap, HeaderValue, header::COOKIE};
+use sjf_config::Secret;
+use time::Duration;
+use tower_sessions::cookie::{Cookie, CookieJar, Key, SameSite};
+use tracing::warn;
+
+static KEY: OnceLock<Key> = OnceLock::new();
+
+/// Uses the configured secret so cookies survive restarts, otherwise a key
+/// that only lives as long as the process.
+pub(crate) fn init(secret: Option<&Secret>) {
+    let key = match secret {
+        // The configuration requires at least the 64 bytes a key is made of
+        Some(secret) => {
+            Key::try_from(secret.expose().as_bytes()).unwrap_or_else(|_| Key::generate())
+        }
+        None => {
+            warn!("ANONYMOUS_COOKIE_SECRET not set, anonymous cookies are lost on restart");
+            Key::generate()
+        }
+    };
+    let _ = KEY.set(key);
+}
+
+fn key() -> &'static Key {
+    KEY.get_or_init(Key::generate)
+}
+
+/// Value of the signed cookie `name`, if present and not tampered with.
+pub fn read(headers: &HeaderMap, name: &str) -> Option<String> {
+    let mut jar = CookieJar::new();
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(|c| c.ok())
+        .for_each(|c| jar.add_original(c.into_owned()));
+
+    jar.signed(key()).get(name).map(|c| c.value().to_owned())
+}
+
+/// A `Set-Cookie` header storing `value` signed under `name`.
+pub fn set(name: &str, value: &str, max_age_days: i64) -> Option<HeaderValue> {
+    let mut jar = CookieJar::new();
+    let cookie = Cookie::build((name.to_owned(), value.to_owned()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(max_age_days));
+    jar.signed_mut(key()).add(cookie);
+
+    let cookie = jar.delta().next()?;
+    HeaderValue::from_str(&cookie.encoded().to_string()).ok()
+}
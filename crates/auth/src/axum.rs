@@ -14,14 +14,14 @@ pub async fn protect_authenticated_routes(
     next: Next,
 ) -> Response {
     let path = request.uri().path();
-    if path.contains("api/auth/") {
-        if auth_session.user.is_none() {
-            return StatusCode::FORBIDDEN.into_response();
-        }
+    let is_admin = auth_session
+        .user
+        .is_some_and(|u| u.role == crate::Role::Admin);
+    if path.contains("api/auth/") && !is_admin {
+        return StatusCode::FORBIDDEN.into_response();
     }
 
-    let response = next.run(request).await;
-    response
+    next.run(request).await
 }
 
 pub fn create_auth_layer()
@@ -1,6 +1,6 @@
+use crate::Role;
 use crate::error::WebauthnError;
 use crate::state::AuthState;
-use axum::routing::get;
 use tower_sessions::Session;
 use tracing::{error, info};
 
@@ -30,13 +30,13 @@ impl User {
         let keys = sjf_db::auth::get_keys(id).await?;
         let keys: Result<Vec<Passkey>, WebauthnError> = keys
             .iter()
-            .map(|pk| minicbor_serde::from_slice(&pk).map_err(|e| e.into()))
+            .map(|pk| minicbor_serde::from_slice(pk).map_err(|e| e.into()))
             .collect();
         keys
     }
 
-    async fn lookup_name(username: &str) -> Result<Option<Self>, WebauthnError> {
-        match sjf_db::auth::lookup_name(username).await? {
+    async fn lookup_name(username: &str, role: Role) -> Result<Option<Self>, WebauthnError> {
+        match sjf_db::auth::lookup_name(username, role).await? {
             Some(id) => Ok(Some(User {
                 id,
                 passkeys: Self::get_passkeys(&id).await?,
@@ -45,15 +45,20 @@ impl User {
         }
     }
 
-    pub(crate) async fn lookup_id(id: &Uuid) -> Result<Option<String>, WebauthnError> {
+    pub(crate) async fn lookup_id(id: &Uuid) -> Result<Option<(String, Role)>, WebauthnError> {
         sjf_db::auth::lookup_id(id).await.map_err(|e| e.into())
     }
 
-    async fn add(id: Uuid, name: String, passkey: Passkey) -> Result<(), WebauthnError> {
+    async fn add(
+        id: Uuid,
+        name: String,
+        role: Role,
+        passkey: Passkey,
+    ) -> Result<(), WebauthnError> {
         let keyid: Vec<u8> = passkey.cred_id().clone().into();
         let passkey: Vec<u8> = minicbor_serde::to_vec(passkey)?;
 
-        sjf_db::auth::add(id, name, keyid, passkey)
+        sjf_db::auth::add(id, name, role, keyid, passkey)
             .await
             .map_err(|e| e.into())
     }
@@ -63,7 +68,7 @@ impl User {
         auth_result: &AuthenticationResult,
     ) -> Result<(), WebauthnError> {
         let keyid: Vec<u8> = auth_result.cred_id().clone().into();
-        let (tx, rawkey) = sjf_db::auth::begin_passkey_update(uuid.clone(), keyid.clone()).await?;
+        let (tx, rawkey) = sjf_db::auth::begin_passkey_update(uuid, keyid.clone()).await?;
         let mut passkey: Passkey = minicbor_serde::from_slice(&rawkey)?;
 
         if let Some(true) = passkey.update_credential(auth_result) {
@@ -89,6 +94,20 @@ pub async fn terminal_challenge(session: Session, username: String) -> Result<()
         .await?)
 }
 
+/// Creates an invite that lets `username` register an admin passkey without
+/// the code written to the log.
+pub async fn create_invite(
+    username: &str,
+    valid_for: std::time::Duration,
+) -> Result<Uuid, WebauthnError> {
+    if username.is_empty() || username.len() > 100 {
+        return Err(WebauthnError::InvalidUsername);
+    }
+    let code = sjf_db::auth::create_invite(username, valid_for).await?;
+    info!("Created invite for '{}'", username);
+    Ok(code)
+}
+
 pub async fn start_register(
     Extension(app_state): Extension<AuthState>,
     session: Session,
@@ -100,24 +119,46 @@ pub async fn start_register(
     let challenge = session.get::<(String, Uuid)>("terminal_challenge").await;
     let _ = session.remove::<(String, Uuid)>("terminal_challenge").await;
 
+    // An invite from `sjf-admin create-invite` works in place of the code in the log
+    let uuid =
+        Uuid::try_parse(&terminal_challenge).map_err(|_| WebauthnError::InvalidChallengeUuid)?;
     match challenge? {
-        Some((name, challenge)) => {
-            let uuid = Uuid::try_parse(&terminal_challenge)
-                .map_err(|_| WebauthnError::InvalidChallengeUuid)?;
-            if challenge == uuid && username == name {
-                Ok(())
-            } else {
-                Err(WebauthnError::InvalidTerminalChallenge)
-            }
-        }
+        Some((name, challenge)) if challenge == uuid && username == name => Ok(()),
+        _ if sjf_db::auth::use_invite(&username, &uuid).await? => Ok(()),
+        Some(_) => Err(WebauthnError::InvalidTerminalChallenge),
         None => Err(WebauthnError::NoTerminalChallenge),
     }?;
 
-    let user = User::lookup_name(&username).await?;
-    let user_unique_id = user
-        .as_ref()
-        .map(|f| f.id.clone())
-        .unwrap_or(Uuid::new_v4());
+    let user = User::lookup_name(&username, Role::Admin).await?;
+    begin_registration(app_state, session, username, Role::Admin, user).await
+}
+
+/// Starts the registration of a new customer account. Unlike admins, customers
+/// don't need a terminal challenge but can't add passkeys to an existing account.
+pub async fn start_customer_register(
+    Extension(app_state): Extension<AuthState>,
+    session: Session,
+    email: String,
+) -> Result<CreationChallengeResponse, WebauthnError> {
+    let email = email.trim().to_lowercase();
+    if email.is_empty() || email.len() > 100 || !email.contains('@') {
+        return Err(WebauthnError::InvalidUsername);
+    }
+    if User::lookup_name(&email, Role::Customer).await?.is_some() {
+        return Err(WebauthnError::UserExists);
+    }
+
+    begin_registration(app_state, session, email, Role::Customer, None).await
+}
+
+async fn begin_registration(
+    app_state: AuthState,
+    session: Session,
+    username: String,
+    role: Role,
+    user: Option<User>,
+) -> Result<CreationChallengeResponse, WebauthnError> {
+    let user_unique_id = user.as_ref().map(|f| f.id).unwrap_or(Uuid::new_v4());
 
     // Remove any previous registrations that may have occured from the session.
     let _ = session.remove_value("reg_state").await;
@@ -132,7 +173,7 @@ pub async fn start_register(
     ) {
         Ok((ccr, reg_state)) => {
             session
-                .insert("reg_state", (username, user_unique_id, reg_state))
+                .insert("reg_state", (username, role, user_unique_id, reg_state))
                 .await
                 .expect("Failed to insert");
             info!("Registration Successful!");
@@ -151,8 +192,10 @@ pub async fn finish_register(
     session: Session,
     reg: RegisterPublicKeyCredential,
 ) -> Result<(), WebauthnError> {
-    let (username, user_unique_id, reg_state) = match session.get("reg_state").await? {
-        Some((username, user_unique_id, reg_state)) => (username, user_unique_id, reg_state),
+    let (username, role, user_unique_id, reg_state) = match session.get("reg_state").await? {
+        Some((username, role, user_unique_id, reg_state)) => {
+            (username, role, user_unique_id, reg_state)
+        }
         None => {
             error!("Failed to get session");
             return Err(WebauthnError::CorruptSession);
@@ -161,27 +204,48 @@ pub async fn finish_register(
 
     let _ = session.remove_value("reg_state").await;
 
-    let res = match app_state
+    match app_state
         .webauthn
         .finish_passkey_registration(&reg, &reg_state)
     {
-        Ok(passkey) => User::add(user_unique_id, username, passkey).await,
+        Ok(passkey) => User::add(user_unique_id, username, role, passkey).await,
         Err(e) => Err(e.into()),
-    };
-    res
+    }
 }
 
 pub async fn start_authentication(
     Extension(app_state): Extension<AuthState>,
     session: Session,
     username: String,
+) -> Result<RequestChallengeResponse, WebauthnError> {
+    begin_authentication(app_state, session, username, Role::Admin).await
+}
+
+pub async fn start_customer_authentication(
+    Extension(app_state): Extension<AuthState>,
+    session: Session,
+    email: String,
+) -> Result<RequestChallengeResponse, WebauthnError> {
+    let email = email.trim().to_lowercase();
+    begin_authentication(app_state, session, email, Role::Customer).await
+}
+
+async fn begin_authentication(
+    app_state: AuthState,
+    session: Session,
+    username: String,
+    role: Role,
 ) -> Result<RequestChallengeResponse, WebauthnError> {
     let _ = session.remove_value("auth_state").await;
 
     info!("Authentication attempt '{}'", username);
-    let user = User::lookup_name(&username)
+    let user = User::lookup_name(&username, role)
         .await?
         .ok_or(WebauthnError::UserNotFound)?;
+    if User::lookup_id(&user.id).await?.is_none() {
+        info!("User '{}' is disabled", username);
+        return Err(WebauthnError::UserNotFound);
+    }
     let allow_credentials = user.passkeys();
     info!(
         "Lookup user '{}'={} with {}passkeys",
@@ -227,7 +291,7 @@ pub async fn finish_authentication(
     {
         Ok(auth_result) => {
             if auth_result.needs_update() {
-                User::update_credential(user_unique_id.clone(), &auth_result).await?;
+                User::update_credential(user_unique_id, &auth_result).await?;
             }
             Ok(user_unique_id)
         }
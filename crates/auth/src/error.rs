@@ -1,7 +1,6 @@
 use std::convert::Infallible;
 
 use thiserror::Error;
-use webauthn_rs::prelude::Uuid;
 
 #[derive(Error, Debug)]
 pub enum WebauthnError {
@@ -19,6 +18,8 @@ pub enum WebauthnError {
     UserNotFound,
     #[error("Invalid username")]
     InvalidUsername,
+    #[error("User already exists")]
+    UserExists,
     #[error("User Has No Credentials")]
     UserHasNoCredentials,
     #[error("Deserialising Session failed: {0}")]
@@ -1,3 +1,4 @@
+pub mod anonymous;
 pub mod axum;
 pub mod endpoints;
 mod error;
@@ -6,12 +7,21 @@ pub mod state;
 use ::axum::async_trait;
 use axum_login::{AuthUser, AuthnBackend, UserId};
 pub use error::WebauthnError as Error;
+pub use sjf_api::account::Role;
+use sjf_config::AuthConfig;
 use webauthn_rs::prelude::Uuid;
 
+/// Sets up the anonymous cookies and the passkey state shared by the endpoints.
+pub fn init(config: &AuthConfig) -> Result<state::AuthState, Error> {
+    anonymous::init(config.anonymous_cookie_secret.as_ref());
+    state::AuthState::new(config)
+}
+
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: Uuid,
     pub name: String,
+    pub role: Role,
 }
 
 impl AuthUser for User {
@@ -48,10 +58,11 @@ impl AuthnBackend for Backend {
     }
 
     async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
-        match crate::endpoints::User::lookup_id(&user_id).await? {
-            Some(name) => Ok(Some(User {
-                id: user_id.clone(),
+        match crate::endpoints::User::lookup_id(user_id).await? {
+            Some((name, role)) => Ok(Some(User {
+                id: *user_id,
                 name,
+                role,
             })),
             None => Ok(None),
         }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use sjf_config::AuthConfig;
 use std::sync::Arc;
 use webauthn_rs::prelude::*;
 
@@ -8,15 +8,13 @@ pub struct AuthState {
 }
 
 impl AuthState {
-    pub fn new() -> Self {
-        let rp_id = dotenvy::var("AUTH_RP_ID").expect("AUTH_RP_ID must be set");
-        let rp_origin = Url::parse(&dotenvy::var("AUTH_RP_URL").expect("AUTH_RP_URL must be set"))
-            .expect("Invalid URL");
-        let builder = WebauthnBuilder::new(&rp_id, &rp_origin).expect("Invalid configuration");
+    pub fn new(config: &AuthConfig) -> Result<Self, crate::Error> {
+        let rp_origin = Url::parse(&config.rp_url).map_err(|_| WebauthnError::Configuration)?;
+        let builder = WebauthnBuilder::new(&config.rp_id, &rp_origin)?;
 
         let builder = builder.rp_name("Axum Webauthn-rs");
-        let webauthn = Arc::new(builder.build().expect("Invalid passkey configuration"));
+        let webauthn = Arc::new(builder.build()?);
 
-        AuthState { webauthn }
+        Ok(AuthState { webauthn })
     }
 }
@@ -0,0 +1,195 @@
+use std::fmt;
+
+use crate::Secret;
+
+/// Where a setting is read from: an environment variable, or `name` in
+/// `[section]` of the configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub env: &'static str,
+    pub section: &'static str,
+    pub name: &'static str,
+}
+
+impl Key {
+    pub(crate) const fn new(env: &'static str, section: &'static str, name: &'static str) -> Self {
+        Key { env, section, name }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ([{}] {})", self.env, self.section, self.name)
+    }
+}
+
+/// A problem with one setting. Values aren't included as they may be secret.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("{0} is not set")]
+    Missing(Key),
+    #[error("{0} is invalid: {1}")]
+    Invalid(Key, String),
+    #[error("Failed to read the configuration file {0}: {1}")]
+    File(String, String),
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration")?;
+        for e in self.0.iter() {
+            write!(f, "\n  - {e}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Looks up an environment variable.
+type Env = Box<dyn Fn(&str) -> Option<String>>;
+
+/// The environment and configuration file, collecting the problems of every
+/// setting read from them.
+pub(crate) struct Source {
+    env: Env,
+    file: toml::Table,
+    errors: Vec<ConfigError>,
+}
+
+impl Source {
+    /// Reads the TOML file named by the environment variable `file_var`, if set.
+    pub(crate) fn open(file_var: &str) -> Self {
+        let mut source = Source {
+            env: Box::new(|name| dotenvy::var(name).ok()),
+            file: toml::Table::new(),
+            errors: vec![],
+        };
+        if let Ok(path) = dotenvy::var(file_var) {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => match text.parse() {
+                    Ok(table) => source.file = table,
+                    Err(e) => source.errors.push(ConfigError::File(path, e.to_string())),
+                },
+                Err(e) => source.errors.push(ConfigError::File(path, e.to_string())),
+            }
+        }
+        source
+    }
+
+    /// A source with `env` as the environment and `file` as the configuration file.
+    #[cfg(test)]
+    pub(crate) fn from_map(env: &[(&str, &str)], file: &str) -> Self {
+        let env: std::collections::HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Source {
+            env: Box::new(move |name| env.get(name).cloned()),
+            file: file.parse().expect("the test file should be valid TOML"),
+            errors: vec![],
+        }
+    }
+
+    /// The environment wins over the file, empty values count as unset.
+    fn raw(&self, key: Key) -> Option<String> {
+        let value = (self.env)(key.env).or_else(|| {
+            match self.file.get(key.section)?.as_table()?.get(key.name)? {
+                toml::Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            }
+        })?;
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_owned())
+    }
+
+    pub(crate) fn is_set(&self, key: Key) -> bool {
+        self.raw(key).is_some()
+    }
+
+    pub(crate) fn optional<T>(
+        &mut self,
+        key: Key,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = self.raw(key)?;
+        parse(&value)
+            .map_err(|reason| self.invalid(key, reason))
+            .ok()
+    }
+
+    pub(crate) fn required<T: Default>(
+        &mut self,
+        key: Key,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> T {
+        if !self.is_set(key) {
+            self.errors.push(ConfigError::Missing(key));
+        }
+        self.optional(key, parse).unwrap_or_default()
+    }
+
+    pub(crate) fn or<T>(
+        &mut self,
+        key: Key,
+        default: T,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> T {
+        self.optional(key, parse).unwrap_or(default)
+    }
+
+    /// Records a problem found by checking settings against each other.
+    pub(crate) fn invalid(&mut self, key: Key, reason: impl Into<String>) {
+        self.errors.push(ConfigError::Invalid(key, reason.into()));
+    }
+
+    pub(crate) fn finish<T>(self, value: T) -> Result<T, ConfigErrors> {
+        match self.errors.is_empty() {
+            true => Ok(value),
+            false => Err(ConfigErrors(self.errors)),
+        }
+    }
+}
+
+pub(crate) fn text(value: &str) -> Result<String, String> {
+    Ok(value.to_owned())
+}
+
+pub(crate) fn secret(value: &str) -> Result<Secret, String> {
+    Ok(Secret::from(value.to_owned()))
+}
+
+/// An absolute http(s) URL, without a trailing slash so that paths can be appended.
+pub(crate) fn url(value: &str) -> Result<String, String> {
+    let url = url::Url::parse(value).map_err(|e| e.to_string())?;
+    match url.scheme() {
+        "http" | "https" => Ok(value.trim_end_matches('/').to_owned()),
+        scheme => Err(format!("expected an http or https URL, not {scheme}")),
+    }
+}
+
+pub(crate) fn email(value: &str) -> Result<String, String> {
+    match value.contains('@') {
+        true => Ok(value.to_owned()),
+        false => Err("expected an email address".into()),
+    }
+}
+
+pub(crate) fn number<const MIN: u32, const MAX: u32>(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(n) if (MIN..=MAX).contains(&n) => Ok(n),
+        _ => Err(format!("expected a number from {MIN} to {MAX}")),
+    }
+}
+
+pub(crate) fn boolean(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err("expected true or false".into()),
+    }
+}
@@ -1,5 +1,5 @@
 SELECT 
-    p.id,p.name,p.price,p.description,p.quantity,p.created,p.updated, image_ids, tax_rate,category
+    p.id,p.name,p.price,p.description,p.quantity,p.created,p.updated, image_ids, tax_rate,category,p.reorder_threshold,p.stock_policy,p.backorder_limit,p.preorder_ship_date,p.sku
 from products p 
 LEFT JOIN 
     (
@@ -9,5 +9,5 @@ LEFT JOIN
         GROUP BY product_id
     ) AS I 
 ON p.id = i.product_id 
-WHERE category = $1
+WHERE category = $1 OR $1 IS NULL
 ORDER BY p.name ASC
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT a.id, a.category, a.name, a.kind, a.unit, a.options
+FROM category_attributes a
+JOIN product_categories_hierarchy h ON h.ancestor = a.category
+JOIN product_categories_hierarchy own ON own.ancestor = a.category AND own.descendant = a.category
+WHERE h.descendant = $1
+ORDER BY own.depth, a.id
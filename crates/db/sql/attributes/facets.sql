@@ -0,0 +1,29 @@
+WITH listed AS (
+    SELECT p.id FROM products p
+    WHERE p.category = $1
+        AND ($2::integer IS NULL OR p.price >= $2)
+        AND ($3::integer IS NULL OR p.price <= $3)
+        AND (NOT $4 OR (p.stock_policy <> 'preorder' AND (p.quantity IS NULL OR p.quantity > 0)))
+),
+selected AS (
+    SELECT * FROM unnest($5::integer[], $6::text[]) AS s(attribute_id, value)
+),
+vals AS (
+    SELECT pa.product_id, pa.attribute_id, pa.value
+    FROM product_attributes pa
+    JOIN listed l ON l.id = pa.product_id
+)
+SELECT v.attribute_id, v.value, count(*) AS "count!"
+FROM vals v
+WHERE NOT EXISTS (
+    -- Another attribute with a selection the product doesn't match
+    SELECT 1 FROM selected s
+    WHERE s.attribute_id <> v.attribute_id
+    AND NOT EXISTS (
+        SELECT 1 FROM vals v2
+        JOIN selected s2 ON s2.attribute_id = v2.attribute_id AND s2.value = v2.value
+        WHERE v2.product_id = v.product_id AND v2.attribute_id = s.attribute_id
+    )
+)
+GROUP BY v.attribute_id, v.value
+ORDER BY v.attribute_id, v.value
@@ -0,0 +1,8 @@
+SELECT a.id, a.category, a.name, a.kind, a.unit, a.options, pa.value
+FROM product_attributes pa
+JOIN category_attributes a ON a.id = pa.attribute_id
+JOIN products p ON p.id = pa.product_id
+JOIN product_categories_hierarchy h ON h.ancestor = a.category AND h.descendant = p.category
+JOIN product_categories_hierarchy own ON own.ancestor = a.category AND own.descendant = a.category
+WHERE pa.product_id = $1
+ORDER BY own.depth, a.id
@@ -0,0 +1,5 @@
+SELECT
+    id, sku, name, price, description, quantity, created, updated, tax_rate, category,
+    reorder_threshold, stock_policy, backorder_limit, preorder_ship_date
+FROM products
+ORDER BY id
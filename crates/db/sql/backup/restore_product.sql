@@ -0,0 +1,3 @@
+INSERT INTO products (sku, name, price, description, quantity, created, updated, tax_rate, category, reorder_threshold, stock_policy, backorder_limit, preorder_ship_date)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+RETURNING id
@@ -0,0 +1,2 @@
+INSERT INTO cart_items(cart_id, product_id, quantity, price)
+SELECT $1, * FROM UNNEST($2::integer[], $3::integer[], $4::integer[])
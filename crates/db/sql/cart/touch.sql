@@ -0,0 +1,3 @@
+INSERT INTO carts(id) VALUES (COALESCE($1, gen_random_uuid()))
+ON CONFLICT (id) DO UPDATE SET updated = CURRENT_TIMESTAMP
+RETURNING id
@@ -1,3 +1,3 @@
-INSERT INTO products  (id,      name,price,description,quantity, created,updated,  category,tax_rate)
-            VALUES    (DEFAULT, $1,  $2,   $3,         $4,       DEFAULT,NOW(),    $5,$6)
-RETURNING id;
\ No newline at end of file
+INSERT INTO products  (id,      name,price,description,quantity, created,updated,  category,tax_rate,reorder_threshold,stock_policy,backorder_limit,preorder_ship_date,sku)
+            VALUES    (DEFAULT, $1,  $2,   $3,         $4,       DEFAULT,NOW(),    $5,$6,$7,$8,$9,$10,$11)
+RETURNING id;
@@ -1,3 +1,2 @@
-
-    INSERT INTO users (id,name,updated) VALUES ($1, $2, current_timestamp AT TIME ZONE 'UTC') 
-    ON CONFLICT(id) DO update SET updated=excluded.updated, name=excluded.name;
\ No newline at end of file
+    INSERT INTO users (id,name,role,updated) VALUES ($1, $2, $3, current_timestamp AT TIME ZONE 'UTC') 
+    ON CONFLICT(id) DO update SET updated=excluded.updated, name=excluded.name;
@@ -0,0 +1,12 @@
+-- The Google product category of each product, from its closest categorised ancestor
+SELECT p.id, (
+    SELECT c.google_category
+    FROM product_categories_hierarchy h
+    JOIN product_categories c ON c.id = h.ancestor
+    JOIN product_categories_hierarchy own ON own.ancestor = c.id AND own.descendant = c.id
+    WHERE h.descendant = p.category AND c.google_category IS NOT NULL
+    ORDER BY own.depth DESC
+    LIMIT 1
+) AS google_category
+FROM products p
+ORDER BY p.id
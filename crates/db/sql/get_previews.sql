@@ -1,12 +1,36 @@
-SELECT product.price, product.id, product.name,"images: _", pv.names FROM product_paths pv
-RIGHT JOIN 
-(
-    SELECT price,id,name, created, category, (i.images) as "images: _" 
-    FROM products p
-    LEFT JOIN  product_image_info i
-    ON p.id = i.product_id 
-    WHERE (category=ANY($1)) 
-) AS product
-ON pv.id = product.category
-ORDER BY created DESC
-LIMIT $2 
+WITH sold AS (
+    SELECT l.product_id, sum(l.quantity) AS quantity
+    FROM order_lines l
+    JOIN orders o ON o.id = l.order_id
+    WHERE o.created > CURRENT_TIMESTAMP - make_interval(days => $2)
+    GROUP BY l.product_id
+)
+SELECT p.price, p.id, p.name, i.images AS "images: _", pv.names, count(*) OVER () AS total
+FROM products p
+LEFT JOIN product_image_info i ON p.id = i.product_id
+LEFT JOIN product_paths pv ON pv.id = p.category
+LEFT JOIN sold s ON s.product_id = p.id
+WHERE (p.category = ANY($1))
+    AND ($3::integer IS NULL OR p.price >= $3)
+    AND ($4::integer IS NULL OR p.price <= $4)
+    AND (NOT $5 OR (p.stock_policy <> 'preorder' AND (p.quantity IS NULL OR p.quantity > 0)))
+    AND NOT EXISTS (
+        -- An attribute with a selection the product doesn't match
+        SELECT 1 FROM unnest($9::integer[]) AS s(attribute_id)
+        WHERE NOT EXISTS (
+            SELECT 1 FROM product_attributes pa
+            JOIN unnest($9::integer[], $10::text[]) AS f(attribute_id, value)
+                ON f.attribute_id = pa.attribute_id AND f.value = pa.value
+            WHERE pa.product_id = p.id AND pa.attribute_id = s.attribute_id
+        )
+    )
+ORDER BY
+    CASE WHEN $6 = 'price_asc' THEN p.price END ASC,
+    CASE WHEN $6 = 'price_desc' THEN p.price END DESC,
+    CASE WHEN $6 = 'name' THEN p.name END ASC,
+    CASE WHEN $6 = 'popularity' THEN coalesce(s.quantity, 0) END DESC,
+    CASE WHEN $6 = 'random' THEN random() END,
+    p.created DESC,
+    p.id DESC
+LIMIT $7
+OFFSET $8
@@ -1,8 +1,8 @@
 
-SELECT product.*, pv.names FROM product_paths pv
+SELECT product.price, product.id, product.name, product.description, product.quantity, product.stock_policy, product.backorder_limit, product.preorder_ship_date, product."images: _", pv.names FROM product_paths pv
 RIGHT JOIN 
 (
-    SELECT price,id,name,description,category,quantity, (i.images) as "images: _"  FROM products p
+    SELECT price,id,name,description,category,quantity,stock_policy,backorder_limit,preorder_ship_date, (i.images) as "images: _"  FROM products p
     LEFT JOIN  product_image_info i
     ON p.id = i.product_id 
     WHERE (id = $1) LIMIT 1
@@ -0,0 +1,6 @@
+UPDATE jobs SET
+    status = 'dead',
+    finished = CURRENT_TIMESTAMP,
+    locked_until = NULL,
+    last_error = 'Stopped without finishing'
+WHERE status = 'running' AND locked_until < CURRENT_TIMESTAMP AND attempts >= max_attempts
@@ -0,0 +1,14 @@
+UPDATE jobs SET
+    status = 'running',
+    attempts = attempts + 1,
+    locked_until = $3
+WHERE id IN (
+    SELECT id FROM jobs
+    WHERE kind = ANY($1)
+        AND ((status = 'pending' AND run_at <= CURRENT_TIMESTAMP)
+            OR (status = 'running' AND locked_until < CURRENT_TIMESTAMP AND attempts < max_attempts))
+    ORDER BY run_at, id
+    LIMIT $2
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING id, kind, payload, data, attempts, max_attempts
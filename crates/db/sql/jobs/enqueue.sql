@@ -0,0 +1,9 @@
+INSERT INTO jobs(kind, payload, data, once, max_attempts, run_at)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (kind) WHERE once AND ((status = 'pending') OR (status = 'running')) DO UPDATE SET
+    run_at = CASE
+        WHEN jobs.status = 'running' AND NOT jobs.rerun THEN EXCLUDED.run_at
+        ELSE LEAST(jobs.run_at, EXCLUDED.run_at)
+    END,
+    rerun = jobs.rerun OR jobs.status = 'running'
+RETURNING id
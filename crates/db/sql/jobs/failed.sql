@@ -0,0 +1,8 @@
+UPDATE jobs SET
+    status = CASE WHEN attempts >= max_attempts THEN 'dead' ELSE 'pending' END,
+    run_at = CASE WHEN rerun THEN LEAST(run_at, $3) ELSE $3 END,
+    finished = CASE WHEN attempts >= max_attempts THEN CURRENT_TIMESTAMP END,
+    locked_until = NULL,
+    last_error = $4
+WHERE id = $1 AND attempts = $2 AND status = 'running'
+RETURNING status
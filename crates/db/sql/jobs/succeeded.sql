@@ -0,0 +1,13 @@
+UPDATE jobs SET
+    status = CASE WHEN rerun OR $3::timestamptz IS NOT NULL THEN 'pending' ELSE 'done' END,
+    run_at = CASE
+        WHEN rerun THEN LEAST(run_at, COALESCE($3, run_at))
+        ELSE COALESCE($3, run_at)
+    END,
+    attempts = CASE WHEN rerun OR $3::timestamptz IS NOT NULL THEN 0 ELSE attempts END,
+    data = CASE WHEN rerun OR $3::timestamptz IS NOT NULL THEN data END,
+    finished = CASE WHEN rerun OR $3::timestamptz IS NOT NULL THEN NULL ELSE CURRENT_TIMESTAMP END,
+    rerun = false,
+    locked_until = NULL,
+    last_error = NULL
+WHERE id = $1 AND attempts = $2 AND status = 'running'
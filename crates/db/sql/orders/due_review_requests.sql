@@ -0,0 +1,10 @@
+SELECT o.id, o.email AS "email!", o.locale,
+    array_agg(l.id ORDER BY l.name) AS "line_ids!",
+    array_agg(l.name ORDER BY l.name) AS "names!"
+FROM orders o
+JOIN order_lines l ON l.order_id = o.id
+JOIN products p ON p.id = l.product_id
+WHERE o.email IS NOT NULL
+    AND o.review_requested IS NULL
+    AND o.delivered <= CURRENT_TIMESTAMP - make_interval(days => $1)
+GROUP BY o.id
@@ -0,0 +1,12 @@
+SELECT o.id, o.vat_country AS "country!", l.tax_rate AS "rate!", o.currency, o.sek_micros,
+    l.price, l.quantity
+FROM orders o
+JOIN order_lines l ON l.order_id = o.id
+WHERE o.created >= $1 AND o.created < $2
+    AND o.vat_country <> 'SE' AND l.tax_rate IS NOT NULL
+UNION ALL
+SELECT o.id, o.vat_country, o.shipping_tax_rate, o.currency, o.sek_micros, o.shipping, 1
+FROM orders o
+WHERE o.created >= $1 AND o.created < $2
+    AND o.vat_country <> 'SE' AND o.shipping > 0
+ORDER BY 1
@@ -0,0 +1,10 @@
+SELECT o.id, o.email, o.created, o.delivered, o.currency, o.sek_micros,
+    array_agg(l.product_id ORDER BY l.name) AS "product_ids!: Vec<Option<i32>>",
+    array_agg(l.name ORDER BY l.name) AS "names!",
+    array_agg(l.quantity ORDER BY l.name) AS "quantities!",
+    array_agg(l.price ORDER BY l.name) AS "prices!"
+FROM orders o
+JOIN order_lines l ON l.order_id = o.id
+GROUP BY o.id
+ORDER BY o.created DESC
+LIMIT $1
\ No newline at end of file
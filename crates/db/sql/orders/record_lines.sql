@@ -0,0 +1,6 @@
+INSERT INTO order_lines(order_id, product_id, name, quantity, price, product_tax_rate)
+SELECT $1, r.product_id, p.name, r.quantity, p.price, p.tax_rate
+FROM product_reservations r
+JOIN products p ON p.id = r.product_id
+WHERE r.reservation_id = $1
+ON CONFLICT (order_id, product_id) DO NOTHING
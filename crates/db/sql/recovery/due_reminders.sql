@@ -0,0 +1,12 @@
+SELECT a.id, a.email, a.locale, a.product_ids, a.quantities
+FROM abandoned_checkouts a
+WHERE a.reminder_sent IS NULL
+    AND a.recovered IS NULL
+    AND a.created <= CURRENT_TIMESTAMP - make_interval(mins => $1)
+    AND a.created > CURRENT_TIMESTAMP - make_interval(mins => $1) - make_interval(days => $2)
+    AND NOT EXISTS (SELECT 1 FROM checkout_reminder_opt_outs o WHERE o.email = a.email)
+    -- Only the latest abandoned checkout of each customer is worth a reminder
+    AND NOT EXISTS (
+        SELECT 1 FROM abandoned_checkouts b
+        WHERE b.email = a.email AND b.created > a.created
+    )
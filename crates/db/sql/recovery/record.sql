@@ -0,0 +1,9 @@
+INSERT INTO abandoned_checkouts(reservation_id, email, product_ids, quantities, value, locale)
+SELECT $1, $2, array_agg(r.product_id ORDER BY r.product_id), array_agg(r.quantity ORDER BY r.product_id), sum(r.quantity * p.price),
+    min(o.locale)
+FROM product_reservations r
+JOIN products p ON p.id = r.product_id
+JOIN pending_orders o ON o.id = r.reservation_id
+WHERE r.reservation_id = $1
+HAVING count(*) > 0
+ON CONFLICT (reservation_id) DO NOTHING
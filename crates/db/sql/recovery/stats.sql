@@ -0,0 +1,8 @@
+SELECT
+    count(*) AS "abandoned!",
+    count(reminder_sent) AS "reminded!",
+    count(clicked) AS "clicked!",
+    count(recovered) AS "recovered!",
+    COALESCE(sum(value) FILTER (WHERE recovered IS NOT NULL), 0)::bigint AS "recovered_value!"
+FROM abandoned_checkouts
+WHERE created > CURRENT_TIMESTAMP - make_interval(days => $1)
@@ -0,0 +1,18 @@
+WITH candidates AS (
+    SELECT related_id, 0 AS source, position AS rank FROM related_products WHERE product_id = $1
+    UNION ALL
+    SELECT related_id, 1 AS source, -orders AS rank FROM co_purchases WHERE product_id = $1
+),
+ranked AS (
+    SELECT DISTINCT ON (related_id) related_id, source, rank
+    FROM candidates
+    ORDER BY related_id, source, rank
+)
+SELECT p.price, p.id, p.name, i.images AS "images: _", pv.names
+FROM ranked r
+JOIN products p ON p.id = r.related_id
+LEFT JOIN product_image_info i ON p.id = i.product_id
+LEFT JOIN product_paths pv ON pv.id = p.category
+WHERE p.stock_policy <> 'stop' OR p.quantity IS NULL OR p.quantity > 0
+ORDER BY r.source, r.rank
+LIMIT $2
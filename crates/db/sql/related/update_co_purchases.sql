@@ -0,0 +1,7 @@
+INSERT INTO co_purchases(product_id, related_id, orders)
+SELECT a.product_id, b.product_id, count(DISTINCT a.order_id)
+FROM order_lines a
+JOIN order_lines b ON b.order_id = a.order_id AND b.product_id <> a.product_id
+JOIN orders o ON o.id = a.order_id
+WHERE o.created > CURRENT_TIMESTAMP - make_interval(days => $1)
+GROUP BY a.product_id, b.product_id
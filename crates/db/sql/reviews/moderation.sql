@@ -0,0 +1,5 @@
+SELECT r.id, r.product_id, p.name AS product_name, r.rating, r.text, r.author, r.created, r.reply, r.status
+FROM reviews r
+JOIN products p ON p.id = r.product_id
+WHERE r.status = 'pending' OR r.created > CURRENT_TIMESTAMP - make_interval(days => $1)
+ORDER BY (r.status = 'pending') DESC, r.created DESC
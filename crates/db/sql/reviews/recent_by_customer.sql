@@ -0,0 +1,7 @@
+SELECT count(*) AS "count!" FROM reviews r
+JOIN order_lines l ON l.id = r.order_line
+JOIN orders o ON o.id = l.order_id
+WHERE o.email = (
+    SELECT o2.email FROM order_lines l2 JOIN orders o2 ON o2.id = l2.order_id WHERE l2.id = $1
+)
+AND r.created > CURRENT_TIMESTAMP - INTERVAL '1 day'
@@ -0,0 +1,3 @@
+INSERT INTO reviews(order_line, product_id, rating, text, author)
+SELECT l.id, l.product_id, $2, $3, $4 FROM order_lines l WHERE l.id = $1
+ON CONFLICT (order_line) DO NOTHING
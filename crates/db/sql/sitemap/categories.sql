@@ -0,0 +1,7 @@
+-- Categories with the latest product change anywhere below them
+SELECT pv.names, max(p.updated) AS updated
+FROM product_paths pv
+JOIN product_categories_hierarchy h ON h.ancestor = pv.id
+LEFT JOIN products p ON p.category = h.descendant
+GROUP BY pv.id, pv.names
+ORDER BY pv.names
@@ -0,0 +1,5 @@
+SELECT p.price, p.id, p.name, i.images AS "images: _", pv.names, p.updated
+FROM products p
+LEFT JOIN product_image_info i ON p.id = i.product_id
+JOIN product_paths pv ON pv.id = p.category
+ORDER BY p.id
@@ -0,0 +1,8 @@
+SELECT
+    CASE WHEN quantity <= 0 THEN 'sold_out' ELSE 'low' END AS "level!",
+    COUNT(*) AS "count!"
+FROM products
+WHERE
+    quantity IS NOT NULL
+    AND (quantity <= 0 OR quantity < reorder_threshold)
+GROUP BY 1
@@ -0,0 +1,7 @@
+UPDATE stock_alerts a
+    SET level = 'low'
+FROM products p
+WHERE
+    a.product_id = p.id
+    AND a.level = 'sold_out'
+    AND p.quantity > 0
@@ -0,0 +1,7 @@
+SELECT s.id, s.email, s.locale, p.id AS product_id, p.name, pv.names AS "category_name!"
+FROM stock_subscriptions s
+JOIN products p ON p.id = s.product_id
+JOIN product_paths pv ON pv.id = p.category
+WHERE
+    s.confirmed
+    AND (p.quantity IS NULL OR p.quantity > 0)
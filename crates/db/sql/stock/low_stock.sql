@@ -0,0 +1,7 @@
+SELECT
+    p.id, p.name, p.quantity AS "quantity!", p.reorder_threshold
+FROM products p
+WHERE
+    p.quantity IS NOT NULL
+    AND (p.quantity <= 0 OR p.quantity < p.reorder_threshold)
+ORDER BY p.quantity ASC, p.name ASC
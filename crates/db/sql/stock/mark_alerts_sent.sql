@@ -0,0 +1,3 @@
+INSERT INTO stock_alerts (product_id, level, notified)
+SELECT product_id, level, NOW() FROM UNNEST($1::integer[], $2::varchar[]) AS t(product_id, level)
+ON CONFLICT (product_id) DO UPDATE SET level = EXCLUDED.level, notified = EXCLUDED.notified
@@ -0,0 +1,9 @@
+SELECT
+    p.id, p.name, p.quantity AS "quantity!", p.reorder_threshold
+FROM products p
+LEFT JOIN stock_alerts a ON a.product_id = p.id
+WHERE
+    p.quantity IS NOT NULL
+    AND (p.quantity <= 0 OR p.quantity < p.reorder_threshold)
+    AND (a.product_id IS NULL OR (p.quantity <= 0 AND a.level <> 'sold_out'))
+ORDER BY p.quantity ASC, p.name ASC
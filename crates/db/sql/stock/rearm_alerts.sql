@@ -0,0 +1,8 @@
+DELETE FROM stock_alerts a
+USING products p
+WHERE
+    a.product_id = p.id
+    AND (
+        p.quantity IS NULL
+        OR (p.quantity > 0 AND (p.reorder_threshold IS NULL OR p.quantity >= p.reorder_threshold))
+    )
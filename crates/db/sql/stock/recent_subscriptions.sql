@@ -0,0 +1,2 @@
+SELECT COUNT(*) AS "count!" FROM stock_subscriptions
+WHERE email = $1 AND confirmation_sent > NOW() - interval '1 day'
@@ -0,0 +1,6 @@
+INSERT INTO stock_subscriptions (product_id, email, locale) VALUES ($1, $2, $3)
+ON CONFLICT (product_id, email) DO UPDATE SET confirmation_sent = NOW(), locale = excluded.locale
+WHERE
+    stock_subscriptions.confirmed = false
+    AND stock_subscriptions.confirmation_sent < NOW() - interval '10 minutes'
+RETURNING id
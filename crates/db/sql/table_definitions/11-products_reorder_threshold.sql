@@ -0,0 +1 @@
+ALTER TABLE products ADD COLUMN IF NOT EXISTS reorder_threshold integer CHECK ((reorder_threshold IS NULL) OR (reorder_threshold >= 0));
\ No newline at end of file
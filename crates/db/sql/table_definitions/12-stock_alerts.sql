@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS stock_alerts(
+    product_id integer NOT NULL,
+    level varchar(16) NOT NULL,
+    notified timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(product_id),
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT known_level CHECK ((level = 'low') OR (level = 'sold_out'))
+);
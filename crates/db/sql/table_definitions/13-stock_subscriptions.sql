@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS stock_subscriptions(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id integer NOT NULL,
+    email varchar(254) NOT NULL,
+    confirmed boolean NOT NULL DEFAULT false,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    confirmation_sent timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT unique_product_email UNIQUE (product_id, email)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE products
+    ADD COLUMN IF NOT EXISTS stock_policy varchar(16) NOT NULL DEFAULT 'stop' CHECK ((stock_policy = 'stop') OR (stock_policy = 'backorder') OR (stock_policy = 'preorder')),
+    ADD COLUMN IF NOT EXISTS backorder_limit integer NOT NULL DEFAULT 0 CHECK (backorder_limit >= 0),
+    ADD COLUMN IF NOT EXISTS preorder_ship_date date;
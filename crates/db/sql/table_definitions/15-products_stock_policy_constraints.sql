@@ -0,0 +1,11 @@
+ALTER TABLE products
+    DROP CONSTRAINT IF EXISTS positive_quantity,
+    DROP CONSTRAINT IF EXISTS stock_policy_quantity,
+    DROP CONSTRAINT IF EXISTS preorder_has_ship_date,
+    ADD CONSTRAINT stock_policy_quantity CHECK (
+        (quantity IS NULL)
+        OR (quantity >= 0)
+        OR ((stock_policy = 'backorder') AND (quantity >= -backorder_limit))
+        OR (stock_policy = 'preorder')
+    ),
+    ADD CONSTRAINT preorder_has_ship_date CHECK ((stock_policy <> 'preorder') OR (preorder_ship_date IS NOT NULL));
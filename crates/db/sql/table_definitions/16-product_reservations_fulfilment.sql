@@ -0,0 +1,2 @@
+ALTER TABLE product_reservations
+    ADD COLUMN IF NOT EXISTS fulfilment varchar(16) NOT NULL DEFAULT 'stock' CHECK ((fulfilment = 'stock') OR (fulfilment = 'backorder') OR (fulfilment = 'preorder'));
@@ -0,0 +1 @@
+ALTER TABLE pending_orders ADD COLUMN IF NOT EXISTS expires_at timestamp with time zone;
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS carts(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    updated timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
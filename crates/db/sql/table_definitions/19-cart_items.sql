@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS cart_items(
+    cart_id UUID NOT NULL,
+    product_id integer NOT NULL,
+    quantity integer NOT NULL CHECK (quantity > 0),
+    price integer NOT NULL,
+    PRIMARY KEY (cart_id, product_id),
+    CONSTRAINT cart_id_fkey FOREIGN key(cart_id) REFERENCES carts(id) ON DELETE CASCADE,
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS abandoned_checkouts(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reservation_id UUID NOT NULL UNIQUE,
+    email varchar(254) NOT NULL,
+    product_ids integer[] NOT NULL,
+    quantities integer[] NOT NULL,
+    value integer NOT NULL,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reminder_sent timestamp with time zone,
+    clicked timestamp with time zone,
+    recovered_by UUID,
+    recovered timestamp with time zone
+);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS checkout_reminder_opt_outs(
+    email varchar(254) PRIMARY KEY,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
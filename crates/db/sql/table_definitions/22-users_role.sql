@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS role varchar(16) NOT NULL DEFAULT 'admin' CHECK ((role = 'admin') OR (role = 'customer'));
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS wishlist_items(
+    user_id UUID NOT NULL,
+    product_id integer NOT NULL,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, product_id),
+    CONSTRAINT user_id_fkey FOREIGN key(user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE
+);
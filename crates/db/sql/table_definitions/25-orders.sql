@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS orders(
+    id UUID PRIMARY KEY,
+    email varchar(254),
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered timestamp with time zone,
+    review_requested timestamp with time zone
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS order_lines(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL,
+    product_id integer NOT NULL,
+    quantity integer NOT NULL CHECK (quantity > 0),
+    price integer NOT NULL,
+    CONSTRAINT order_id_fkey FOREIGN key(order_id) REFERENCES orders(id) ON DELETE CASCADE,
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT unique_order_product UNIQUE (order_id, product_id)
+);
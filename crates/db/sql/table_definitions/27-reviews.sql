@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS reviews(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_line UUID NOT NULL UNIQUE,
+    product_id integer NOT NULL,
+    rating smallint NOT NULL CHECK ((rating >= 1) AND (rating <= 5)),
+    text text NOT NULL,
+    author varchar(100) NOT NULL,
+    status varchar(16) NOT NULL DEFAULT 'pending' CHECK ((status = 'pending') OR (status = 'approved') OR (status = 'rejected')),
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reply text,
+    replied timestamp with time zone,
+    CONSTRAINT order_line_fkey FOREIGN key(order_line) REFERENCES order_lines(id) ON DELETE CASCADE,
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS related_products(
+    product_id integer NOT NULL,
+    related_id integer NOT NULL,
+    position integer NOT NULL DEFAULT 0,
+    PRIMARY KEY (product_id, related_id),
+    CHECK (product_id <> related_id),
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT related_id_fkey FOREIGN key(related_id) REFERENCES products(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS co_purchases(
+    product_id integer NOT NULL,
+    related_id integer NOT NULL,
+    orders integer NOT NULL,
+    PRIMARY KEY (product_id, related_id),
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT related_id_fkey FOREIGN key(related_id) REFERENCES products(id) ON DELETE CASCADE
+);
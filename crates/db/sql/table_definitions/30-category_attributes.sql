@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS category_attributes(
+    id integer GENERATED ALWAYS AS IDENTITY NOT NULL,
+    category integer NOT NULL,
+    name varchar(100) NOT NULL,
+    kind varchar(16) NOT NULL CHECK ((kind = 'text') OR (kind = 'number') OR (kind = 'enum')),
+    unit varchar(16) NOT NULL DEFAULT '',
+    options text[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY(id),
+    CONSTRAINT unique_category_attribute UNIQUE (category, name),
+    CONSTRAINT category_fkey FOREIGN key(category) REFERENCES product_categories(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS product_attributes(
+    product_id integer NOT NULL,
+    attribute_id integer NOT NULL,
+    value text NOT NULL,
+    PRIMARY KEY (product_id, attribute_id),
+    CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT attribute_id_fkey FOREIGN key(attribute_id) REFERENCES category_attributes(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE product_categories ADD COLUMN IF NOT EXISTS google_category integer CHECK ((google_category IS NULL) OR (google_category > 0));
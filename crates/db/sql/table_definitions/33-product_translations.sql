@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS product_translations (
+    product_id integer NOT NULL,
+    locale text NOT NULL,
+    name character varying(100) NOT NULL,
+    description text NOT NULL DEFAULT '',
+    PRIMARY KEY (product_id, locale),
+    CONSTRAINT product_fkey FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    CONSTRAINT translated_locale CHECK (locale IN ('en'))
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS category_translations (
+    category_id integer NOT NULL,
+    locale text NOT NULL,
+    name character varying(255) NOT NULL,
+    PRIMARY KEY (category_id, locale),
+    CONSTRAINT category_fkey FOREIGN KEY (category_id) REFERENCES product_categories(id) ON DELETE CASCADE,
+    CONSTRAINT translated_locale CHECK (locale IN ('en'))
+);
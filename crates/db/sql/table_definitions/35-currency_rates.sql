@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS currency_rates (
+    currency varchar(3) PRIMARY KEY,
+    sek_micros integer NOT NULL CHECK (sek_micros > 0),
+    updated timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT foreign_currency CHECK (currency IN ('EUR', 'NOK', 'DKK'))
+);
@@ -0,0 +1,3 @@
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS currency varchar(3) NOT NULL DEFAULT 'SEK',
+    ADD COLUMN IF NOT EXISTS sek_micros integer NOT NULL DEFAULT 1000000 CHECK (sek_micros > 0);
@@ -0,0 +1,5 @@
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS country varchar(2),
+    ADD COLUMN IF NOT EXISTS vat_country varchar(2),
+    ADD COLUMN IF NOT EXISTS shipping integer NOT NULL DEFAULT 0 CHECK (shipping >= 0),
+    ADD COLUMN IF NOT EXISTS shipping_tax_rate integer NOT NULL DEFAULT 2500 CHECK (shipping_tax_rate >= 0);
@@ -0,0 +1 @@
+ALTER TABLE order_lines ADD COLUMN IF NOT EXISTS tax_rate integer CHECK ((tax_rate IS NULL) OR (tax_rate >= 0));
@@ -0,0 +1 @@
+ALTER TABLE products ADD COLUMN IF NOT EXISTS sku varchar(64) UNIQUE CHECK ((sku IS NULL) OR (sku <> ''));
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS admin_invites(
+    code UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name varchar(100) NOT NULL,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires timestamp with time zone NOT NULL
+);
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS jobs(
+    id bigserial PRIMARY KEY,
+    kind varchar(64) NOT NULL,
+    payload jsonb NOT NULL,
+    data bytea,
+    status varchar(16) NOT NULL DEFAULT 'pending' CHECK ((status = 'pending') OR (status = 'running') OR (status = 'done') OR (status = 'dead')),
+    once boolean NOT NULL DEFAULT false,
+    rerun boolean NOT NULL DEFAULT false,
+    attempts integer NOT NULL DEFAULT 0,
+    max_attempts integer NOT NULL,
+    run_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    locked_until timestamp with time zone,
+    last_error text,
+    created timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished timestamp with time zone
+);
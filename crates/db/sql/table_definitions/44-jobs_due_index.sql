@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS jobs_due_index ON jobs (run_at) WHERE (status = 'pending') OR (status = 'running');
@@ -0,0 +1 @@
+CREATE UNIQUE INDEX IF NOT EXISTS jobs_once_index ON jobs (kind) WHERE once AND ((status = 'pending') OR (status = 'running'));
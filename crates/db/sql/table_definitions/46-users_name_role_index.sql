@@ -0,0 +1,16 @@
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_indexes WHERE indexname = 'users_name_role_index') THEN
+        -- Names used to be unique per shop only by convention. The user that
+        -- logged in last keeps a duplicated name, the others get their id
+        -- appended so that they can still be told apart and renamed
+        UPDATE users u SET name = left(u.name, 63) || ' ' || u.id
+        FROM (
+            SELECT id, row_number() OVER (PARTITION BY name, role ORDER BY last_login DESC, created) AS n
+            FROM users
+            WHERE name IS NOT NULL
+        ) d
+        WHERE u.id = d.id AND d.n > 1;
+        CREATE UNIQUE INDEX users_name_role_index ON users (name, role);
+    END IF;
+END $$;
@@ -0,0 +1 @@
+ALTER TABLE order_lines ADD COLUMN IF NOT EXISTS name varchar(100);
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE order_lines l SET name = p.name FROM products p WHERE p.id = l.product_id AND l.name IS NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE order_lines ALTER COLUMN name SET NOT NULL;
\ No newline at end of file
@@ -0,0 +1,8 @@
+DO $$
+BEGIN
+    -- Sales are kept when their product is deleted, the line has its name
+    IF EXISTS (SELECT 1 FROM pg_constraint WHERE conrelid = 'order_lines'::regclass AND conname = 'product_id_fkey' AND confdeltype <> 'n') THEN
+        ALTER TABLE order_lines DROP CONSTRAINT product_id_fkey;
+        ALTER TABLE order_lines ADD CONSTRAINT product_id_fkey FOREIGN key(product_id) REFERENCES products(id) ON DELETE SET NULL;
+    END IF;
+END $$;
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE SEQUENCE IF NOT EXISTS view_generation;
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE TABLE IF NOT EXISTS schema_steps(name text PRIMARY KEY, applied timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP);
@@ -0,0 +1,8 @@
+DO $$
+BEGIN
+    -- The old combined notifications job is dropped once, its checks are separate jobs now
+    INSERT INTO schema_steps(name) VALUES ('jobs_split_notifications') ON CONFLICT DO NOTHING;
+    IF FOUND THEN
+        DELETE FROM jobs WHERE kind = 'notifications' AND status <> 'done';
+    END IF;
+END $$;
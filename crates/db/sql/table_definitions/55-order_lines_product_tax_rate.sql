@@ -0,0 +1 @@
+ALTER TABLE order_lines ADD COLUMN IF NOT EXISTS product_tax_rate integer CHECK ((product_tax_rate IS NULL) OR (product_tax_rate >= 0));
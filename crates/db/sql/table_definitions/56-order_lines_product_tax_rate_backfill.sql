@@ -0,0 +1 @@
+UPDATE order_lines l SET product_tax_rate = p.tax_rate FROM products p WHERE p.id = l.product_id AND l.product_tax_rate IS NULL;
@@ -0,0 +1 @@
+ALTER TABLE product_reservations ADD COLUMN IF NOT EXISTS backordered integer NOT NULL DEFAULT 0 CHECK ((backordered >= 0) AND (backordered <= quantity));
@@ -0,0 +1 @@
+ALTER TABLE pending_orders ADD COLUMN IF NOT EXISTS locale text NOT NULL DEFAULT 'sv';
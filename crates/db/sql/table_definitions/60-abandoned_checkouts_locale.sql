@@ -0,0 +1 @@
+ALTER TABLE abandoned_checkouts ADD COLUMN IF NOT EXISTS locale text NOT NULL DEFAULT 'sv';
@@ -0,0 +1 @@
+ALTER TABLE stock_subscriptions ADD COLUMN IF NOT EXISTS locale text NOT NULL DEFAULT 'sv';
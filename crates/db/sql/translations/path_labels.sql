@@ -0,0 +1,8 @@
+-- The names along a category path, translated where there is a translation
+SELECT coalesce(t.name, c.name) AS "name!"
+FROM product_paths pv
+CROSS JOIN unnest(pv.ids) WITH ORDINALITY AS p(id, position)
+JOIN product_categories c ON c.id = p.id
+LEFT JOIN category_translations t ON t.category_id = c.id AND t.locale = $2
+WHERE pv.names = $1
+ORDER BY p.position
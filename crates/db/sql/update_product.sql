@@ -1,9 +1,14 @@
-
 UPDATE products  
     SET  
         name=$1,
         price=$2,
         description=$3,
         quantity=$4, 
+        reorder_threshold=$5,
+        stock_policy=$6,
+        backorder_limit=$7,
+        preorder_ship_date=$8,
+        tax_rate=$10,
+        sku=$11,
         updated=NOW()
-    where id = $5;
\ No newline at end of file
+    where id = $9;
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT p.id, p.name, count(*) AS "wishes!"
+FROM wishlist_items w
+JOIN products p ON p.id = w.product_id
+GROUP BY p.id, p.name
+ORDER BY count(*) DESC, p.name
+LIMIT $1
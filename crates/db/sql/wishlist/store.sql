@@ -0,0 +1,3 @@
+INSERT INTO wishlist_items(user_id, product_id)
+SELECT $1, p.id FROM products p WHERE p.id = ANY($2)
+ON CONFLICT (user_id, product_id) DO NOTHING
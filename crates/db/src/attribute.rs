@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::postgres::POOL;
+use sjf_api::attribute::{AttributeDefinition, AttributeKind, Facet, Spec};
+use sjf_api::product::PreviewFilter;
+use sqlx::{PgExecutor, Postgres, Transaction, query, query_file};
+
+fn definition(
+    id: i32,
+    category: i32,
+    name: String,
+    kind: &str,
+    unit: String,
+    options: Vec<String>,
+) -> AttributeDefinition {
+    AttributeDefinition {
+        id: id as u32,
+        category: category as u32,
+        name,
+        kind: match kind {
+            "number" => AttributeKind::Number { unit },
+            "enum" => AttributeKind::Enum { options },
+            _ => AttributeKind::Text,
+        },
+    }
+}
+
+/// The attributes products in `category` can have, including those defined
+/// on its parent categories, outermost category first.
+pub async fn definitions(category: u32) -> Result<Vec<AttributeDefinition>, sqlx::Error> {
+    definitions_in(POOL.get().unwrap(), category).await
+}
+
+async fn definitions_in<'e>(
+    e: impl PgExecutor<'e>,
+    category: u32,
+) -> Result<Vec<AttributeDefinition>, sqlx::Error> {
+    let res = query_file!("sql/attributes/definitions.sql", category as i32)
+        .fetch_all(e)
+        .await?
+        .into_iter()
+        .map(|r| definition(r.id, r.category, r.name, &r.kind, r.unit, r.options))
+        .collect();
+    Ok(res)
+}
+
+pub async fn create(definition: AttributeDefinition) -> Result<u32, sqlx::Error> {
+    let (unit, options) = match definition.kind {
+        AttributeKind::Text => (String::new(), Vec::new()),
+        AttributeKind::Number { ref unit } => (unit.trim().to_owned(), Vec::new()),
+        AttributeKind::Enum { ref options } => (
+            String::new(),
+            options
+                .iter()
+                .map(|o| o.trim().to_owned())
+                .filter(|o| !o.is_empty())
+                .collect(),
+        ),
+    };
+    let res = query!(
+        "INSERT INTO category_attributes(category, name, kind, unit, options) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        definition.category as i32,
+        definition.name.trim(),
+        definition.kind.as_str(),
+        unit,
+        &options
+    )
+    .fetch_one(POOL.get().unwrap())
+    .await?;
+    Ok(res.id as u32)
+}
+
+/// Removes an attribute definition together with all product values for it.
+pub async fn delete(id: u32) -> Result<(), sqlx::Error> {
+    query!("DELETE FROM category_attributes WHERE id=$1", id as i32)
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(())
+}
+
+/// Attribute values of the given products, by product id.
+pub(crate) async fn values(
+    product_ids: &[i32],
+) -> Result<HashMap<i32, BTreeMap<u32, String>>, sqlx::Error> {
+    let mut res: HashMap<i32, BTreeMap<u32, String>> = HashMap::new();
+    query!(
+        "SELECT product_id, attribute_id, value FROM product_attributes WHERE product_id = ANY($1)",
+        product_ids
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .for_each(|r| {
+        res.entry(r.product_id)
+            .or_default()
+            .insert(r.attribute_id as u32, r.value);
+    });
+    Ok(res)
+}
+
+/// Replaces the attribute values of a product. Values for attributes that
+/// don't apply to the product's category, or that aren't valid, are dropped.
+pub(crate) async fn store_values(
+    tx: &mut Transaction<'static, Postgres>,
+    product_id: i32,
+    category: u32,
+    values: &BTreeMap<u32, String>,
+) -> Result<(), sqlx::Error> {
+    let definitions = definitions_in(&mut **tx, category).await?;
+    let (ids, values): (Vec<i32>, Vec<String>) = definitions
+        .iter()
+        .filter_map(|d| {
+            let value = d.normalize(values.get(&d.id)?)?;
+            Some((d.id as i32, value))
+        })
+        .unzip();
+
+    query!(
+        "DELETE FROM product_attributes WHERE product_id=$1",
+        product_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    query!(
+        "INSERT INTO product_attributes(product_id, attribute_id, value)
+        SELECT $1, attribute_id, value FROM unnest($2::integer[], $3::text[]) AS v(attribute_id, value)",
+        product_id,
+        &ids,
+        &values
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The specification table of a product.
+pub async fn specs(product_id: u32) -> Result<Vec<Spec>, sqlx::Error> {
+    let res = query_file!("sql/attributes/specs.sql", product_id as i32)
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| {
+            let d = definition(r.id, r.category, r.name, &r.kind, r.unit, r.options);
+            Spec {
+                value: d.display(&r.value),
+                name: d.name,
+            }
+        })
+        .collect();
+    Ok(res)
+}
+
+/// Attribute values among the products listed in `category` with their
+/// counts. The count of a value is the number of products that would be
+/// listed if it was selected, keeping the selection of other attributes.
+pub async fn facets(category: u32, filter: PreviewFilter) -> Result<Vec<Facet>, sqlx::Error> {
+    let (ids, values): (Vec<i32>, Vec<String>) = filter
+        .attributes
+        .into_iter()
+        .map(|(a, v)| (a as i32, v))
+        .unzip();
+
+    let mut counts: BTreeMap<u32, Vec<(String, u32)>> = BTreeMap::new();
+    query_file!(
+        "sql/attributes/facets.sql",
+        category as i32,
+        filter.min_price.map(|p| p as i32),
+        filter.max_price.map(|p| p as i32),
+        filter.in_stock,
+        &ids,
+        &values
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .for_each(|r| {
+        counts
+            .entry(r.attribute_id as u32)
+            .or_default()
+            .push((r.value, r.count as u32));
+    });
+
+    let res = definitions(category)
+        .await?
+        .into_iter()
+        .filter_map(|attribute| {
+            let mut values = counts.remove(&attribute.id)?;
+            match &attribute.kind {
+                AttributeKind::Number { .. } => values.sort_by(|(a, _), (b, _)| {
+                    let a = a.parse::<f64>().unwrap_or_default();
+                    let b = b.parse::<f64>().unwrap_or_default();
+                    a.total_cmp(&b)
+                }),
+                AttributeKind::Enum { options } => {
+                    values.sort_by_key(|(v, _)| options.iter().position(|o| o == v))
+                }
+                AttributeKind::Text => {}
+            }
+            Some(Facet { attribute, values })
+        })
+        .collect();
+    Ok(res)
+}
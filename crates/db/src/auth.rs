@@ -1,14 +1,18 @@
 use crate::postgres::POOL;
-use sqlx::{Postgres, Transaction, query, query_file, types::Uuid};
+use chrono::{DateTime, TimeDelta, Utc};
+use sjf_api::account::Role;
+pub use sqlx::types::Uuid;
+use sqlx::{Postgres, Transaction, query, query_file};
 
 pub async fn add(
     id: Uuid,
     name: String,
+    role: Role,
     keyid: Vec<u8>,
     passkey: Vec<u8>,
 ) -> Result<(), sqlx::Error> {
     let mut tx = POOL.get().unwrap().begin().await?;
-    query_file!("sql/create_user.sql", id, name)
+    query_file!("sql/create_user.sql", id, name, role.as_str())
         .execute(&mut *tx)
         .await?;
 
@@ -23,17 +27,45 @@ pub async fn add(
     tx.commit().await
 }
 
-pub async fn lookup_name(name: &str) -> Result<Option<Uuid>, sqlx::Error> {
-    let res = query!("SELECT id FROM users WHERE name=$1", name)
-        .fetch_optional(POOL.get().unwrap())
-        .await?;
+/// Admins and customers live in separate namespaces, the same name can exist in both.
+pub async fn lookup_name(name: &str, role: Role) -> Result<Option<Uuid>, sqlx::Error> {
+    let res = query!(
+        "SELECT id FROM users WHERE name=$1 AND role=$2",
+        name,
+        role.as_str()
+    )
+    .fetch_optional(POOL.get().unwrap())
+    .await?;
     Ok(res.map(|r| r.id))
 }
-pub async fn lookup_id(id: &Uuid) -> Result<Option<String>, sqlx::Error> {
-    let res = query!("SELECT name FROM users WHERE id=$1", id)
+/// Disabled users aren't found, which ends their sessions.
+pub async fn lookup_id(id: &Uuid) -> Result<Option<(String, Role)>, sqlx::Error> {
+    let res = query!(
+        "SELECT name, role FROM users WHERE id=$1 AND disabled IS NULL",
+        id
+    )
+    .fetch_optional(POOL.get().unwrap())
+    .await?;
+    Ok(res.map(|r| (r.name.unwrap(), r.role.as_str().into())))
+}
+
+/// The Stripe customer that checkouts of a customer account are made as.
+pub async fn stripe_customer(id: &Uuid) -> Result<Option<String>, sqlx::Error> {
+    let res = query!("SELECT stripe_customer FROM users WHERE id=$1", id)
         .fetch_optional(POOL.get().unwrap())
         .await?;
-    Ok(res.map(|r| r.name.unwrap()))
+    Ok(res.and_then(|r| r.stripe_customer))
+}
+
+pub async fn set_stripe_customer(id: &Uuid, customer: &str) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE users SET stripe_customer=$2 WHERE id=$1",
+        id,
+        customer
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
 }
 
 pub async fn get_keys(id: &Uuid) -> Result<Vec<Vec<u8>>, sqlx::Error> {
@@ -79,3 +111,84 @@ pub async fn complete_passkey_update(
     .await?;
     tx.commit().await
 }
+
+#[derive(Debug)]
+pub struct UserInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Role,
+    pub created: DateTime<Utc>,
+    pub last_login: DateTime<Utc>,
+    pub disabled: Option<DateTime<Utc>>,
+    pub passkeys: i64,
+}
+
+/// Every account, admins first.
+pub async fn users() -> Result<Vec<UserInfo>, sqlx::Error> {
+    let res = query!(
+        "SELECT u.id, u.name, u.role, u.created, u.last_login, u.disabled, COUNT(k.user_id) AS passkeys
+        FROM users u LEFT JOIN user_passkeys k ON k.user_id = u.id
+        GROUP BY u.id ORDER BY u.role, u.name"
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| UserInfo {
+        id: r.id,
+        name: r.name.unwrap_or_default(),
+        role: r.role.as_str().into(),
+        created: r.created,
+        last_login: r.last_login,
+        disabled: r.disabled,
+        passkeys: r.passkeys.unwrap_or_default(),
+    })
+    .collect();
+    Ok(res)
+}
+
+/// Disables or enables an account, false if there's no such user.
+pub async fn set_disabled(id: &Uuid, disabled: bool) -> Result<bool, sqlx::Error> {
+    let res = query!(
+        "UPDATE users SET disabled = CASE WHEN $2 THEN COALESCE(disabled, NOW()) END, updated=NOW() WHERE id=$1",
+        id,
+        disabled
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Creates a code that lets `name` register an admin passkey in place of the
+/// terminal challenge.
+pub async fn create_invite(
+    name: &str,
+    valid_for: std::time::Duration,
+) -> Result<Uuid, sqlx::Error> {
+    let expires = TimeDelta::from_std(valid_for)
+        .ok()
+        .and_then(|d| Utc::now().checked_add_signed(d))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let res = query!(
+        "INSERT INTO admin_invites (name, expires) VALUES ($1, $2) RETURNING code",
+        name,
+        expires
+    )
+    .fetch_one(POOL.get().unwrap())
+    .await?;
+    Ok(res.code)
+}
+
+/// Uses up an invite, true if it was valid for `name`.
+pub async fn use_invite(name: &str, code: &Uuid) -> Result<bool, sqlx::Error> {
+    query!("DELETE FROM admin_invites WHERE expires < NOW()")
+        .execute(POOL.get().unwrap())
+        .await?;
+    let res = query!(
+        "DELETE FROM admin_invites WHERE code=$1 AND name=$2",
+        code,
+        name
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
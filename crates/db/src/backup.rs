@@ -0,0 +1,491 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, Transaction, query, query_file};
+
+use crate::postgres::POOL;
+
+/// Version of the catalogue format, raised when a backup can't be restored
+/// by an older version of the shop.
+pub const VERSION: u32 = 1;
+
+/// Everything in the catalogue except the image files, with the ids it had
+/// in the database it was taken from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Catalogue {
+    pub version: u32,
+    pub created: DateTime<Utc>,
+    pub categories: Vec<Category>,
+    /// Rows of the category closure table.
+    pub hierarchy: Vec<Hierarchy>,
+    pub attributes: Vec<Attribute>,
+    pub images: Vec<Image>,
+    pub products: Vec<Product>,
+    pub related: Vec<Related>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Category {
+    pub id: i32,
+    pub name: String,
+    pub google_category: Option<i32>,
+    /// Names by locale code.
+    pub translations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hierarchy {
+    pub ancestor: i32,
+    pub descendant: i32,
+    pub depth: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attribute {
+    pub id: i32,
+    pub category: i32,
+    pub name: String,
+    pub kind: String,
+    pub unit: String,
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Image {
+    pub id: i32,
+    pub avg_color: String,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Variant {
+    pub id: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Product {
+    pub id: i32,
+    pub sku: Option<String>,
+    pub name: String,
+    pub price: i32,
+    pub description: String,
+    pub quantity: Option<i32>,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub tax_rate: i32,
+    pub category: i32,
+    pub reorder_threshold: Option<i32>,
+    pub stock_policy: String,
+    pub backorder_limit: i32,
+    pub preorder_ship_date: Option<NaiveDate>,
+    pub images: Vec<i32>,
+    /// Values by attribute id.
+    pub attributes: BTreeMap<i32, String>,
+    /// Name and description by locale code.
+    pub translations: BTreeMap<String, (String, String)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Related {
+    pub product: i32,
+    pub related: i32,
+    pub position: i32,
+}
+
+/// Image and variant id of a stored image file.
+pub type VariantKey = (i32, i32);
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Summary {
+    pub categories: usize,
+    pub products: usize,
+    pub images: usize,
+    pub variants: usize,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} categories, {} products, {} images with {} variants",
+            self.categories, self.products, self.images, self.variants
+        )
+    }
+}
+
+impl Catalogue {
+    pub fn summary(&self) -> Summary {
+        Summary {
+            categories: self.categories.len(),
+            products: self.products.len(),
+            images: self.images.len(),
+            variants: self.images.iter().map(|i| i.variants.len()).sum(),
+        }
+    }
+
+    /// The image files the catalogue refers to.
+    pub fn variants(&self) -> impl Iterator<Item = VariantKey> + '_ {
+        self.images
+            .iter()
+            .flat_map(|i| i.variants.iter().map(|v| (i.id, v.id)))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RestoreError {
+    #[error("Database error {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("Backup version {0} isn't supported, expected {VERSION}")]
+    Version(u32),
+    #[error("The catalogue isn't empty, backups are only restored into an empty shop")]
+    NotEmpty,
+    #[error("The backup refers to {0} {1} which it doesn't contain")]
+    Missing(&'static str, i32),
+}
+
+/// Reads the whole catalogue in one snapshot.
+pub async fn export() -> Result<Catalogue, sqlx::Error> {
+    let mut tx = POOL.get().unwrap().begin().await?;
+    query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let mut category_names: HashMap<i32, BTreeMap<String, String>> = HashMap::new();
+    for r in query!("SELECT category_id, locale, name FROM category_translations")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        category_names
+            .entry(r.category_id)
+            .or_default()
+            .insert(r.locale, r.name);
+    }
+    let categories = query!("SELECT id, name, google_category FROM product_categories ORDER BY id")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| Category {
+            id: r.id,
+            translations: category_names.remove(&r.id).unwrap_or_default(),
+            name: r.name,
+            google_category: r.google_category,
+        })
+        .collect();
+
+    let hierarchy = query!("SELECT ancestor, descendant, depth FROM product_categories_hierarchy")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| Hierarchy {
+            ancestor: r.ancestor,
+            descendant: r.descendant,
+            depth: r.depth,
+        })
+        .collect();
+
+    let attributes = query!(
+        "SELECT id, category, name, kind, unit, options FROM category_attributes ORDER BY id"
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|r| Attribute {
+        id: r.id,
+        category: r.category,
+        name: r.name,
+        kind: r.kind,
+        unit: r.unit,
+        options: r.options,
+    })
+    .collect();
+
+    let mut variants: HashMap<i32, Vec<Variant>> = HashMap::new();
+    for r in
+        query!("SELECT variant_id, image_id, width, height FROM image_variants ORDER BY variant_id")
+            .fetch_all(&mut *tx)
+            .await?
+    {
+        variants.entry(r.image_id).or_default().push(Variant {
+            id: r.variant_id,
+            width: r.width,
+            height: r.height,
+        });
+    }
+    let images = query!("SELECT image_id, avg_color FROM images ORDER BY image_id")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| Image {
+            id: r.image_id,
+            avg_color: r.avg_color,
+            variants: variants.remove(&r.image_id).unwrap_or_default(),
+        })
+        .collect();
+
+    let mut product_images: HashMap<i32, Vec<i32>> = HashMap::new();
+    for r in query!("SELECT product_id, image_id FROM product_images")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        product_images
+            .entry(r.product_id)
+            .or_default()
+            .push(r.image_id);
+    }
+    let mut values: HashMap<i32, BTreeMap<i32, String>> = HashMap::new();
+    for r in query!("SELECT product_id, attribute_id, value FROM product_attributes")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        values
+            .entry(r.product_id)
+            .or_default()
+            .insert(r.attribute_id, r.value);
+    }
+    let mut texts: HashMap<i32, BTreeMap<String, (String, String)>> = HashMap::new();
+    for r in query!("SELECT product_id, locale, name, description FROM product_translations")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        texts
+            .entry(r.product_id)
+            .or_default()
+            .insert(r.locale, (r.name, r.description));
+    }
+    let products = query_file!("sql/backup/products.sql")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| Product {
+            images: product_images.remove(&r.id).unwrap_or_default(),
+            attributes: values.remove(&r.id).unwrap_or_default(),
+            translations: texts.remove(&r.id).unwrap_or_default(),
+            id: r.id,
+            sku: r.sku,
+            name: r.name,
+            price: r.price,
+            description: r.description,
+            quantity: r.quantity,
+            created: r.created,
+            updated: r.updated,
+            tax_rate: r.tax_rate,
+            category: r.category,
+            reorder_threshold: r.reorder_threshold,
+            stock_policy: r.stock_policy,
+            backorder_limit: r.backorder_limit,
+            preorder_ship_date: r.preorder_ship_date,
+        })
+        .collect();
+
+    let related = query!("SELECT product_id, related_id, position FROM related_products")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| Related {
+            product: r.product_id,
+            related: r.related_id,
+            position: r.position,
+        })
+        .collect();
+
+    tx.commit().await?;
+    Ok(Catalogue {
+        version: VERSION,
+        created: Utc::now(),
+        categories,
+        hierarchy,
+        attributes,
+        images,
+        products,
+        related,
+    })
+}
+
+/// A restored catalogue that isn't committed until its image files are
+/// stored, dropping it rolls the restore back.
+pub struct Restore {
+    tx: Transaction<'static, Postgres>,
+    /// The ids each image file of the backup got.
+    pub variants: HashMap<VariantKey, VariantKey>,
+    pub summary: Summary,
+}
+
+impl Restore {
+    pub async fn commit(self) -> Result<Summary, sqlx::Error> {
+        self.tx.commit().await?;
+        let pool = POOL.get().unwrap();
+        crate::category::update_paths_view(pool, false).await?;
+        crate::image::update_image_view(pool, false).await?;
+        Ok(self.summary)
+    }
+}
+
+fn remap(ids: &HashMap<i32, i32>, kind: &'static str, id: i32) -> Result<i32, RestoreError> {
+    ids.get(&id).copied().ok_or(RestoreError::Missing(kind, id))
+}
+
+/// Inserts a backed up catalogue into an empty shop. Everything gets new
+/// ids, references between the parts are mapped to them.
+pub async fn restore(catalogue: &Catalogue) -> Result<Restore, RestoreError> {
+    if catalogue.version != VERSION {
+        return Err(RestoreError::Version(catalogue.version));
+    }
+    let mut tx = POOL.get().unwrap().begin().await?;
+    let used = query!(
+        "SELECT EXISTS (SELECT 1 FROM products) OR EXISTS (SELECT 1 FROM product_categories) OR EXISTS (SELECT 1 FROM images) AS used"
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .used
+    .unwrap_or(true);
+    if used {
+        return Err(RestoreError::NotEmpty);
+    }
+
+    let mut categories = HashMap::new();
+    for c in catalogue.categories.iter() {
+        let id = query!(
+            "INSERT INTO product_categories (name, google_category) VALUES ($1, $2) RETURNING id",
+            c.name,
+            c.google_category
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id;
+        for (locale, name) in c.translations.iter() {
+            query!(
+                "INSERT INTO category_translations (category_id, locale, name) VALUES ($1, $2, $3)",
+                id,
+                locale,
+                name
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        categories.insert(c.id, id);
+    }
+    for h in catalogue.hierarchy.iter() {
+        query!(
+            "INSERT INTO product_categories_hierarchy (ancestor, descendant, depth) VALUES ($1, $2, $3)",
+            remap(&categories, "category", h.ancestor)?,
+            remap(&categories, "category", h.descendant)?,
+            h.depth
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut attributes = HashMap::new();
+    for a in catalogue.attributes.iter() {
+        let id = query!(
+            "INSERT INTO category_attributes (category, name, kind, unit, options) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            remap(&categories, "category", a.category)?,
+            a.name,
+            a.kind,
+            a.unit,
+            &a.options
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id;
+        attributes.insert(a.id, id);
+    }
+
+    let mut images = HashMap::new();
+    let mut variants = HashMap::new();
+    for i in catalogue.images.iter() {
+        let id = query!(
+            "INSERT INTO images (avg_color) VALUES ($1) RETURNING image_id",
+            i.avg_color
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .image_id;
+        for v in i.variants.iter() {
+            let variant = query!(
+                "INSERT INTO image_variants (image_id, width, height) VALUES ($1, $2, $3) RETURNING variant_id",
+                id,
+                v.width,
+                v.height
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .variant_id;
+            variants.insert((i.id, v.id), (id, variant));
+        }
+        images.insert(i.id, id);
+    }
+
+    let mut products = HashMap::new();
+    for p in catalogue.products.iter() {
+        let id = query_file!(
+            "sql/backup/restore_product.sql",
+            p.sku,
+            p.name,
+            p.price,
+            p.description,
+            p.quantity,
+            p.created,
+            p.updated,
+            p.tax_rate,
+            remap(&categories, "category", p.category)?,
+            p.reorder_threshold,
+            p.stock_policy,
+            p.backorder_limit,
+            p.preorder_ship_date
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id;
+        for image in p.images.iter() {
+            query!(
+                "INSERT INTO product_images (product_id, image_id) VALUES ($1, $2)",
+                id,
+                remap(&images, "image", *image)?
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        for (attribute, value) in p.attributes.iter() {
+            query!(
+                "INSERT INTO product_attributes (product_id, attribute_id, value) VALUES ($1, $2, $3)",
+                id,
+                remap(&attributes, "attribute", *attribute)?,
+                value
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        for (locale, (name, description)) in p.translations.iter() {
+            query!(
+                "INSERT INTO product_translations (product_id, locale, name, description) VALUES ($1, $2, $3, $4)",
+                id,
+                locale,
+                name,
+                description
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        products.insert(p.id, id);
+    }
+    for r in catalogue.related.iter() {
+        query!(
+            "INSERT INTO related_products (product_id, related_id, position) VALUES ($1, $2, $3)",
+            remap(&products, "product", r.product)?,
+            remap(&products, "product", r.related)?,
+            r.position
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(Restore {
+        tx,
+        variants,
+        summary: catalogue.summary(),
+    })
+}
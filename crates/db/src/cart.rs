@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use crate::postgres::POOL;
+use once_cell::sync::OnceCell;
+use sjf_api::cart::{CartNotice, ProductQuantity, SyncCartRequest, SyncCartResponse};
+use sjf_api::product::{GetProductsRequest, Product, ProductId};
+use sqlx::{query, query_file, types::Uuid};
+
+/// Carts that haven't been touched for this many days are removed.
+const CART_LIFETIME_DAYS: i32 = 30;
+/// Lines kept in one cart, the rest are dropped so that a client can't store
+/// an unbounded number of rows.
+pub const MAX_CART_ITEMS: usize = 100;
+
+static SERVER_CART: OnceCell<bool> = OnceCell::new();
+
+pub(crate) fn configure(config: &sjf_config::CheckoutConfig) {
+    let _ = SERVER_CART.set(config.server_cart);
+}
+
+/// Whether carts are stored, set with `SERVER_CART`.
+pub fn enabled() -> bool {
+    SERVER_CART.get().copied().unwrap_or(true)
+}
+
+struct StoredItem {
+    quantity: ProductQuantity,
+    /// The price the customer was last shown.
+    price: Option<u32>,
+}
+
+/// Combines the browser's cart with the one stored for `cart`, checks every line
+/// against current price and stock and stores the result.
+///
+/// Returns the id of the stored cart, which is `None` if there was nothing to
+/// store or carts aren't stored.
+pub async fn sync(
+    cart: Option<Uuid>,
+    req: SyncCartRequest,
+) -> Result<(Option<Uuid>, SyncCartResponse), sqlx::Error> {
+    if !enabled() {
+        let wanted = req
+            .items
+            .into_iter()
+            .map(|(id, quantity)| {
+                (
+                    id,
+                    StoredItem {
+                        quantity,
+                        price: None,
+                    },
+                )
+            })
+            .collect();
+        let (items, notices) = check(wanted).await?;
+        return Ok((None, SyncCartResponse { items, notices }));
+    }
+    if cart.is_none() && req.items.is_empty() {
+        return Ok((
+            None,
+            SyncCartResponse {
+                items: vec![],
+                notices: vec![],
+            },
+        ));
+    }
+
+    let mut tx = POOL.get().unwrap().begin().await?;
+    let cart = query_file!("sql/cart/touch.sql", cart)
+        .fetch_one(&mut *tx)
+        .await?
+        .id;
+
+    let stored: BTreeMap<ProductId, StoredItem> = query!(
+        "SELECT product_id, quantity, price FROM cart_items WHERE cart_id = $1",
+        cart
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|r| {
+        (
+            r.product_id as ProductId,
+            StoredItem {
+                quantity: r.quantity.max(0) as ProductQuantity,
+                price: Some(r.price.max(0) as u32),
+            },
+        )
+    })
+    .collect();
+
+    let mut wanted: BTreeMap<ProductId, StoredItem> = BTreeMap::new();
+    if req.merge {
+        wanted = stored;
+        for (id, quantity) in req.items {
+            let item = wanted.entry(id).or_insert(StoredItem {
+                quantity: 0,
+                price: None,
+            });
+            item.quantity = item.quantity.max(quantity);
+        }
+    } else {
+        for (id, quantity) in req.items {
+            let price = stored.get(&id).and_then(|s| s.price);
+            wanted.insert(id, StoredItem { quantity, price });
+        }
+    }
+    query!("DELETE FROM cart_items WHERE cart_id = $1", cart)
+        .execute(&mut *tx)
+        .await?;
+    let (items, notices) = check(wanted).await?;
+    let ids: Vec<i32> = items.iter().map(|(p, _)| p.id as i32).collect();
+    let quantities: Vec<i32> = items.iter().map(|(_, q)| *q as i32).collect();
+    let prices: Vec<i32> = items.iter().map(|(p, _)| p.price as i32).collect();
+    query_file!("sql/cart/store_items.sql", cart, &ids, &quantities, &prices)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok((Some(cart), SyncCartResponse { items, notices }))
+}
+
+/// Checks the wanted lines against current price and stock, keeping at most
+/// [`MAX_CART_ITEMS`] of them.
+async fn check(
+    mut wanted: BTreeMap<ProductId, StoredItem>,
+) -> Result<(Vec<(Product, ProductQuantity)>, Vec<CartNotice>), sqlx::Error> {
+    wanted.retain(|_, item| item.quantity > 0);
+    let mut notices = vec![];
+    while wanted.len() > MAX_CART_ITEMS {
+        let (product_id, _) = wanted.pop_last().unwrap();
+        notices.push(CartNotice::Removed {
+            product_id,
+            name: None,
+        });
+    }
+
+    let products = crate::product::get_specified_products(GetProductsRequest {
+        product_ids: wanted.keys().cloned().collect(),
+    })
+    .await?;
+    let mut products: BTreeMap<ProductId, _> = products.into_iter().map(|p| (p.id, p)).collect();
+
+    let mut items = vec![];
+    for (id, item) in wanted {
+        let Some(product) = products.remove(&id) else {
+            notices.push(CartNotice::Removed {
+                product_id: id,
+                name: None,
+            });
+            continue;
+        };
+
+        let available = product.stock.unwrap_or(ProductQuantity::MAX);
+        if available == 0 {
+            notices.push(CartNotice::Removed {
+                product_id: id,
+                name: Some(product.name),
+            });
+            continue;
+        }
+
+        let mut quantity = item.quantity;
+        if quantity > available {
+            notices.push(CartNotice::QuantityReduced {
+                product_id: id,
+                name: product.name.clone(),
+                requested: quantity,
+                available,
+            });
+            quantity = available;
+        }
+
+        if let Some(old_price) = item.price
+            && old_price != product.price
+        {
+            notices.push(CartNotice::PriceChanged {
+                product_id: id,
+                name: product.name.clone(),
+                old_price,
+                new_price: product.price,
+            });
+        }
+
+        items.push((product, quantity));
+    }
+    Ok((items, notices))
+}
+
+/// Removes stale carts, along with old jobs.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct Cleanup;
+
+impl crate::jobs::Job for Cleanup {
+    const KIND: &'static str = "cleanup";
+
+    async fn run(self, _: Option<Vec<u8>>) -> crate::jobs::JobResult {
+        remove_stale_carts().await?;
+        crate::jobs::purge().await?;
+        Ok(())
+    }
+}
+
+pub async fn remove_stale_carts() -> Result<u64, sqlx::Error> {
+    let res = query!(
+        "DELETE FROM carts WHERE updated < CURRENT_TIMESTAMP - make_interval(days => $1)",
+        CART_LIFETIME_DAYS
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected())
+}
@@ -3,7 +3,6 @@ use std::collections::HashMap;
 use crate::postgres::POOL;
 use sjf_api::category::{CreateReq, CreateRsp, DeleteReq, GetChildrenRsp};
 use sqlx::{Executor, Postgres, query, query_as, query_file};
-use tracing::error;
 
 pub async fn create(req: CreateReq) -> Result<CreateRsp, sqlx::Error> {
     let mut tx = POOL.get().unwrap().begin().await?;
@@ -39,8 +38,8 @@ pub async fn create(req: CreateReq) -> Result<CreateRsp, sqlx::Error> {
         .await?;
     }
 
+    update_paths_view_later(&mut *tx).await?;
     tx.commit().await?;
-    update_paths_view_later();
     Ok(CreateRsp {
         id: res.id as u32,
         depth: depth as u32,
@@ -86,6 +85,7 @@ pub async fn get_children(id: Option<u32>) -> Result<GetChildrenRsp, sqlx::Error
 
     Ok(GetChildrenRsp {
         children: rsp.into_iter().map(|r| (r.id as u32, r.name)).collect(),
+        labels: Default::default(),
     })
 }
 
@@ -124,15 +124,39 @@ where
 }
 
 pub async fn update_name(id: u32, name: String) -> Result<(), sqlx::Error> {
+    let mut tx = POOL.get().unwrap().begin().await?;
     query!(
         "UPDATE product_categories SET name=$1 where id=$2 RETURNING ID",
         name,
         id as i32
     )
+    .fetch_one(&mut *tx)
+    .await?;
+    update_paths_view_later(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// The Google product category set on the category itself, not inherited.
+pub async fn google_category(id: u32) -> Result<Option<u32>, sqlx::Error> {
+    let res = query!(
+        "SELECT google_category FROM product_categories WHERE id=$1",
+        id as i32
+    )
     .fetch_one(POOL.get().unwrap())
     .await?;
-    update_paths_view_later();
+    Ok(res.google_category.map(|c| c as u32))
+}
 
+pub async fn set_google_category(id: u32, google_category: Option<u32>) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE product_categories SET google_category=$1 WHERE id=$2",
+        google_category.map(|c| c as i32),
+        id as i32
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
     Ok(())
 }
 
@@ -152,16 +176,31 @@ where
     query!("REFRESH MATERIALIZED VIEW CONCURRENTLY product_paths")
         .execute(e)
         .await?;
-    Ok(())
+    crate::sitemap::views_refreshed(e).await
 }
 
-pub(crate) fn update_paths_view_later() {
-    tokio::spawn(async {
-        let res = update_paths_view(POOL.get().unwrap(), false).await;
-        if let Err(e) = res {
-            error!("Path view update failed! {:#?}", e);
-        }
-    });
+/// Refreshes the view of category paths, once for every change made while
+/// it waits or runs.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct RefreshPaths;
+
+impl crate::jobs::Job for RefreshPaths {
+    const KIND: &'static str = "refresh_paths";
+    const ONCE: bool = true;
+
+    async fn run(self, _: Option<Vec<u8>>) -> crate::jobs::JobResult {
+        update_paths_view(POOL.get().unwrap(), false).await?;
+        Ok(())
+    }
+}
+
+/// Queues a refresh of the paths view as part of the transaction that changed
+/// the categories.
+pub(crate) async fn update_paths_view_later<'e>(
+    e: impl Executor<'e, Database = Postgres>,
+) -> Result<(), sqlx::Error> {
+    crate::jobs::enqueue_in(e, &RefreshPaths, None, chrono::Utc::now()).await?;
+    Ok(())
 }
 
 pub async fn get_paths() -> Result<HashMap<String, u32>, sqlx::Error> {
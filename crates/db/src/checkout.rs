@@ -1,7 +1,10 @@
 use std::str::FromStr;
 
 use crate::postgres::POOL;
+use crate::reservation;
+use chrono::NaiveDate;
 use sjf_api::checkout::CheckoutRequest;
+use sjf_api::stock::Fulfilment;
 use sqlx::{query, query_as, query_file};
 use tracing::info;
 
@@ -15,6 +18,8 @@ pub enum CheckoutError {
     ProductNotFound(u32),
     #[error("Empty order")]
     EmptyOrder,
+    #[error("Currency {0} isn't offered")]
+    CurrencyNotOffered(&'static str),
 }
 
 pub async fn make_reservation(req: CheckoutRequest) -> Result<String, CheckoutError> {
@@ -24,62 +29,104 @@ pub async fn make_reservation(req: CheckoutRequest) -> Result<String, CheckoutEr
 
     let mut tx = crate::postgres::POOL.get().unwrap().begin().await?;
 
-    let reservation_id =
-        query!("INSERT INTO  pending_orders VALUES( default,default) RETURNING id")
-            .fetch_one(&mut *tx)
-            .await?
-            .id;
+    let rate = crate::currency::rate(&mut *tx, req.currency)
+        .await?
+        .ok_or(CheckoutError::CurrencyNotOffered(req.currency.code()))?;
+
+    let expires_at = reservation::windows().provisional_expiry(reservation::clock().now());
+    let reservation_id = query!(
+        "INSERT INTO pending_orders(expires_at, currency, sek_micros, locale) VALUES($1, $2, $3, $4) RETURNING id",
+        expires_at,
+        rate.currency.code(),
+        rate.sek_micros as i32,
+        req.locale.code()
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .id;
 
     for (id, quantity) in &req.order {
         let id = *id as i32;
         let quanity = *quantity as i32;
-        query!("SELECT (1) as exists from products where id=$1", id)
+        let policy = query!("SELECT stock_policy from products where id=$1", id)
             .fetch_one(&mut *tx)
-            .await?;
+            .await?
+            .stock_policy;
 
-        query!(
-            "UPDATE products  SET  quantity=(quantity-$1) WHERE (quantity IS NOT NULL and id=$2)",
+        let remaining = query!(
+            "UPDATE products  SET  quantity=(quantity-$1) WHERE (quantity IS NOT NULL and id=$2) RETURNING quantity",
             quanity,
             id
         )
-        .execute(&mut *tx)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await?
+        .and_then(|r| r.quantity);
+
+        // Only the units beyond what was left in stock wait for a restock
+        let (fulfilment, backordered) = match (policy.as_str(), remaining) {
+            ("preorder", _) => (Fulfilment::Preorder, 0),
+            ("backorder", Some(q)) if q < 0 => (Fulfilment::Backorder, (-q).min(quanity)),
+            _ => (Fulfilment::InStock, 0),
+        };
 
         query!(
-            "INSERT INTO product_reservations(reservation_id,product_id,quantity) VALUES($1,$2,$3)",
+            "INSERT INTO product_reservations(reservation_id,product_id,quantity,fulfilment,backordered) VALUES($1,$2,$3,$4,$5)",
             reservation_id,
             id,
-            quanity
+            quanity,
+            fulfilment.as_str(),
+            backordered
         )
         .execute(&mut *tx)
         .await?;
     }
 
+    reservation::release_at(&mut *tx, expires_at).await?;
     tx.commit().await?;
 
     Ok(reservation_id.to_string())
 }
 
-pub(crate) async fn undo_old_reservations() -> Result<(), CheckoutError> {
-    let mut expired_transactions =
-        query!("SELECT id FROM pending_orders where timestamp  < now() - interval '35min'")
-            .fetch(POOL.get().unwrap());
-
-    use futures_util::TryStreamExt;
-    while let Some(r) = expired_transactions.try_next().await? {
-        undo_reservation(r.id.into()).await?;
-    }
-
-    Ok(())
-}
-
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub struct OrderItem {
     pub product_id: u32,
     pub image_path: Option<String>,
     pub name: String,
     pub price: u32,
     pub ordered_quantity: u32,
+    /// Units of a backordered line that wait for a restock, the rest ship
+    /// from stock.
+    #[serde(default)]
+    pub backordered: u32,
     pub tax_rate: u32,
+    pub fulfilment: Fulfilment,
+    pub ship_date: Option<NaiveDate>,
+}
+
+impl OrderItem {
+    /// The line as it ships. A partly backordered line becomes a line
+    /// shipping from stock and a backordered line with the units that are
+    /// short. Lines saved without a count of backordered units stay whole.
+    pub fn split(self) -> Vec<OrderItem> {
+        match self.fulfilment {
+            Fulfilment::Backorder
+                if self.backordered > 0 && self.backordered < self.ordered_quantity =>
+            {
+                let in_stock = OrderItem {
+                    ordered_quantity: self.ordered_quantity - self.backordered,
+                    backordered: 0,
+                    fulfilment: Fulfilment::InStock,
+                    ..self.clone()
+                };
+                let backordered = OrderItem {
+                    ordered_quantity: self.backordered,
+                    ..self
+                };
+                vec![in_stock, backordered]
+            }
+            _ => vec![self],
+        }
+    }
 }
 
 pub async fn get_order(uuid: &str) -> Result<Vec<OrderItem>, CheckoutError> {
@@ -102,7 +149,10 @@ pub async fn get_order(uuid: &str) -> Result<Vec<OrderItem>, CheckoutError> {
             name: i.name,
             price: i.price as u32,
             ordered_quantity: i.ordered_quantity as u32,
+            backordered: i.backordered.max(0) as u32,
             tax_rate: i.tax_rate as u32,
+            fulfilment: i.fulfilment.as_str().into(),
+            ship_date: i.preorder_ship_date,
         })
         .collect();
 
@@ -145,6 +195,8 @@ pub async fn commit_reservation(uuid: String) -> Result<(), CheckoutError> {
     let uuid = sqlx::types::Uuid::from_str(uuid.as_ref())?;
     let mut tx = crate::postgres::POOL.get().unwrap().begin().await?;
 
+    crate::orders::record(&mut tx, uuid).await?;
+
     query!(
         "DELETE FROM product_reservations WHERE (reservation_id=$1)",
         uuid
@@ -159,3 +211,69 @@ pub async fn commit_reservation(uuid: String) -> Result<(), CheckoutError> {
     tx.commit().await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(fulfilment: Fulfilment, ordered_quantity: u32, backordered: u32) -> OrderItem {
+        OrderItem {
+            product_id: 1,
+            image_path: None,
+            name: "Tröja".into(),
+            price: 100,
+            ordered_quantity,
+            backordered,
+            tax_rate: 25,
+            fulfilment,
+            ship_date: None,
+        }
+    }
+
+    fn lines(item: OrderItem) -> Vec<(Fulfilment, u32)> {
+        item.split()
+            .into_iter()
+            .map(|i| (i.fulfilment, i.ordered_quantity))
+            .collect()
+    }
+
+    #[test]
+    fn partly_backordered_lines_ship_what_is_in_stock() {
+        assert_eq!(
+            lines(item(Fulfilment::Backorder, 5, 3)),
+            vec![(Fulfilment::InStock, 2), (Fulfilment::Backorder, 3)]
+        );
+    }
+
+    #[test]
+    fn whole_lines_are_kept() {
+        assert_eq!(
+            lines(item(Fulfilment::Backorder, 4, 4)),
+            vec![(Fulfilment::Backorder, 4)]
+        );
+        assert_eq!(
+            lines(item(Fulfilment::InStock, 4, 0)),
+            vec![(Fulfilment::InStock, 4)]
+        );
+        assert_eq!(
+            lines(item(Fulfilment::Preorder, 4, 0)),
+            vec![(Fulfilment::Preorder, 4)]
+        );
+    }
+
+    #[test]
+    fn orders_saved_before_backordered_units_were_recorded_stay_whole() {
+        let item: OrderItem = serde_json::from_value(serde_json::json!({
+            "product_id": 1,
+            "image_path": null,
+            "name": "Tröja",
+            "price": 100,
+            "ordered_quantity": 2,
+            "tax_rate": 25,
+            "fulfilment": "Backorder",
+            "ship_date": null,
+        }))
+        .unwrap();
+        assert_eq!(lines(item), vec![(Fulfilment::Backorder, 2)]);
+    }
+}
@@ -0,0 +1,92 @@
+use sjf_api::currency::{Currency, ExchangeRate};
+use sqlx::{Executor, Postgres, query};
+
+use crate::postgres::POOL;
+
+/// Rates of the currencies prices can be shown and paid in, kr first.
+/// Currencies staff haven't set a rate for aren't offered.
+pub async fn rates() -> Result<Vec<ExchangeRate>, sqlx::Error> {
+    let mut res = vec![ExchangeRate::SEK];
+    query!("SELECT currency, sek_micros FROM currency_rates")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .filter_map(|r| {
+            Some(ExchangeRate {
+                currency: Currency::from_code(&r.currency)?,
+                sek_micros: r.sek_micros as u32,
+            })
+        })
+        .for_each(|r| res.push(r));
+    res.sort_by_key(|r| r.currency);
+    Ok(res)
+}
+
+/// The current rate of a currency, `None` when it isn't offered.
+pub(crate) async fn rate<'c, E>(
+    executor: E,
+    currency: Currency,
+) -> Result<Option<ExchangeRate>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    if currency == Currency::Sek {
+        return Ok(Some(ExchangeRate::SEK));
+    }
+    let res = query!(
+        "SELECT sek_micros FROM currency_rates WHERE currency = $1",
+        currency.code()
+    )
+    .fetch_optional(executor)
+    .await?
+    .map(|r| ExchangeRate {
+        currency,
+        sek_micros: r.sek_micros as u32,
+    });
+    Ok(res)
+}
+
+/// The rate of the currency in kr, `None` stops offering it.
+pub async fn set_rate(currency: Currency, sek_micros: Option<u32>) -> Result<(), sqlx::Error> {
+    if currency == Currency::Sek {
+        return Ok(());
+    }
+    match sek_micros.filter(|r| *r > 0) {
+        Some(sek_micros) => {
+            let sek_micros =
+                i32::try_from(sek_micros).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+            query!(
+                "INSERT INTO currency_rates(currency, sek_micros) VALUES($1, $2)
+                ON CONFLICT (currency) DO UPDATE SET sek_micros=excluded.sek_micros, updated=CURRENT_TIMESTAMP",
+                currency.code(),
+                sek_micros
+            )
+            .execute(POOL.get().unwrap())
+            .await?;
+        }
+        None => {
+            query!(
+                "DELETE FROM currency_rates WHERE currency = $1",
+                currency.code()
+            )
+            .execute(POOL.get().unwrap())
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// The currency and rate an order was reserved with.
+pub async fn order_rate(reservation: &str) -> Result<ExchangeRate, crate::checkout::CheckoutError> {
+    let uuid = sqlx::types::Uuid::parse_str(reservation)?;
+    let r = query!(
+        "SELECT currency, sek_micros FROM pending_orders WHERE id = $1",
+        uuid
+    )
+    .fetch_one(POOL.get().unwrap())
+    .await?;
+    Ok(ExchangeRate {
+        currency: Currency::from_code(&r.currency).unwrap_or_default(),
+        sek_micros: r.sek_micros as u32,
+    })
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use sjf_api::product::{GetProductsRequest, Product};
+use sqlx::query_file;
+
+use crate::postgres::POOL;
+
+pub struct FeedProduct {
+    pub product: Product,
+    pub google_category: Option<u32>,
+}
+
+/// Every product with the Google product category it inherits.
+pub async fn products() -> Result<Vec<FeedProduct>, sqlx::Error> {
+    let categories: HashMap<u32, Option<u32>> = query_file!("sql/feed/google_categories.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| (r.id as u32, r.google_category.map(|c| c as u32)))
+        .collect();
+
+    let products = crate::product::get_specified_products(GetProductsRequest {
+        product_ids: categories.keys().copied().collect(),
+    })
+    .await?;
+
+    let mut res: Vec<FeedProduct> = products
+        .into_iter()
+        .map(|product| FeedProduct {
+            google_category: categories.get(&product.id).copied().flatten(),
+            product,
+        })
+        .collect();
+    res.sort_by_key(|p| p.product.id);
+    Ok(res)
+}
@@ -0,0 +1,86 @@
+//! Health of the database and the background jobs, for the health checks.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::query;
+
+use crate::postgres::POOL;
+
+static HEARTBEATS: Mutex<BTreeMap<&'static str, Heartbeat>> = Mutex::new(BTreeMap::new());
+
+/// When a background job last ran and how often it should.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    pub job: &'static str,
+    pub last: DateTime<Utc>,
+    pub interval: Duration,
+}
+
+impl Heartbeat {
+    /// A job is late once it missed a run, with a minute to spare for slow runs.
+    pub fn is_late(&self, now: DateTime<Utc>) -> bool {
+        let allowed = self
+            .interval
+            .checked_mul(2)
+            .and_then(|d| TimeDelta::from_std(d).ok())
+            .and_then(|d| d.checked_add(&TimeDelta::minutes(1)));
+        allowed.is_some_and(|allowed| now - self.last > allowed)
+    }
+}
+
+/// Records that `job` is running, every `interval` at the latest.
+pub fn beat(job: &'static str, interval: Duration) {
+    let heartbeat = Heartbeat {
+        job,
+        last: Utc::now(),
+        interval,
+    };
+    HEARTBEATS.lock().unwrap().insert(job, heartbeat);
+}
+
+/// The jobs that have started, by name.
+pub fn heartbeats() -> Vec<Heartbeat> {
+    HEARTBEATS.lock().unwrap().values().cloned().collect()
+}
+
+/// Checks that a connection can be had from the pool and used.
+pub async fn ping() -> Result<(), sqlx::Error> {
+    let pool = POOL.get().ok_or(sqlx::Error::PoolClosed)?;
+    query!("SELECT 1 AS one").fetch_one(pool).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(last: DateTime<Utc>) -> Heartbeat {
+        Heartbeat {
+            job: "test",
+            last,
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn late_after_two_missed_intervals_and_a_minute() {
+        let last: DateTime<Utc> = "2026-03-02T12:00:00Z".parse().unwrap();
+        let heartbeat = heartbeat(last);
+        assert!(!heartbeat.is_late(last));
+        assert!(!heartbeat.is_late(last + TimeDelta::minutes(3)));
+        assert!(heartbeat.is_late(last + TimeDelta::minutes(3) + TimeDelta::seconds(1)));
+    }
+
+    #[test]
+    fn long_intervals_are_never_late() {
+        let last: DateTime<Utc> = "2026-03-02T12:00:00Z".parse().unwrap();
+        let heartbeat = Heartbeat {
+            interval: Duration::MAX,
+            ..heartbeat(last)
+        };
+        assert!(!heartbeat.is_late(last + TimeDelta::days(365)));
+    }
+}
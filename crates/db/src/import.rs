@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use sjf_api::import::{
+    COLUMNS, ImageRef, ImportAction, ImportChange, ImportReport, ImportRow, RowError, write_csv,
+};
+use sjf_api::product::ARTICLE_PREFIX;
+use sqlx::query;
+
+use crate::postgres::POOL;
+use crate::product::{Product, create_product_in, get_all_products, update_product_in};
+
+/// Every product in the import format, so that an export edited in a
+/// spreadsheet can be imported again. Products without a SKU are exported
+/// with their article number.
+pub async fn export() -> Result<String, sqlx::Error> {
+    let paths: HashMap<u32, String> = crate::category::get_paths()
+        .await?
+        .into_iter()
+        .map(|(path, id)| (id, path))
+        .collect();
+
+    let mut records = vec![COLUMNS.map(String::from).to_vec()];
+    for product in get_all_products().await? {
+        let row = ImportRow {
+            line: 0,
+            sku: Some(
+                product
+                    .sku
+                    .clone()
+                    .unwrap_or_else(|| format!("{}{}", ARTICLE_PREFIX, product.id)),
+            ),
+            name: product.name,
+            description: product.description,
+            price: product.price.max(0) as u32,
+            quantity: product.quantity.map(|q| q.max(0) as u32),
+            tax_rate: product.tax_rate,
+            category: paths
+                .get(&product.category)
+                .map(|p| p.split('/').map(String::from).collect())
+                .unwrap_or_default(),
+            images: product.images.into_iter().map(ImageRef::Existing).collect(),
+        };
+        records.push(row.to_record());
+    }
+    Ok(write_csv(records))
+}
+
+/// What an import will do, worked out without changing anything.
+pub struct ImportPlan {
+    pub report: ImportReport,
+    rows: Vec<PlannedRow>,
+}
+
+/// A row to apply, with its category and the product it updates.
+struct PlannedRow {
+    row: ImportRow,
+    category: u32,
+    existing: Option<Product>,
+}
+
+impl ImportPlan {
+    /// Images that have to be uploaded before the plan is applied, with the
+    /// first line they are on.
+    pub fn new_images(&self) -> BTreeMap<ImageRef, usize> {
+        let mut images = BTreeMap::new();
+        for PlannedRow { row, .. } in self.rows.iter().rev() {
+            for image in row.images.iter() {
+                if !matches!(image, ImageRef::Existing(_)) {
+                    images.insert(image.clone(), row.line);
+                }
+            }
+        }
+        images
+    }
+}
+
+fn change<T: PartialEq + std::fmt::Display>(
+    changes: &mut Vec<String>,
+    field: &str,
+    old: T,
+    new: T,
+) {
+    if old != new {
+        changes.push(format!("{}: {} → {}", field, old, new));
+    }
+}
+
+fn quantity(quantity: Option<i32>) -> String {
+    quantity
+        .map(|q| q.to_string())
+        .unwrap_or_else(|| "-".into())
+}
+
+/// Matches validated rows against the products in the shop and reports what
+/// importing them changes. `files` are the names of the images uploaded with
+/// the import.
+pub async fn plan(
+    rows: Vec<ImportRow>,
+    mut errors: Vec<RowError>,
+    files: &BTreeSet<String>,
+) -> Result<ImportPlan, sqlx::Error> {
+    let paths = crate::category::get_paths().await?;
+    let mut by_id: HashMap<i32, Product> = get_all_products()
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+    let by_sku: HashMap<String, i32> = by_id
+        .values()
+        .filter_map(|p| Some((p.sku.clone()?, p.id)))
+        .collect();
+
+    let image_ids: Vec<i32> = rows
+        .iter()
+        .flat_map(|r| r.images.iter())
+        .filter_map(|i| match i {
+            ImageRef::Existing(id) => Some(*id as i32),
+            _ => None,
+        })
+        .collect();
+    let known_images: BTreeSet<u32> = query!(
+        "SELECT image_id FROM images WHERE image_id = ANY($1)",
+        &image_ids
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| r.image_id as u32)
+    .collect();
+
+    let mut report = ImportReport::default();
+    let mut planned = vec![];
+    for row in rows {
+        let line = row.line;
+        let mut error = |message: String| errors.push(RowError { line, message });
+
+        let Some(category) = paths.get(&row.category.join("/")).copied() else {
+            error(format!("Okänd kategori {}", row.category.join("/")));
+            continue;
+        };
+        if let Some(image) = row.images.iter().find(|i| match i {
+            ImageRef::Existing(id) => !known_images.contains(id),
+            ImageRef::File(name) => !files.contains(name),
+            ImageRef::Url(_) => false,
+        }) {
+            error(format!("Bilden {} finns inte", image.export()));
+            continue;
+        }
+
+        let id = match &row.sku {
+            Some(sku) => by_sku.get(sku).copied().or_else(|| {
+                row.article_id()
+                    .map(|id| id as i32)
+                    .filter(|id| by_id.get(id).is_some_and(|p| p.sku.is_none()))
+            }),
+            None => None,
+        };
+        let existing = match id {
+            Some(id) => match by_id.remove(&id) {
+                Some(product) => Some(product),
+                None => {
+                    error(format!(
+                        "Produkten {} finns på flera rader",
+                        row.sku.as_deref().unwrap_or_default()
+                    ));
+                    continue;
+                }
+            },
+            None if row.article_id().is_some() => {
+                error(format!(
+                    "Produkten {} finns inte",
+                    row.sku.as_deref().unwrap_or_default()
+                ));
+                continue;
+            }
+            None => None,
+        };
+
+        let (action, changes) = match &existing {
+            None => (ImportAction::Create, vec![]),
+            Some(product) if product.category != category => {
+                error("Kategorin kan inte ändras vid import".into());
+                continue;
+            }
+            Some(product) => {
+                let mut changes = vec![];
+                if product.name != row.name {
+                    changes.push(format!("namn: {} → {}", product.name, row.name));
+                }
+                if product.description != row.description {
+                    changes.push("beskrivning".into());
+                }
+                change(&mut changes, "pris", product.price, row.price as i32);
+                change(
+                    &mut changes,
+                    "antal",
+                    quantity(product.quantity),
+                    quantity(row.quantity.map(|q| q as i32)),
+                );
+                change(&mut changes, "moms", product.tax_rate, row.tax_rate);
+                let images: Vec<ImageRef> = product
+                    .images
+                    .iter()
+                    .map(|i| ImageRef::Existing(*i))
+                    .collect();
+                if images.iter().collect::<BTreeSet<_>>() != row.images.iter().collect() {
+                    changes.push(format!("bilder: {} → {}", images.len(), row.images.len()));
+                }
+                match changes.is_empty() {
+                    true => (ImportAction::Unchanged(product.id as u32), changes),
+                    false => (ImportAction::Update(product.id as u32), changes),
+                }
+            }
+        };
+
+        report.changes.push(ImportChange {
+            line,
+            sku: row.sku.clone(),
+            name: row.name.clone(),
+            action,
+            changes,
+        });
+        if !matches!(action, ImportAction::Unchanged(_)) {
+            planned.push(PlannedRow {
+                row,
+                category,
+                existing,
+            });
+        }
+    }
+
+    errors.sort_by_key(|e| e.line);
+    report.errors = errors;
+    Ok(ImportPlan {
+        report,
+        rows: planned,
+    })
+}
+
+/// Creates and updates the products of a plan without errors in one
+/// transaction. `uploaded` holds the ids of the plan's new images.
+pub async fn apply(
+    plan: ImportPlan,
+    uploaded: &BTreeMap<ImageRef, u32>,
+) -> Result<ImportReport, sqlx::Error> {
+    let mut report = plan.report;
+    if !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    let mut tx = POOL.get().unwrap().begin().await?;
+    for PlannedRow {
+        row,
+        category,
+        existing,
+    } in plan.rows
+    {
+        let mut images = vec![];
+        for image in &row.images {
+            let id = match image {
+                ImageRef::Existing(id) => Some(*id),
+                other => uploaded.get(other).copied(),
+            };
+            if let Some(id) = id.filter(|id| !images.contains(id)) {
+                images.push(id);
+            }
+        }
+
+        match existing {
+            Some(product) => {
+                // Products matched by article number keep having none.
+                let sku = match row.article_id() == Some(product.id as u32) {
+                    true => product.sku.clone(),
+                    false => row.sku,
+                };
+                let product = Product {
+                    name: row.name,
+                    description: row.description,
+                    price: row.price as i32,
+                    quantity: row.quantity.map(|q| q as i32),
+                    tax_rate: row.tax_rate,
+                    images,
+                    sku,
+                    ..product
+                };
+                update_product_in(&mut tx, product).await?;
+            }
+            None => {
+                let product = Product {
+                    id: 0,
+                    name: row.name,
+                    price: row.price as i32,
+                    description: row.description,
+                    quantity: row.quantity.map(|q| q as i32),
+                    created: Default::default(),
+                    updated: Default::default(),
+                    tax_rate: row.tax_rate,
+                    images,
+                    category,
+                    reorder_threshold: None,
+                    stock_policy: Default::default(),
+                    attributes: BTreeMap::new(),
+                    translations: BTreeMap::new(),
+                    sku: row.sku,
+                };
+                create_product_in(&mut tx, product).await?;
+            }
+        }
+    }
+    crate::image::update_image_view_later(&mut *tx).await?;
+    tx.commit().await?;
+
+    report.applied = true;
+    Ok(report)
+}
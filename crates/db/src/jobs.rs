@@ -0,0 +1,492 @@
+//! Durable background jobs, stored in the `jobs` table so that they survive
+//! restarts and run once across every replica of the shop.
+//!
+//! Each crate registers the jobs it can run in its `init`, the worker started
+//! by [`start`] claims due jobs of those kinds with `FOR UPDATE SKIP LOCKED`.
+//! Failed jobs are retried with a growing delay and end up `dead` after
+//! [`Job::MAX_ATTEMPTS`], where `sjf-admin jobs` can list and retry them. A
+//! job that isn't finished within [`LOCK_DURATION`], because its server
+//! stopped, is claimed again.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::FutureExt;
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::{PgExecutor, query, query_file};
+use tokio::sync::{Notify, Semaphore};
+use tracing::{error, warn};
+
+use crate::postgres::POOL;
+
+/// How long a claimed job may run before another worker may claim it again.
+pub const LOCK_DURATION: Duration = Duration::from_secs(10 * 60);
+/// How often the worker looks for due jobs when it isn't woken up.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the periodic jobs are checked to be scheduled.
+const ENSURE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Jobs run at the same time by one server.
+const CONCURRENCY: usize = 4;
+
+pub type JobResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Work that is stored as JSON and run by a worker, possibly on another
+/// server.
+pub trait Job: Serialize + DeserializeOwned + Send + 'static {
+    /// Identifies the job in the table, must be unique and stay the same
+    /// between releases.
+    const KIND: &'static str;
+    const MAX_ATTEMPTS: i32 = 8;
+    /// At most one job of this kind is waiting or running at a time. Jobs
+    /// enqueued meanwhile are merged into it, or run it again once it's done
+    /// if it was already running.
+    const ONCE: bool = false;
+
+    /// Runs the job with the bytes enqueued along with it. Errors are retried.
+    fn run(self, data: Option<Vec<u8>>) -> impl Future<Output = JobResult> + Send;
+}
+
+type Handler = Arc<dyn Fn(Value, Option<Vec<u8>>) -> BoxFuture<'static, JobResult> + Send + Sync>;
+
+#[derive(Clone)]
+struct Registered {
+    handler: Handler,
+    periodic: Option<Periodic>,
+}
+
+#[derive(Clone)]
+struct Periodic {
+    every: Duration,
+    payload: Value,
+    max_attempts: i32,
+}
+
+static REGISTERED: LazyLock<Mutex<HashMap<&'static str, Registered>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// Wakes the worker when a job is enqueued or finished by this server.
+static WAKE: Notify = Notify::const_new();
+
+fn handler<T: Job>() -> Handler {
+    Arc::new(
+        |payload: Value, data: Option<Vec<u8>>| -> BoxFuture<'static, JobResult> {
+            Box::pin(async move {
+                let job: T = serde_json::from_value(payload)?;
+                job.run(data).await
+            })
+        },
+    )
+}
+
+/// Lets the worker of this server run jobs of type `T`.
+pub fn register<T: Job>() {
+    let registered = Registered {
+        handler: handler::<T>(),
+        periodic: None,
+    };
+    REGISTERED.lock().unwrap().insert(T::KIND, registered);
+}
+
+/// Runs `job` every `every`, counted from when the previous run finished.
+pub fn register_periodic<T: Job>(job: T, every: Duration) {
+    let payload = serde_json::to_value(job).expect("Job payload can't be serialized");
+    let registered = Registered {
+        handler: handler::<T>(),
+        periodic: Some(Periodic {
+            every,
+            payload,
+            max_attempts: T::MAX_ATTEMPTS,
+        }),
+    };
+    REGISTERED.lock().unwrap().insert(T::KIND, registered);
+}
+
+fn registered(kind: &str) -> Option<Registered> {
+    REGISTERED.lock().unwrap().get(kind).cloned()
+}
+
+async fn insert<'e>(
+    e: impl PgExecutor<'e>,
+    kind: &str,
+    payload: Value,
+    data: Option<Vec<u8>>,
+    once: bool,
+    max_attempts: i32,
+    run_at: DateTime<Utc>,
+) -> Result<i64, sqlx::Error> {
+    let id = query_file!(
+        "sql/jobs/enqueue.sql",
+        kind,
+        payload,
+        data,
+        once,
+        max_attempts,
+        run_at
+    )
+    .fetch_one(e)
+    .await?
+    .id;
+    if run_at <= Utc::now() {
+        WAKE.notify_one();
+    }
+    Ok(id)
+}
+
+/// Adds `job` to the queue as part of a transaction, due at `run_at`.
+pub(crate) async fn enqueue_in<'e, T: Job>(
+    e: impl PgExecutor<'e>,
+    job: &T,
+    data: Option<Vec<u8>>,
+    run_at: DateTime<Utc>,
+) -> Result<i64, sqlx::Error> {
+    let payload = serde_json::to_value(job).map_err(|e| sqlx::Error::Encode(e.into()))?;
+    insert(e, T::KIND, payload, data, T::ONCE, T::MAX_ATTEMPTS, run_at).await
+}
+
+/// Adds `job` to the queue to be run as soon as possible.
+pub async fn enqueue<T: Job>(job: &T) -> Result<i64, sqlx::Error> {
+    enqueue_in(POOL.get().unwrap(), job, None, Utc::now()).await
+}
+
+/// Adds `job` to the queue along with bytes that are too large for JSON, such
+/// as image data. The bytes are dropped once the job succeeded.
+pub async fn enqueue_with_data<T: Job>(job: &T, data: Vec<u8>) -> Result<i64, sqlx::Error> {
+    enqueue_in(POOL.get().unwrap(), job, Some(data), Utc::now()).await
+}
+
+/// Adds `job` to the queue to be run at `run_at`.
+pub async fn schedule<T: Job>(job: &T, run_at: DateTime<Utc>) -> Result<i64, sqlx::Error> {
+    enqueue_in(POOL.get().unwrap(), job, None, run_at).await
+}
+
+/// Makes sure every periodic job is waiting or running.
+async fn ensure_periodic() -> Result<(), sqlx::Error> {
+    let periodic: Vec<_> = REGISTERED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|(kind, r)| Some((*kind, r.periodic.clone()?)))
+        .collect();
+    for (kind, periodic) in periodic {
+        query!(
+            "INSERT INTO jobs(kind, payload, once, max_attempts) VALUES ($1, $2, true, $3)
+            ON CONFLICT (kind) WHERE once AND ((status = 'pending') OR (status = 'running')) DO NOTHING",
+            kind,
+            periodic.payload,
+            periodic.max_attempts
+        )
+        .execute(POOL.get().unwrap())
+        .await?;
+    }
+    Ok(())
+}
+
+struct Claimed {
+    id: i64,
+    kind: String,
+    payload: Value,
+    data: Option<Vec<u8>>,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+async fn claim(limit: usize) -> Result<Vec<Claimed>, sqlx::Error> {
+    let pool = POOL.get().unwrap();
+    let abandoned = query_file!("sql/jobs/abandoned.sql")
+        .execute(pool)
+        .await?
+        .rows_affected();
+    if abandoned > 0 {
+        warn!(
+            "{} jobs stopped without finishing too many times",
+            abandoned
+        );
+    }
+
+    let kinds: Vec<String> = REGISTERED
+        .lock()
+        .unwrap()
+        .keys()
+        .map(|k| k.to_string())
+        .collect();
+    let locked_until = Utc::now() + TimeDelta::from_std(LOCK_DURATION).unwrap_or_default();
+    let jobs = query_file!("sql/jobs/claim.sql", &kinds, limit as i64, locked_until)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|r| Claimed {
+            id: r.id,
+            kind: r.kind,
+            payload: r.payload,
+            data: r.data,
+            attempts: r.attempts,
+            max_attempts: r.max_attempts,
+        })
+        .collect();
+    Ok(jobs)
+}
+
+/// 30s after the first failure, doubling up to an hour.
+fn retry_delay(attempts: i32) -> TimeDelta {
+    let delay = TimeDelta::seconds(30) * 2i32.pow(attempts.clamp(1, 8) as u32 - 1);
+    delay.min(TimeDelta::hours(1))
+}
+
+async fn finish(job: &Claimed, periodic: Option<Duration>, result: JobResult) -> &'static str {
+    let pool = POOL.get().unwrap();
+    let now = Utc::now();
+    let finished = match result {
+        Ok(()) => {
+            let next = periodic.map(|every| now + TimeDelta::from_std(every).unwrap_or_default());
+            query_file!("sql/jobs/succeeded.sql", job.id, job.attempts, next)
+                .execute(pool)
+                .await
+                .map(|_| "ok")
+        }
+        Err(e) => {
+            let retry_at = now + retry_delay(job.attempts);
+            let status = query_file!(
+                "sql/jobs/failed.sql",
+                job.id,
+                job.attempts,
+                retry_at,
+                e.to_string()
+            )
+            .fetch_optional(pool)
+            .await
+            .map(|r| r.map(|r| r.status));
+            match status {
+                Ok(Some(status)) if status == "dead" => {
+                    error!(
+                        "Job {} {} failed {} times, giving up {}",
+                        job.kind, job.id, job.attempts, e
+                    );
+                    Ok("dead")
+                }
+                Ok(_) => {
+                    warn!(
+                        "Job {} {} failed, attempt {} of {} {}",
+                        job.kind, job.id, job.attempts, job.max_attempts, e
+                    );
+                    Ok("retry")
+                }
+                Err(e) => Err(e),
+            }
+        }
+    };
+    // The job is claimed again once its lock expires
+    finished.unwrap_or_else(|e| {
+        error!("Failed to finish job {} {} {}", job.kind, job.id, e);
+        "unfinished"
+    })
+}
+
+async fn run(mut job: Claimed) {
+    let Some(registered) = registered(&job.kind) else {
+        return;
+    };
+    let start = Instant::now();
+    let future = (registered.handler)(job.payload.take(), job.data.take());
+    let result = match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(result) => result,
+        Err(_) => Err("Panicked".into()),
+    };
+    let labels = [("kind", job.kind.clone())];
+    metrics::histogram!("job_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+
+    let result = finish(&job, registered.periodic.map(|p| p.every), result).await;
+    let labels = [("kind", job.kind.clone()), ("result", result.to_string())];
+    metrics::counter!("jobs_run", &labels).increment(1);
+}
+
+async fn work() {
+    let permits = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut ensured: Option<Instant> = None;
+    loop {
+        crate::health::beat("job_worker", POLL_INTERVAL);
+        if ensured.is_none_or(|t| t.elapsed() >= ENSURE_INTERVAL) {
+            match ensure_periodic().await {
+                Ok(()) => ensured = Some(Instant::now()),
+                Err(e) => error!("Failed to schedule periodic jobs {}", e),
+            }
+        }
+
+        let free = permits.available_permits();
+        let jobs = match free {
+            0 => vec![],
+            _ => claim(free).await.unwrap_or_else(|e| {
+                error!("Failed to claim jobs {}", e);
+                vec![]
+            }),
+        };
+        let more = free > 0 && jobs.len() == free;
+        for job in jobs {
+            let permit = permits.clone().try_acquire_owned().unwrap();
+            crate::tasks::spawn_tracked("job", async move {
+                run(job).await;
+                drop(permit);
+                WAKE.notify_one();
+            });
+        }
+
+        if !more {
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {},
+                _ = WAKE.notified() => {},
+            }
+        }
+    }
+}
+
+/// Claims the due jobs once and runs them to completion, like one round of
+/// the worker. Returns the ids of the jobs that ran.
+pub async fn run_due(limit: usize) -> Result<Vec<i64>, sqlx::Error> {
+    ensure_periodic().await?;
+    let jobs = claim(limit).await?;
+    let ids = jobs.iter().map(|j| j.id).collect();
+    for job in jobs {
+        run(job).await;
+    }
+    Ok(ids)
+}
+
+/// Starts the worker running the registered jobs.
+pub fn start() {
+    crate::tasks::supervise("job_worker", work);
+}
+
+/// A job that hasn't succeeded yet.
+#[derive(Debug)]
+pub struct JobInfo {
+    pub id: i64,
+    pub kind: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+/// The waiting, running and dead jobs, or only the dead ones, oldest first.
+pub async fn unfinished(dead_only: bool) -> Result<Vec<JobInfo>, sqlx::Error> {
+    let res = query!(
+        "SELECT id, kind, status, attempts, max_attempts, run_at, last_error FROM jobs
+        WHERE status = 'dead' OR (NOT $1 AND status <> 'done') ORDER BY run_at, id",
+        dead_only
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| JobInfo {
+        id: r.id,
+        kind: r.kind,
+        status: r.status,
+        attempts: r.attempts,
+        max_attempts: r.max_attempts,
+        run_at: r.run_at,
+        last_error: r.last_error,
+    })
+    .collect();
+    Ok(res)
+}
+
+/// What [`retry`] did with a dead job.
+#[derive(Debug, PartialEq)]
+pub enum Retried {
+    /// The job waits to run again with its attempts reset.
+    Queued,
+    /// The job is one of a kind that runs once at a time and another one of
+    /// its kind was already waiting or running. The dead job was merged into
+    /// that job, which has the returned id.
+    Merged(i64),
+    /// There is no dead job with the id.
+    NotDead,
+}
+
+/// Runs a dead job again with its attempts reset.
+pub async fn retry(id: i64) -> Result<Retried, sqlx::Error> {
+    let mut tx = POOL.get().unwrap().begin().await?;
+    let Some(dead) = query!(
+        "SELECT kind, once FROM jobs WHERE id = $1 AND status = 'dead' FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(Retried::NotDead);
+    };
+
+    if dead.once {
+        // Merged like enqueue.sql does, the unique index allows only one of them
+        let merged = query!(
+            "UPDATE jobs SET
+                run_at = CASE
+                    WHEN status = 'running' AND NOT rerun THEN CURRENT_TIMESTAMP
+                    ELSE LEAST(run_at, CURRENT_TIMESTAMP)
+                END,
+                rerun = rerun OR status = 'running'
+            WHERE kind = $1 AND once AND status IN ('pending', 'running')
+            RETURNING id",
+            dead.kind
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(merged) = merged {
+            query!("DELETE FROM jobs WHERE id = $1", id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            WAKE.notify_one();
+            return Ok(Retried::Merged(merged.id));
+        }
+    }
+
+    query!(
+        "UPDATE jobs SET status = 'pending', attempts = 0, run_at = CURRENT_TIMESTAMP, finished = NULL
+        WHERE id = $1",
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    WAKE.notify_one();
+    Ok(Retried::Queued)
+}
+
+/// Deletes succeeded jobs after a week and dead jobs after a month.
+pub(crate) async fn purge() -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM jobs WHERE (status = 'done' AND finished < CURRENT_TIMESTAMP - INTERVAL '7 days')
+        OR (status = 'dead' AND finished < CURRENT_TIMESTAMP - INTERVAL '30 days')"
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn update_gauges() -> Result<(), sqlx::Error> {
+    let counts = query!(
+        r#"SELECT kind, status, count(*) AS "count!" FROM jobs WHERE status <> 'done' GROUP BY kind, status"#
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?;
+    let kinds: Vec<&'static str> = REGISTERED.lock().unwrap().keys().copied().collect();
+    for kind in kinds {
+        for status in ["pending", "running", "dead"] {
+            let count = counts
+                .iter()
+                .find(|c| c.kind == kind && c.status == status)
+                .map(|c| c.count)
+                .unwrap_or(0);
+            let labels = [("kind", kind), ("status", status)];
+            metrics::gauge!("jobs", &labels).set(count as f64);
+        }
+    }
+    Ok(())
+}
@@ -1,17 +1,39 @@
 mod postgres;
 
+pub mod attribute;
 pub mod auth;
+pub mod backup;
+pub mod cart;
 pub mod category;
 pub mod checkout;
+pub mod currency;
+pub mod feed;
+pub mod health;
+pub mod import;
+pub mod jobs;
+pub mod orders;
 pub mod product;
+pub mod recovery;
+pub mod related;
+pub mod reservation;
+pub mod review;
+pub mod sitemap;
+pub mod stock;
+pub mod tasks;
+pub mod tax;
+pub mod translation;
+pub mod wishlist;
 pub use postgres::image;
 pub use product::*;
+use std::time::Duration;
+
+use sjf_config::{CheckoutConfig, DatabaseConfig};
 pub use sqlx::Error;
 use sqlx::{query, query_file};
-use tracing::{error, info};
+use tracing::error;
 
 async fn update_gauges() -> Result<(), sqlx::Error> {
-    query!("ANALYZE products,users,images,image_variants,pending_orders,product_categories,product_images, product_reservations")
+    query!("ANALYZE products,users,images,image_variants,pending_orders,product_categories,product_images, product_reservations, stock_alerts")
     .execute(crate::postgres::POOL.get().unwrap()).await?;
     let rows = query_file!("sql/size_estimates.sql")
         .fetch_all(crate::postgres::POOL.get().unwrap())
@@ -22,37 +44,68 @@ async fn update_gauges() -> Result<(), sqlx::Error> {
         metrics::gauge!("postgres_table_size_estimate", &lables).set(row.estimate)
     }
 
+    stock::update_stock_gauges().await?;
+    jobs::update_gauges().await?;
+
     Ok(())
 }
 
-pub async fn init() -> bool {
-    if let Err(e) = postgres::init().await {
+/// Connects to the database and creates the tables, without the background
+/// work of [`init`]. For tools that run next to the shop.
+pub async fn connect(
+    database: &DatabaseConfig,
+    checkout: &CheckoutConfig,
+) -> Result<(), sqlx::Error> {
+    cart::configure(checkout);
+    reservation::configure(checkout);
+    tax::configure(checkout);
+    postgres::init(database).await
+}
+
+/// Refreshes the materialized views of category paths and product images,
+/// which are otherwise refreshed as the catalogue is edited.
+pub async fn refresh_views() -> Result<(), sqlx::Error> {
+    let pool = postgres::POOL.get().unwrap();
+    category::update_paths_view(pool, false).await?;
+    image::update_image_view(pool, false).await
+}
+
+/// Closes the connections of the pool once the queries using them are done.
+pub async fn close() {
+    if let Some(pool) = postgres::POOL.get() {
+        pool.close().await;
+    }
+}
+
+pub async fn init(database: &DatabaseConfig, checkout: &CheckoutConfig) -> bool {
+    if let Err(e) = connect(database, checkout).await {
         tracing::error!("Failed to initialize DB connection {:#?}", e);
         return false;
     }
 
-    tokio::task::spawn(async {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
-        loop {
-            interval.tick().await;
-            info!("Removing stale reservations");
-            match checkout::undo_old_reservations().await {
-                Err(e) => error!("Periodic reservation cleanup failed with error {}", e),
-                _ => (),
-            }
-        }
-    });
+    jobs::register::<image::RefreshImageView>();
+    jobs::register::<category::RefreshPaths>();
+    jobs::register_periodic(
+        reservation::ReleaseReservations,
+        reservation::ReleaseReservations::EVERY
+            .to_std()
+            .unwrap_or_default(),
+    );
+    jobs::register_periodic(related::UpdateCoPurchases, Duration::from_secs(60 * 60));
+    jobs::register_periodic(cart::Cleanup, Duration::from_secs(10 * 60));
 
-    tokio::task::spawn(async {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
+    // Gauges are kept by each server, so every server updates its own
+    tasks::supervise("db_maintenance", || async {
+        let period = Duration::from_secs(10 * 60);
+        let mut interval = tokio::time::interval(period);
         loop {
             interval.tick().await;
-            match update_gauges().await {
-                Err(e) => error!("Periodic gauge check failed with error {}", e),
-                _ => (),
+            health::beat("db_maintenance", period);
+            if let Err(e) = update_gauges().await {
+                error!("Periodic gauge check failed with error {}", e)
             }
         }
     });
 
-    return true;
+    true
 }
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use crate::postgres::POOL;
+use sjf_api::currency::{Currency, ExchangeRate};
+use sjf_api::i18n::Locale;
+use sjf_api::order::{Order, OrderedProduct};
+use sqlx::{Postgres, Transaction, query, query_file, types::Uuid};
+
+/// Keeps the lines of a reservation that is being committed as an order.
+pub(crate) async fn record(
+    tx: &mut Transaction<'static, Postgres>,
+    reservation: Uuid,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO orders(id, currency, sek_micros, locale) SELECT id, currency, sek_micros, locale FROM pending_orders WHERE id=$1
+        ON CONFLICT (id) DO UPDATE SET currency=excluded.currency, sek_micros=excluded.sek_micros, locale=excluded.locale",
+        reservation
+    )
+    .execute(&mut **tx)
+    .await?;
+    query_file!("sql/orders/record_lines.sql", reservation)
+        .execute(&mut **tx)
+        .await?;
+
+    let totals = query!(
+        r#"SELECT sum(quantity)::integer AS "quantity!", sum(quantity * price)::integer AS "total!"
+        FROM order_lines WHERE order_id=$1"#,
+        reservation
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    let shipping = sjf_api::checkout::shipping_price(totals.quantity as u32, totals.total as u32);
+    query!(
+        "UPDATE orders SET shipping=$2 WHERE id=$1",
+        reservation,
+        shipping as i32
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    crate::tax::apply(tx, reservation).await
+}
+
+/// Remembers the email Stripe collected for an order. The order may not have
+/// been committed yet, depending on whether the customer or Stripe gets back first.
+pub async fn set_email(reservation: &str, email: &str) -> Result<(), sqlx::Error> {
+    let Ok(reservation) = Uuid::from_str(reservation) else {
+        return Ok(());
+    };
+    query!(
+        "INSERT INTO orders(id, email) SELECT $1, $2
+        WHERE EXISTS (SELECT 1 FROM pending_orders WHERE id=$1) OR EXISTS (SELECT 1 FROM orders WHERE id=$1)
+        ON CONFLICT (id) DO UPDATE SET email=excluded.email",
+        reservation,
+        email.to_lowercase()
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+pub async fn recent(limit: u32) -> Result<Vec<Order>, sqlx::Error> {
+    let res = query_file!("sql/orders/recent.sql", limit as i64)
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| Order {
+            id: r.id.to_string(),
+            email: r.email,
+            created: r.created,
+            delivered: r.delivered,
+            exchange_rate: ExchangeRate {
+                currency: Currency::from_code(&r.currency).unwrap_or_default(),
+                sek_micros: r.sek_micros as u32,
+            },
+            lines: r
+                .product_ids
+                .into_iter()
+                .zip(r.names)
+                .zip(r.quantities.into_iter().zip(r.prices))
+                .map(|((product_id, name), (quantity, price))| OrderedProduct {
+                    product_id: product_id.map(|id| id as u32),
+                    name,
+                    quantity: quantity as u32,
+                    price: price as u32,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(res)
+}
+
+pub async fn mark_delivered(id: &str) -> Result<bool, sqlx::Error> {
+    let Ok(id) = Uuid::from_str(id) else {
+        return Ok(false);
+    };
+    let res = query!(
+        "UPDATE orders SET delivered=CURRENT_TIMESTAMP WHERE id=$1 AND delivered IS NULL",
+        id
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// A delivered order whose customer hasn't been asked for reviews yet.
+pub struct DueReviewRequest {
+    pub id: Uuid,
+    pub email: String,
+    /// Language the customer shopped in.
+    pub locale: Locale,
+    /// Review token and product name of every line.
+    pub lines: Vec<(Uuid, String)>,
+}
+
+pub async fn due_review_requests(delay_days: i32) -> Result<Vec<DueReviewRequest>, sqlx::Error> {
+    let res = query_file!("sql/orders/due_review_requests.sql", delay_days)
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| DueReviewRequest {
+            id: r.id,
+            email: r.email,
+            locale: Locale::from_code(&r.locale).unwrap_or_default(),
+            lines: r.line_ids.into_iter().zip(r.names).collect(),
+        })
+        .collect();
+    Ok(res)
+}
+
+pub async fn mark_review_requested(id: Uuid) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE orders SET review_requested=CURRENT_TIMESTAMP WHERE id=$1",
+        id
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
@@ -1,24 +1,13 @@
 use once_cell::sync::OnceCell;
+use sjf_config::DatabaseConfig;
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions, query_file, query_file_as};
 
 pub(crate) static POOL: OnceCell<Pool<Postgres>> = OnceCell::new();
 
-pub async fn init() -> Result<(), sqlx::Error> {
-    let url = dotenvy::var("DATABASE_URL").unwrap_or_else(|_| {
-        let user = dotenvy::var("POSTGRES_USER")
-            .expect("POSTGRES_USER environment variable has to be set. ");
-        let address = dotenvy::var("POSTGRES_ADDRESS")
-            .expect("POSTGRES_ADDRESS environment variable has to be set. ");
-        let database = dotenvy::var("POSTGRES_DB_NAME")
-            .expect("POSTGRES_DB_NAME environment variable has to be set. ");
-        let password = dotenvy::var("POSTGRES_PASSWORD")
-            .expect("POSTGRES_PASSWORD environment variable has to be set. ");
-        format!("postgres://{user}:{password}@{address}/{database}")
-    });
-
+pub async fn init(config: &DatabaseConfig) -> Result<(), sqlx::Error> {
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&url)
+        .max_connections(config.pool_size)
+        .connect(config.url.expose())
         .await?;
 
     {
@@ -65,6 +54,159 @@ pub async fn init() -> Result<(), sqlx::Error> {
         query_file!("sql/table_definitions/10-user-passkeys.sql")
             .execute(&mut *tx)
             .await?;
+        query_file!("sql/table_definitions/11-products_reorder_threshold.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/12-stock_alerts.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/13-stock_subscriptions.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/14-products_stock_policy.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/15-products_stock_policy_constraints.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/16-product_reservations_fulfilment.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/17-pending_orders_expires_at.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/18-carts.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/19-cart_items.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/20-abandoned_checkouts.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/21-checkout_reminder_opt_outs.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/22-users_role.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/23-users_stripe_customer.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/24-wishlist_items.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/25-orders.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/26-order_lines.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/27-reviews.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/28-related_products.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/29-co_purchases.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/30-category_attributes.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/31-product_attributes.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/32-product_categories_google_category.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/33-product_translations.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/34-category_translations.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/35-currency_rates.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/36-pending_orders_currency.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/37-orders_currency.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/38-orders_vat.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/39-order_lines_tax_rate.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/40-products_sku.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/41-users_disabled.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/42-admin_invites.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/43-jobs.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/44-jobs_due_index.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/45-jobs_once_index.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/46-users_name_role_index.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/47-order_lines_name.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/48-order_lines_name_backfill.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/49-order_lines_name_not_null.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/50-order_lines_product_nullable.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/51-order_lines_product_set_null.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/52-view_generation.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/53-schema_steps.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/54-jobs_split_notifications.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/55-order_lines_product_tax_rate.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/56-order_lines_product_tax_rate_backfill.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/57-product_reservations_backordered.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/58-pending_orders_locale.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/59-orders_locale.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/60-abandoned_checkouts_locale.sql")
+            .execute(&mut *tx)
+            .await?;
+        query_file!("sql/table_definitions/61-stock_subscriptions_locale.sql")
+            .execute(&mut *tx)
+            .await?;
         tx.commit().await?;
     }
 
@@ -79,7 +221,6 @@ pub mod image {
     use std::collections::BTreeMap;
 
     use sqlx::{Executor, query};
-    use tracing::error;
 
     use super::*;
     pub struct ImageInsertVariant {
@@ -99,6 +240,30 @@ pub mod image {
 
     pub async fn insert_image(
         image: ImageInsertRequest,
+    ) -> Result<Vec<InsertedImage>, sqlx::Error> {
+        insert_image_in(POOL.get().unwrap(), image).await
+    }
+
+    /// Inserts `image` and enqueues `upload` with the encoded bytes of every
+    /// variant in the same transaction, so no image is stored without its
+    /// uploads queued.
+    pub async fn insert_image_with_uploads<T: crate::jobs::Job>(
+        image: ImageInsertRequest,
+        data: Vec<Vec<u8>>,
+        upload: impl Fn(&InsertedImage) -> T,
+    ) -> Result<Vec<InsertedImage>, sqlx::Error> {
+        let mut tx = POOL.get().unwrap().begin().await?;
+        let inserted = insert_image_in(&mut *tx, image).await?;
+        for (id, data) in inserted.iter().zip(data) {
+            crate::jobs::enqueue_in(&mut *tx, &upload(id), Some(data), chrono::Utc::now()).await?;
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn insert_image_in<'e>(
+        e: impl sqlx::PgExecutor<'e>,
+        image: ImageInsertRequest,
     ) -> Result<Vec<InsertedImage>, sqlx::Error> {
         query_file_as!(
             InsertedImage,
@@ -113,7 +278,7 @@ pub mod image {
             image.variants[3].width,
             image.variants[3].height,
         )
-        .fetch_all(POOL.get().unwrap())
+        .fetch_all(e)
         .await
     }
 
@@ -150,6 +315,44 @@ pub mod image {
         Ok(res)
     }
 
+    /// Image and variant id of every stored image file.
+    pub async fn all_variants() -> Result<Vec<(u32, u32)>, sqlx::Error> {
+        let res =
+            query!("SELECT image_id, variant_id FROM image_variants ORDER BY image_id, variant_id")
+                .fetch_all(POOL.get().unwrap())
+                .await?
+                .into_iter()
+                .map(|r| (r.image_id as u32, r.variant_id as u32))
+                .collect();
+        Ok(res)
+    }
+
+    pub async fn all_images() -> Result<Vec<u32>, sqlx::Error> {
+        let res = query!("SELECT image_id FROM images ORDER BY image_id")
+            .fetch_all(POOL.get().unwrap())
+            .await?
+            .into_iter()
+            .map(|r| r.image_id as u32)
+            .collect();
+        Ok(res)
+    }
+
+    pub async fn set_variant_size(
+        variant_id: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), sqlx::Error> {
+        query!(
+            "UPDATE image_variants SET width=$2, height=$3 WHERE variant_id=$1",
+            variant_id as i32,
+            width as i32,
+            height as i32
+        )
+        .execute(POOL.get().unwrap())
+        .await?;
+        Ok(())
+    }
+
     pub async fn update_image_view<'c, E>(e: E, create: bool) -> Result<(), sqlx::Error>
     where
         E: Copy + Executor<'c, Database = Postgres>,
@@ -164,15 +367,30 @@ pub mod image {
         query!("REFRESH MATERIALIZED VIEW CONCURRENTLY product_image_info")
             .execute(e)
             .await?;
-        Ok(())
+        crate::sitemap::views_refreshed(e).await
+    }
+
+    /// Refreshes the view of product images, once for every change made
+    /// while it waits or runs.
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub struct RefreshImageView;
+
+    impl crate::jobs::Job for RefreshImageView {
+        const KIND: &'static str = "refresh_image_view";
+        const ONCE: bool = true;
+
+        async fn run(self, _: Option<Vec<u8>>) -> crate::jobs::JobResult {
+            update_image_view(POOL.get().unwrap(), false).await?;
+            Ok(())
+        }
     }
 
-    pub fn update_image_view_later() {
-        tokio::spawn(async {
-            let res = update_image_view(POOL.get().unwrap(), false).await;
-            if let Err(e) = res {
-                error!("Image view update failed! {:#?}", e);
-            }
-        });
+    /// Queues a refresh of the image view as part of the transaction that
+    /// changed the catalogue, so it's never committed without one.
+    pub(crate) async fn update_image_view_later<'e>(
+        e: impl sqlx::PgExecutor<'e>,
+    ) -> Result<(), sqlx::Error> {
+        crate::jobs::enqueue_in(e, &RefreshImageView, None, chrono::Utc::now()).await?;
+        Ok(())
     }
 }
@@ -1,11 +1,17 @@
+use std::collections::BTreeMap;
+
 use crate::postgres::POOL;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use sjf_api::i18n::Locale;
 use sjf_api::product::{
     GetPreviewsRequest, GetPreviewsResp, GetProductRequest, GetProductResponse, GetProductsRequest,
-    GetProductsResponse, Preview, Product as ApiProduct,
+    GetProductsResponse, Preview, Product as ApiProduct, ProductTranslation,
 };
-use sqlx::{query, query_file, query_file_as};
+use sjf_api::stock::StockPolicy;
+use sqlx::{Postgres, Transaction, query, query_file, query_file_as};
+
+use crate::stock::{stock_policy, stock_policy_columns};
 
 #[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
 pub struct Product {
@@ -19,9 +25,26 @@ pub struct Product {
     pub tax_rate: u32,
     pub images: Vec<u32>,
     pub category: u32,
+    pub reorder_threshold: Option<i32>,
+    pub stock_policy: StockPolicy,
+    /// Attribute values by attribute id.
+    pub attributes: BTreeMap<u32, String>,
+    /// Names and descriptions in languages other than Swedish.
+    pub translations: BTreeMap<Locale, ProductTranslation>,
+    /// Stock keeping unit, used to match products on import.
+    pub sku: Option<String>,
 }
 
 pub async fn get_products(category: u32) -> Result<Vec<Product>, sqlx::Error> {
+    fetch_products(Some(category)).await
+}
+
+/// Every product in the shop, by name.
+pub async fn get_all_products() -> Result<Vec<Product>, sqlx::Error> {
+    fetch_products(None).await
+}
+
+async fn fetch_products(category: Option<u32>) -> Result<Vec<Product>, sqlx::Error> {
     #[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
     pub struct ProductT {
         pub id: i32,
@@ -34,6 +57,11 @@ pub async fn get_products(category: u32) -> Result<Vec<Product>, sqlx::Error> {
         pub tax_rate: i32,
         pub image_ids: Option<Vec<i32>>,
         pub category: i32,
+        pub reorder_threshold: Option<i32>,
+        pub stock_policy: String,
+        pub backorder_limit: i32,
+        pub preorder_ship_date: Option<NaiveDate>,
+        pub sku: Option<String>,
     }
 
     impl From<ProductT> for Product {
@@ -48,27 +76,59 @@ pub async fn get_products(category: u32) -> Result<Vec<Product>, sqlx::Error> {
                 updated: p.updated,
                 tax_rate: p.tax_rate as u32,
                 category: p.category as u32,
+                reorder_threshold: p.reorder_threshold,
+                stock_policy: stock_policy(
+                    &p.stock_policy,
+                    p.backorder_limit,
+                    p.preorder_ship_date,
+                ),
                 images: p
                     .image_ids
                     .unwrap_or_default()
                     .into_iter()
                     .map(|x| x as u32)
                     .collect(),
+                attributes: BTreeMap::new(),
+                translations: BTreeMap::new(),
+                sku: p.sku,
             }
         }
     }
 
-    let res: Vec<_> = query_file_as!(ProductT, "sql/all_products.sql", category as i32)
-        .fetch_all(POOL.get().unwrap())
-        .await?
-        .into_iter()
-        .map(|x| x.into())
-        .collect();
+    let mut res: Vec<Product> =
+        query_file_as!(ProductT, "sql/all_products.sql", category.map(|c| c as i32))
+            .fetch_all(POOL.get().unwrap())
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    let ids: Vec<i32> = res.iter().map(|p| p.id).collect();
+    let mut attributes = crate::attribute::values(&ids).await?;
+    let mut translations = crate::translation::products(&ids).await?;
+    for product in res.iter_mut() {
+        product.attributes = attributes.remove(&product.id).unwrap_or_default();
+        product.translations = translations.remove(&product.id).unwrap_or_default();
+    }
     Ok(res)
 }
 
 pub async fn create_product(product: Product) -> Result<i32, sqlx::Error> {
     let mut tx = POOL.get().unwrap().begin().await?;
+    let id = create_product_in(&mut tx, product).await?;
+    crate::image::update_image_view_later(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Creates a product as part of a larger transaction, the caller queues the
+/// image view refresh in it.
+pub(crate) async fn create_product_in(
+    tx: &mut Transaction<'static, Postgres>,
+    product: Product,
+) -> Result<i32, sqlx::Error> {
+    let (policy, backorder_limit, ship_date) = stock_policy_columns(&product.stock_policy);
 
     let query = query_file!(
         "sql/create_product.sql",
@@ -78,8 +138,13 @@ pub async fn create_product(product: Product) -> Result<i32, sqlx::Error> {
         product.quantity,
         product.category as i32,
         product.tax_rate as i32,
+        product.reorder_threshold,
+        policy,
+        backorder_limit,
+        ship_date,
+        product.sku,
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await?;
 
     for image in product.images {
@@ -88,17 +153,29 @@ pub async fn create_product(product: Product) -> Result<i32, sqlx::Error> {
             query.id,
             image as i32
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
-
-    tx.commit().await?;
-    crate::image::update_image_view_later();
+    crate::attribute::store_values(tx, query.id, product.category, &product.attributes).await?;
+    crate::translation::store_product(tx, query.id, &product.translations).await?;
 
     Ok(query.id)
 }
+
 pub async fn update_product(product: Product) -> Result<(), sqlx::Error> {
     let mut tx = POOL.get().unwrap().begin().await?;
+    update_product_in(&mut tx, product).await?;
+    crate::image::update_image_view_later(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn update_product_in(
+    tx: &mut Transaction<'static, Postgres>,
+    product: Product,
+) -> Result<(), sqlx::Error> {
+    let (policy, backorder_limit, ship_date) = stock_policy_columns(&product.stock_policy);
 
     query_file!(
         "sql/update_product.sql",
@@ -106,13 +183,19 @@ pub async fn update_product(product: Product) -> Result<(), sqlx::Error> {
         product.price,
         product.description,
         product.quantity,
-        product.id
+        product.reorder_threshold,
+        policy,
+        backorder_limit,
+        ship_date,
+        product.id,
+        product.tax_rate as i32,
+        product.sku,
     )
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
     query!("DELETE from product_images where product_id=$1", product.id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
 
     for image in product.images {
@@ -121,20 +204,18 @@ pub async fn update_product(product: Product) -> Result<(), sqlx::Error> {
             product.id,
             image as i32
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
-
-    tx.commit().await?;
-
-    crate::image::update_image_view_later();
+    crate::attribute::store_values(tx, product.id, product.category, &product.attributes).await?;
+    crate::translation::store_product(tx, product.id, &product.translations).await?;
 
     Ok(())
 }
 
 #[derive(sqlx::Type)]
 #[sqlx(type_name = "image_variant")]
-struct ImageVariant {
+pub(crate) struct ImageVariant {
     width: i32,
     height: i32,
     variant: i32,
@@ -142,45 +223,23 @@ struct ImageVariant {
 
 #[derive(sqlx::Type)]
 #[sqlx(type_name = "image_info_type")]
-struct ImageInfo {
+pub(crate) struct ImageInfo {
     id: i32,
     avg_color: String,
     variants: Vec<ImageVariant>,
 }
 
-pub async fn get_previews(req: GetPreviewsRequest) -> Result<GetPreviewsResp, sqlx::Error> {
-    let categories =
-        crate::category::get_child_categories(req.category, req.recursive, POOL.get().unwrap())
-            .await?;
-
-    struct T {
-        id: Option<i32>,
-        price: Option<i32>,
-        name: Option<String>,
-        images: Option<Vec<ImageInfo>>,
-        names: Option<Vec<String>>,
-    }
-
-    let q = {
-        if req.random {
-            query_file_as!(
-                T,
-                "sql/get_previews_random.sql",
-                &categories,
-                req.limit as i32
-            )
-            .fetch_all(POOL.get().unwrap())
-            .await?
-        } else {
-            query_file_as!(T, "sql/get_previews.sql", &categories, req.limit as i32)
-                .fetch_all(POOL.get().unwrap())
-                .await?
-        }
-    };
+pub(crate) struct SqlPreview {
+    pub(crate) id: Option<i32>,
+    pub(crate) price: Option<i32>,
+    pub(crate) name: Option<String>,
+    pub(crate) images: Option<Vec<ImageInfo>>,
+    pub(crate) names: Option<Vec<String>>,
+}
 
-    let result = q
-        .into_iter()
-        .map(|t| Preview {
+impl From<SqlPreview> for Preview {
+    fn from(t: SqlPreview) -> Self {
+        Preview {
             id: t.id.unwrap() as u32,
             name: t.name.unwrap(),
             price: t.price.unwrap() as u32,
@@ -202,32 +261,114 @@ pub async fn get_previews(req: GetPreviewsRequest) -> Result<GetPreviewsResp, sq
                 })
                 .collect(),
             category_name: t.names.unwrap(),
+        }
+    }
+}
+
+/// Days of orders counted when sorting by popularity.
+const POPULARITY_DAYS: i32 = 90;
+
+pub async fn get_previews(req: GetPreviewsRequest) -> Result<GetPreviewsResp, sqlx::Error> {
+    let categories =
+        crate::category::get_child_categories(req.category, req.recursive, POOL.get().unwrap())
+            .await?;
+
+    struct T {
+        id: Option<i32>,
+        price: Option<i32>,
+        name: Option<String>,
+        images: Option<Vec<ImageInfo>>,
+        names: Option<Vec<String>>,
+        total: Option<i64>,
+    }
+
+    let (attribute_ids, attribute_values): (Vec<i32>, Vec<String>) = req
+        .filter
+        .attributes
+        .into_iter()
+        .map(|(a, v)| (a as i32, v))
+        .unzip();
+
+    let page = |limit: i64, offset: i64| {
+        query_file_as!(
+            T,
+            "sql/get_previews.sql",
+            &categories,
+            POPULARITY_DAYS,
+            req.filter.min_price.map(|p| p as i32),
+            req.filter.max_price.map(|p| p as i32),
+            req.filter.in_stock,
+            req.order.as_str(),
+            limit,
+            offset,
+            &attribute_ids,
+            &attribute_values
+        )
+        .fetch_all(POOL.get().unwrap())
+    };
+
+    let limit = req.limit as i64;
+    let q = page(limit, req.page as i64 * limit).await?;
+    // Past the last page there is no row to count the matches on
+    let total = match q.first() {
+        Some(t) => t.total,
+        None if req.page > 0 => page(1, 0).await?.first().and_then(|t| t.total),
+        None => None,
+    };
+    let total = total.unwrap_or_default() as u32;
+    let previews = q
+        .into_iter()
+        .map(|t| {
+            SqlPreview {
+                id: t.id,
+                price: t.price,
+                name: t.name,
+                images: t.images,
+                names: t.names,
+            }
+            .into()
         })
         .collect();
 
-    Ok(GetPreviewsResp { previews: result })
+    Ok(GetPreviewsResp { previews, total })
 }
 
 struct SqlProduct {
     id: Option<i32>,
     price: Option<i32>,
     quantity: Option<i32>,
+    stock_policy: Option<String>,
+    backorder_limit: Option<i32>,
+    preorder_ship_date: Option<NaiveDate>,
     description: Option<String>,
     name: Option<String>,
     images: Option<Vec<ImageInfo>>,
     names: Option<Vec<String>>,
-    category: Option<i32>,
 }
 
-impl Into<ApiProduct> for SqlProduct {
-    fn into(self) -> ApiProduct {
-        let t = self;
+impl From<SqlProduct> for ApiProduct {
+    fn from(val: SqlProduct) -> Self {
+        let t = val;
+        let stock_policy = stock_policy(
+            t.stock_policy.as_deref().unwrap_or_default(),
+            t.backorder_limit.unwrap_or_default(),
+            t.preorder_ship_date,
+        );
+        let backorder_limit = match stock_policy {
+            StockPolicy::Backorder { limit } => limit as i32,
+            _ => 0,
+        };
         ApiProduct {
             id: t.id.unwrap() as u32,
             name: t.name.unwrap(),
             description: t.description.unwrap(),
             price: t.price.unwrap() as u32,
-            stock: t.quantity.map(|f| f as u32),
+            stock: match stock_policy {
+                // Made to order, the quantity only counts what has been sold
+                StockPolicy::Preorder { .. } => None,
+                _ => t.quantity.map(|f| (f + backorder_limit).max(0) as u32),
+            },
+            stock_policy,
             images: t
                 .images
                 .unwrap_or_default()
@@ -276,10 +417,19 @@ pub async fn delete(id: u32) -> Result<(), sqlx::Error> {
     query!("DELETE from products where id=$1", id)
         .execute(&mut *tx)
         .await?;
-
-    crate::image::update_image_view_later();
-
+    crate::image::update_image_view_later(&mut *tx).await?;
     tx.commit().await?;
 
     Ok(())
 }
+
+/// Id and name of every product, by name.
+pub async fn names() -> Result<Vec<(u32, String)>, sqlx::Error> {
+    let res = query!("SELECT id, name FROM products ORDER BY name")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| (r.id as u32, r.name))
+        .collect();
+    Ok(res)
+}
@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use crate::postgres::POOL;
+use sjf_api::checkout::{CheckoutRequest, RecoveryStats};
+use sjf_api::i18n::Locale;
+use sqlx::{query, query_file, types::Uuid};
+
+/// An abandoned checkout that is due for its reminder email.
+pub struct DueReminder {
+    pub id: Uuid,
+    pub email: String,
+    /// Language the customer shopped in.
+    pub locale: Locale,
+    pub order: Vec<(u32, u32)>,
+}
+
+/// Remembers the contents of an expired checkout session so that the customer
+/// can be reminded. Has to run before the reservation is released.
+pub async fn record_abandoned(reservation: &str, email: &str) -> Result<bool, sqlx::Error> {
+    let Ok(reservation) = Uuid::from_str(reservation) else {
+        return Ok(false);
+    };
+    let res = query_file!("sql/recovery/record.sql", reservation, email.to_lowercase())
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Checkouts abandoned at least `delay_minutes` ago but no more than `max_age_days`
+/// before that, that haven't been reminded yet.
+pub async fn due_reminders(
+    delay_minutes: i32,
+    max_age_days: i32,
+) -> Result<Vec<DueReminder>, sqlx::Error> {
+    let res = query_file!(
+        "sql/recovery/due_reminders.sql",
+        delay_minutes,
+        max_age_days
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| DueReminder {
+        id: r.id,
+        email: r.email,
+        locale: Locale::from_code(&r.locale).unwrap_or_default(),
+        order: r
+            .product_ids
+            .into_iter()
+            .zip(r.quantities)
+            .map(|(p, q)| (p as u32, q.max(0) as u32))
+            .collect(),
+    })
+    .collect();
+    Ok(res)
+}
+
+pub async fn mark_reminded(id: Uuid) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE abandoned_checkouts SET reminder_sent=CURRENT_TIMESTAMP WHERE id=$1",
+        id
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+/// The cart of an abandoned checkout, ready to be checked out again.
+pub async fn restore(token: &str) -> Result<Option<CheckoutRequest>, sqlx::Error> {
+    let Ok(id) = Uuid::from_str(token) else {
+        return Ok(None);
+    };
+    let res = query!(
+        "UPDATE abandoned_checkouts SET clicked=COALESCE(clicked, CURRENT_TIMESTAMP) WHERE id=$1 RETURNING product_ids, quantities, locale",
+        id
+    )
+    .fetch_optional(POOL.get().unwrap())
+    .await?;
+
+    Ok(res.map(|r| CheckoutRequest {
+        order: r
+            .product_ids
+            .into_iter()
+            .zip(r.quantities)
+            .map(|(p, q)| (p as u32, q.max(0) as u32))
+            .collect(),
+        recovery: Some(token.to_owned()),
+        locale: Locale::from_code(&r.locale).unwrap_or_default(),
+        currency: Default::default(),
+    }))
+}
+
+/// Connects a new reservation to the abandoned checkout it was restored from.
+pub async fn link(token: &str, reservation: &str) -> Result<(), sqlx::Error> {
+    let (Ok(id), Ok(reservation)) = (Uuid::from_str(token), Uuid::from_str(reservation)) else {
+        return Ok(());
+    };
+    query!(
+        "UPDATE abandoned_checkouts SET recovered_by=$2 WHERE id=$1 AND recovered IS NULL",
+        id,
+        reservation
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+/// Marks the abandoned checkout restored into `reservation` as recovered, if any.
+pub async fn mark_recovered(reservation: &str) -> Result<bool, sqlx::Error> {
+    let Ok(reservation) = Uuid::from_str(reservation) else {
+        return Ok(false);
+    };
+    let res = query!(
+        "UPDATE abandoned_checkouts SET recovered=CURRENT_TIMESTAMP WHERE recovered_by=$1 AND recovered IS NULL",
+        reservation
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Stops all future reminders to the customer behind `token`.
+pub async fn opt_out(token: &str) -> Result<bool, sqlx::Error> {
+    let Ok(id) = Uuid::from_str(token) else {
+        return Ok(false);
+    };
+    let res = query!(
+        "INSERT INTO checkout_reminder_opt_outs(email) SELECT email FROM abandoned_checkouts WHERE id=$1 ON CONFLICT (email) DO NOTHING",
+        id
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Removes abandoned checkouts that are too old to matter for the statistics.
+pub async fn remove_old(days: i32) -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM abandoned_checkouts WHERE created < CURRENT_TIMESTAMP - make_interval(days => $1)",
+        days
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+pub async fn stats(days: u32) -> Result<RecoveryStats, sqlx::Error> {
+    let r = query_file!("sql/recovery/stats.sql", days as i32)
+        .fetch_one(POOL.get().unwrap())
+        .await?;
+    Ok(RecoveryStats {
+        days,
+        abandoned: r.abandoned as u32,
+        reminded: r.reminded as u32,
+        clicked: r.clicked as u32,
+        recovered: r.recovered as u32,
+        recovered_value: r.recovered_value as u32,
+    })
+}
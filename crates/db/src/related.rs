@@ -0,0 +1,91 @@
+use crate::postgres::POOL;
+use crate::product::SqlPreview;
+use sjf_api::product::{Preview, ProductId};
+use sqlx::{query, query_file, query_file_as};
+
+/// Orders older than this no longer count towards "customers also bought".
+const CO_PURCHASE_DAYS: i32 = 365;
+
+/// Curated products first, in the order staff put them, followed by the
+/// products most often bought together with `product_id`. Sold out products are left out.
+pub async fn get_related(product_id: ProductId, limit: u32) -> Result<Vec<Preview>, sqlx::Error> {
+    let res = query_file_as!(
+        SqlPreview,
+        "sql/related/get_related.sql",
+        product_id as i32,
+        limit as i64
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?;
+    Ok(res.into_iter().map(|t| t.into()).collect())
+}
+
+/// The curated related products with their names, in display order.
+pub async fn curated(product_id: ProductId) -> Result<Vec<(ProductId, String)>, sqlx::Error> {
+    let res = query!(
+        "SELECT p.id, p.name FROM related_products r JOIN products p ON p.id = r.related_id
+        WHERE r.product_id = $1 ORDER BY r.position",
+        product_id as i32
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| (r.id as u32, r.name))
+    .collect();
+    Ok(res)
+}
+
+/// Replaces the curated related products of `product_id`.
+pub async fn set_curated(
+    product_id: ProductId,
+    related: Vec<ProductId>,
+) -> Result<(), sqlx::Error> {
+    let related: Vec<i32> = related
+        .into_iter()
+        .filter(|id| *id != product_id)
+        .map(|id| id as i32)
+        .collect();
+
+    let mut tx = POOL.get().unwrap().begin().await?;
+    query!(
+        "DELETE FROM related_products WHERE product_id=$1",
+        product_id as i32
+    )
+    .execute(&mut *tx)
+    .await?;
+    query!(
+        "INSERT INTO related_products(product_id, related_id, position)
+        SELECT $1, r.id, min(r.position) FROM unnest($2::integer[]) WITH ORDINALITY AS r(id, position)
+        GROUP BY r.id",
+        product_id as i32,
+        &related
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Recomputes the co-purchases every hour.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct UpdateCoPurchases;
+
+impl crate::jobs::Job for UpdateCoPurchases {
+    const KIND: &'static str = "update_co_purchases";
+
+    async fn run(self, _: Option<Vec<u8>>) -> crate::jobs::JobResult {
+        update_co_purchases().await?;
+        Ok(())
+    }
+}
+
+/// Recomputes which products are bought together from the stored order lines.
+pub async fn update_co_purchases() -> Result<(), sqlx::Error> {
+    let mut tx = POOL.get().unwrap().begin().await?;
+    query!("DELETE FROM co_purchases").execute(&mut *tx).await?;
+    query_file!("sql/related/update_co_purchases.sql", CO_PURCHASE_DAYS)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
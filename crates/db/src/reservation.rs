@@ -0,0 +1,259 @@
+//! Lifetime of stock reservations.
+//!
+//! A reservation is created before the payment session and lives exactly as
+//! long as the session it is attached to, plus a short grace period for
+//! payments that complete at the last second. Both windows are set in the
+//! `[checkout]` configuration.
+
+use std::sync::{Arc, LazyLock, RwLock};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use once_cell::sync::OnceCell;
+use sjf_config::CheckoutConfig;
+use sqlx::{PgExecutor, query, types::Uuid};
+use tracing::debug;
+
+use crate::postgres::POOL;
+
+/// Source of the current time, replaceable so expiry can be driven by a fake clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservationWindows {
+    /// How long the customer has to complete the payment session.
+    pub session_lifetime: TimeDelta,
+    /// Extra time the stock is held after the session expired.
+    pub grace: TimeDelta,
+}
+
+impl Default for ReservationWindows {
+    fn default() -> Self {
+        (&CheckoutConfig::default()).into()
+    }
+}
+
+impl From<&CheckoutConfig> for ReservationWindows {
+    fn from(config: &CheckoutConfig) -> Self {
+        Self {
+            session_lifetime: TimeDelta::minutes(config.session_minutes.into()),
+            grace: TimeDelta::minutes(config.reservation_grace_minutes.into()),
+        }
+    }
+}
+
+impl ReservationWindows {
+    /// When a payment session created at `now` should expire.
+    pub fn session_expiry(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + self.session_lifetime
+    }
+
+    /// When the stock held for a session expiring at `session_expiry` is released.
+    pub fn reservation_expiry(&self, session_expiry: DateTime<Utc>) -> DateTime<Utc> {
+        session_expiry + self.grace
+    }
+
+    /// Expiry of a reservation made at `now` that isn't attached to a session yet.
+    pub fn provisional_expiry(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.reservation_expiry(self.session_expiry(now))
+    }
+
+    /// How long to wait before releasing the next reservation.
+    pub fn time_until(
+        now: DateTime<Utc>,
+        next_expiry: Option<DateTime<Utc>>,
+        max: TimeDelta,
+    ) -> TimeDelta {
+        next_expiry
+            .map(|e| e - now)
+            .unwrap_or(max)
+            .clamp(TimeDelta::zero(), max)
+    }
+}
+
+static WINDOWS: OnceCell<ReservationWindows> = OnceCell::new();
+static CLOCK: LazyLock<RwLock<Arc<dyn Clock>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(SystemClock)));
+
+pub(crate) fn configure(config: &CheckoutConfig) {
+    let _ = WINDOWS.set(config.into());
+}
+
+pub fn windows() -> ReservationWindows {
+    WINDOWS.get().copied().unwrap_or_default()
+}
+
+pub fn clock() -> Arc<dyn Clock> {
+    CLOCK.read().unwrap().clone()
+}
+
+/// Replaces the clock reservations expire by, for tests.
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *CLOCK.write().unwrap() = clock;
+}
+
+/// Attaches a reservation to a payment session that expires at `session_expiry`.
+pub async fn attach_to_session(
+    uuid: &str,
+    session_expiry: DateTime<Utc>,
+) -> Result<(), crate::checkout::CheckoutError> {
+    let uuid = Uuid::parse_str(uuid)?;
+    let expires_at = windows().reservation_expiry(session_expiry);
+    query!(
+        "UPDATE pending_orders SET expires_at=$1 WHERE id=$2",
+        expires_at,
+        uuid
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    release_at(POOL.get().unwrap(), expires_at).await?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct PendingReservation {
+    pub id: Uuid,
+    pub created: DateTime<Utc>,
+    /// Not set until the reservation is attached to a payment session.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub currency: String,
+    /// Product id, name and reserved quantity.
+    pub lines: Vec<(i32, String, i32)>,
+}
+
+/// Reservations holding stock for checkouts that haven't completed, oldest first.
+pub async fn pending() -> Result<Vec<PendingReservation>, sqlx::Error> {
+    let res = query!(
+        r#"SELECT o.id, o.timestamp, o.expires_at, o.currency,
+            ARRAY_AGG(r.product_id ORDER BY r.product_id) FILTER (WHERE r.product_id IS NOT NULL) AS "product_ids: Vec<i32>",
+            ARRAY_AGG(p.name ORDER BY r.product_id) FILTER (WHERE r.product_id IS NOT NULL) AS "names: Vec<String>",
+            ARRAY_AGG(r.quantity ORDER BY r.product_id) FILTER (WHERE r.product_id IS NOT NULL) AS "quantities: Vec<i32>"
+        FROM pending_orders o
+        LEFT JOIN product_reservations r ON r.reservation_id = o.id
+        LEFT JOIN products p ON p.id = r.product_id
+        GROUP BY o.id ORDER BY o.timestamp"#
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| PendingReservation {
+        id: r.id,
+        created: r.timestamp,
+        expires_at: r.expires_at,
+        currency: r.currency,
+        lines: r
+            .product_ids
+            .unwrap_or_default()
+            .into_iter()
+            .zip(r.names.unwrap_or_default())
+            .zip(r.quantities.unwrap_or_default())
+            .map(|((id, name), quantity)| (id, name, quantity))
+            .collect(),
+    })
+    .collect();
+    Ok(res)
+}
+
+/// Releases the stock of the reservations that have expired by now.
+pub async fn release_expired() -> Result<(), crate::checkout::CheckoutError> {
+    let now = clock().now();
+    // Reservations made before expiries were stored only have their creation time
+    let w = windows();
+    let legacy_cutoff = now - (w.session_lifetime + w.grace);
+    let expired = query!(
+        "SELECT id FROM pending_orders WHERE expires_at <= $1 OR (expires_at IS NULL AND timestamp <= $2)",
+        now,
+        legacy_cutoff
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?;
+
+    for r in expired {
+        crate::checkout::undo_reservation(r.id.into()).await?;
+    }
+    Ok(())
+}
+
+async fn next_expiry() -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    Ok(query!("SELECT MIN(expires_at) AS next FROM pending_orders")
+        .fetch_one(POOL.get().unwrap())
+        .await?
+        .next)
+}
+
+/// Releases expired reservations, at the next expiry or at the latest every
+/// ten minutes.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct ReleaseReservations;
+
+impl ReleaseReservations {
+    pub(crate) const EVERY: TimeDelta = TimeDelta::minutes(10);
+}
+
+impl crate::jobs::Job for ReleaseReservations {
+    const KIND: &'static str = "release_reservations";
+    const ONCE: bool = true;
+
+    async fn run(self, _: Option<Vec<u8>>) -> crate::jobs::JobResult {
+        release_expired().await?;
+
+        let now = clock().now();
+        let next = next_expiry().await?;
+        let wait = ReservationWindows::time_until(now, next, Self::EVERY);
+        debug!("Next reservation check in {}s", wait.num_seconds());
+        release_at(POOL.get().unwrap(), now + wait).await?;
+        Ok(())
+    }
+}
+
+/// Makes sure expired reservations are released by `expires_at`.
+pub(crate) async fn release_at<'e>(
+    e: impl PgExecutor<'e>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    crate::jobs::enqueue_in(e, &ReleaseReservations, None, expires_at).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        format!("2026-03-02T{time}Z").parse().unwrap()
+    }
+
+    #[test]
+    fn windows_follow_the_checkout_config() {
+        let windows = ReservationWindows::from(&CheckoutConfig {
+            session_minutes: 45,
+            reservation_grace_minutes: 10,
+            ..Default::default()
+        });
+        assert_eq!(windows.session_expiry(at("12:00:00")), at("12:45:00"));
+        assert_eq!(windows.reservation_expiry(at("12:45:00")), at("12:55:00"));
+        assert_eq!(windows.provisional_expiry(at("12:00:00")), at("12:55:00"));
+    }
+
+    #[test]
+    fn waits_until_the_next_expiry_but_never_longer_than_max() {
+        let max = TimeDelta::minutes(5);
+        let now = at("12:00:00");
+        let wait = |next| ReservationWindows::time_until(now, next, max);
+
+        assert_eq!(wait(Some(at("12:02:00"))), TimeDelta::minutes(2));
+        assert_eq!(wait(Some(at("13:00:00"))), max);
+        assert_eq!(wait(None), max);
+        // Reservations that are already due are released right away
+        assert_eq!(wait(Some(at("11:59:00"))), TimeDelta::zero());
+    }
+}
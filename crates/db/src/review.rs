@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use crate::postgres::POOL;
+use sjf_api::review::{
+    MAX_AUTHOR_LENGTH, MAX_REVIEW_LENGTH, ModeratedReview, ProductReviews, RatingSummary, Review,
+    ReviewInvitation, ReviewStatus, SubmitReviewRequest, SubmitReviewStatus,
+};
+use sqlx::{query, query_file, types::Uuid};
+
+/// Reviews one customer can write per day.
+const MAX_REVIEWS_PER_CUSTOMER: i64 = 10;
+/// Reviews accepted per minute from all customers together.
+const MAX_REVIEWS_PER_MINUTE: i64 = 30;
+/// Links in a review text, more than this is most likely spam.
+const MAX_LINKS: usize = 2;
+
+pub async fn invitation(token: &str) -> Result<Option<ReviewInvitation>, sqlx::Error> {
+    let Ok(token) = Uuid::from_str(token) else {
+        return Ok(None);
+    };
+    let res = query!(
+        r#"SELECT p.name, EXISTS (SELECT 1 FROM reviews r WHERE r.order_line = l.id) AS "reviewed!"
+        FROM order_lines l JOIN products p ON p.id = l.product_id WHERE l.id = $1"#,
+        token
+    )
+    .fetch_optional(POOL.get().unwrap())
+    .await?;
+    Ok(res.map(|r| ReviewInvitation {
+        product_name: r.name,
+        already_reviewed: r.reviewed,
+    }))
+}
+
+fn is_valid(req: &SubmitReviewRequest) -> bool {
+    let text = req.text.trim();
+    let author = req.author.trim();
+    (1..=5).contains(&req.rating)
+        && !text.is_empty()
+        && text.chars().count() <= MAX_REVIEW_LENGTH
+        && !author.is_empty()
+        && author.chars().count() <= MAX_AUTHOR_LENGTH
+        && text.matches("http").count() <= MAX_LINKS
+}
+
+/// Stores a review for moderation. Every order line can be reviewed once.
+pub async fn submit(req: SubmitReviewRequest) -> Result<SubmitReviewStatus, sqlx::Error> {
+    let Ok(token) = Uuid::from_str(&req.token) else {
+        return Ok(SubmitReviewStatus::InvalidToken);
+    };
+    let Some(invitation) = invitation(&req.token).await? else {
+        return Ok(SubmitReviewStatus::InvalidToken);
+    };
+    if invitation.already_reviewed {
+        return Ok(SubmitReviewStatus::AlreadyReviewed);
+    }
+    if !is_valid(&req) {
+        return Ok(SubmitReviewStatus::Invalid);
+    }
+
+    let pool = POOL.get().unwrap();
+    let by_customer = query_file!("sql/reviews/recent_by_customer.sql", token)
+        .fetch_one(pool)
+        .await?
+        .count;
+    let last_minute = query!(
+        r#"SELECT count(*) AS "count!" FROM reviews WHERE created > CURRENT_TIMESTAMP - INTERVAL '1 minute'"#
+    )
+    .fetch_one(pool)
+    .await?
+    .count;
+    if by_customer >= MAX_REVIEWS_PER_CUSTOMER || last_minute >= MAX_REVIEWS_PER_MINUTE {
+        return Ok(SubmitReviewStatus::RateLimited);
+    }
+
+    let res = query_file!(
+        "sql/reviews/submit.sql",
+        token,
+        req.rating as i16,
+        req.text.trim(),
+        req.author.trim()
+    )
+    .execute(pool)
+    .await?;
+
+    match res.rows_affected() {
+        0 => Ok(SubmitReviewStatus::AlreadyReviewed),
+        _ => Ok(SubmitReviewStatus::Submitted),
+    }
+}
+
+/// Approved reviews of a product, newest first, and their average rating.
+pub async fn product_reviews(product_id: u32, limit: u32) -> Result<ProductReviews, sqlx::Error> {
+    let pool = POOL.get().unwrap();
+    let summary = query!(
+        r#"SELECT avg(rating)::real AS average, count(*) AS "count!" FROM reviews WHERE product_id=$1 AND status='approved'"#,
+        product_id as i32
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let reviews = query!(
+        "SELECT id, rating, text, author, created, reply FROM reviews
+        WHERE product_id=$1 AND status='approved' ORDER BY created DESC LIMIT $2",
+        product_id as i32,
+        limit as i64
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| Review {
+        id: r.id.to_string(),
+        rating: r.rating as u8,
+        text: r.text,
+        author: r.author,
+        created: r.created,
+        reply: r.reply,
+    })
+    .collect();
+
+    Ok(ProductReviews {
+        summary: summary.average.map(|average| RatingSummary {
+            average,
+            count: summary.count as u32,
+        }),
+        reviews,
+    })
+}
+
+/// Pending reviews and those handled during the last `days`.
+pub async fn moderation(days: u32) -> Result<Vec<ModeratedReview>, sqlx::Error> {
+    let res = query_file!("sql/reviews/moderation.sql", days as i32)
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| ModeratedReview {
+            review: Review {
+                id: r.id.to_string(),
+                rating: r.rating as u8,
+                text: r.text,
+                author: r.author,
+                created: r.created,
+                reply: r.reply,
+            },
+            product_id: r.product_id as u32,
+            product_name: r.product_name,
+            status: r.status.as_str().into(),
+        })
+        .collect();
+    Ok(res)
+}
+
+pub async fn moderate(id: &str, status: ReviewStatus) -> Result<(), sqlx::Error> {
+    let Ok(id) = Uuid::from_str(id) else {
+        return Ok(());
+    };
+    query!(
+        "UPDATE reviews SET status=$2 WHERE id=$1",
+        id,
+        status.as_str()
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
+
+/// Sets the public staff reply of a review, an empty reply removes it.
+pub async fn reply(id: &str, reply: &str) -> Result<(), sqlx::Error> {
+    let Ok(id) = Uuid::from_str(id) else {
+        return Ok(());
+    };
+    let reply = Some(reply.trim()).filter(|r| !r.is_empty());
+    query!(
+        "UPDATE reviews SET reply=$2, replied=CASE WHEN $2::text IS NULL THEN NULL ELSE CURRENT_TIMESTAMP END WHERE id=$1",
+        id,
+        reply
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use sjf_api::product::Preview;
+use sqlx::{Executor, Postgres, query, query_file, query_file_as};
+
+use crate::postgres::POOL;
+use crate::product::{ImageInfo, SqlPreview};
+
+/// Bumps the `view_generation` sequence every time the materialized views are
+/// refreshed, by any replica or tool, so that anything built from them knows
+/// when to rebuild.
+pub(crate) async fn views_refreshed<'c, E>(e: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    query!("SELECT nextval('view_generation')")
+        .fetch_one(e)
+        .await?;
+    Ok(())
+}
+
+pub async fn views_generation() -> Result<i64, sqlx::Error> {
+    // last_value is 1 both before and after the first nextval
+    Ok(query!(
+        r#"SELECT CASE WHEN is_called THEN last_value ELSE 0 END AS "generation!" FROM view_generation"#
+    )
+    .fetch_one(POOL.get().unwrap())
+    .await?
+    .generation)
+}
+
+pub struct SitemapProduct {
+    pub preview: Preview,
+    pub updated: DateTime<Utc>,
+}
+
+pub struct SitemapCategory {
+    pub names: Vec<String>,
+    pub updated: Option<DateTime<Utc>>,
+}
+
+struct SqlSitemapProduct {
+    id: i32,
+    price: i32,
+    name: String,
+    images: Option<Vec<ImageInfo>>,
+    names: Option<Vec<String>>,
+    updated: DateTime<Utc>,
+}
+
+/// Every product that has a category, with the time it was last changed.
+pub async fn products() -> Result<Vec<SitemapProduct>, sqlx::Error> {
+    let res = query_file_as!(SqlSitemapProduct, "sql/sitemap/products.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| SitemapProduct {
+            preview: SqlPreview {
+                id: Some(r.id),
+                price: Some(r.price),
+                name: Some(r.name),
+                images: r.images,
+                names: r.names,
+            }
+            .into(),
+            updated: r.updated,
+        })
+        .collect();
+    Ok(res)
+}
+
+/// Every category, with the latest change of a product in it or its subcategories.
+pub async fn categories() -> Result<Vec<SitemapCategory>, sqlx::Error> {
+    let res = query_file!("sql/sitemap/categories.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| SitemapCategory {
+            names: r.names.unwrap_or_default(),
+            updated: r.updated,
+        })
+        .collect();
+    Ok(res)
+}
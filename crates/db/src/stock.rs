@@ -0,0 +1,207 @@
+use std::str::FromStr;
+
+use crate::postgres::POOL;
+use chrono::NaiveDate;
+use sjf_api::i18n::Locale;
+use sjf_api::stock::{StockAlert, StockLevel, StockPolicy};
+use sqlx::{query, query_file, query_file_as, types::Uuid};
+
+pub(crate) fn stock_policy(
+    policy: &str,
+    backorder_limit: i32,
+    preorder_ship_date: Option<NaiveDate>,
+) -> StockPolicy {
+    match (policy, preorder_ship_date) {
+        ("backorder", _) => StockPolicy::Backorder {
+            limit: backorder_limit.max(0) as u32,
+        },
+        ("preorder", Some(ship_date)) => StockPolicy::Preorder { ship_date },
+        _ => StockPolicy::Stop,
+    }
+}
+
+/// Splits a policy into its `stock_policy`, `backorder_limit` and `preorder_ship_date` columns.
+pub(crate) fn stock_policy_columns(policy: &StockPolicy) -> (&'static str, i32, Option<NaiveDate>) {
+    match policy {
+        StockPolicy::Stop => (policy.as_str(), 0, None),
+        StockPolicy::Backorder { limit } => (policy.as_str(), *limit as i32, None),
+        StockPolicy::Preorder { ship_date } => (policy.as_str(), 0, Some(*ship_date)),
+    }
+}
+
+struct StockRow {
+    id: i32,
+    name: String,
+    quantity: i32,
+    reorder_threshold: Option<i32>,
+}
+
+impl From<StockRow> for StockAlert {
+    fn from(r: StockRow) -> Self {
+        StockAlert {
+            product_id: r.id as u32,
+            name: r.name,
+            quantity: r.quantity,
+            reorder_threshold: r.reorder_threshold.map(|x| x as u32),
+            level: StockLevel::from_quantity(r.quantity),
+        }
+    }
+}
+
+/// All tracked products that are currently sold out or below their reorder threshold.
+pub async fn low_stock() -> Result<Vec<StockAlert>, sqlx::Error> {
+    let res = query_file_as!(StockRow, "sql/stock/low_stock.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| r.into())
+        .collect();
+    Ok(res)
+}
+
+/// Products that have crossed a stock threshold since staff was last notified,
+/// either newly low/sold out or escalated from low to sold out.
+pub async fn pending_stock_alerts() -> Result<Vec<StockAlert>, sqlx::Error> {
+    let res = query_file_as!(StockRow, "sql/stock/pending_alerts.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| r.into())
+        .collect();
+    Ok(res)
+}
+
+pub async fn mark_stock_alerts_sent(alerts: &[StockAlert]) -> Result<(), sqlx::Error> {
+    let ids: Vec<i32> = alerts.iter().map(|a| a.product_id as i32).collect();
+    let levels: Vec<String> = alerts.iter().map(|a| a.level.as_str().to_owned()).collect();
+    query_file!("sql/stock/mark_alerts_sent.sql", &ids, &levels)
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(())
+}
+
+/// Clears alerts for products that have been restocked so that they can fire again.
+pub async fn rearm_stock_alerts() -> Result<(), sqlx::Error> {
+    let mut tx = POOL.get().unwrap().begin().await?;
+    query_file!("sql/stock/rearm_alerts.sql")
+        .execute(&mut *tx)
+        .await?;
+    query_file!("sql/stock/downgrade_alerts.sql")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn update_stock_gauges() -> Result<(), sqlx::Error> {
+    let rows = query_file!("sql/stock/alert_counts.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?;
+
+    for level in [StockLevel::Low, StockLevel::SoldOut] {
+        let count = rows
+            .iter()
+            .find(|r| r.level == level.as_str())
+            .map(|r| r.count)
+            .unwrap_or(0);
+        let lables = [("level", level.as_str())];
+        metrics::gauge!("products_stock_alerts", &lables).set(count as f64)
+    }
+
+    Ok(())
+}
+
+/// A confirmed back in stock subscription for a product that is available again.
+pub struct DueSubscription {
+    pub id: Uuid,
+    pub email: String,
+    /// Language the customer subscribed in.
+    pub locale: Locale,
+    pub product_id: i32,
+    pub name: String,
+    pub category_name: Vec<String>,
+}
+
+/// Number of subscription confirmations sent to `email` during the last day.
+pub async fn recent_subscription_requests(email: &str) -> Result<i64, sqlx::Error> {
+    let res = query_file!("sql/stock/recent_subscriptions.sql", email)
+        .fetch_one(POOL.get().unwrap())
+        .await?;
+    Ok(res.count)
+}
+
+/// Creates an unconfirmed back in stock subscription and returns its token.
+///
+/// Returns `None` when no confirmation should be sent, either because the
+/// subscription is already confirmed or because one was sent recently.
+pub async fn subscribe(
+    product_id: u32,
+    email: &str,
+    locale: Locale,
+) -> Result<Option<String>, sqlx::Error> {
+    let res = query_file!(
+        "sql/stock/subscribe.sql",
+        product_id as i32,
+        email,
+        locale.code()
+    )
+    .fetch_optional(POOL.get().unwrap())
+    .await?;
+    Ok(res.map(|r| r.id.to_string()))
+}
+
+pub async fn confirm_subscription(token: &str) -> Result<bool, sqlx::Error> {
+    let Ok(token) = Uuid::from_str(token) else {
+        return Ok(false);
+    };
+    let res = query!(
+        "UPDATE stock_subscriptions SET confirmed=true WHERE id=$1",
+        token
+    )
+    .execute(POOL.get().unwrap())
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn unsubscribe(token: &str) -> Result<bool, sqlx::Error> {
+    let Ok(token) = Uuid::from_str(token) else {
+        return Ok(false);
+    };
+    let res = query!("DELETE FROM stock_subscriptions WHERE id=$1", token)
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn due_subscriptions() -> Result<Vec<DueSubscription>, sqlx::Error> {
+    let res = query_file!("sql/stock/due_subscriptions.sql")
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| DueSubscription {
+            id: r.id,
+            email: r.email,
+            locale: Locale::from_code(&r.locale).unwrap_or_default(),
+            product_id: r.product_id,
+            name: r.name,
+            category_name: r.category_name,
+        })
+        .collect();
+    Ok(res)
+}
+
+/// Removes subscriptions that have been notified.
+pub async fn expire_subscriptions(ids: &[Uuid]) -> Result<(), sqlx::Error> {
+    query!("DELETE FROM stock_subscriptions WHERE id = ANY($1)", ids)
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(())
+}
+
+/// Removes subscriptions that were never confirmed.
+pub async fn remove_unconfirmed_subscriptions() -> Result<(), sqlx::Error> {
+    query!("DELETE FROM stock_subscriptions WHERE confirmed = false AND confirmation_sent < NOW() - interval '2 days'")
+        .execute(POOL.get().unwrap())
+        .await?;
+    Ok(())
+}
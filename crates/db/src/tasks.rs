@@ -0,0 +1,231 @@
+//! Background work of the shop. Loops are supervised, restarted when they
+//! stop and ended at shutdown, while one-off work such as running jobs is
+//! tracked so that shutdown can wait for it to finish.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinError;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+static SHUTDOWN: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+
+static SUPERVISED: Mutex<BTreeMap<&'static str, TaskStatus>> = Mutex::new(BTreeMap::new());
+
+/// Number of unfinished tracked tasks by name.
+static TRACKED: LazyLock<watch::Sender<BTreeMap<&'static str, usize>>> =
+    LazyLock::new(|| watch::channel(BTreeMap::new()).0);
+
+/// State of a supervised task.
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    /// False while waiting to be restarted, and after shutdown.
+    pub running: bool,
+    pub restarts: u32,
+    pub last_failure: Option<String>,
+}
+
+fn set_status(name: &'static str, update: impl FnOnce(&mut TaskStatus)) {
+    let mut tasks = SUPERVISED.lock().unwrap();
+    let status = tasks.entry(name).or_insert(TaskStatus {
+        name,
+        running: false,
+        restarts: 0,
+        last_failure: None,
+    });
+    update(status);
+}
+
+fn failure(result: Result<(), JoinError>) -> String {
+    match result {
+        Ok(()) => "returned".into(),
+        Err(e) if e.is_panic() => {
+            let panic = e.into_panic();
+            match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+                (Some(s), _) => format!("panicked: {s}"),
+                (_, Some(s)) => format!("panicked: {s}"),
+                _ => "panicked".into(),
+            }
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+/// A loop that ran this long before it stopped was healthy, and its restart
+/// doesn't wait longer for the failures before it.
+const HEALTHY_RUN: Duration = Duration::from_secs(5 * 60);
+
+/// Failures in a row once a loop stopped after running for `ran`, `before`
+/// being the failures in a row before it started.
+fn failures_in_row(before: u32, ran: Duration) -> u32 {
+    match ran >= HEALTHY_RUN {
+        true => 1,
+        false => before + 1,
+    }
+}
+
+/// How long to wait before restarting a loop, 2s doubling up to a minute.
+fn backoff(failures_in_row: u32) -> Duration {
+    Duration::from_secs(1 << failures_in_row.min(6))
+}
+
+/// Runs the loop made by `task` until shutdown, starting it again with a
+/// growing delay whenever it panics or returns.
+pub fn supervise<F, Fut>(name: &'static str, task: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut shutdown = SHUTDOWN.subscribe();
+        let mut failures = 0;
+        loop {
+            set_status(name, |s| s.running = true);
+            let started = Instant::now();
+            let mut handle = tokio::spawn(task());
+            let result = tokio::select! {
+                result = &mut handle => result,
+                _ = shutdown.wait_for(|stop| *stop) => {
+                    handle.abort();
+                    set_status(name, |s| s.running = false);
+                    return;
+                }
+            };
+
+            failures = failures_in_row(failures, started.elapsed());
+            let failure = failure(result);
+            let backoff = backoff(failures);
+            error!(
+                "Background task {} {}, restarting in {}s",
+                name,
+                failure,
+                backoff.as_secs()
+            );
+            metrics::counter!("background_task_restarts", "task" => name).increment(1);
+            set_status(name, |s| {
+                s.running = false;
+                s.restarts += 1;
+                s.last_failure = Some(failure);
+            });
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {},
+                _ = shutdown.wait_for(|stop| *stop) => return,
+            }
+        }
+    });
+}
+
+/// The supervised tasks, by name.
+pub fn supervised() -> Vec<TaskStatus> {
+    SUPERVISED.lock().unwrap().values().cloned().collect()
+}
+
+/// Removes a finished tracked task from the count, also when it panicked.
+struct Tracked(&'static str);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        TRACKED.send_modify(|tracked| {
+            if let Some(n) = tracked.get_mut(self.0) {
+                *n -= 1;
+                if *n == 0 {
+                    tracked.remove(self.0);
+                }
+            }
+        });
+    }
+}
+
+/// Spawns work that shutdown waits for, such as a running job.
+pub fn spawn_tracked<F>(name: &'static str, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    TRACKED.send_modify(|tracked| *tracked.entry(name).or_default() += 1);
+    let tracked = Tracked(name);
+    tokio::spawn(async move {
+        let _tracked = tracked;
+        task.await;
+    });
+}
+
+/// Starts the shutdown, ending the supervised tasks.
+pub fn shutdown() {
+    SHUTDOWN.send_replace(true);
+}
+
+pub fn is_shutting_down() -> bool {
+    *SHUTDOWN.borrow()
+}
+
+/// Completes once the shutdown has started.
+pub async fn shutdown_started() {
+    let _ = SHUTDOWN.subscribe().wait_for(|stop| *stop).await;
+}
+
+/// Waits until the tracked tasks have finished, or returns those still
+/// running at the deadline.
+pub async fn drain(deadline: Instant) -> Result<(), BTreeMap<&'static str, usize>> {
+    let mut tracked = TRACKED.subscribe();
+    let pending = tracked.borrow().values().sum::<usize>();
+    if pending > 0 {
+        info!("Waiting for {} background tasks", pending);
+    }
+    let drained = tokio::time::timeout_at(deadline, tracked.wait_for(|t| t.is_empty()))
+        .await
+        .is_ok();
+    match drained {
+        true => Ok(()),
+        false => Err(TRACKED.borrow().clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_with_failures_in_a_row() {
+        let waits: Vec<u64> = (1..=8).map(|f| backoff(f).as_secs()).collect();
+        assert_eq!(waits, vec![2, 4, 8, 16, 32, 64, 64, 64]);
+    }
+
+    #[test]
+    fn failures_are_forgotten_after_running_healthy() {
+        let quick = Duration::from_secs(1);
+        let failures = (0..10).fold(0, |f, _| failures_in_row(f, quick));
+        assert_eq!(failures, 10);
+        assert_eq!(failures_in_row(failures, HEALTHY_RUN - quick), 11);
+        assert_eq!(failures_in_row(failures, HEALTHY_RUN), 1);
+        assert_eq!(backoff(failures_in_row(failures, HEALTHY_RUN)).as_secs(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn loops_are_restarted_until_shutdown() {
+        static STARTS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+        supervise("test_loop", || async {
+            STARTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            panic!("broken");
+        });
+
+        // Started, then restarted after 2s and 4s
+        tokio::time::sleep(Duration::from_secs(7)).await;
+        assert_eq!(STARTS.load(std::sync::atomic::Ordering::SeqCst), 3);
+        let status = supervised()
+            .into_iter()
+            .find(|t| t.name == "test_loop")
+            .unwrap();
+        assert_eq!(status.restarts, 3);
+        assert_eq!(status.last_failure.as_deref(), Some("panicked: broken"));
+
+        shutdown();
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(STARTS.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,143 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use once_cell::sync::OnceCell;
+use sjf_api::currency::{Currency, ExchangeRate};
+use sjf_api::tax::{OssReportRow, applied_rate, shipping_rate, vat_country};
+use sqlx::{Postgres, Transaction, query, query_file, types::Uuid};
+
+use crate::postgres::POOL;
+
+static OSS: OnceCell<bool> = OnceCell::new();
+
+pub(crate) fn configure(config: &sjf_config::CheckoutConfig) {
+    let _ = OSS.set(config.vat_oss);
+}
+
+/// Whether we're registered for the One Stop Shop and charge EU consumers the
+/// VAT of their country, set with `VAT_OSS=true` once sales to other member
+/// states pass the EU wide threshold.
+pub fn oss() -> bool {
+    OSS.get().copied().unwrap_or(false)
+}
+
+/// Sets the VAT applied to the lines and shipping of an order from the
+/// country it ships to. Orders whose destination isn't known yet are taxed as
+/// Swedish, and taxed again when Stripe tells where they are going.
+pub(crate) async fn apply(
+    tx: &mut Transaction<'static, Postgres>,
+    order: Uuid,
+) -> Result<(), sqlx::Error> {
+    let Some(destination) = query!("SELECT country FROM orders WHERE id=$1 FOR UPDATE", order)
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|r| r.country.unwrap_or_else(|| "SE".into()))
+    else {
+        return Ok(());
+    };
+    let oss = oss();
+
+    // Lines keep the product's rate, as the product may have been deleted since
+    let lines = query!(
+        r#"SELECT l.id, COALESCE(l.product_tax_rate, p.tax_rate, 25) AS "tax_rate!", l.price * l.quantity AS "value!"
+        FROM order_lines l LEFT JOIN products p ON p.id = l.product_id WHERE l.order_id=$1"#,
+        order
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    let shipping = shipping_rate(
+        lines
+            .iter()
+            .map(|l| (l.tax_rate as u32, l.value.max(0) as u32)),
+    );
+    for line in lines {
+        let rate = applied_rate(line.tax_rate as u32, &destination, oss);
+        query!(
+            "UPDATE order_lines SET tax_rate=$2 WHERE id=$1",
+            line.id,
+            rate as i32
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    query!(
+        "UPDATE orders SET vat_country=$2, shipping_tax_rate=$3 WHERE id=$1",
+        order,
+        vat_country(&destination, oss),
+        applied_rate(shipping, &destination, oss) as i32
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Remembers the country an order ships to, as collected by Stripe. Like the
+/// email, this may arrive before the order is committed.
+pub async fn set_destination(reservation: &str, country: &str) -> Result<(), sqlx::Error> {
+    let Ok(reservation) = Uuid::from_str(reservation) else {
+        return Ok(());
+    };
+    let mut tx = POOL.get().unwrap().begin().await?;
+    query!(
+        "INSERT INTO orders(id, country) SELECT $1, $2
+        WHERE EXISTS (SELECT 1 FROM pending_orders WHERE id=$1) OR EXISTS (SELECT 1 FROM orders WHERE id=$1)
+        ON CONFLICT (id) DO UPDATE SET country=excluded.country",
+        reservation,
+        country.to_uppercase()
+    )
+    .execute(&mut *tx)
+    .await?;
+    apply(&mut tx, reservation).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Sales to consumers in other member states taxed through OSS in a quarter,
+/// `quarter` counted from 1. Each line is counted at what was charged for it,
+/// converted like the payment provider was asked to charge it.
+pub async fn oss_report(year: i32, quarter: u32) -> Result<Vec<OssReportRow>, sqlx::Error> {
+    let start_month = 3 * (quarter.clamp(1, 4) - 1) + 1;
+    let start = NaiveDate::from_ymd_opt(year, start_month, 1).unwrap_or_default();
+    let end = start
+        .checked_add_months(chrono::Months::new(3))
+        .unwrap_or(start);
+    let midnight =
+        |d: NaiveDate| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap_or_default());
+
+    let lines = query_file!("sql/orders/oss_report.sql", midnight(start), midnight(end))
+        .fetch_all(POOL.get().unwrap())
+        .await?;
+
+    let mut rows = BTreeMap::new();
+    let mut counted = BTreeSet::new();
+    for line in lines {
+        let (Some(id), Some(price), Some(quantity)) = (line.id, line.price, line.quantity) else {
+            continue;
+        };
+        let rate = ExchangeRate {
+            currency: line
+                .currency
+                .as_deref()
+                .and_then(Currency::from_code)
+                .unwrap_or_default(),
+            sek_micros: line.sek_micros.unwrap_or(1_000_000).max(1) as u32,
+        };
+        let key = (line.country, Reverse(line.rate as u32), rate.currency);
+        let row = rows.entry(key.clone()).or_insert_with(|| OssReportRow {
+            country: key.0.clone(),
+            rate: key.1.0,
+            currency: key.2,
+            orders: 0,
+            gross: 0,
+        });
+        if counted.insert((id, key)) {
+            row.orders += 1;
+        }
+        row.gross += rate.minor_units(price.max(0) as u32) * quantity.max(0) as u64;
+    }
+    let res = rows.into_values().collect();
+    Ok(res)
+}
@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sjf_api::i18n::Locale;
+use sjf_api::product::ProductTranslation;
+use sqlx::{Postgres, Transaction, query, query_file};
+
+use crate::postgres::POOL;
+
+/// Translated names and descriptions of the given products. Products without
+/// a translation are left out.
+pub async fn product_texts(
+    locale: Locale,
+    product_ids: &[u32],
+) -> Result<HashMap<u32, ProductTranslation>, sqlx::Error> {
+    let ids: Vec<i32> = product_ids.iter().map(|id| *id as i32).collect();
+    let res = query!(
+        "SELECT product_id, name, description FROM product_translations
+        WHERE locale = $1 AND product_id = ANY($2)",
+        locale.code(),
+        &ids
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| {
+        (
+            r.product_id as u32,
+            ProductTranslation {
+                name: r.name,
+                description: r.description,
+            },
+        )
+    })
+    .collect();
+    Ok(res)
+}
+
+/// Every translation of the given products, for editing.
+pub(crate) async fn products(
+    product_ids: &[i32],
+) -> Result<HashMap<i32, BTreeMap<Locale, ProductTranslation>>, sqlx::Error> {
+    let mut res: HashMap<i32, BTreeMap<Locale, ProductTranslation>> = HashMap::new();
+    query!(
+        "SELECT product_id, locale, name, description FROM product_translations WHERE product_id = ANY($1)",
+        product_ids
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .for_each(|r| {
+        if let Some(locale) = Locale::from_code(&r.locale) {
+            res.entry(r.product_id).or_default().insert(
+                locale,
+                ProductTranslation {
+                    name: r.name,
+                    description: r.description,
+                },
+            );
+        }
+    });
+    Ok(res)
+}
+
+/// Replaces the translations of a product. Translations without a name are dropped.
+pub(crate) async fn store_product(
+    tx: &mut Transaction<'static, Postgres>,
+    product_id: i32,
+    translations: &BTreeMap<Locale, ProductTranslation>,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM product_translations WHERE product_id=$1",
+        product_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    for (locale, t) in translations
+        .iter()
+        .filter(|(l, t)| **l != Locale::Sv && !t.name.trim().is_empty())
+    {
+        query!(
+            "INSERT INTO product_translations (product_id, locale, name, description) VALUES ($1, $2, $3, $4)",
+            product_id,
+            locale.code(),
+            t.name.trim(),
+            t.description
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Translated names of the given categories.
+pub async fn category_names(
+    locale: Locale,
+    category_ids: &[u32],
+) -> Result<BTreeMap<u32, String>, sqlx::Error> {
+    let ids: Vec<i32> = category_ids.iter().map(|id| *id as i32).collect();
+    let res = query!(
+        "SELECT category_id, name FROM category_translations WHERE locale = $1 AND category_id = ANY($2)",
+        locale.code(),
+        &ids
+    )
+    .fetch_all(POOL.get().unwrap())
+    .await?
+    .into_iter()
+    .map(|r| (r.category_id as u32, r.name))
+    .collect();
+    Ok(res)
+}
+
+/// Sets the translated name of a category, an empty name removes it.
+pub async fn set_category_name(id: u32, locale: Locale, name: &str) -> Result<(), sqlx::Error> {
+    if name.trim().is_empty() {
+        query!(
+            "DELETE FROM category_translations WHERE category_id=$1 AND locale=$2",
+            id as i32,
+            locale.code()
+        )
+        .execute(POOL.get().unwrap())
+        .await?;
+    } else {
+        query!(
+            "INSERT INTO category_translations (category_id, locale, name) VALUES ($1, $2, $3)
+            ON CONFLICT (category_id, locale) DO UPDATE SET name = excluded.name",
+            id as i32,
+            locale.code(),
+            name.trim()
+        )
+        .execute(POOL.get().unwrap())
+        .await?;
+    }
+    Ok(())
+}
+
+/// The names to show along a category path given by its (Swedish) names.
+pub async fn path_labels(locale: Locale, path: &[String]) -> Result<Vec<String>, sqlx::Error> {
+    if locale == Locale::Sv {
+        return Ok(path.to_vec());
+    }
+    let res: Vec<String> = query_file!("sql/translations/path_labels.sql", path, locale.code())
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| r.name)
+        .collect();
+    // Unknown paths keep their names
+    Ok(match res.len() == path.len() {
+        true => res,
+        false => path.to_vec(),
+    })
+}
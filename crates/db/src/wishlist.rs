@@ -0,0 +1,50 @@
+use crate::postgres::POOL;
+use sjf_api::product::ProductId;
+use sjf_api::wishlist::{SyncWishlistRequest, WishedProduct};
+use sqlx::{query, query_file, types::Uuid};
+
+/// Stores the browser's wishlist for a customer account, merged with what was
+/// already stored if requested, and returns the resulting wishlist.
+pub async fn sync(user: &Uuid, req: SyncWishlistRequest) -> Result<Vec<ProductId>, sqlx::Error> {
+    let ids: Vec<i32> = req.items.into_iter().map(|x| x as i32).collect();
+
+    let mut tx = POOL.get().unwrap().begin().await?;
+    if !req.merge {
+        query!(
+            "DELETE FROM wishlist_items WHERE user_id=$1 AND NOT (product_id = ANY($2))",
+            user,
+            &ids
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    query_file!("sql/wishlist/store.sql", user, &ids)
+        .execute(&mut *tx)
+        .await?;
+    let res = query!(
+        "SELECT product_id FROM wishlist_items WHERE user_id=$1 ORDER BY created",
+        user
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|r| r.product_id as ProductId)
+    .collect();
+    tx.commit().await?;
+
+    Ok(res)
+}
+
+pub async fn most_wished(limit: u32) -> Result<Vec<WishedProduct>, sqlx::Error> {
+    let res = query_file!("sql/wishlist/most_wished.sql", limit as i64)
+        .fetch_all(POOL.get().unwrap())
+        .await?
+        .into_iter()
+        .map(|r| WishedProduct {
+            product_id: r.id as ProductId,
+            name: r.name,
+            wishes: r.wishes as u32,
+        })
+        .collect();
+    Ok(res)
+}
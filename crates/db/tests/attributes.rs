@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use sjf_api::attribute::{AttributeDefinition, AttributeKind, Facet};
+use sjf_api::product::PreviewFilter;
+use sjf_db::attribute;
+use sjf_test_support as common;
+
+struct Attributes {
+    category: u32,
+    height: u32,
+    color: u32,
+    material: u32,
+}
+
+/// A category with a color and a material, below one with a height.
+async fn attributes() -> Attributes {
+    let create = |name: &str, parent| sjf_api::category::CreateReq {
+        name: name.into(),
+        parent,
+    };
+    let furniture = sjf_db::category::create(create("Inredning", None))
+        .await
+        .unwrap()
+        .id;
+    let category = sjf_db::category::create(create("Pallar", Some(furniture)))
+        .await
+        .unwrap()
+        .id;
+    let define = |category, name: &str, kind| AttributeDefinition {
+        id: 0,
+        category,
+        name: name.into(),
+        kind,
+    };
+    let height = attribute::create(define(
+        furniture,
+        "Höjd",
+        AttributeKind::Number { unit: "cm".into() },
+    ))
+    .await
+    .unwrap();
+    let color = attribute::create(define(
+        category,
+        "Färg",
+        AttributeKind::Enum {
+            options: vec!["Röd".into(), "Blå".into()],
+        },
+    ))
+    .await
+    .unwrap();
+    let material = attribute::create(define(category, "Material", AttributeKind::Text))
+        .await
+        .unwrap();
+    Attributes {
+        category,
+        height,
+        color,
+        material,
+    }
+}
+
+async fn product(category: u32, attributes: BTreeMap<u32, String>) -> u32 {
+    sjf_db::create_product(sjf_db::Product {
+        id: 0,
+        name: "Pall".into(),
+        price: 100,
+        description: String::new(),
+        quantity: Some(1),
+        created: chrono::Utc::now(),
+        updated: chrono::Utc::now(),
+        tax_rate: 25,
+        images: vec![],
+        category,
+        reorder_threshold: None,
+        stock_policy: Default::default(),
+        attributes,
+        translations: Default::default(),
+        sku: None,
+    })
+    .await
+    .unwrap() as u32
+}
+
+async fn stored(category: u32, id: u32) -> sjf_db::Product {
+    sjf_db::product::get_products(category)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|p| p.id == id as i32)
+        .unwrap()
+}
+
+fn values(facet: &Facet) -> Vec<(&str, u32)> {
+    facet.values.iter().map(|(v, n)| (v.as_str(), *n)).collect()
+}
+
+#[test]
+fn only_valid_values_are_stored() {
+    common::run("sjf_test_attributes", async {
+        let a = attributes().await;
+        let unrelated = common::product("Annan", Some(1)).await;
+        let id = product(
+            a.category,
+            BTreeMap::from([
+                (a.height, " 45,50 ".into()),
+                (a.color, "Grön".into()),
+                (a.material, "  ".into()),
+                (unrelated, "Något".into()),
+            ]),
+        )
+        .await;
+        let product = stored(a.category, id).await;
+        assert_eq!(
+            product.attributes,
+            BTreeMap::from([(a.height, "45.5".into())])
+        );
+
+        sjf_db::update_product(sjf_db::Product {
+            attributes: BTreeMap::from([
+                (a.height, "tre".into()),
+                (a.color, "Blå".into()),
+                (a.material, "Ek".into()),
+            ]),
+            ..product
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            stored(a.category, id).await.attributes,
+            BTreeMap::from([(a.color, "Blå".into()), (a.material, "Ek".into())])
+        );
+    });
+}
+
+#[test]
+fn facets_count_the_products_matching_other_selections() {
+    common::run("sjf_test_attributes", async {
+        let a = attributes().await;
+        for (color, height) in [("Blå", "45"), ("Röd", "45"), ("Röd", "100"), ("Röd", "60")] {
+            let values = BTreeMap::from([(a.color, color.into()), (a.height, height.into())]);
+            product(a.category, values).await;
+        }
+
+        let facets = attribute::facets(a.category, PreviewFilter::default())
+            .await
+            .unwrap();
+        // Outermost category first, values of numbers by size and of
+        // choices in the order of the options
+        let names: Vec<_> = facets.iter().map(|f| f.attribute.name.as_str()).collect();
+        assert_eq!(names, vec!["Höjd", "Färg"]);
+        assert_eq!(values(&facets[0]), vec![("45", 2), ("60", 1), ("100", 1)]);
+        assert_eq!(values(&facets[1]), vec![("Röd", 3), ("Blå", 1)]);
+
+        let filter = PreviewFilter {
+            attributes: vec![(a.height, "45".into())],
+            ..Default::default()
+        };
+        let facets = attribute::facets(a.category, filter).await.unwrap();
+        assert_eq!(values(&facets[0]), vec![("45", 2), ("60", 1), ("100", 1)]);
+        assert_eq!(values(&facets[1]), vec![("Röd", 1), ("Blå", 1)]);
+
+        let filter = PreviewFilter {
+            attributes: vec![(a.height, "45".into()), (a.color, "Röd".into())],
+            max_price: Some(50),
+            ..Default::default()
+        };
+        assert!(
+            attribute::facets(a.category, filter)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    });
+}
@@ -0,0 +1,46 @@
+use sjf_api::cart::{CartNotice, SyncCartRequest};
+use sjf_db::cart::{self, MAX_CART_ITEMS};
+use sjf_test_support as common;
+use sqlx::{Connection, types::Uuid};
+
+async fn stored_lines(cart: Uuid) -> i64 {
+    let mut conn = common::connection().await;
+    let count = sqlx::query_scalar("SELECT count(*) FROM cart_items WHERE cart_id = $1")
+        .bind(cart)
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    conn.close().await.unwrap();
+    count
+}
+
+#[test]
+fn carts_keep_a_limited_number_of_lines() {
+    common::run("sjf_test_cart", async {
+        let mut products = vec![];
+        for i in 0..=MAX_CART_ITEMS {
+            products.push(common::product(&format!("Vara {i}"), Some(10)).await);
+        }
+
+        let (id, rsp) = cart::sync(
+            None,
+            SyncCartRequest {
+                items: products.iter().map(|p| (*p, 1)).collect(),
+                merge: true,
+                locale: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(rsp.items.len(), MAX_CART_ITEMS);
+        assert_eq!(
+            rsp.notices,
+            vec![CartNotice::Removed {
+                product_id: *products.iter().max().unwrap(),
+                name: None,
+            }]
+        );
+        assert_eq!(stored_lines(id.unwrap()).await, MAX_CART_ITEMS as i64);
+    });
+}
@@ -0,0 +1,32 @@
+use sjf_api::currency::Currency;
+use sjf_db::currency;
+use sjf_test_support as common;
+
+async fn sek_micros(of: Currency) -> Option<u32> {
+    currency::rates()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|r| r.currency == of)
+        .map(|r| r.sek_micros)
+}
+
+#[test]
+fn rates_that_can_not_be_stored_are_refused() {
+    common::run("sjf_test_currency", async {
+        currency::set_rate(Currency::Eur, Some(11_250_000))
+            .await
+            .unwrap();
+        assert_eq!(sek_micros(Currency::Eur).await, Some(11_250_000));
+
+        assert!(
+            currency::set_rate(Currency::Eur, Some(i32::MAX as u32 + 1))
+                .await
+                .is_err()
+        );
+        assert_eq!(sek_micros(Currency::Eur).await, Some(11_250_000));
+
+        currency::set_rate(Currency::Eur, None).await.unwrap();
+        assert_eq!(sek_micros(Currency::Eur).await, None);
+    });
+}
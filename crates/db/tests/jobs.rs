@@ -0,0 +1,253 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sjf_db::jobs::{self, Job, JobResult, Retried};
+use sjf_test_support as common;
+use sqlx::Connection;
+
+struct Row {
+    status: String,
+    attempts: i32,
+    run_at: DateTime<Utc>,
+    rerun: bool,
+    last_error: Option<String>,
+}
+
+async fn row(id: i64) -> Option<Row> {
+    let mut conn = common::connection().await;
+    sqlx::query_as::<_, (String, i32, DateTime<Utc>, bool, Option<String>)>(
+        "SELECT status, attempts, run_at, rerun, last_error FROM jobs WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&mut conn)
+    .await
+    .unwrap()
+    .map(|(status, attempts, run_at, rerun, last_error)| Row {
+        status,
+        attempts,
+        run_at,
+        rerun,
+        last_error,
+    })
+}
+
+/// Moves a waiting job's retry to now.
+async fn make_due(id: i64) {
+    let mut conn = common::connection().await;
+    sqlx::query("UPDATE jobs SET run_at = CURRENT_TIMESTAMP WHERE id = $1")
+        .bind(id)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+}
+
+async fn run_due() -> Vec<i64> {
+    jobs::run_due(100).await.unwrap()
+}
+
+fn assert_near(time: DateTime<Utc>, expected: DateTime<Utc>) {
+    let off = (time - expected).abs();
+    assert!(off < TimeDelta::seconds(5), "{time} should be {expected}");
+}
+
+static SUCCEEDED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct Succeeding;
+
+impl Job for Succeeding {
+    const KIND: &'static str = "test_succeeding";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        SUCCEEDED.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Failing;
+
+impl Job for Failing {
+    const KIND: &'static str = "test_failing";
+    const MAX_ATTEMPTS: i32 = 3;
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        Err("always fails".into())
+    }
+}
+
+/// Enqueues itself again while running when set.
+static ENQUEUE_WHILE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize)]
+struct Once;
+
+impl Job for Once {
+    const KIND: &'static str = "test_once";
+    const ONCE: bool = true;
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        if ENQUEUE_WHILE_RUNNING.load(Ordering::SeqCst) {
+            jobs::enqueue(&Once).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FailingOnce;
+
+impl Job for FailingOnce {
+    const KIND: &'static str = "test_failing_once";
+    const MAX_ATTEMPTS: i32 = 1;
+    const ONCE: bool = true;
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        Err("always fails".into())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Periodic;
+
+impl Job for Periodic {
+    const KIND: &'static str = "test_periodic";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        Ok(())
+    }
+}
+
+#[test]
+fn locked_jobs_are_skipped() {
+    common::run("sjf_test_jobs", async {
+        jobs::register::<Succeeding>();
+        let id = jobs::enqueue(&Succeeding).await.unwrap();
+
+        // Another worker holding the row doesn't block this one
+        let mut conn = common::connection().await;
+        let mut tx = conn.begin().await.unwrap();
+        sqlx::query("SELECT id FROM jobs WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        assert!(!run_due().await.contains(&id));
+        assert_eq!(row(id).await.unwrap().status, "pending");
+        assert_eq!(SUCCEEDED.load(Ordering::SeqCst), 0);
+        tx.rollback().await.unwrap();
+
+        assert!(run_due().await.contains(&id));
+        assert_eq!(row(id).await.unwrap().status, "done");
+        assert_eq!(SUCCEEDED.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn failed_jobs_are_retried_later_and_then_given_up() {
+    common::run("sjf_test_jobs", async {
+        jobs::register::<Failing>();
+        let id = jobs::enqueue(&Failing).await.unwrap();
+
+        assert!(run_due().await.contains(&id));
+        let failed = row(id).await.unwrap();
+        assert_eq!(failed.status, "pending");
+        assert_eq!(failed.attempts, 1);
+        assert_eq!(failed.last_error.as_deref(), Some("always fails"));
+        assert_near(failed.run_at, Utc::now() + TimeDelta::seconds(30));
+        assert!(!run_due().await.contains(&id));
+
+        make_due(id).await;
+        assert!(run_due().await.contains(&id));
+        let failed = row(id).await.unwrap();
+        assert_eq!(failed.attempts, 2);
+        assert_near(failed.run_at, Utc::now() + TimeDelta::seconds(60));
+
+        make_due(id).await;
+        assert!(run_due().await.contains(&id));
+        let dead = row(id).await.unwrap();
+        assert_eq!(dead.status, "dead");
+        assert_eq!(dead.attempts, 3);
+        let listed = jobs::unfinished(true).await.unwrap();
+        assert!(listed.iter().any(|j| j.id == id));
+
+        assert_eq!(jobs::retry(id).await.unwrap(), Retried::Queued);
+        let retried = row(id).await.unwrap();
+        assert_eq!(retried.status, "pending");
+        assert_eq!(retried.attempts, 0);
+        assert_eq!(jobs::retry(id).await.unwrap(), Retried::NotDead);
+    });
+}
+
+#[test]
+fn once_jobs_are_merged_and_rerun() {
+    common::run("sjf_test_jobs", async {
+        jobs::register::<Once>();
+        let id = jobs::enqueue(&Once).await.unwrap();
+        assert_eq!(jobs::enqueue(&Once).await.unwrap(), id);
+
+        // Enqueued while running, so it runs once more afterwards
+        ENQUEUE_WHILE_RUNNING.store(true, Ordering::SeqCst);
+        assert!(run_due().await.contains(&id));
+        let rerun = row(id).await.unwrap();
+        assert_eq!(rerun.status, "pending");
+        assert!(!rerun.rerun);
+        assert_eq!(rerun.attempts, 0);
+
+        ENQUEUE_WHILE_RUNNING.store(false, Ordering::SeqCst);
+        assert!(run_due().await.contains(&id));
+        assert_eq!(row(id).await.unwrap().status, "done");
+        assert_ne!(jobs::enqueue(&Once).await.unwrap(), id);
+    });
+}
+
+#[test]
+fn retried_once_jobs_are_merged_into_a_waiting_one() {
+    common::run("sjf_test_jobs", async {
+        jobs::register::<FailingOnce>();
+        let dead = jobs::enqueue(&FailingOnce).await.unwrap();
+        assert!(run_due().await.contains(&dead));
+        assert_eq!(row(dead).await.unwrap().status, "dead");
+
+        let waiting = jobs::schedule(&FailingOnce, Utc::now() + TimeDelta::hours(1))
+            .await
+            .unwrap();
+        assert_ne!(waiting, dead);
+        assert_eq!(jobs::retry(dead).await.unwrap(), Retried::Merged(waiting));
+        assert!(row(dead).await.is_none());
+        let merged = row(waiting).await.unwrap();
+        assert_eq!(merged.status, "pending");
+        assert_near(merged.run_at, Utc::now());
+    });
+}
+
+#[test]
+fn periodic_jobs_are_scheduled_again_after_running() {
+    common::run("sjf_test_jobs", async {
+        jobs::register_periodic(Periodic, Duration::from_secs(60 * 60));
+        let ran = run_due().await;
+
+        let mut conn = common::connection().await;
+        let ids: Vec<(i64,)> = sqlx::query_as("SELECT id FROM jobs WHERE kind = $1")
+            .bind(Periodic::KIND)
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 1);
+        let id = ids[0].0;
+        assert!(ran.contains(&id));
+        let next = row(id).await.unwrap();
+        assert_eq!(next.status, "pending");
+        assert_near(next.run_at, Utc::now() + TimeDelta::hours(1));
+
+        assert!(!run_due().await.contains(&id));
+        let count: (i64,) = sqlx::query_as("SELECT count(*) FROM jobs WHERE kind = $1")
+            .bind(Periodic::KIND)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(count.0, 1);
+    });
+}
@@ -0,0 +1,54 @@
+use sjf_api::product::{GetPreviewsRequest, GetPreviewsResp};
+use sjf_test_support as common;
+use sqlx::Connection;
+
+/// Three products moved into the category of the first, which is returned.
+async fn category_of_three() -> u32 {
+    let first = common::product("Listad 1", Some(1)).await;
+    let rest = [
+        common::product("Listad 2", Some(1)).await as i32,
+        common::product("Listad 3", Some(1)).await as i32,
+    ];
+    let mut conn = common::connection().await;
+    let category: i32 = sqlx::query_scalar(
+        "UPDATE products SET category = (SELECT category FROM products WHERE id = $1)
+        WHERE id = ANY($2) RETURNING category",
+    )
+    .bind(first as i32)
+    .bind(&rest[..])
+    .fetch_one(&mut conn)
+    .await
+    .unwrap();
+    conn.close().await.unwrap();
+    category as u32
+}
+
+async fn page(category: u32, page: u32) -> GetPreviewsResp {
+    sjf_db::product::get_previews(GetPreviewsRequest {
+        category: Some(category),
+        recursive: false,
+        limit: 2,
+        page,
+        order: Default::default(),
+        filter: Default::default(),
+        locale: Default::default(),
+    })
+    .await
+    .unwrap()
+}
+
+#[test]
+fn every_page_counts_all_matches() {
+    common::run("sjf_test_listing", async {
+        let category = category_of_three().await;
+
+        let pages = [
+            page(category, 0).await,
+            page(category, 1).await,
+            page(category, 5).await,
+        ];
+        let shown: Vec<_> = pages.iter().map(|p| p.previews.len()).collect();
+        assert_eq!(shown, vec![2, 1, 0]);
+        assert!(pages.iter().all(|p| p.total == 3));
+    });
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use sjf_api::checkout::CheckoutRequest;
+use sjf_api::i18n::Locale;
+use sjf_db::recovery;
+use sjf_test_support as common;
+
+/// An abandoned checkout of `email` in `locale`, returning its token.
+async fn abandoned(email: &str, locale: Locale) -> String {
+    let product = common::product("Övergiven", Some(5)).await;
+    let reservation = sjf_db::checkout::make_reservation(CheckoutRequest {
+        order: BTreeMap::from([(product, 1)]),
+        recovery: None,
+        locale,
+        currency: Default::default(),
+    })
+    .await
+    .unwrap();
+    assert!(
+        recovery::record_abandoned(&reservation, email)
+            .await
+            .unwrap()
+    );
+    due(email).await.expect("the checkout should be due")
+}
+
+/// The token of the reminder due for `email`.
+async fn due(email: &str) -> Option<String> {
+    recovery::due_reminders(0, 1)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|r| r.email == email)
+        .map(|r| r.id.to_string())
+}
+
+#[test]
+fn opting_out_stops_every_reminder_to_the_customer() {
+    common::run("sjf_test_recovery", async {
+        let email = "glomsk@butik.example";
+        let token = abandoned(email, Locale::Sv).await;
+
+        assert!(recovery::opt_out(&token).await.unwrap());
+        assert_eq!(due(email).await, None);
+
+        // Later checkouts aren't reminded either
+        let product = common::product("Senare", Some(5)).await;
+        let reservation = sjf_db::checkout::make_reservation(CheckoutRequest {
+            order: BTreeMap::from([(product, 1)]),
+            recovery: None,
+            locale: Default::default(),
+            currency: Default::default(),
+        })
+        .await
+        .unwrap();
+        assert!(
+            recovery::record_abandoned(&reservation, email)
+                .await
+                .unwrap()
+        );
+        assert_eq!(due(email).await, None);
+    });
+}
+
+#[test]
+fn unknown_or_repeated_opt_outs_change_nothing() {
+    common::run("sjf_test_recovery", async {
+        let email = "annan@butik.example";
+        let token = abandoned(email, Locale::Sv).await;
+
+        assert!(!recovery::opt_out("inte-en-token").await.unwrap());
+        assert!(
+            !recovery::opt_out("00000000-0000-0000-0000-000000000000")
+                .await
+                .unwrap()
+        );
+        assert_eq!(due(email).await, Some(token.clone()));
+
+        assert!(recovery::opt_out(&token).await.unwrap());
+        assert!(!recovery::opt_out(&token).await.unwrap());
+    });
+}
+
+#[test]
+fn reminders_keep_the_language_of_the_checkout() {
+    common::run("sjf_test_recovery", async {
+        let email = "engelsk@butik.example";
+        let token = abandoned(email, Locale::En).await;
+
+        let reminder = recovery::due_reminders(0, 1)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|r| r.email == email)
+            .unwrap();
+        assert_eq!(reminder.locale, Locale::En);
+
+        let restored = recovery::restore(&token).await.unwrap().unwrap();
+        assert_eq!(restored.locale, Locale::En);
+        assert_eq!(restored.recovery, Some(token));
+    });
+}
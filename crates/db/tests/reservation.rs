@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sjf_api::checkout::CheckoutRequest;
+use sjf_db::reservation::{self, Clock};
+use sjf_test_support as common;
+
+struct FakeClock(Mutex<DateTime<Utc>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+fn set_time(now: DateTime<Utc>) {
+    reservation::set_clock(Arc::new(FakeClock(Mutex::new(now))));
+}
+
+fn start() -> DateTime<Utc> {
+    "2026-03-02T12:00:00Z".parse().unwrap()
+}
+
+async fn reserve(product: u32, quantity: u32) -> String {
+    sjf_db::checkout::make_reservation(CheckoutRequest {
+        order: BTreeMap::from([(product, quantity)]),
+        recovery: None,
+        locale: Default::default(),
+        currency: Default::default(),
+    })
+    .await
+    .unwrap()
+}
+
+async fn is_pending(id: &str) -> bool {
+    reservation::pending()
+        .await
+        .unwrap()
+        .iter()
+        .any(|r| r.id.to_string() == id)
+}
+
+#[test]
+fn stock_is_held_until_the_checkout_window_expires() {
+    common::run("sjf_test_reservation", async {
+        // Before a payment session is attached the reservation lasts a whole
+        // session lifetime plus the grace period, 30 + 5 minutes by default
+        let product = common::product("Provisorisk", Some(5)).await;
+        set_time(start());
+        let id = reserve(product, 2).await;
+        assert_eq!(common::quantity(product).await, Some(3));
+
+        set_time(start() + TimeDelta::minutes(35) - TimeDelta::seconds(1));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, Some(3));
+        assert!(is_pending(&id).await);
+
+        set_time(start() + TimeDelta::minutes(35));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, Some(5));
+        assert!(!is_pending(&id).await);
+    });
+}
+
+#[test]
+fn stock_is_held_for_the_session_and_its_grace_period() {
+    common::run("sjf_test_reservation", async {
+        let product = common::product("Session", Some(4)).await;
+        set_time(start());
+        let id = reserve(product, 1).await;
+        reservation::attach_to_session(&id, start() + TimeDelta::minutes(60))
+            .await
+            .unwrap();
+
+        // Past the provisional expiry, but the session is still open
+        set_time(start() + TimeDelta::minutes(40));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, Some(3));
+
+        set_time(start() + TimeDelta::minutes(65) - TimeDelta::seconds(1));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, Some(3));
+
+        set_time(start() + TimeDelta::minutes(65));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, Some(4));
+        assert!(!is_pending(&id).await);
+    });
+}
+
+#[test]
+fn untracked_products_are_not_counted() {
+    common::run("sjf_test_reservation", async {
+        let product = common::product("Obegränsad", None).await;
+        set_time(start());
+        let id = reserve(product, 3).await;
+        assert_eq!(common::quantity(product).await, None);
+
+        set_time(start() + TimeDelta::minutes(35));
+        reservation::release_expired().await.unwrap();
+        assert_eq!(common::quantity(product).await, None);
+        assert!(!is_pending(&id).await);
+    });
+}
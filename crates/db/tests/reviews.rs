@@ -0,0 +1,213 @@
+use sjf_api::review::{ReviewStatus, SubmitReviewRequest, SubmitReviewStatus};
+use sjf_db::review;
+use sjf_test_support as common;
+use sqlx::{Connection, Executor};
+
+/// Tokens of `orders` orders by `email`, each with one line of `product`.
+async fn order_lines(email: &str, product: u32, orders: usize) -> Vec<String> {
+    let mut conn = common::connection().await;
+    let mut tokens = vec![];
+    for _ in 0..orders {
+        let line: sqlx::types::Uuid = sqlx::query_scalar(
+            "WITH o AS (INSERT INTO orders(id, email) VALUES (gen_random_uuid(), $1) RETURNING id)
+            INSERT INTO order_lines(order_id, product_id, quantity, price, name)
+            SELECT id, $2, 1, 100, 'Rad' FROM o RETURNING id",
+        )
+        .bind(email)
+        .bind(product as i32)
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+        tokens.push(line.to_string());
+    }
+    conn.close().await.unwrap();
+    tokens
+}
+
+fn review(token: &str, rating: u8, text: &str) -> SubmitReviewRequest {
+    SubmitReviewRequest {
+        token: token.into(),
+        rating,
+        text: text.into(),
+        author: "Kim".into(),
+    }
+}
+
+#[test]
+fn every_order_line_can_be_reviewed_once() {
+    common::run("sjf_test_reviews", async {
+        let product = common::product("Recenserad", Some(1)).await;
+        let token = &order_lines("en@butik.example", product, 1).await[0];
+
+        for invalid in ["", "inte-en-token", "00000000-0000-0000-0000-000000000000"] {
+            assert_eq!(
+                review::submit(review(invalid, 5, "Bra")).await.unwrap(),
+                SubmitReviewStatus::InvalidToken
+            );
+        }
+
+        let invitation = review::invitation(token).await.unwrap().unwrap();
+        assert_eq!(invitation.product_name, "Recenserad");
+        assert!(!invitation.already_reviewed);
+
+        assert_eq!(
+            review::submit(review(token, 5, "Bra")).await.unwrap(),
+            SubmitReviewStatus::Submitted
+        );
+        assert!(
+            review::invitation(token)
+                .await
+                .unwrap()
+                .unwrap()
+                .already_reviewed
+        );
+        assert_eq!(
+            review::submit(review(token, 1, "Ändrade mig"))
+                .await
+                .unwrap(),
+            SubmitReviewStatus::AlreadyReviewed
+        );
+    });
+}
+
+#[test]
+fn invalid_reviews_are_refused() {
+    common::run("sjf_test_reviews", async {
+        let product = common::product("Ogiltig", Some(1)).await;
+        let token = &order_lines("ogiltig@butik.example", product, 1).await[0];
+
+        let too_long = "a".repeat(sjf_api::review::MAX_REVIEW_LENGTH + 1);
+        for req in [
+            review(token, 0, "Bra"),
+            review(token, 6, "Bra"),
+            review(token, 5, "  "),
+            review(token, 5, &too_long),
+            review(token, 5, "http://a http://b http://c"),
+            SubmitReviewRequest {
+                author: " ".into(),
+                ..review(token, 5, "Bra")
+            },
+        ] {
+            assert_eq!(
+                review::submit(req).await.unwrap(),
+                SubmitReviewStatus::Invalid
+            );
+        }
+        assert!(
+            !review::invitation(token)
+                .await
+                .unwrap()
+                .unwrap()
+                .already_reviewed
+        );
+    });
+}
+
+#[test]
+fn customers_can_only_write_a_few_reviews_a_day() {
+    common::run("sjf_test_reviews", async {
+        let product = common::product("Många", Some(1)).await;
+        let tokens = order_lines("flitig@butik.example", product, 11).await;
+
+        for token in &tokens[..10] {
+            assert_eq!(
+                review::submit(review(token, 4, "Bra")).await.unwrap(),
+                SubmitReviewStatus::Submitted
+            );
+        }
+        assert_eq!(
+            review::submit(review(&tokens[10], 4, "Bra")).await.unwrap(),
+            SubmitReviewStatus::RateLimited
+        );
+
+        // Another customer isn't held back
+        let other = &order_lines("annan@butik.example", product, 1).await[0];
+        assert_eq!(
+            review::submit(review(other, 4, "Bra")).await.unwrap(),
+            SubmitReviewStatus::Submitted
+        );
+    });
+}
+
+#[test]
+fn only_approved_reviews_are_shown() {
+    common::run("sjf_test_reviews", async {
+        let product = common::product("Granskad", Some(1)).await;
+        let tokens = order_lines("granskad@butik.example", product, 2).await;
+        for (token, rating) in tokens.iter().zip([5, 2]) {
+            review::submit(review(token, rating, "Text")).await.unwrap();
+        }
+        assert!(
+            review::product_reviews(product, 10)
+                .await
+                .unwrap()
+                .summary
+                .is_none()
+        );
+
+        let pending: Vec<_> = review::moderation(7)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.product_id == product)
+            .collect();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|r| r.status == ReviewStatus::Pending));
+        let id = |rating| {
+            pending
+                .iter()
+                .find(|r| r.review.rating == rating)
+                .unwrap()
+                .review
+                .id
+                .clone()
+        };
+
+        review::moderate(&id(5), ReviewStatus::Approved)
+            .await
+            .unwrap();
+        review::moderate(&id(2), ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        review::reply(&id(5), " Tack! ").await.unwrap();
+
+        let shown = review::product_reviews(product, 10).await.unwrap();
+        let summary = shown.summary.unwrap();
+        assert_eq!((summary.average, summary.count), (5.0, 1));
+        assert_eq!(shown.reviews.len(), 1);
+        assert_eq!(shown.reviews[0].reply.as_deref(), Some("Tack!"));
+
+        review::reply(&id(5), "").await.unwrap();
+        let shown = review::product_reviews(product, 10).await.unwrap();
+        assert_eq!(shown.reviews[0].reply, None);
+    });
+}
+
+#[test]
+fn reviews_are_limited_across_customers() {
+    common::run("sjf_test_reviews", async {
+        let product = common::product("Spam", Some(1)).await;
+        let tokens = order_lines("spam@butik.example", product, 1).await;
+        let mut conn = common::connection().await;
+        let flood = format!(
+            "INSERT INTO reviews(order_line, product_id, rating, text, author)
+            SELECT gen_random_uuid(), {product}, 5, 'Spam', 'Bot' FROM generate_series(1, 30)"
+        );
+        // Reviews of lines that don't exist, removed again below
+        conn.execute("ALTER TABLE reviews DISABLE TRIGGER ALL")
+            .await
+            .unwrap();
+        conn.execute(flood.as_str()).await.unwrap();
+        conn.execute("ALTER TABLE reviews ENABLE TRIGGER ALL")
+            .await
+            .unwrap();
+
+        let status = review::submit(review(&tokens[0], 5, "Bra")).await.unwrap();
+
+        conn.execute("DELETE FROM reviews WHERE author = 'Bot'")
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        assert_eq!(status, SubmitReviewStatus::RateLimited);
+    });
+}
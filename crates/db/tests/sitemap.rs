@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use sjf_api::category::CreateReq;
+use sjf_db::sitemap;
+use sjf_test_support as common;
+use sqlx::Connection;
+
+async fn category(name: &str, parent: Option<u32>) -> u32 {
+    let req = CreateReq {
+        name: name.into(),
+        parent,
+    };
+    sjf_db::category::create(req).await.unwrap().id
+}
+
+#[test]
+fn products_and_categories_are_listed_with_their_latest_change() {
+    common::run("sjf_test_sitemap", async {
+        let top = category("Överst", None).await;
+        let middle = category("Mitten", Some(top)).await;
+        category("Tom", Some(top)).await;
+        let product = common::product("Karta", Some(1)).await;
+
+        // Moved below the top category, last changed a while ago
+        let updated: DateTime<Utc> = "2026-02-01T12:00:00Z".parse().unwrap();
+        let mut conn = common::connection().await;
+        sqlx::query("UPDATE products SET category = $2, updated = $3 WHERE id = $1")
+            .bind(product as i32)
+            .bind(middle as i32)
+            .bind(updated)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+
+        let generation = sitemap::views_generation().await.unwrap();
+        sjf_db::refresh_views().await.unwrap();
+        assert!(sitemap::views_generation().await.unwrap() > generation);
+
+        let products = sitemap::products().await.unwrap();
+        let listed = products.iter().find(|p| p.preview.id == product).unwrap();
+        assert_eq!(
+            listed.preview.product_url(),
+            sjf_api::product::product_url(&["Överst".into(), "Mitten".into()], product)
+        );
+        assert_eq!(listed.updated, updated);
+
+        let categories = sitemap::categories().await.unwrap();
+        let lastmod = |names: &[&str]| {
+            categories
+                .iter()
+                .find(|c| c.names == names)
+                .map(|c| c.updated)
+                .unwrap()
+        };
+        assert_eq!(lastmod(&["Överst"]), Some(updated));
+        assert_eq!(lastmod(&["Överst", "Mitten"]), Some(updated));
+        assert_eq!(lastmod(&["Överst", "Tom"]), None);
+    });
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use sjf_api::checkout::CheckoutRequest;
+use sjf_test_support as common;
+
+async fn reserve(product: u32, quantity: u32) -> String {
+    sjf_db::checkout::make_reservation(CheckoutRequest {
+        order: BTreeMap::from([(product, quantity)]),
+        recovery: None,
+        locale: Default::default(),
+        currency: Default::default(),
+    })
+    .await
+    .unwrap()
+}
+
+/// The fulfilment and backordered units of the reserved line, read from the
+/// reservation as the checkout only lists products with images.
+async fn reserved(reservation: &str) -> (String, i32) {
+    sqlx::query_as(
+        "SELECT fulfilment, backordered FROM product_reservations WHERE reservation_id = $1::uuid",
+    )
+    .bind(reservation)
+    .fetch_one(&mut common::connection().await)
+    .await
+    .unwrap()
+}
+
+async fn set_policy(product: u32, policy: &str) {
+    sqlx::query(
+        "UPDATE products SET stock_policy = $1, backorder_limit = 10, preorder_ship_date = CURRENT_DATE + 30 WHERE id = $2",
+    )
+    .bind(policy)
+    .bind(product as i32)
+    .execute(&mut common::connection().await)
+    .await
+    .unwrap();
+}
+
+#[test]
+fn only_the_units_short_of_stock_are_backordered() {
+    common::run("sjf_test_stock", async {
+        let product = common::product("Restorder", Some(2)).await;
+        set_policy(product, "backorder").await;
+
+        let id = reserve(product, 5).await;
+        assert_eq!(common::quantity(product).await, Some(-3));
+
+        assert_eq!(reserved(&id).await, ("backorder".into(), 3));
+    });
+}
+
+#[test]
+fn pre_orders_keep_selling_past_zero() {
+    common::run("sjf_test_stock", async {
+        let product = common::product("Förbeställning", Some(1)).await;
+        set_policy(product, "preorder").await;
+
+        reserve(product, 3).await;
+        let id = reserve(product, 2).await;
+        assert_eq!(common::quantity(product).await, Some(-4));
+
+        assert_eq!(reserved(&id).await, ("preorder".into(), 0));
+    });
+}
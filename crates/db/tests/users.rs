@@ -0,0 +1,51 @@
+use sjf_test_support as common;
+use sqlx::{Connection, Executor};
+
+const NAME_ROLE_INDEX: &str = include_str!("../sql/table_definitions/46-users_name_role_index.sql");
+
+#[test]
+fn duplicated_names_are_renamed_before_they_are_made_unique() {
+    common::run("sjf_test_users", async {
+        let mut conn = common::connection().await;
+        conn.execute("DROP INDEX users_name_role_index")
+            .await
+            .unwrap();
+        conn.execute(
+            "INSERT INTO users(name, role, last_login) VALUES
+                ('Kim', 'customer', now() - interval '1 day'),
+                ('Kim', 'customer', now()),
+                ('Kim', 'admin', now() - interval '1 day')",
+        )
+        .await
+        .unwrap();
+
+        // Runs at every start, also once the index exists
+        conn.execute(NAME_ROLE_INDEX).await.unwrap();
+        conn.execute(NAME_ROLE_INDEX).await.unwrap();
+
+        let users: Vec<(String, String, bool)> = sqlx::query_as(
+            "SELECT name, role, last_login > now() - interval '1 hour' FROM users
+            WHERE name LIKE 'Kim%' ORDER BY role, name",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        let (renamed, kept): (Vec<_>, Vec<_>) = users.into_iter().partition(|u| u.0 != "Kim");
+        assert_eq!(
+            kept,
+            vec![
+                ("Kim".into(), "admin".into(), false),
+                ("Kim".into(), "customer".into(), true)
+            ]
+        );
+        assert_eq!(renamed.len(), 1);
+        assert!(renamed[0].0.starts_with("Kim "));
+        assert_eq!((&*renamed[0].1, renamed[0].2), ("customer", false));
+
+        let duplicate = conn
+            .execute("INSERT INTO users(name, role) VALUES ('Kim', 'customer')")
+            .await;
+        assert!(duplicate.is_err());
+        conn.close().await.unwrap();
+    });
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+use sjf_db as db;
+use tracing::info;
+
+use crate::ImageId;
+use crate::object_storage::{get_image, put_image};
+
+/// The catalogue as JSON, always the first file of an archive.
+const CATALOGUE_FILE: &str = "catalogue.json";
+/// Image files are stored as `images/<image id>-<variant id>.jpeg`.
+const IMAGE_DIR: &str = "images/";
+
+fn image_path((image, variant): db::backup::VariantKey) -> String {
+    format!("{IMAGE_DIR}{image}-{variant}.jpeg")
+}
+
+fn parse_image_path(path: &str) -> Option<db::backup::VariantKey> {
+    let (image, variant) = path
+        .strip_prefix(IMAGE_DIR)?
+        .strip_suffix(".jpeg")?
+        .split_once('-')?;
+    Some((image.parse().ok()?, variant.parse().ok()?))
+}
+
+fn key_id((image, variant): db::backup::VariantKey) -> ImageId {
+    (image as u32, variant as u32).into()
+}
+
+fn append(
+    archive: &mut tar::Builder<impl Write>,
+    path: &str,
+    data: &[u8],
+) -> Result<(), std::io::Error> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    archive.append_data(&mut header, path, data)
+}
+
+/// Writes the catalogue and every image file in object storage as a tar
+/// archive. The uploaded originals aren't kept, the stored variants are.
+pub async fn create(out: impl Write) -> Result<db::backup::Summary, crate::Error> {
+    let catalogue = db::backup::export().await?;
+    let mut archive = tar::Builder::new(out);
+    append(
+        &mut archive,
+        CATALOGUE_FILE,
+        &serde_json::to_vec_pretty(&catalogue)?,
+    )?;
+
+    for key in catalogue.variants() {
+        let data = get_image(key_id(key)).await?;
+        append(&mut archive, &image_path(key), &data)?;
+    }
+    archive.into_inner()?.flush()?;
+
+    let summary = catalogue.summary();
+    info!("Backed up {}", summary);
+    Ok(summary)
+}
+
+/// Restores an archive written by [`create`] into an empty shop. The
+/// database isn't committed until every image file is stored, a failed
+/// restore only leaves unreferenced objects in the bucket.
+pub async fn restore(input: impl Read) -> Result<db::backup::Summary, crate::Error> {
+    let mut archive = tar::Archive::new(input);
+    let mut entries = archive.entries()?;
+
+    let catalogue: db::backup::Catalogue = match entries.next() {
+        Some(entry) => {
+            let entry = entry?;
+            if entry.path()?.to_str() != Some(CATALOGUE_FILE) {
+                return Err(crate::Error::internal(format!(
+                    "The archive doesn't start with {CATALOGUE_FILE}"
+                )));
+            }
+            serde_json::from_reader(entry)?
+        }
+        None => return Err(crate::Error::internal("The archive is empty".into())),
+    };
+
+    let restore = db::backup::restore(&catalogue).await?;
+    let mut missing: HashSet<_> = catalogue.variants().collect();
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let Some(key) = parse_image_path(&path) else {
+            continue;
+        };
+        let Some(new_key) = restore.variants.get(&key).copied() else {
+            continue;
+        };
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        if data.len() as u64 != entry.size() {
+            return Err(crate::Error::internal(format!("{path} is truncated")));
+        }
+        put_image(key_id(new_key), data).await?;
+        missing.remove(&key);
+    }
+
+    if let Some(key) = missing.into_iter().next() {
+        return Err(crate::Error::internal(format!(
+            "The archive doesn't contain {}",
+            image_path(key)
+        )));
+    }
+
+    let summary = restore.commit().await?;
+    info!("Restored {}", summary);
+    Ok(summary)
+}
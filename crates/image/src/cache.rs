@@ -1,11 +1,8 @@
-use std::{
-    collections::{BTreeMap, VecDeque},
-    sync::Arc,
-};
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::{RwLock, RwLockWriteGuard};
 use tracing::{debug, info};
 
-use chrono::{Local, NaiveDateTime, Utc, naive};
+use chrono::{NaiveDateTime, Utc};
 use lazy_static::lazy_static;
 
 use crate::ImageId;
@@ -65,11 +62,8 @@ pub async fn add_image(id: ImageId, data: Vec<u8>) {
 
 async fn update_acces_time(id: ImageId, access_time: NaiveDateTime) {
     let mut cache = CACHE.lock.write().await;
-    match cache.images.get_mut(&id) {
-        Some(e) => {
-            e.timestamp = access_time;
-        }
-        None => (),
+    if let Some(e) = cache.images.get_mut(&id) {
+        e.timestamp = access_time;
     }
 }
 
@@ -101,9 +95,9 @@ fn enforce_size<'a>(cache: &mut RwLockWriteGuard<'a, CacheContent>) {
         let mut tmp: Vec<(NaiveDateTime, ImageId)> = cache
             .images
             .iter()
-            .map(|(k, v)| (v.timestamp.clone(), (*k).clone()))
+            .map(|(k, v)| (v.timestamp, (*k).clone()))
             .collect();
-        tmp.sort_by(|(a, _), (b, _)| a.cmp(b));
+        tmp.sort_by_key(|(a, _)| *a);
         let mut oldest = tmp.into_iter().map(|(_, x)| x);
 
         while cache.cache_size > ImageCache::TARGET_SIZE {
@@ -114,8 +108,7 @@ fn enforce_size<'a>(cache: &mut RwLockWriteGuard<'a, CacheContent>) {
 }
 
 fn remove_image<'a>(cache: &mut RwLockWriteGuard<'a, CacheContent>, id: &ImageId) {
-    match cache.images.remove(id) {
-        Some(e) => cache.cache_size -= e.data.len() as u64,
-        None => {}
+    if let Some(e) = cache.images.remove(id) {
+        cache.cache_size -= e.data.len() as u64
     }
 }
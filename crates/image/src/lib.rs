@@ -4,46 +4,53 @@ use image::ImageError;
 use sjf_db as db;
 use tracing::warn;
 
+pub mod backup;
 mod cache;
 mod object_storage;
 mod thumbnails;
-pub use object_storage::init_bucket as init;
 
-pub use thumbnails::upload_image;
+pub use thumbnails::{PreparedImage, prepare_image, regenerate, store_image, upload_image};
 
-#[derive(Debug)]
-enum ErrorTypes {
-    Image(image::ImageError),
-    Io(std::io::Error),
-    Internal(std::string::String),
-    Sql(db::Error),
-}
+/// The largest image file that is accepted, uploaded or downloaded.
+pub const MAX_IMAGE_SIZE: usize = 20 * 1024 * 1024;
 
-#[derive(Debug)]
-pub struct Error {
-    error: ErrorTypes,
+/// Connects to object storage and lets this server upload images in the
+/// background.
+pub async fn init(
+    config: &sjf_config::StorageConfig,
+) -> Result<(), object_storage::ObjectStorageError> {
+    object_storage::init_bucket(config).await?;
+    db::jobs::register::<thumbnails::UploadImage>();
+    Ok(())
 }
 
-impl From<ImageError> for Error {
-    fn from(value: ImageError) -> Self {
-        Self {
-            error: ErrorTypes::Image(value),
-        }
-    }
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Image failed {0}")]
+    Image(#[from] ImageError),
+    #[error("IO failed {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Internal(String),
+    #[error("Sql failed {0}")]
+    Sql(#[from] db::Error),
+    #[error("Object storage failed {0}")]
+    Storage(#[from] Box<object_storage::ObjectStorageError>),
+    #[error("Json failed {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Restore failed {0}")]
+    Restore(#[from] db::backup::RestoreError),
 }
-impl From<std::io::Error> for Error {
-    fn from(value: std::io::Error) -> Self {
-        Self {
-            error: ErrorTypes::Io(value),
-        }
+
+impl From<object_storage::ObjectStorageError> for Error {
+    fn from(value: object_storage::ObjectStorageError) -> Self {
+        Self::Storage(Box::new(value))
     }
 }
 
-impl From<db::Error> for Error {
-    fn from(value: db::Error) -> Self {
-        Self {
-            error: ErrorTypes::Sql(value),
-        }
+impl Error {
+    pub(crate) fn internal(message: String) -> Self {
+        Self::Internal(message)
     }
 }
 
@@ -89,3 +96,23 @@ pub async fn get(id: ImageId) -> Option<Arc<Vec<u8>>> {
         },
     }
 }
+
+/// The stored image files that are missing from object storage.
+pub async fn missing_objects() -> Result<Vec<ImageId>, Error> {
+    let mut missing = vec![];
+    for id in db::image::all_variants().await? {
+        let id: ImageId = id.into();
+        if !object_storage::exists(id.clone()).await? {
+            missing.push(id);
+        }
+    }
+    Ok(missing)
+}
+
+/// Checks that object storage can be reached.
+pub async fn ping() -> Result<(), Error> {
+    if !object_storage::is_initialized() {
+        return Err(Error::internal("Object storage isn't initialized".into()));
+    }
+    Ok(object_storage::ping().await?)
+}
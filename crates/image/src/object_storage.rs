@@ -1,9 +1,7 @@
-use lazy_static::lazy_static;
 use once_cell::sync::OnceCell;
-use std::env;
-use std::time::Duration;
 
 use crate::ImageId;
+use sjf_config::StorageConfig;
 use thiserror::Error;
 
 use aws_sdk_s3::{
@@ -11,17 +9,16 @@ use aws_sdk_s3::{
     config::http::HttpResponse,
     error::SdkError,
     operation::{
-        create_bucket::CreateBucketError, get_object::GetObjectError,
-        list_buckets::ListBucketsError, put_object::PutObjectError,
+        create_bucket::CreateBucketError, get_object::GetObjectError, head_bucket::HeadBucketError,
+        head_object::HeadObjectError, list_buckets::ListBucketsError, put_object::PutObjectError,
     },
-    primitives::{ByteStream, ByteStreamError},
-    types::{CreateBucketConfiguration, LocationInfo},
-    waiters::bucket_exists,
+    primitives::ByteStreamError,
 };
 
 const BUCKET_NAME: &str = "sjf-images-bucket";
 static CLIENT: OnceCell<Box<Client>> = OnceCell::new();
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug)]
 pub enum ObjectStorageError {
     #[error("Failed to list buckets: {0}")]
@@ -30,33 +27,38 @@ pub enum ObjectStorageError {
     CreateBucketError(#[from] SdkError<CreateBucketError, HttpResponse>),
     #[error("Failed object from bucket: {0}")]
     GetObjectError(#[from] SdkError<GetObjectError, HttpResponse>),
+    #[error("Failed to reach bucket: {0}")]
+    HeadBucketError(#[from] SdkError<HeadBucketError, HttpResponse>),
+    #[error("Failed to check object in bucket: {0}")]
+    HeadObjectError(#[from] SdkError<HeadObjectError, HttpResponse>),
     #[error("Failed object from bucket: {0}")]
     PutObjectError(#[from] SdkError<PutObjectError, HttpResponse>),
     #[error("ByteStreamError: {0}")]
     ByteStreamError(#[from] ByteStreamError),
 }
 
-fn client() -> &'static Box<Client> {
+fn client() -> &'static Client {
     CLIENT.get().expect("Client should have been intialized")
 }
 
-pub async fn init_bucket() -> Result<(), ObjectStorageError> {
+pub(crate) fn is_initialized() -> bool {
+    CLIENT.get().is_some()
+}
+
+pub async fn init_bucket(config: &StorageConfig) -> Result<(), ObjectStorageError> {
     use aws_sdk_s3::config::Credentials;
     use aws_sdk_s3::config::Region;
-    let key_id = dotenvy::var("S3_ACCESS_KEY_ID")
-        .expect("Environment variable S3_ACCESS_KEY_ID is required");
-    let secret_key = dotenvy::var("S3_SECRET_ACCESS_KEY")
-        .expect("Environment variable S#_SECRET_ACCESS_KEY is required");
-    let cred = Credentials::new(&key_id, &secret_key, None, None, "loaded-from-custom-env");
-    let endpoint = dotenvy::var("OBJECT_STORAGE_URI")
-        .expect("Environment variable OBJECT_STORAGE_URI required")
-        .to_owned();
+    let cred = Credentials::new(
+        &config.access_key_id,
+        config.secret_access_key.expose(),
+        None,
+        None,
+        "loaded-from-custom-env",
+    );
     let s3_config = aws_sdk_s3::config::Builder::new()
-        .endpoint_url(endpoint)
+        .endpoint_url(&config.endpoint)
         .credentials_provider(cred)
-        .region(Region::new(
-            dotenvy::var("S3_REGION").unwrap_or("eu-central-1".into()),
-        ))
+        .region(Region::new(config.region.clone()))
         .force_path_style(true) // apply bucketname as path param instead of pre-domain
         .behavior_version_latest()
         .build();
@@ -108,3 +110,23 @@ pub async fn put_image(id: ImageId, data: Vec<u8>) -> Result<(), ObjectStorageEr
         .await?;
     Ok(())
 }
+
+pub async fn exists(id: ImageId) -> Result<bool, ObjectStorageError> {
+    let rsp = client()
+        .head_object()
+        .bucket(BUCKET_NAME)
+        .set_key(Some(id_filename(id)))
+        .send()
+        .await;
+    match rsp {
+        Ok(_) => Ok(true),
+        Err(SdkError::ServiceError(e)) if e.err().is_not_found() => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Checks that the bucket can be reached.
+pub async fn ping() -> Result<(), ObjectStorageError> {
+    client().head_bucket().bucket(BUCKET_NAME).send().await?;
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageError, ImageReader};
+use serde::{Deserialize, Serialize};
 use sjf_db as db;
 use std::{collections::VecDeque, io::Write};
-use tracing::{info, warn};
+use tracing::info;
 
 use crate::ImageId;
 
@@ -13,9 +14,19 @@ enum ThumbnailSize {
     Huge,
 }
 
-impl Into<u32> for ThumbnailSize {
-    fn into(self) -> u32 {
-        match self {
+impl ThumbnailSize {
+    /// The variants stored of every image, smallest first.
+    const ALL: [ThumbnailSize; 4] = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Large,
+        ThumbnailSize::Huge,
+    ];
+}
+
+impl From<ThumbnailSize> for u32 {
+    fn from(val: ThumbnailSize) -> Self {
+        match val {
             ThumbnailSize::Small => 256,
             ThumbnailSize::Medium => 512,
             ThumbnailSize::Large => 2048,
@@ -23,9 +34,9 @@ impl Into<u32> for ThumbnailSize {
         }
     }
 }
-impl Into<&'static str> for ThumbnailSize {
-    fn into(self) -> &'static str {
-        match self {
+impl From<ThumbnailSize> for &'static str {
+    fn from(val: ThumbnailSize) -> Self {
+        match val {
             ThumbnailSize::Small => "small",
             ThumbnailSize::Medium => "medium",
             ThumbnailSize::Large => "large",
@@ -53,17 +64,64 @@ fn load_image(bytes: Vec<u8>) -> Result<LoadedImage, ImageError> {
         icc_profile,
     })
 }
+struct EncodedImageData {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+fn encode(
+    image: &LoadedImage,
+    sizes: &[ThumbnailSize],
+) -> Result<Vec<EncodedImageData>, crate::Error> {
+    use image::codecs::jpeg::JpegEncoder;
+
+    let init: Vec<EncodedImageData> = Vec::with_capacity(sizes.len());
+    sizes
+        .iter()
+        .map(|size| generate_thumbnail(&image.image, size.clone()))
+        .try_fold(init, |mut acc: Vec<EncodedImageData>, thumbnail| {
+            let mut w = std::io::Cursor::new(Vec::new());
+            let mut encoder = JpegEncoder::new_with_quality(&mut w, 80);
+            if let Some(ref profile) = image.icc_profile {
+                let _ = encoder.set_icc_profile(profile.clone());
+            }
+
+            thumbnail.write_with_encoder(encoder)?;
+            w.flush()?;
+
+            acc.push(EncodedImageData {
+                data: w.into_inner(),
+                width: thumbnail.width(),
+                height: thumbnail.height(),
+            });
+            Ok(acc)
+        })
+}
+
 fn generate_thumbnail(image: &DynamicImage, size: ThumbnailSize) -> DynamicImage {
     let size: u32 = size.into();
     image.resize(size, size, image::imageops::FilterType::Lanczos3)
 }
 
-pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
-    let image = load_image(bytes)?;
-    use image::codecs::jpeg::JpegEncoder;
+/// An image encoded in every size, that isn't stored yet.
+pub struct PreparedImage {
+    avg_color: String,
+    variants: Vec<EncodedImageData>,
+}
 
-    use ThumbnailSize::*;
-    let desired_sizes = [Small, Medium, Large, Huge];
+/// Decodes an uploaded image and encodes its variants, without storing
+/// anything. Images larger than [`crate::MAX_IMAGE_SIZE`] are refused.
+pub fn prepare_image(bytes: Vec<u8>) -> Result<PreparedImage, crate::Error> {
+    if bytes.len() > crate::MAX_IMAGE_SIZE {
+        return Err(crate::Error::internal(format!(
+            "Image of {} bytes is larger than {} bytes",
+            bytes.len(),
+            crate::MAX_IMAGE_SIZE
+        )));
+    }
+    let image = load_image(bytes)?;
+    let desired_sizes = ThumbnailSize::ALL;
 
     let hex_color = {
         let rgb_img = image.image.thumbnail(300, 300).into_rgb8();
@@ -85,39 +143,22 @@ pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
         g /= u128::from(height);
         b /= u128::from(height);
 
-        String::from(&format!("{:#08x}", r << 16 | g << 8 | b << 0)[2..])
+        String::from(&format!("{:#08x}", r << 16 | g << 8 | b)[2..])
     };
 
-    struct EncodedImageData {
-        data: Vec<u8>,
-        width: u32,
-        height: u32,
-    }
-
-    let mut init: Vec<EncodedImageData> = Vec::new();
-    init.reserve(desired_sizes.len());
-    let images: Result<_, crate::Error> = desired_sizes
-        .into_iter()
-        .map(|size| generate_thumbnail(&image.image, size))
-        .try_fold(init, |mut acc: Vec<EncodedImageData>, thumbnail| {
-            let mut w = std::io::Cursor::new(Vec::new());
-            let mut encoder = JpegEncoder::new_with_quality(&mut w, 80);
-            if let Some(ref profile) = image.icc_profile {
-                let _ = encoder.set_icc_profile(profile.clone());
-            }
-
-            thumbnail.write_with_encoder(encoder)?;
-            w.flush()?;
-
-            acc.push(EncodedImageData {
-                data: w.into_inner(),
-                width: thumbnail.width(),
-                height: thumbnail.height(),
-            });
-            Ok(acc)
-        });
+    Ok(PreparedImage {
+        avg_color: hex_color,
+        variants: encode(&image, &desired_sizes)?,
+    })
+}
 
-    let mut images = images?;
+/// Stores a prepared image and uploads its variants to object storage in the
+/// background.
+pub async fn store_image(image: PreparedImage) -> Result<ImageId, crate::Error> {
+    let PreparedImage {
+        avg_color,
+        variants: images,
+    } = image;
 
     let req = {
         let mut vs: VecDeque<_> = images
@@ -128,7 +169,7 @@ pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
             })
             .collect();
         db::image::ImageInsertRequest {
-            avg_color: hex_color,
+            avg_color,
             variants: [
                 vs.pop_front().unwrap(),
                 vs.pop_front().unwrap(),
@@ -138,7 +179,13 @@ pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
         }
     };
 
-    let mut image_ids = db::image::insert_image(req).await?;
+    let cached = images.last().unwrap().data.clone();
+    let data = images.into_iter().map(|img| img.data).collect();
+    let image_ids = db::image::insert_image_with_uploads(req, data, |id| UploadImage {
+        image_id: id.image_id as u32,
+        variant_id: id.variant_id as u32,
+    })
+    .await?;
 
     let id = {
         let id = image_ids.last().unwrap();
@@ -147,29 +194,68 @@ pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
             variant_id: id.variant_id as u32,
         }
     };
-    let data = images.last().unwrap().data.clone();
-
-    let result = Ok(id.clone());
 
     info!("Uploaded image");
-    tokio::spawn(async move {
-        crate::cache::add_image(id, data).await;
+    tokio::spawn({
+        let id = id.clone();
+        async move {
+            crate::cache::add_image(id, cached).await;
+        }
     });
 
-    tokio::spawn(async move {
-        let iter = image_ids.into_iter().zip(images.into_iter());
-        for (id, image) in iter {
-            match crate::object_storage::put_image(
-                (id.image_id as u32, id.variant_id as u32).into(),
-                image.data,
-            )
+    Ok(id)
+}
+
+/// Prepares and stores an image, see [`prepare_image`].
+pub async fn upload_image(bytes: Vec<u8>) -> Result<ImageId, crate::Error> {
+    store_image(prepare_image(bytes)?).await
+}
+
+/// Writes an encoded image variant to object storage.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UploadImage {
+    image_id: u32,
+    variant_id: u32,
+}
+
+impl db::jobs::Job for UploadImage {
+    const KIND: &'static str = "upload_image";
+    const MAX_ATTEMPTS: i32 = 10;
+
+    async fn run(self, data: Option<Vec<u8>>) -> db::jobs::JobResult {
+        let data = data.ok_or("No image data")?;
+        crate::object_storage::put_image((self.image_id, self.variant_id).into(), data)
             .await
-            {
-                Ok(()) => (),
-                Err(e) => warn!("Image put error {:#?}", e),
-            }
-        }
-    });
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Encodes the smaller variants of a stored image again from its largest
+/// variant, as the uploaded original isn't kept. The largest variant is left
+/// as it is.
+pub async fn regenerate(image_id: u32) -> Result<(), crate::Error> {
+    let variants = db::image::get_image_variants(image_id).await?;
+    let Some((largest, smaller)) = variants.split_last() else {
+        return Err(crate::Error::internal(format!(
+            "Image {image_id} has no variants"
+        )));
+    };
+    let sizes = &ThumbnailSize::ALL[..ThumbnailSize::ALL.len() - 1];
+    if smaller.len() != sizes.len() {
+        return Err(crate::Error::internal(format!(
+            "Image {image_id} has {} variants, expected {}",
+            variants.len(),
+            ThumbnailSize::ALL.len()
+        )));
+    }
 
-    return result;
+    let bytes = crate::object_storage::get_image((image_id, *largest).into()).await?;
+    let image = load_image(bytes)?;
+    for (variant, encoded) in smaller.iter().zip(encode(&image, sizes)?) {
+        crate::object_storage::put_image((image_id, *variant).into(), encoded.data).await?;
+        db::image::set_variant_size(*variant, encoded.width, encoded.height).await?;
+    }
+    info!("Regenerated image {}", image_id);
+    Ok(())
 }
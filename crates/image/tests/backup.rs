@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::Path;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::{get, put};
+use serde_json::{Value, json};
+use sjf_api::attribute::{AttributeDefinition, AttributeKind};
+use sjf_api::i18n::Locale;
+use sjf_api::product::ProductTranslation;
+use sjf_db::backup::{Catalogue, VariantKey};
+use sjf_db::image::{ImageInsertRequest, ImageInsertVariant};
+use sjf_image::backup;
+use sjf_test_support as common;
+use sqlx::{Connection, Executor};
+use tokio::sync::OnceCell;
+
+const DATABASE: &str = "sjf_test_image_backup";
+
+/// The objects of the fake bucket by key.
+static OBJECTS: LazyLock<Mutex<BTreeMap<String, Vec<u8>>>> = LazyLock::new(Default::default);
+static STORAGE: OnceCell<()> = OnceCell::const_new();
+
+/// Removes the framing of a body sent with `Content-Encoding: aws-chunked`.
+fn decode_chunked(mut body: &[u8]) -> Vec<u8> {
+    let mut data = vec![];
+    while let Some(end) = body.windows(2).position(|w| w == b"\r\n") {
+        let size = String::from_utf8_lossy(&body[..end]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap(), 16).unwrap();
+        body = &body[end + 2..];
+        if size == 0 {
+            break;
+        }
+        data.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+    data
+}
+
+async fn put_object(
+    Path((_, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let chunked = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("aws-chunked"));
+    let data = match chunked {
+        true => decode_chunked(&body),
+        false => body.to_vec(),
+    };
+    OBJECTS.lock().unwrap().insert(key, data);
+    StatusCode::OK
+}
+
+async fn get_object(Path((_, key)): Path<(String, String)>) -> impl IntoResponse {
+    match OBJECTS.lock().unwrap().get(&key) {
+        Some(data) => (StatusCode::OK, data.clone()).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, "application/xml")],
+            "<Error><Code>NoSuchKey</Code><Message>Not found</Message></Error>",
+        )
+            .into_response(),
+    }
+}
+
+async fn head_object(Path((_, key)): Path<(String, String)>) -> StatusCode {
+    match OBJECTS.lock().unwrap().contains_key(&key) {
+        true => StatusCode::OK,
+        false => StatusCode::NOT_FOUND,
+    }
+}
+
+/// Serves a bucket from memory and connects the images to it.
+async fn storage() {
+    STORAGE
+        .get_or_init(|| async {
+            let buckets = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListAllMyBucketsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Buckets></Buckets></ListAllMyBucketsResult>"#;
+            let app = Router::new()
+                .route("/", get(move || async move { buckets }))
+                .route("/:bucket", put(|| async { StatusCode::OK }))
+                .route("/:bucket/", put(|| async { StatusCode::OK }))
+                .route(
+                    "/:bucket/*key",
+                    put(put_object).get(get_object).head(head_object),
+                );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let endpoint = format!("http://{}", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            sjf_image::init(&sjf_config::StorageConfig {
+                endpoint,
+                access_key_id: "test".into(),
+                secret_access_key: String::from("test").into(),
+                region: "eu-central-1".into(),
+            })
+            .await
+            .unwrap();
+        })
+        .await;
+}
+
+fn object((image, variant): VariantKey) -> Option<Vec<u8>> {
+    OBJECTS
+        .lock()
+        .unwrap()
+        .get(&format!("{image}-{variant}.jpeg"))
+        .cloned()
+}
+
+/// Removes the catalogue, without restarting the ids so that a restore gets
+/// new ones.
+async fn empty_catalogue() {
+    let mut connection = common::connection().await;
+    connection
+        .execute("TRUNCATE products, product_categories, images CASCADE")
+        .await
+        .unwrap();
+    connection.close().await.unwrap();
+}
+
+async fn image(color: &str) -> u32 {
+    let variant = |size| ImageInsertVariant {
+        width: size,
+        height: size / 2,
+    };
+    let inserted = sjf_db::image::insert_image(ImageInsertRequest {
+        avg_color: color.into(),
+        variants: [variant(256), variant(512), variant(2048), variant(3000)],
+    })
+    .await
+    .unwrap();
+    for (i, v) in inserted.iter().enumerate() {
+        let data = format!("{color} {i} ").repeat(300).into_bytes();
+        OBJECTS
+            .lock()
+            .unwrap()
+            .insert(format!("{}-{}.jpeg", v.image_id, v.variant_id), data);
+    }
+    inserted[0].image_id as u32
+}
+
+/// Two categories with translations and attributes, two products with
+/// images, attribute values and translations, related to each other.
+async fn seed() {
+    let furniture = sjf_db::category::create(sjf_api::category::CreateReq {
+        name: "Möbler".into(),
+        parent: None,
+    })
+    .await
+    .unwrap()
+    .id;
+    let chairs = sjf_db::category::create(sjf_api::category::CreateReq {
+        name: "Stolar".into(),
+        parent: Some(furniture),
+    })
+    .await
+    .unwrap()
+    .id;
+    sjf_db::translation::set_category_name(furniture, Locale::En, "Furniture")
+        .await
+        .unwrap();
+    sjf_db::translation::set_category_name(chairs, Locale::En, "Chairs")
+        .await
+        .unwrap();
+    let height = sjf_db::attribute::create(AttributeDefinition {
+        id: 0,
+        category: furniture,
+        name: "Höjd".into(),
+        kind: AttributeKind::Number { unit: "cm".into() },
+    })
+    .await
+    .unwrap();
+    let color = sjf_db::attribute::create(AttributeDefinition {
+        id: 0,
+        category: chairs,
+        name: "Färg".into(),
+        kind: AttributeKind::Enum {
+            options: vec!["Röd".into(), "Blå".into()],
+        },
+    })
+    .await
+    .unwrap();
+
+    let oak = image("aa8844").await;
+    let pine = image("ddcc99").await;
+    let product = |name: &str, category, images| sjf_db::Product {
+        id: 0,
+        name: name.into(),
+        price: 1200,
+        description: format!("Beskrivning av {name}"),
+        quantity: Some(4),
+        created: chrono::Utc::now(),
+        updated: chrono::Utc::now(),
+        tax_rate: 25,
+        images,
+        category,
+        reorder_threshold: Some(2),
+        stock_policy: Default::default(),
+        attributes: Default::default(),
+        translations: Default::default(),
+        sku: None,
+    };
+    let chair = sjf_db::create_product(sjf_db::Product {
+        attributes: BTreeMap::from([(height, "45".into()), (color, "Röd".into())]),
+        translations: BTreeMap::from([(
+            Locale::En,
+            ProductTranslation {
+                name: "Chair Åsa".into(),
+                description: "An oak chair".into(),
+            },
+        )]),
+        sku: Some("stol-1".into()),
+        ..product("Stol Åsa", chairs, vec![oak, pine])
+    })
+    .await
+    .unwrap() as u32;
+    let table = sjf_db::create_product(product("Bord", furniture, vec![pine]))
+        .await
+        .unwrap() as u32;
+    sjf_db::related::set_curated(chair, vec![table])
+        .await
+        .unwrap();
+}
+
+/// Ids by their order, which a restore keeps.
+fn ranks(ids: impl Iterator<Item = i32>) -> HashMap<i32, usize> {
+    let mut ids: Vec<_> = ids.collect();
+    ids.sort();
+    ids.into_iter().enumerate().map(|(i, id)| (id, i)).collect()
+}
+
+/// The catalogue with its ids replaced by their order.
+fn normalize(c: &Catalogue) -> Value {
+    let categories = ranks(c.categories.iter().map(|c| c.id));
+    let attributes = ranks(c.attributes.iter().map(|a| a.id));
+    let images = ranks(c.images.iter().map(|i| i.id));
+    let products = ranks(c.products.iter().map(|p| p.id));
+
+    let mut hierarchy: Vec<_> = c
+        .hierarchy
+        .iter()
+        .map(|h| (categories[&h.ancestor], categories[&h.descendant], h.depth))
+        .collect();
+    hierarchy.sort();
+    let mut related: Vec<_> = c
+        .related
+        .iter()
+        .map(|r| (products[&r.product], products[&r.related], r.position))
+        .collect();
+    related.sort();
+
+    json!({
+        "categories": c.categories.iter().map(|c| json!({
+            "id": categories[&c.id],
+            "name": c.name,
+            "google_category": c.google_category,
+            "translations": c.translations,
+        })).collect::<Vec<_>>(),
+        "hierarchy": hierarchy,
+        "attributes": c.attributes.iter().map(|a| json!({
+            "id": attributes[&a.id],
+            "category": categories[&a.category],
+            "name": a.name,
+            "kind": a.kind,
+            "unit": a.unit,
+            "options": a.options,
+        })).collect::<Vec<_>>(),
+        "images": c.images.iter().map(|i| json!({
+            "id": images[&i.id],
+            "avg_color": i.avg_color,
+            "variants": i.variants.iter().map(|v| (v.width, v.height)).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "products": c.products.iter().map(|p| {
+            let mut product_images: Vec<_> = p.images.iter().map(|i| images[i]).collect();
+            product_images.sort();
+            let values: BTreeMap<_, _> = p.attributes.iter().map(|(a, v)| (attributes[a], v)).collect();
+            json!({
+                "id": products[&p.id],
+                "sku": p.sku,
+                "name": p.name,
+                "price": p.price,
+                "description": p.description,
+                "quantity": p.quantity,
+                "created": p.created,
+                "updated": p.updated,
+                "tax_rate": p.tax_rate,
+                "category": categories[&p.category],
+                "reorder_threshold": p.reorder_threshold,
+                "stock_policy": p.stock_policy,
+                "backorder_limit": p.backorder_limit,
+                "preorder_ship_date": p.preorder_ship_date,
+                "images": product_images,
+                "attributes": values,
+                "translations": p.translations,
+            })
+        }).collect::<Vec<_>>(),
+        "related": related,
+    })
+}
+
+#[test]
+fn restore_recreates_the_backed_up_catalogue() {
+    common::run(DATABASE, async {
+        storage().await;
+        empty_catalogue().await;
+        seed().await;
+
+        let mut archive = vec![];
+        let created = backup::create(&mut archive).await.unwrap();
+        let before = sjf_db::backup::export().await.unwrap();
+        assert_eq!(
+            created.to_string(),
+            "2 categories, 2 products, 2 images with 8 variants"
+        );
+
+        empty_catalogue().await;
+        let restored = backup::restore(archive.as_slice()).await.unwrap();
+        assert_eq!(restored.to_string(), created.to_string());
+
+        let after = sjf_db::backup::export().await.unwrap();
+        assert_eq!(normalize(&after), normalize(&before));
+        assert!(
+            before
+                .products
+                .iter()
+                .all(|p| after.products.iter().all(|q| p.id != q.id))
+        );
+
+        // Each image file is stored under the ids its variant got
+        let (old, new): (Vec<_>, Vec<_>) =
+            (before.variants().collect(), after.variants().collect());
+        assert_eq!(old.len(), new.len());
+        for (old, new) in old.into_iter().zip(new) {
+            assert_ne!(old, new);
+            assert!(object(new).is_some());
+            assert_eq!(object(new), object(old));
+        }
+    });
+}
+
+#[test]
+fn truncated_archives_are_not_restored() {
+    common::run(DATABASE, async {
+        storage().await;
+        empty_catalogue().await;
+        seed().await;
+
+        let mut archive = vec![];
+        backup::create(&mut archive).await.unwrap();
+        empty_catalogue().await;
+
+        // Within the catalogue, and within the last image file before the two
+        // empty blocks that end the archive
+        for length in [700, archive.len() - 1024 - 1000] {
+            assert!(backup::restore(&archive[..length]).await.is_err());
+            let catalogue = sjf_db::backup::export().await.unwrap();
+            assert_eq!(
+                catalogue.summary().to_string(),
+                "0 categories, 0 products, 0 images with 0 variants"
+            );
+        }
+    });
+}
@@ -0,0 +1,226 @@
+use std::time::{Duration, Instant};
+
+use lettre::Address;
+use serde::{Deserialize, Serialize};
+use sjf_api::i18n::Locale;
+use sjf_api::stock::{SubscribeStatus, confirm_subscription_path, unsubscribe_path};
+use sjf_db::jobs::{self, Job, JobResult};
+use sjf_db::stock;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::NotifyError;
+use crate::mail::send_email_later;
+
+/// Confirmation emails sent to one address per day.
+const MAX_REQUESTS_PER_EMAIL: i64 = 5;
+/// Confirmation emails sent in total per minute, protects the mail reputation
+/// of the shop against scripted sign ups.
+const MAX_REQUESTS_PER_MINUTE: u32 = 30;
+
+static RATE_WINDOW: Mutex<Option<(Instant, u32)>> = Mutex::const_new(None);
+static SENDING: Mutex<()> = Mutex::const_new(());
+
+async fn within_global_rate() -> bool {
+    let mut window = RATE_WINDOW.lock().await;
+    let now = Instant::now();
+    match &mut *window {
+        Some((start, count)) if now.duration_since(*start) < Duration::from_secs(60) => {
+            *count += 1;
+            *count <= MAX_REQUESTS_PER_MINUTE
+        }
+        _ => {
+            *window = Some((now, 1));
+            true
+        }
+    }
+}
+
+fn links(site: &str, token: &str, locale: Locale) -> (String, String) {
+    (
+        format!("{site}{}/{token}", confirm_subscription_path(locale)),
+        format!("{site}{}/{token}", unsubscribe_path(locale)),
+    )
+}
+
+/// Subject and body of the email asking to confirm a subscription.
+fn confirmation_email(
+    locale: Locale,
+    product: &str,
+    confirm: &str,
+    unsubscribe: &str,
+) -> (&'static str, String) {
+    match locale {
+        Locale::Sv => (
+            "Bekräfta din lagerbevakning",
+            format!(
+                "Hej!\n\n\
+                Vi har fått en förfrågan om att meddela dig när {product} finns i lager igen.\n\
+                Bekräfta bevakningen genom att öppna länken nedan:\n\n{confirm}\n\n\
+                Om du inte har begärt detta kan du ignorera mailet eller avsluta bevakningen här:\n{unsubscribe}\n"
+            ),
+        ),
+        Locale::En => (
+            "Confirm your stock alert",
+            format!(
+                "Hi!\n\n\
+                We have been asked to let you know when {product} is back in stock.\n\
+                Confirm the alert by opening the link below:\n\n{confirm}\n\n\
+                If you didn't ask for this, ignore this email or cancel the alert here:\n{unsubscribe}\n"
+            ),
+        ),
+    }
+}
+
+/// Subject and body of the email telling a subscriber the product is back.
+fn back_in_stock_email(locale: Locale, product: &str, url: &str) -> (String, String) {
+    match locale {
+        Locale::Sv => (
+            format!("{product} finns i lager igen"),
+            format!(
+                "Hej!\n\n{product} finns i lager igen:\n{url}\n\n\
+                Det här är ett engångsmeddelande och din bevakning är nu avslutad.\n"
+            ),
+        ),
+        Locale::En => (
+            format!("{product} is back in stock"),
+            format!(
+                "Hi!\n\n{product} is back in stock:\n{url}\n\n\
+                This is a one-off message and your alert has now ended.\n"
+            ),
+        ),
+    }
+}
+
+/// Registers an unconfirmed back in stock subscription and queues an email
+/// with a confirmation link to the customer. Nothing is stored unless the
+/// product exists and the email can be sent. The emails and the pages they
+/// link to are in `locale`.
+pub async fn subscribe(
+    product_id: u32,
+    email: &str,
+    locale: Locale,
+) -> Result<SubscribeStatus, NotifyError> {
+    let Ok(address) = email.trim().parse::<Address>() else {
+        return Ok(SubscribeStatus::InvalidEmail);
+    };
+    let email = address.to_string().to_lowercase();
+    if !crate::email_enabled() {
+        return Err(NotifyError::MailNotConfigured);
+    }
+
+    if stock::recent_subscription_requests(&email).await? >= MAX_REQUESTS_PER_EMAIL
+        || !within_global_rate().await
+    {
+        warn!("Rate limited back in stock subscription");
+        return Ok(SubscribeStatus::RateLimited);
+    }
+
+    let product =
+        sjf_db::product::get_product(sjf_api::product::GetProductRequest { product_id }).await?;
+    let site = crate::site_url()?;
+
+    let Some(token) = stock::subscribe(product_id, &email, locale).await? else {
+        return Ok(SubscribeStatus::ConfirmationSent);
+    };
+
+    let (confirm, unsubscribe) = links(site, &token, locale);
+    let (subject, body) = confirmation_email(locale, &product.name, &confirm, &unsubscribe);
+    send_email_later(&email, subject, &body).await?;
+    Ok(SubscribeStatus::ConfirmationSent)
+}
+
+/// Queues an email to every confirmed subscriber whose product is back in
+/// stock and removes their subscriptions.
+pub async fn send_back_in_stock_notifications() -> Result<(), NotifyError> {
+    if !crate::email_enabled() {
+        return Ok(());
+    }
+    let _guard = SENDING.lock().await;
+
+    let due = stock::due_subscriptions().await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    info!("Sending {} back in stock notifications", due.len());
+
+    for s in due {
+        let url = crate::site_url()?.to_owned()
+            + &sjf_api::product::product_url_in(s.locale, &s.category_name, s.product_id as u32);
+        let (subject, body) = back_in_stock_email(s.locale, &s.name, &url);
+        send_email_later(&s.email, &subject, &body).await?;
+        stock::expire_subscriptions(&[s.id]).await?;
+    }
+
+    Ok(())
+}
+
+/// Sends the due back in stock notifications, once for every change to the
+/// stock made while it waits or runs, and periodically for subscriptions
+/// confirmed since.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BackInStockNotifications;
+
+impl Job for BackInStockNotifications {
+    const KIND: &'static str = "back_in_stock_notifications";
+    const ONCE: bool = true;
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        send_back_in_stock_notifications().await?;
+        Ok(())
+    }
+}
+
+/// Removes the subscriptions that were never confirmed.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UnconfirmedSubscriptions;
+
+impl Job for UnconfirmedSubscriptions {
+    const KIND: &'static str = "unconfirmed_subscriptions";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        stock::remove_unconfirmed_subscriptions().await?;
+        Ok(())
+    }
+}
+
+pub async fn send_back_in_stock_notifications_later() {
+    if let Err(e) = jobs::enqueue(&BackInStockNotifications).await {
+        error!("Failed to queue back in stock notifications {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_point_at_the_site() {
+        let (confirm, unsubscribe) = links("https://butik.example", "abc", Locale::Sv);
+        assert_eq!(confirm, "https://butik.example/bevakning/bekrafta/abc");
+        assert_eq!(unsubscribe, "https://butik.example/bevakning/avsluta/abc");
+    }
+
+    #[test]
+    fn emails_follow_the_language_subscribed_in() {
+        let (confirm, unsubscribe) = links("https://butik.example", "abc", Locale::En);
+        assert_eq!(confirm, "https://butik.example/en/alerts/confirm/abc");
+        assert_eq!(unsubscribe, "https://butik.example/en/alerts/cancel/abc");
+
+        let (subject, body) = confirmation_email(Locale::En, "Mug", &confirm, &unsubscribe);
+        assert_eq!(subject, "Confirm your stock alert");
+        assert!(body.contains("when Mug is back in stock"));
+        assert!(body.contains(&confirm) && body.contains(&unsubscribe));
+
+        let (subject, _) = back_in_stock_email(Locale::Sv, "Mugg", "https://butik.example/");
+        assert_eq!(subject, "Mugg finns i lager igen");
+    }
+
+    #[test]
+    fn links_are_not_sent_without_the_site() {
+        assert!(matches!(
+            crate::site_url(),
+            Err(NotifyError::SiteNotConfigured)
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+mod back_in_stock;
+mod mail;
+mod order;
+mod recovery;
+mod reviews;
+mod stock;
+mod webhook;
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use sjf_config::{NotifyConfig, Secret, SiteConfig};
+use sjf_db::jobs;
+use tracing::{error, warn};
+
+pub use back_in_stock::{
+    send_back_in_stock_notifications, send_back_in_stock_notifications_later, subscribe,
+};
+pub use mail::{is_enabled as email_enabled, send_email};
+pub use order::{notify_split_order, notify_split_order_later};
+pub use recovery::send_checkout_reminders;
+pub use reviews::{notify_new_review, send_review_requests};
+
+#[derive(thiserror::Error, Debug)]
+pub enum NotifyError {
+    #[error("Sql failed {0}")]
+    Sql(#[from] sjf_db::Error),
+    #[error("Invalid email address {0}")]
+    Address(#[from] lettre::address::AddressError),
+    #[error("Failed to build email {0}")]
+    Email(#[from] lettre::error::Error),
+    #[error("SMTP failed {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("Webhook failed {0}")]
+    Webhook(#[from] reqwest::Error),
+    #[error("Email is not configured")]
+    MailNotConfigured,
+    #[error("WEBSITE_URL is not configured, links can't be sent")]
+    SiteNotConfigured,
+}
+
+struct Config {
+    site_url: String,
+    staff_email: Option<String>,
+    staff_webhook: Option<Secret>,
+    reminder_delay: Option<i32>,
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Keeps the notification settings and schedules the periodic stock checks.
+///
+/// Staff notifications are emailed to `staff_email` and/or posted to
+/// `staff_webhook_url`, whichever are set. Customers that abandon a checkout
+/// are reminded after `checkout_reminder_hours`, and customers of delivered
+/// orders are asked to review their products.
+pub fn init(config: &NotifyConfig, site: &SiteConfig) {
+    mail::init(config);
+
+    let config = Config {
+        site_url: site.url.clone(),
+        staff_email: config.staff_email.clone(),
+        staff_webhook: config.staff_webhook_url.clone(),
+        reminder_delay: recovery::reminder_delay(config.checkout_reminder_hours),
+    };
+
+    if config.staff_email.is_none() && config.staff_webhook.is_none() {
+        warn!("Neither STAFF_EMAIL nor STAFF_WEBHOOK_URL is set, staff notifications are disabled");
+    }
+    let _ = CONFIG.set(config);
+
+    // Each check is its own job so that a failing one is retried without
+    // holding back the others
+    let every = Duration::from_secs(5 * 60);
+    jobs::register::<mail::Email>();
+    jobs::register::<order::SplitOrderNotification>();
+    jobs::register_periodic(stock::StockAlerts, every);
+    jobs::register_periodic(back_in_stock::UnconfirmedSubscriptions, every);
+    jobs::register_periodic(back_in_stock::BackInStockNotifications, every);
+    jobs::register_periodic(recovery::CheckoutReminders, every);
+    jobs::register_periodic(reviews::ReviewRequests, every);
+}
+
+/// Sends a message to every configured staff channel. Succeeds as long as at
+/// least one channel delivered the message.
+pub async fn notify_staff(subject: &str, body: &str) -> Result<(), NotifyError> {
+    let Some(config) = CONFIG.get() else {
+        return Ok(());
+    };
+
+    let mut delivered = false;
+    let mut last_error = None;
+    if let Some(to) = &config.staff_email {
+        match send_email(to, subject, body).await {
+            Ok(()) => delivered = true,
+            Err(e) => {
+                error!("Failed to email staff {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+    if let Some(url) = &config.staff_webhook {
+        match webhook::post(url.expose(), subject, body).await {
+            Ok(()) => delivered = true,
+            Err(e) => {
+                error!("Failed to post staff webhook {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if !delivered => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// The address of the shop that links in emails point at.
+fn site_url() -> Result<&'static str, NotifyError> {
+    CONFIG
+        .get()
+        .map(|c| c.site_url.as_str())
+        .filter(|url| !url.is_empty())
+        .ok_or(NotifyError::SiteNotConfigured)
+}
+
+fn staff_notifications_enabled() -> bool {
+    CONFIG
+        .get()
+        .is_some_and(|c| c.staff_email.is_some() || c.staff_webhook.is_some())
+}
@@ -0,0 +1,93 @@
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::header::ContentType,
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sjf_config::NotifyConfig;
+use sjf_db::jobs::{self, Job, JobResult};
+use tracing::warn;
+
+use crate::NotifyError;
+
+struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: lettre::message::Mailbox,
+}
+
+static MAILER: OnceCell<Mailer> = OnceCell::new();
+
+pub(crate) fn init(config: &NotifyConfig) {
+    let (Some(url), Some(from)) = (&config.smtp_url, &config.mail_from) else {
+        warn!("SMTP_URL or MAIL_FROM is not set, email is disabled");
+        return;
+    };
+
+    let transport = match AsyncSmtpTransport::<Tokio1Executor>::from_url(url.expose()) {
+        Ok(t) => t.build(),
+        Err(e) => {
+            warn!("Invalid SMTP_URL, email is disabled {}", e);
+            return;
+        }
+    };
+    let from = match from.parse() {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Invalid MAIL_FROM, email is disabled {}", e);
+            return;
+        }
+    };
+
+    let _ = MAILER.set(Mailer { transport, from });
+}
+
+pub fn is_enabled() -> bool {
+    MAILER.get().is_some()
+}
+
+/// Sends a plain text email from `MAIL_FROM`.
+pub async fn send_email(to: &str, subject: &str, body: &str) -> Result<(), NotifyError> {
+    let mailer = MAILER.get().ok_or(NotifyError::MailNotConfigured)?;
+
+    let message = Message::builder()
+        .from(mailer.from.clone())
+        .to(to.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_owned())?;
+
+    mailer.transport.send(message).await?;
+    Ok(())
+}
+
+/// A customer email sent by the job queue, so that it's retried on its own
+/// when the SMTP server fails.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Email {
+    to: String,
+    subject: String,
+    body: String,
+}
+
+impl Job for Email {
+    const KIND: &'static str = "email";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        send_email(&self.to, &self.subject, &self.body).await?;
+        Ok(())
+    }
+}
+
+/// Queues an email, see [`send_email`].
+pub(crate) async fn send_email_later(
+    to: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), NotifyError> {
+    let job = Email {
+        to: to.to_owned(),
+        subject: subject.to_owned(),
+        body: body.to_owned(),
+    };
+    jobs::enqueue(&job).await?;
+    Ok(())
+}
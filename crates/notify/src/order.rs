@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use sjf_api::stock::Fulfilment;
+use sjf_db::checkout::OrderItem;
+use sjf_db::jobs::{self, Job, JobResult};
+use tracing::error;
+
+use crate::NotifyError;
+
+fn describe(item: &OrderItem) -> String {
+    let ship_date = item
+        .ship_date
+        .map(|d| format!(", skickas {}", d))
+        .unwrap_or_default();
+    format!(
+        "  {}st {} (#{}){}",
+        item.ordered_quantity, item.name, item.product_id, ship_date
+    )
+}
+
+/// Tells staff about a completed order that contains lines that can't ship
+/// right away, with the lines split by how they will be fulfilled.
+pub async fn notify_split_order(reference: &str, items: &[OrderItem]) -> Result<(), NotifyError> {
+    let items: Vec<_> = items.iter().cloned().flat_map(OrderItem::split).collect();
+    if items.iter().all(|i| i.fulfilment == Fulfilment::InStock) {
+        return Ok(());
+    }
+
+    let section = |title: &str, fulfilment: Fulfilment| {
+        let lines: Vec<_> = items
+            .iter()
+            .filter(|i| i.fulfilment == fulfilment)
+            .map(describe)
+            .collect();
+        if lines.is_empty() {
+            String::new()
+        } else {
+            format!("{}:\n{}\n\n", title, lines.join("\n"))
+        }
+    };
+
+    let body = format!(
+        "Order {} innehåller varor som inte kan skickas direkt.\n\n{}{}{}",
+        reference,
+        section("Skickas direkt", Fulfilment::InStock),
+        section("Restnoterade", Fulfilment::Backorder),
+        section("Förbeställda", Fulfilment::Preorder),
+    );
+    crate::notify_staff(&format!("Delad order {}", reference), body.trim_end()).await
+}
+
+/// Tells staff about a split order, see [`notify_split_order`].
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SplitOrderNotification {
+    reference: String,
+    items: Vec<OrderItem>,
+}
+
+impl Job for SplitOrderNotification {
+    const KIND: &'static str = "split_order_notification";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        notify_split_order(&self.reference, &self.items).await?;
+        Ok(())
+    }
+}
+
+pub async fn notify_split_order_later(reference: &str, items: Vec<OrderItem>) {
+    let job = SplitOrderNotification {
+        reference: reference.to_owned(),
+        items,
+    };
+    if let Err(e) = jobs::enqueue(&job).await {
+        error!(
+            "Failed to queue notification about order {} {}",
+            reference, e
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sjf_api::checkout::{recover_checkout_path, reminder_opt_out_path};
+use sjf_api::i18n::Locale;
+use sjf_api::product::GetProductsRequest;
+use sjf_db::jobs::{Job, JobResult};
+use sjf_db::recovery;
+use tracing::info;
+
+use crate::NotifyError;
+use crate::mail::send_email_later;
+
+/// Reminders are not sent for checkouts abandoned longer than this before they became due.
+const MAX_REMINDER_AGE_DAYS: i32 = 7;
+/// Abandoned checkouts are kept this long for the dashboard statistics.
+const RETENTION_DAYS: i32 = 90;
+
+/// Minutes to wait after a checkout was abandoned before reminding the customer,
+/// `None` if reminders are turned off.
+pub(crate) fn reminder_delay(hours: u32) -> Option<i32> {
+    match hours {
+        0 => None,
+        hours => Some(hours.min(24 * MAX_REMINDER_AGE_DAYS as u32) as i32 * 60),
+    }
+}
+
+/// Subject and body of the reminder of an abandoned checkout of `lines`.
+fn reminder_email(
+    locale: Locale,
+    site: &str,
+    token: &str,
+    lines: &[String],
+) -> (&'static str, String) {
+    let restore = format!("{site}{}/{token}", recover_checkout_path(locale));
+    let opt_out = format!("{site}{}/{token}", reminder_opt_out_path(locale));
+    let lines = lines.join("\n");
+    match locale {
+        Locale::Sv => (
+            "Du glömde något i varukorgen",
+            format!(
+                "Hej!\n\n\
+                Det ser ut som att du inte slutförde ditt köp. Din varukorg innehöll:\n\n{lines}\n\n\
+                Du kan fortsätta där du slutade här:\n{restore}\n\n\
+                Vi skickar bara den här påminnelsen en gång. Vill du aldrig få påminnelser om \
+                ofullständiga köp kan du avregistrera dig här:\n{opt_out}\n"
+            ),
+        ),
+        Locale::En => (
+            "You left something in your cart",
+            format!(
+                "Hi!\n\n\
+                It looks like you didn't complete your purchase. Your cart contained:\n\n{lines}\n\n\
+                You can pick up where you left off here:\n{restore}\n\n\
+                We only send this reminder once. If you never want reminders about \
+                unfinished purchases, you can opt out here:\n{opt_out}\n"
+            ),
+        ),
+    }
+}
+
+/// Queues one reminder email for every abandoned checkout that has waited long enough.
+pub async fn send_checkout_reminders() -> Result<(), NotifyError> {
+    recovery::remove_old(RETENTION_DAYS).await?;
+
+    let Some(delay) = crate::CONFIG.get().and_then(|c| c.reminder_delay) else {
+        return Ok(());
+    };
+    if !crate::email_enabled() {
+        return Ok(());
+    }
+
+    let due = recovery::due_reminders(delay, MAX_REMINDER_AGE_DAYS).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    info!("Sending {} abandoned checkout reminders", due.len());
+
+    for reminder in due {
+        let products: BTreeMap<_, _> =
+            sjf_db::product::get_specified_products(GetProductsRequest {
+                product_ids: reminder.order.iter().map(|(id, _)| *id).collect(),
+            })
+            .await?
+            .into_iter()
+            .map(|p| (p.id, p.name))
+            .collect();
+
+        let lines = reminder
+            .order
+            .iter()
+            .filter_map(|(id, quantity)| {
+                let pieces = reminder.locale.messages().pieces;
+                Some(format!("  {} {} {}", quantity, pieces, products.get(id)?))
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            recovery::mark_reminded(reminder.id).await?;
+            continue;
+        }
+
+        let site = crate::site_url()?;
+        let token = reminder.id.to_string();
+        let (subject, body) = reminder_email(reminder.locale, site, &token, &lines);
+        send_email_later(&reminder.email, subject, &body).await?;
+        recovery::mark_reminded(reminder.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Reminds the customers of abandoned checkouts, see [`send_checkout_reminders`].
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CheckoutReminders;
+
+impl Job for CheckoutReminders {
+    const KIND: &'static str = "checkout_reminders";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        send_checkout_reminders().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminders_follow_the_language_of_the_checkout() {
+        let lines = ["  2 pcs Mug".to_owned()];
+        let (subject, body) = reminder_email(Locale::En, "https://butik.example", "abc", &lines);
+        assert_eq!(subject, "You left something in your cart");
+        assert!(body.contains("  2 pcs Mug\n"));
+        assert!(body.contains("https://butik.example/en/cart/restore/abc\n"));
+        assert!(body.contains("https://butik.example/en/cart/no-reminders/abc\n"));
+
+        let (_, body) = reminder_email(Locale::Sv, "https://butik.example", "abc", &lines);
+        assert!(body.contains("https://butik.example/varukorg/aterstall/abc\n"));
+        assert!(body.contains("https://butik.example/varukorg/inga-paminnelser/abc\n"));
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use sjf_api::i18n::Locale;
+use sjf_api::review::review_path;
+use sjf_db::jobs::{Job, JobResult};
+use sjf_db::orders;
+use tracing::info;
+
+/// Days after delivery before the customer is asked to review the products.
+const REVIEW_REQUEST_DELAY_DAYS: i32 = 7;
+
+/// Subject and body of the email asking for reviews of `lines`, given as
+/// product name and link.
+fn review_request_email(locale: Locale, lines: &[(String, String)]) -> (&'static str, String) {
+    let lines = lines
+        .iter()
+        .map(|(name, link)| format!("  {name}\n  {link}"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    match locale {
+        Locale::Sv => (
+            "Vad tyckte du om ditt köp?",
+            format!(
+                "Hej!\n\n\
+                Tack för ditt köp! Vi hoppas att du är nöjd och skulle bli glada om du \
+                ville berätta vad du tycker om det du köpte:\n\n{lines}\n\n\
+                Recensionerna publiceras på produktsidan efter granskning.\n"
+            ),
+        ),
+        Locale::En => (
+            "What did you think of your purchase?",
+            format!(
+                "Hi!\n\n\
+                Thank you for your purchase! We hope you are happy with it and would be glad \
+                to hear what you think of what you bought:\n\n{lines}\n\n\
+                Reviews are published on the product page once they have been checked.\n"
+            ),
+        ),
+    }
+}
+
+/// Asks customers of delivered orders to review what they bought, once per order.
+pub async fn send_review_requests() -> Result<(), crate::NotifyError> {
+    if !crate::email_enabled() {
+        return Ok(());
+    }
+
+    let due = orders::due_review_requests(REVIEW_REQUEST_DELAY_DAYS).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    info!("Sending {} review requests", due.len());
+
+    let site = crate::site_url()?;
+    for request in due {
+        let path = review_path(request.locale);
+        let lines = request
+            .lines
+            .iter()
+            .map(|(token, name)| (name.clone(), format!("{site}{path}/{token}")))
+            .collect::<Vec<_>>();
+        let (subject, body) = review_request_email(request.locale, &lines);
+        crate::mail::send_email_later(&request.email, subject, &body).await?;
+        orders::mark_review_requested(request.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Asks for reviews of delivered orders, see [`send_review_requests`].
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReviewRequests;
+
+impl Job for ReviewRequests {
+    const KIND: &'static str = "review_requests";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        send_review_requests().await?;
+        Ok(())
+    }
+}
+
+/// Lets staff know there is a new review waiting for moderation.
+pub async fn notify_new_review(product_name: &str, rating: u8) -> Result<(), crate::NotifyError> {
+    if !crate::staff_notifications_enabled() {
+        return Ok(());
+    }
+    let body = format!(
+        "En ny recension av {} ({} av 5) väntar på granskning.\n\n{}/admin/reviews\n",
+        product_name,
+        rating,
+        crate::site_url()?
+    );
+    crate::notify_staff("Ny recension att granska", &body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_requests_follow_the_language_of_the_order() {
+        let lines = [(
+            "Mug".to_owned(),
+            "https://butik.example/en/review/abc".to_owned(),
+        )];
+        let (subject, body) = review_request_email(Locale::En, &lines);
+        assert_eq!(subject, "What did you think of your purchase?");
+        assert!(body.contains("  Mug\n  https://butik.example/en/review/abc\n"));
+
+        let (subject, _) = review_request_email(Locale::Sv, &lines);
+        assert_eq!(subject, "Vad tyckte du om ditt köp?");
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use sjf_api::stock::{StockAlert, StockLevel};
+use sjf_db::jobs::{Job, JobResult};
+use sjf_db::stock;
+use tracing::info;
+
+use crate::NotifyError;
+
+fn describe(alert: &StockAlert) -> String {
+    match alert.level {
+        StockLevel::SoldOut => format!("{} (#{}) är slutsåld", alert.name, alert.product_id),
+        StockLevel::Low => format!(
+            "{} (#{}) har {} kvar i lager (beställningsgräns {})",
+            alert.name,
+            alert.product_id,
+            alert.quantity,
+            alert.reorder_threshold.unwrap_or_default()
+        ),
+    }
+}
+
+pub(crate) async fn check_stock_levels() -> Result<(), NotifyError> {
+    stock::rearm_stock_alerts().await?;
+
+    if !crate::staff_notifications_enabled() {
+        return Ok(());
+    }
+
+    let alerts = stock::pending_stock_alerts().await?;
+    if alerts.is_empty() {
+        return Ok(());
+    }
+
+    info!("Sending {} stock alerts", alerts.len());
+    let sold_out = alerts
+        .iter()
+        .filter(|a| a.level == StockLevel::SoldOut)
+        .count();
+    let subject = if sold_out > 0 {
+        format!("Lagervarning: {} slutsålda produkter", sold_out)
+    } else {
+        format!("Lagervarning: {} produkter med lågt lager", alerts.len())
+    };
+    let body = alerts.iter().map(describe).collect::<Vec<_>>().join("\n");
+
+    crate::notify_staff(&subject, &body).await?;
+    stock::mark_stock_alerts_sent(&alerts).await?;
+    Ok(())
+}
+
+/// Tells staff about products that sold out or are running low.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StockAlerts;
+
+impl Job for StockAlerts {
+    const KIND: &'static str = "stock_alerts";
+
+    async fn run(self, _: Option<Vec<u8>>) -> JobResult {
+        check_stock_levels().await?;
+        Ok(())
+    }
+}
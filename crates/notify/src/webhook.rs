@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use crate::NotifyError;
+
+#[derive(Serialize)]
+struct WebhookMessage<'a> {
+    subject: &'a str,
+    text: &'a str,
+}
+
+/// Posts a generic JSON message. The `text` field makes it render in Slack and
+/// Mattermost style incoming webhooks without further configuration.
+pub(crate) async fn post(url: &str, subject: &str, body: &str) -> Result<(), NotifyError> {
+    let text = format!("{subject}\n\n{body}");
+    reqwest::Client::new()
+        .post(url)
+        .json(&WebhookMessage {
+            subject,
+            text: &text,
+        })
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
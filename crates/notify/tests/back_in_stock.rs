@@ -0,0 +1,117 @@
+use sjf_api::i18n::Locale;
+use sjf_api::stock::{CONFIRM_SUBSCRIPTION_PATH, SubscribeStatus};
+use sjf_config::{NotifyConfig, Secret, SiteConfig};
+use sjf_test_support as common;
+use sqlx::{Connection, PgConnection};
+
+const SITE: &str = "https://butik.example";
+
+/// Email goes to a server that is never contacted, the emails are only
+/// queued.
+fn init() {
+    sjf_notify::init(
+        &NotifyConfig {
+            smtp_url: Some(Secret::from("smtp://localhost:2525".to_owned())),
+            mail_from: Some("butik@butik.example".into()),
+            ..Default::default()
+        },
+        &SiteConfig {
+            url: SITE.into(),
+            ..Default::default()
+        },
+    );
+}
+
+async fn subscriptions(conn: &mut PgConnection, email: &str) -> i64 {
+    sqlx::query_scalar("SELECT count(*) FROM stock_subscriptions WHERE email = $1")
+        .bind(email)
+        .fetch_one(conn)
+        .await
+        .unwrap()
+}
+
+/// Bodies of the queued emails to `email`.
+async fn queued(conn: &mut PgConnection, email: &str) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT payload->>'body' FROM jobs WHERE kind = 'email' AND payload->>'to' = $1 ORDER BY id",
+    )
+    .bind(email)
+    .fetch_all(conn)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn unknown_products_are_refused_before_subscribing() {
+    common::run("sjf_test_notify", async {
+        init();
+        let email = "okand@butik.example";
+        assert!(
+            sjf_notify::subscribe(u32::MAX >> 1, email, Locale::Sv)
+                .await
+                .is_err()
+        );
+
+        let mut conn = common::connection().await;
+        assert_eq!(subscriptions(&mut conn, email).await, 0);
+        assert!(queued(&mut conn, email).await.is_empty());
+        conn.close().await.unwrap();
+    });
+}
+
+#[test]
+fn confirmation_links_point_at_the_site() {
+    common::run("sjf_test_notify", async {
+        init();
+        let email = "kund@butik.example";
+        let product = common::product("Slutsåld", Some(0)).await;
+        assert_eq!(
+            sjf_notify::subscribe(product, email, Locale::Sv)
+                .await
+                .unwrap(),
+            SubscribeStatus::ConfirmationSent
+        );
+
+        let mut conn = common::connection().await;
+        assert_eq!(subscriptions(&mut conn, email).await, 1);
+        let bodies = queued(&mut conn, email).await;
+        assert_eq!(bodies.len(), 1);
+        assert!(bodies[0].contains(&format!("{SITE}{CONFIRM_SUBSCRIPTION_PATH}/")));
+        conn.close().await.unwrap();
+    });
+}
+
+#[test]
+fn emails_follow_the_language_subscribed_in() {
+    common::run("sjf_test_notify", async {
+        init();
+        let email = "customer@butik.example";
+        let product = common::product("Utsåld", Some(0)).await;
+        sjf_notify::subscribe(product, email, Locale::En)
+            .await
+            .unwrap();
+
+        let mut conn = common::connection().await;
+        let confirmation = queued(&mut conn, email).await.remove(0);
+        let prefix = format!("{SITE}/en/alerts/confirm/");
+        let token = confirmation
+            .lines()
+            .find_map(|l| l.strip_prefix(&prefix))
+            .unwrap();
+        assert!(sjf_db::stock::confirm_subscription(token).await.unwrap());
+
+        sqlx::query("UPDATE products SET quantity = 1 WHERE id = $1")
+            .bind(product as i32)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sjf_notify::send_back_in_stock_notifications()
+            .await
+            .unwrap();
+        let bodies = queued(&mut conn, email).await;
+        assert_eq!(bodies.len(), 2);
+        assert!(bodies[1].contains("Utsåld is back in stock"));
+        assert!(bodies[1].contains(&format!("{SITE}/en/products/")));
+        conn.close().await.unwrap();
+    });
+}
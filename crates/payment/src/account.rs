@@ -0,0 +1,117 @@
+use sjf_api::account::{Account, Address, OrderLine, PastOrder};
+use sjf_api::currency::Currency;
+use sjf_db::auth::Uuid;
+use stripe::{
+    CheckoutSession, CheckoutSessionStatus, CreateCustomer, Customer, CustomerId,
+    ListCheckoutSessions,
+};
+
+use crate::PaymentError;
+use crate::stripe::client;
+
+/// Past orders shown on an account page.
+const MAX_ORDERS: u64 = 50;
+
+/// A logged in customer making a checkout.
+pub struct CustomerAccount {
+    pub id: Uuid,
+    pub email: String,
+}
+
+/// The Stripe customer of a customer account, created on its first checkout.
+pub(crate) async fn stripe_customer(account: &CustomerAccount) -> Result<CustomerId, PaymentError> {
+    if let Some(id) = sjf_db::auth::stripe_customer(&account.id).await?
+        && let Ok(id) = id.parse()
+    {
+        return Ok(id);
+    }
+
+    let mut params = CreateCustomer::new();
+    params.email = Some(&account.email);
+    params.metadata = Some([("account".to_owned(), account.id.to_string())].into());
+    let customer = Customer::create(&client(), params).await?;
+
+    sjf_db::auth::set_stripe_customer(&account.id, customer.id.as_str()).await?;
+    Ok(customer.id)
+}
+
+fn address(name: Option<String>, a: stripe::Address) -> Address {
+    Address {
+        name,
+        line1: a.line1,
+        line2: a.line2,
+        postal_code: a.postal_code,
+        city: a.city,
+        country: a.country,
+    }
+}
+
+fn past_order(session: CheckoutSession) -> PastOrder {
+    let lines = session
+        .line_items
+        .map(|l| l.data)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|i| OrderLine {
+            description: i.description,
+            quantity: i.quantity.unwrap_or(1) as u32,
+            total: i.amount_total.max(0) as u64,
+        })
+        .collect();
+
+    PastOrder {
+        reference: session.client_reference_id.unwrap_or_default(),
+        created: chrono::DateTime::from_timestamp(session.created, 0).unwrap_or_default(),
+        currency: session
+            .currency
+            .and_then(|c| Currency::from_code(c.to_string().as_str()))
+            .unwrap_or_default(),
+        total: session.amount_total.unwrap_or_default().max(0) as u64,
+        lines,
+    }
+}
+
+/// Order history and saved addresses of a customer account, as Stripe knows them.
+pub async fn account(account: &CustomerAccount) -> Result<Account, PaymentError> {
+    let mut res = Account {
+        email: account.email.clone(),
+        orders: vec![],
+        addresses: vec![],
+    };
+    let Some(customer) = sjf_db::auth::stripe_customer(&account.id).await? else {
+        return Ok(res);
+    };
+    let Ok(customer) = customer.parse::<CustomerId>() else {
+        return Ok(res);
+    };
+    let client = client();
+
+    let params = ListCheckoutSessions {
+        customer: Some(customer.clone()),
+        status: Some(CheckoutSessionStatus::Complete),
+        limit: Some(MAX_ORDERS),
+        expand: &["data.line_items"],
+        ..Default::default()
+    };
+    res.orders = CheckoutSession::list(&client, &params)
+        .await?
+        .data
+        .into_iter()
+        .map(past_order)
+        .collect();
+
+    let customer = Customer::retrieve(&client, &customer, &[]).await?;
+    if let Some(shipping) = customer.shipping
+        && let Some(a) = shipping.address
+    {
+        res.addresses.push(address(shipping.name, a));
+    }
+    if let Some(a) = customer.address {
+        let billing = address(customer.name, a);
+        if !res.addresses.contains(&billing) {
+            res.addresses.push(billing);
+        }
+    }
+
+    Ok(res)
+}
@@ -1,17 +1,25 @@
-use ::stripe::Client;
-
+mod account;
 mod stripe;
+mod webhook;
+pub use account::{CustomerAccount, account};
 pub use sjf_api::payment::{CANCLE_PATH, SUCCESS_PATH};
-pub use stripe::{checkout, init,is_payment_demo};
+pub use stripe::{checkout, init, is_configured, is_payment_demo};
+pub use webhook::handle_webhook;
 
 #[derive(thiserror::Error, Debug)]
 pub enum PaymentError {
     #[error("Sql failed {0}")]
     Sql(#[from] sjf_db::checkout::CheckoutError),
+    #[error("Database failed {0}")]
+    Db(#[from] sjf_db::Error),
     #[error("Stripe failed {0}")]
     Stripe(#[from] ::stripe::StripeError),
-    #[error("Invalid tax rate {0}")]
-    InvalidTaxRate(u8),
+    #[error("Invalid tax rate {1} for {0}")]
+    InvalidTaxRate(&'static str, sjf_api::tax::BasisPoints),
     #[error("Stripe didn't return a URL for checkout")]
     NoUrl,
+    #[error("Webhook verification failed {0}")]
+    Webhook(#[from] ::stripe::WebhookError),
+    #[error("STRIPE_WEBHOOK_SECRET is not set")]
+    WebhookNotConfigured,
 }
@@ -1,5 +1,12 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
+use sjf_api::currency::{EU_COUNTRIES, ExchangeRate};
+use sjf_api::i18n::Locale;
+use sjf_api::product::ARTICLE_PREFIX;
+use sjf_api::stock::Fulfilment;
+use sjf_api::tax::{BasisPoints, TaxCategory, format_rate, vat_country};
+use sjf_config::{Secret, SiteConfig, StripeConfig};
+use sjf_db::reservation;
 use stripe::{
     CheckoutSession, CheckoutSessionMode, Client, CreateCheckoutSession,
     CreateCheckoutSessionCustomFields, CreateCheckoutSessionCustomFieldsDropdown,
@@ -8,19 +15,8 @@ use stripe::{
     CreateCheckoutSessionLineItemsPriceData, CreateCheckoutSessionLineItemsPriceDataProductData,
     CreateCheckoutSessionLineItemsPriceDataTaxBehavior, CreateCheckoutSessionPaymentIntentData,
     CreateCheckoutSessionPhoneNumberCollection, CreateCheckoutSessionShippingAddressCollection,
-    CreateCheckoutSessionShippingAddressCollectionAllowedCountries,
-    CreateCheckoutSessionShippingOptions, CreateCheckoutSessionShippingOptionsShippingRateData,
-    CreateCheckoutSessionShippingOptionsShippingRateDataDeliveryEstimate,
-    CreateCheckoutSessionShippingOptionsShippingRateDataDeliveryEstimateMaximum,
-    CreateCheckoutSessionShippingOptionsShippingRateDataDeliveryEstimateMaximumUnit,
-    CreateCheckoutSessionShippingOptionsShippingRateDataDeliveryEstimateMinimum,
-    CreateCheckoutSessionShippingOptionsShippingRateDataDeliveryEstimateMinimumUnit,
-    CreateCheckoutSessionShippingOptionsShippingRateDataFixedAmount,
-    CreateCheckoutSessionShippingOptionsShippingRateDataTaxBehavior,
-    CreateCheckoutSessionShippingOptionsShippingRateDataType, CreateCustomer, CreatePrice,
-    CreateProduct, CreateTaxRate, Currency, Customer, Expandable, IdOrCreate, ListTaxRates,
-    OrderItem, Price, Product, TaxRate, TaxRateId,
-    generated::{billing::tax_rate, core::tax_code},
+    CreateCheckoutSessionShippingAddressCollectionAllowedCountries as AllowedCountries,
+    CreateTaxRate, Currency, ListTaxRates, TaxRate, TaxRateId,
 };
 use tracing::info;
 
@@ -28,22 +24,27 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 const MAJOR_VERSION: &str = env!("CARGO_PKG_VERSION_MAJOR");
 const NAME: &str = env!("CARGO_PKG_NAME");
 
-fn api_secret() -> String 
-{
-    dotenvy::var("STRIPE_API_KEY").expect("Missing STRIPE_API_KEY")
-
+fn api_secret() -> &'static str {
+    API_KEY
+        .get()
+        .expect("Stripe should have been initialized")
+        .expose()
 }
 
-fn client() -> ::stripe::Client {
+pub(crate) fn client() -> ::stripe::Client {
     let secret_key = api_secret();
-    let client = Client::new(secret_key).with_app_info(NAME.into(), Some(VERSION.into()), None);
 
-    client
+    Client::new(secret_key).with_app_info(NAME.into(), Some(VERSION.into()), None)
+}
+
+/// Whether Stripe is set up to take payments: the API key is known and the tax
+/// rates of line items exist.
+pub fn is_configured() -> bool {
+    API_KEY.get().is_some() && TAX_RATES.get().is_some()
 }
 
-pub fn is_payment_demo() -> bool
-{
-    let secret= api_secret();
+pub fn is_payment_demo() -> bool {
+    let secret = api_secret();
     secret.contains("test") && !secret.contains("live")
 }
 //struct ShippingOption {
@@ -81,7 +82,7 @@ pub fn is_payment_demo() -> bool
 //                    fixed_amount: Some(
 //                        CreateCheckoutSessionShippingOptionsShippingRateDataFixedAmount {
 //                            amount: (self.ammount*100) as i64,
-//                            currency: Currency::SEK,
+//                            currency: currency(&rate),
 //                            ..Default::default()
 //                        }
 //                    ),
@@ -98,16 +99,36 @@ pub fn is_payment_demo() -> bool
 //}
 
 use once_cell::sync::OnceCell;
-static TAX_RATES: OnceCell<BTreeMap<u8, TaxRateId>> = OnceCell::new();
+static TAX_RATES: OnceCell<BTreeMap<(&'static str, BasisPoints), TaxRateId>> = OnceCell::new();
 static SITE_URL: OnceCell<String> = OnceCell::new();
+static API_KEY: OnceCell<Secret> = OnceCell::new();
+
+pub async fn init(config: &StripeConfig, site: &SiteConfig) -> Result<(), crate::PaymentError> {
+    let _ = API_KEY.set(config.api_key.clone());
+    let _ = SITE_URL.set(site.url.clone());
+    crate::webhook::init(config.webhook_secret.clone());
+    create_tax_rates().await
+}
 
-pub async fn init() -> Result<(), crate::PaymentError> {
-    SITE_URL.set(dotenvy::var("WEBSITE_URL").unwrap()).unwrap();
-    create_swedish_tax_rates().await
+/// The rates line items can be taxed with: the Swedish ones, and once we're
+/// registered for OSS those of every other member state too.
+fn needed_tax_rates() -> BTreeSet<(&'static str, BasisPoints)> {
+    let countries: &[&'static str] = match sjf_db::tax::oss() {
+        true => &EU_COUNTRIES,
+        false => &["SE"],
+    };
+    let mut rates: BTreeSet<_> = countries
+        .iter()
+        .flat_map(|c| TaxCategory::ALL.into_iter().map(|t| (*c, t.rate(c))))
+        .filter(|(_, rate)| *rate > 0)
+        .collect();
+    // Zero rated goods and exports all use the Swedish 0% rate
+    rates.insert(("SE", 0));
+    rates
 }
 
-async fn create_swedish_tax_rates() -> Result<(), crate::PaymentError> {
-    let predefined_tax_rates: BTreeSet<_> = [25u8, 12, 6, 0].into_iter().collect();
+async fn create_tax_rates() -> Result<(), crate::PaymentError> {
+    let needed = needed_tax_rates();
     let client = client();
 
     let params = ListTaxRates {
@@ -122,31 +143,38 @@ async fn create_swedish_tax_rates() -> Result<(), crate::PaymentError> {
 
     let description_string = format!("tax-{}-{}", NAME, MAJOR_VERSION);
 
-    let mut tax_rate_names = BTreeMap::<u8, TaxRateId>::new();
+    let mut tax_rate_names = BTreeMap::<(&'static str, BasisPoints), TaxRateId>::new();
 
     while let Some(existing_tax_rate) = stream.try_next().await? {
-        let swedish = existing_tax_rate.country.unwrap_or_default() == "SE";
+        let country = existing_tax_rate.country.unwrap_or_default();
         let from_app = existing_tax_rate.description.unwrap_or_default() == description_string;
-        let rate: u8 = existing_tax_rate.percentage as u8;
+        let rate = (existing_tax_rate.percentage * 100.0).round() as BasisPoints;
         let id = existing_tax_rate.id;
-        if swedish && from_app && predefined_tax_rates.contains(&rate) {
+        if let Some(key) = needed.iter().find(|(c, r)| *c == country && *r == rate)
+            && from_app
+        {
             info!("Found STRIPE tax rate {}", id);
-            tax_rate_names.insert(rate, id);
+            tax_rate_names.insert(*key, id);
         }
     }
 
-    for predefined_tax_rate in predefined_tax_rates {
-        if !tax_rate_names.contains_key(&predefined_tax_rate) {
-            info!("Creating STRIPE tax rate {}", predefined_tax_rate);
-            let display_name = format!("Moms {}%", predefined_tax_rate);
-            let mut tax_rate = CreateTaxRate::new(&display_name, predefined_tax_rate as f64);
+    for (country, rate) in needed {
+        if let std::collections::btree_map::Entry::Vacant(e) = tax_rate_names.entry((country, rate))
+        {
+            info!("Creating STRIPE tax rate {} {}", country, format_rate(rate));
+            let display_name = match country {
+                "SE" => format!("Moms {}", format_rate(rate)),
+                _ => format!("VAT {}", format_rate(rate)),
+            };
+            let mut tax_rate = CreateTaxRate::new(&display_name, rate as f64 / 100.0);
             tax_rate.inclusive = true;
-            tax_rate.country = Some("SE");
+            tax_rate.country = Some(country);
+            tax_rate.jurisdiction = Some(country);
             tax_rate.description = Some(&description_string);
             tax_rate.active = Some(true);
             tax_rate.tax_type = Some(stripe::TaxRateTaxType::Vat);
             let created_tax_rate = TaxRate::create(&client, tax_rate).await?;
-            tax_rate_names.insert(predefined_tax_rate, created_tax_rate.id);
+            e.insert(created_tax_rate.id);
         }
     }
 
@@ -155,42 +183,155 @@ async fn create_swedish_tax_rates() -> Result<(), crate::PaymentError> {
     Ok(())
 }
 
-pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
+/// A Stripe tax rate by country and rate. Every 0% rate is the Swedish one,
+/// as exports carry no VAT wherever they go.
+type TaxRateKey = (&'static str, BasisPoints);
+
+fn tax_rate_key(country: &'static str, rate: BasisPoints) -> TaxRateKey {
+    match rate {
+        0 => ("SE", 0),
+        _ => (country, rate),
+    }
+}
+
+fn tax_rate_id(key: TaxRateKey) -> Result<String, crate::PaymentError> {
+    TAX_RATES
+        .get()
+        .unwrap()
+        .get(&key)
+        .map(|id| id.as_str().into())
+        .ok_or(crate::PaymentError::InvalidTaxRate(key.0, key.1))
+}
+
+fn currency(rate: &ExchangeRate) -> Currency {
+    use sjf_api::currency::Currency as Shop;
+    match rate.currency {
+        Shop::Sek => Currency::SEK,
+        Shop::Eur => Currency::EUR,
+        Shop::Nok => Currency::NOK,
+        Shop::Dkk => Currency::DKK,
+    }
+}
+
+fn allowed_country(code: &str) -> Option<AllowedCountries> {
+    Some(match code {
+        "AT" => AllowedCountries::At,
+        "BE" => AllowedCountries::Be,
+        "BG" => AllowedCountries::Bg,
+        "CY" => AllowedCountries::Cy,
+        "CZ" => AllowedCountries::Cz,
+        "DE" => AllowedCountries::De,
+        "DK" => AllowedCountries::Dk,
+        "EE" => AllowedCountries::Ee,
+        "ES" => AllowedCountries::Es,
+        "FI" => AllowedCountries::Fi,
+        "FR" => AllowedCountries::Fr,
+        "GR" => AllowedCountries::Gr,
+        "HR" => AllowedCountries::Hr,
+        "HU" => AllowedCountries::Hu,
+        "IE" => AllowedCountries::Ie,
+        "IT" => AllowedCountries::It,
+        "LT" => AllowedCountries::Lt,
+        "LU" => AllowedCountries::Lu,
+        "LV" => AllowedCountries::Lv,
+        "MT" => AllowedCountries::Mt,
+        "NL" => AllowedCountries::Nl,
+        "NO" => AllowedCountries::No,
+        "PL" => AllowedCountries::Pl,
+        "PT" => AllowedCountries::Pt,
+        "RO" => AllowedCountries::Ro,
+        "SE" => AllowedCountries::Se,
+        "SI" => AllowedCountries::Si,
+        "SK" => AllowedCountries::Sk,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum LineTaxRates {
+    Fixed(TaxRateKey),
+    /// Stripe picks the one of the country the order ships to.
+    Dynamic(Vec<TaxRateKey>),
+}
+
+/// The tax rates of a line with the given Swedish rate. Orders that can only
+/// go to one country get its rate right away. Orders in euro can go anywhere
+/// in the EU, once we're registered for OSS they get the rate of the country
+/// they ship to.
+fn line_tax_rates(rate: &ExchangeRate, swedish_percent: u32, oss: bool) -> LineTaxRates {
+    let category = TaxCategory::from_swedish_rate(swedish_percent);
+    match rate.currency.shipping_countries() {
+        [country] => LineTaxRates::Fixed(match vat_country(country, oss) {
+            Some(vat_country) => tax_rate_key(vat_country, category.rate(vat_country)),
+            None => tax_rate_key("SE", 0),
+        }),
+        _ if !oss => LineTaxRates::Fixed(tax_rate_key("SE", category.rate("SE"))),
+        countries => {
+            let keys: BTreeSet<_> = countries
+                .iter()
+                .map(|c| tax_rate_key(c, category.rate(c)))
+                .collect();
+            LineTaxRates::Dynamic(keys.into_iter().collect())
+        }
+    }
+}
+
+/// Creates a Stripe checkout session for a reservation. Sessions of a logged in
+/// customer are made as their Stripe customer so that addresses are pre-filled.
+/// The checkout page and the return links follow the visitor's language, and
+/// the order is charged in the currency it was reserved in.
+pub async fn checkout(
+    uuid: String,
+    account: Option<&crate::CustomerAccount>,
+    locale: Locale,
+) -> Result<String, crate::PaymentError> {
     let url = SITE_URL.get().unwrap();
     let client = client();
 
+    let rate = sjf_db::currency::order_rate(&uuid).await?;
     let items = {
-        let mut items = sjf_db::checkout::get_order(&uuid).await?;
-        let shipping_price = {
-            let total_order_quantity = items
+        let mut items: Vec<_> = sjf_db::checkout::get_order(&uuid)
+            .await?
+            .into_iter()
+            .flat_map(sjf_db::checkout::OrderItem::split)
+            .collect();
+        if locale != Locale::Sv {
+            let ids: Vec<u32> = items.iter().map(|i| i.product_id).collect();
+            let texts = sjf_db::translation::product_texts(locale, &ids).await?;
+            for item in items.iter_mut() {
+                if let Some(t) = texts.get(&item.product_id) {
+                    item.name = t.name.clone();
+                }
+            }
+        }
+        let shipping_tax_rate = sjf_api::tax::shipping_rate(
+            items
                 .iter()
-                .map(|i| i.ordered_quantity)
-                .fold(0, |acc, i| acc + i);
+                .map(|i| (i.tax_rate, i.price * i.ordered_quantity)),
+        );
+        let shipping_price = {
+            let total_order_quantity = items.iter().map(|i| i.ordered_quantity).sum::<u32>();
             let total_order_price = items
                 .iter()
                 .map(|i| i.price * i.ordered_quantity)
-                .fold(0, |acc, i| acc + i);
-
-            let initial_price = match total_order_quantity {
-                0..=2 => 89,
-                3..=4 => 99,
-                5.. => 129,
-            };
+                .sum::<u32>();
 
-            if total_order_price > 999 {
-                0
-            } else {
-                initial_price
-            }
+            sjf_api::checkout::shipping_price(total_order_quantity, total_order_price)
         };
 
         items.push(sjf_db::checkout::OrderItem {
             product_id: 0,
             image_path: None,
-            name: "Frakt".into(),
+            name: match locale {
+                Locale::Sv => "Frakt".into(),
+                Locale::En => "Shipping".into(),
+            },
             price: shipping_price,
             ordered_quantity: 1,
-            tax_rate: 25,
+            backordered: 0,
+            tax_rate: shipping_tax_rate,
+            fulfilment: Fulfilment::InStock,
+            ship_date: None,
         });
 
         items
@@ -221,43 +362,81 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
         map
     };
 
+    // Lets staff see which lines can't ship right away without opening each line item
+    let split_lines = {
+        let mut map: HashMap<String, String> = HashMap::new();
+        for fulfilment in [Fulfilment::Backorder, Fulfilment::Preorder] {
+            let lines = items
+                .iter()
+                .filter(|i| i.fulfilment == fulfilment)
+                .map(|i| format!("{}{}", ARTICLE_PREFIX, i.product_id))
+                .collect::<Vec<_>>();
+            if !lines.is_empty() {
+                map.insert(format!("{}-lines", fulfilment.as_str()), lines.join(","));
+            }
+        }
+        map
+    };
+
     let items = items
         .into_iter()
         .map(|item| {
-            let item_tax_rate = item.tax_rate as u8;
-            let tax_rate_id = TAX_RATES
-                .get()
-                .unwrap()
-                .get(&item_tax_rate)
-                .ok_or(crate::PaymentError::InvalidTaxRate(item_tax_rate))?;
-            let tax_rate_id = String::from(tax_rate_id.as_str());
+            let oss = sjf_db::tax::oss();
+            let (tax_rates, dynamic_tax_rates) = match line_tax_rates(&rate, item.tax_rate, oss) {
+                LineTaxRates::Fixed(key) => (Some(vec![tax_rate_id(key)?]), None),
+                LineTaxRates::Dynamic(keys) => {
+                    let ids = keys
+                        .into_iter()
+                        .map(tax_rate_id)
+                        .collect::<Result<_, _>>()?;
+                    (None, Some(ids))
+                }
+            };
             let image_urls = item.image_path.map(|i| vec![format!("{}{}", url, i)]);
 
             Ok::<CreateCheckoutSessionLineItems, crate::PaymentError>(
                 CreateCheckoutSessionLineItems {
                     quantity: Some(item.ordered_quantity.into()),
-                    tax_rates: Some(vec![tax_rate_id]),
+                    tax_rates,
+                    dynamic_tax_rates,
                     price_data: Some(CreateCheckoutSessionLineItemsPriceData {
-                        currency: Currency::SEK,
+                        currency: currency(&rate),
                         product_data: Some(CreateCheckoutSessionLineItemsPriceDataProductData {
                             description: None,
                             images: image_urls,
-                            name: item.name,
+                            name: match (item.fulfilment, locale) {
+                                (Fulfilment::InStock, _) => item.name,
+                                (Fulfilment::Backorder, Locale::Sv) => {
+                                    format!("{} (restnoterad)", item.name)
+                                }
+                                (Fulfilment::Backorder, Locale::En) => {
+                                    format!("{} (backordered)", item.name)
+                                }
+                                (Fulfilment::Preorder, Locale::Sv) => {
+                                    format!("{} (förbeställning)", item.name)
+                                }
+                                (Fulfilment::Preorder, Locale::En) => {
+                                    format!("{} (pre-order)", item.name)
+                                }
+                            },
                             tax_code: None,
                             metadata: Some({
                                 let mut map = metadata.clone();
                                 map.insert(
                                     "article-number".into(),
-                                    format!("artikel-{}", item.product_id),
+                                    format!("{}{}", ARTICLE_PREFIX, item.product_id),
                                 );
+                                map.insert("fulfilment".into(), item.fulfilment.as_str().into());
+                                if let Some(ship_date) = item.ship_date {
+                                    map.insert("ship-date".into(), ship_date.to_string());
+                                }
                                 map
                             }),
-                            ..Default::default()
                         }),
                         tax_behavior: Some(
                             CreateCheckoutSessionLineItemsPriceDataTaxBehavior::Inclusive,
                         ),
-                        unit_amount: Some((100 * item.price).into()),
+                        unit_amount: Some(rate.minor_units(item.price) as i64),
                         ..Default::default()
                     }),
                     ..Default::default()
@@ -270,20 +449,43 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
         map.insert("reservation".into(), uuid.clone());
         map.insert("app".into(), NAME.into());
         map.insert("app-version".into(), VERSION.into());
+        map.extend(split_lines);
         map
     };
 
+    let session_expiry = reservation::windows().session_expiry(reservation::clock().now());
     let checkout_session = {
         let mut params = CreateCheckoutSession::new();
 
-        let cancel_url = format!("{}{}/{}", url, sjf_api::payment::CANCLE_PATH, uuid);
-        let success_url = format!("{}{}/{}", url, sjf_api::payment::SUCCESS_PATH, uuid);
+        let cancel_url = format!("{}{}/{}", url, sjf_api::payment::cancel_path(locale), uuid);
+        let success_url = format!("{}{}/{}", url, sjf_api::payment::success_path(locale), uuid);
+
+        params.locale = Some(match locale {
+            Locale::Sv => stripe::CheckoutSessionLocale::Sv,
+            Locale::En => stripe::CheckoutSessionLocale::En,
+        });
 
         //params.shipping_options = Some(shipping_options);
         params.cancel_url = Some(&cancel_url);
         params.success_url = Some(&success_url);
         params.client_reference_id = Some(uuid.as_ref());
-        params.customer_creation = Some(stripe::CheckoutSessionCustomerCreation::Always);
+        let customer = match account {
+            Some(account) => Some(crate::account::stripe_customer(account).await?),
+            None => None,
+        };
+        match &customer {
+            Some(customer) => {
+                params.customer = Some(customer.clone());
+                params.customer_update = Some(stripe::CreateCheckoutSessionCustomerUpdate {
+                    address: Some(stripe::CreateCheckoutSessionCustomerUpdateAddress::Auto),
+                    name: Some(stripe::CreateCheckoutSessionCustomerUpdateName::Auto),
+                    shipping: Some(stripe::CreateCheckoutSessionCustomerUpdateShipping::Auto),
+                });
+            }
+            None => {
+                params.customer_creation = Some(stripe::CheckoutSessionCustomerCreation::Always)
+            }
+        }
         params.payment_intent_data = Some(CreateCheckoutSessionPaymentIntentData {
             metadata: Some(metadata.clone()),
             ..Default::default()
@@ -292,9 +494,12 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
         params.phone_number_collection =
             Some(CreateCheckoutSessionPhoneNumberCollection { enabled: true });
         params.shipping_address_collection = Some(CreateCheckoutSessionShippingAddressCollection {
-            allowed_countries: vec![
-                CreateCheckoutSessionShippingAddressCollectionAllowedCountries::Se,
-            ],
+            allowed_countries: rate
+                .currency
+                .shipping_countries()
+                .iter()
+                .filter_map(|c| allowed_country(c))
+                .collect(),
         });
 
         params.custom_fields = Some(vec![CreateCheckoutSessionCustomFields {
@@ -311,7 +516,11 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
                 ],
             }),
             label: CreateCheckoutSessionCustomFieldsLabel {
-                custom: "Frakt alternativ".into(),
+                custom: match locale {
+                    Locale::Sv => "Frakt alternativ",
+                    Locale::En => "Shipping option",
+                }
+                .into(),
                 type_: stripe::CreateCheckoutSessionCustomFieldsLabelType::Custom,
             },
             key: "selected_shipping_option".into(),
@@ -322,13 +531,7 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
         params.billing_address_collection =
             Some(stripe::CheckoutSessionBillingAddressCollection::Auto);
 
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let expiry = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + 30 * 60;
-        params.expires_at = Some(expiry as i64);
+        params.expires_at = Some(session_expiry.timestamp());
 
         params.mode = Some(CheckoutSessionMode::Payment);
         params.line_items = Some(items);
@@ -336,5 +539,71 @@ pub async fn checkout(uuid: String) -> Result<String, crate::PaymentError> {
         CheckoutSession::create(&client, params).await?
     };
 
-    Ok(checkout_session.url.ok_or(crate::PaymentError::NoUrl)?)
+    let session_expiry =
+        chrono::DateTime::from_timestamp(checkout_session.expires_at, 0).unwrap_or(session_expiry);
+    reservation::attach_to_session(&uuid, session_expiry).await?;
+
+    checkout_session.url.ok_or(crate::PaymentError::NoUrl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sjf_api::currency::Currency as Shop;
+
+    fn rate(currency: Shop) -> ExchangeRate {
+        ExchangeRate {
+            currency,
+            sek_micros: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn single_country_orders_get_its_rate() {
+        let sek = rate(Shop::Sek);
+        assert_eq!(
+            line_tax_rates(&sek, 25, false),
+            LineTaxRates::Fixed(("SE", 2500))
+        );
+        assert_eq!(
+            line_tax_rates(&sek, 6, true),
+            LineTaxRates::Fixed(("SE", 600))
+        );
+        // Danish orders pay Swedish VAT until we're registered for OSS
+        let dkk = rate(Shop::Dkk);
+        assert_eq!(
+            line_tax_rates(&dkk, 12, false),
+            LineTaxRates::Fixed(("SE", 1200))
+        );
+        assert_eq!(
+            line_tax_rates(&dkk, 12, true),
+            LineTaxRates::Fixed(("DK", 2500))
+        );
+        // Exports carry no VAT
+        let nok = rate(Shop::Nok);
+        assert_eq!(
+            line_tax_rates(&nok, 25, true),
+            LineTaxRates::Fixed(("SE", 0))
+        );
+    }
+
+    #[test]
+    fn euro_orders_let_stripe_pick_the_rate_with_oss() {
+        let eur = rate(Shop::Eur);
+        assert_eq!(
+            line_tax_rates(&eur, 25, false),
+            LineTaxRates::Fixed(("SE", 2500))
+        );
+
+        let LineTaxRates::Dynamic(keys) = line_tax_rates(&eur, 12, true) else {
+            panic!("euro orders should get every member state's rate");
+        };
+        assert!(keys.contains(&("DE", 700)));
+        assert!(keys.contains(&("FR", 550)));
+        assert!(keys.contains(&("SE", 1200)));
+        // Ireland and Malta don't tax food, which is the shared 0% rate
+        assert!(keys.contains(&("SE", 0)));
+        assert!(!keys.iter().any(|k| *k == ("IE", 0) || *k == ("MT", 0)));
+        assert_eq!(keys.len(), 26);
+    }
 }
@@ -0,0 +1,72 @@
+use once_cell::sync::OnceCell;
+use sjf_config::Secret;
+use stripe::{EventObject, EventType, Webhook};
+use tracing::{error, info, warn};
+
+static WEBHOOK_SECRET: OnceCell<Option<Secret>> = OnceCell::new();
+
+pub(crate) fn init(secret: Option<Secret>) {
+    if secret.is_none() {
+        warn!(
+            "STRIPE_WEBHOOK_SECRET is not set, expired checkout sessions are only released by their expiry"
+        );
+    }
+    let _ = WEBHOOK_SECRET.set(secret);
+}
+
+/// Verifies and handles a Stripe webhook event. Expired checkout sessions
+/// release their stock reservation right away and are remembered for a
+/// reminder if the customer got as far as entering an email. Completed
+/// sessions leave the customer email on the order, used to ask for reviews,
+/// and the country it ships to, which decides the VAT of the order.
+pub async fn handle_webhook(payload: &str, signature: &str) -> Result<(), crate::PaymentError> {
+    let secret = WEBHOOK_SECRET
+        .get()
+        .and_then(|s| s.as_ref())
+        .map(|s| s.expose())
+        .ok_or(crate::PaymentError::WebhookNotConfigured)?;
+
+    let event = Webhook::construct_event(payload, signature, secret)?;
+
+    match (event.type_, event.data.object) {
+        (EventType::CheckoutSessionExpired, EventObject::CheckoutSession(session)) => {
+            if let Some(uuid) = session.client_reference_id {
+                info!("Checkout session for reservation {} expired", uuid);
+                let email = session
+                    .customer_details
+                    .and_then(|d| d.email)
+                    .or(session.customer_email);
+                if let Some(email) = email
+                    && let Err(e) = sjf_db::recovery::record_abandoned(&uuid, &email).await
+                {
+                    error!("Failed to record abandoned checkout {} {}", uuid, e);
+                }
+                sjf_db::checkout::undo_reservation(uuid).await?;
+            }
+        }
+        (EventType::CheckoutSessionCompleted, EventObject::CheckoutSession(session)) => {
+            let country = session
+                .shipping_details
+                .as_ref()
+                .and_then(|s| s.address.as_ref())
+                .and_then(|a| a.country.clone());
+            if let (Some(uuid), Some(country)) = (&session.client_reference_id, country)
+                && let Err(e) = sjf_db::tax::set_destination(uuid, &country).await
+            {
+                error!("Failed to store destination of order {} {}", uuid, e);
+            }
+            let email = session
+                .customer_details
+                .and_then(|d| d.email)
+                .or(session.customer_email);
+            if let (Some(uuid), Some(email)) = (session.client_reference_id, email)
+                && let Err(e) = sjf_db::orders::set_email(&uuid, &email).await
+            {
+                error!("Failed to store email of order {} {}", uuid, e);
+            }
+        }
+        (t, _) => info!("Ignoring stripe event {}", t),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Scratch databases for the integration tests, created on the server
+//! `DATABASE_URL` points at. The tests fail when it isn't set.
+
+use std::future::Future;
+use std::sync::{LazyLock, Mutex, MutexGuard, OnceLock};
+
+use sjf_config::{CheckoutConfig, DatabaseConfig};
+use sqlx::{Connection, Executor, PgConnection};
+use tokio::runtime::Runtime;
+use tokio::sync::OnceCell;
+
+/// The pool of `sjf_db` is global, so the tests of a binary share a runtime
+/// and a database.
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| Runtime::new().unwrap());
+static CONNECTED: OnceCell<()> = OnceCell::const_new();
+static SERIAL: Mutex<()> = Mutex::new(());
+static URL: OnceLock<String> = OnceLock::new();
+
+/// `url` with the database name replaced by `name`.
+pub fn database_url(url: &str, name: &str) -> String {
+    let (url, params) = url.split_once('?').unwrap_or((url, ""));
+    let base = url.rsplit_once('/').map(|(base, _)| base).unwrap_or(url);
+    match params {
+        "" => format!("{base}/{name}"),
+        params => format!("{base}/{name}?{params}"),
+    }
+}
+
+/// Drops and creates the database `name`, returning its url.
+pub async fn empty_database(name: &str) -> String {
+    let url = dotenvy::var("DATABASE_URL")
+        .expect("DATABASE_URL should point at a server the tests can create databases on");
+    let mut admin = PgConnection::connect(&url)
+        .await
+        .expect("DATABASE_URL should be connectable");
+    admin
+        .execute(format!(r#"DROP DATABASE IF EXISTS "{name}" WITH (FORCE)"#).as_str())
+        .await
+        .unwrap();
+    admin
+        .execute(format!(r#"CREATE DATABASE "{name}""#).as_str())
+        .await
+        .unwrap();
+    admin.close().await.unwrap();
+    database_url(&url, name)
+}
+
+/// Runs `test` connected to the scratch database `name`, which is created
+/// with the shop's tables on first use. Tests using this run one at a time,
+/// as they share the clock and the catalogue.
+pub fn run<F>(name: &'static str, test: F)
+where
+    F: Future<Output = ()>,
+{
+    let _serial = serial();
+    RUNTIME.block_on(async {
+        CONNECTED
+            .get_or_init(|| async {
+                let url = empty_database(name).await;
+                URL.set(url.clone()).unwrap();
+                let database = DatabaseConfig {
+                    url: url.into(),
+                    pool_size: 5,
+                };
+                sjf_db::connect(&database, &CheckoutConfig::default())
+                    .await
+                    .expect("the scratch database should be initialized");
+            })
+            .await;
+        test.await
+    });
+}
+
+/// A connection of its own to the scratch database, for looking at and
+/// changing rows the shop's functions don't expose.
+pub async fn connection() -> PgConnection {
+    PgConnection::connect(URL.get().expect("run should have connected"))
+        .await
+        .unwrap()
+}
+
+fn serial() -> MutexGuard<'static, ()> {
+    // A failed test mustn't fail the ones after it
+    SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A product in a new category, with `quantity` in stock.
+pub async fn product(name: &str, quantity: Option<i32>) -> u32 {
+    let category = sjf_db::category::create(sjf_api::category::CreateReq {
+        name: format!("{name} kategori"),
+        parent: None,
+    })
+    .await
+    .unwrap()
+    .id;
+    let id = sjf_db::create_product(sjf_db::Product {
+        id: 0,
+        name: name.into(),
+        price: 100,
+        description: format!("Beskrivning av {name}"),
+        quantity,
+        created: chrono::Utc::now(),
+        updated: chrono::Utc::now(),
+        tax_rate: 25,
+        images: vec![],
+        category,
+        reorder_threshold: None,
+        stock_policy: Default::default(),
+        attributes: Default::default(),
+        translations: Default::default(),
+        sku: None,
+    })
+    .await
+    .unwrap();
+
+    // The path of the category is refreshed by a job that the tests don't run
+    let mut conn = connection().await;
+    conn.execute("REFRESH MATERIALIZED VIEW product_paths")
+        .await
+        .unwrap();
+    conn.close().await.unwrap();
+    id as u32
+}
+
+/// The quantity in stock of the product `id`.
+pub async fn quantity(id: u32) -> Option<i32> {
+    sjf_db::get_all_products()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|p| p.id == id as i32)
+        .expect("the product should exist")
+        .quantity
+}
@@ -1,7 +1,11 @@
 use dioxus::prelude::*;
+use sjf_api::i18n::Locale;
 
 #[component]
 pub fn About() -> Element {
+    if crate::i18n::use_locale() == Locale::En {
+        return rsx! { AboutEn {} };
+    }
     rsx! {
 
         document::Title { "SJF Concept - Om butiken" }
@@ -34,3 +38,38 @@ pub fn About() -> Element {
 
     }
 }
+
+#[component]
+fn AboutEn() -> Element {
+    rsx! {
+
+        document::Title { "SJF Concept - About the shop" }
+        div {
+            class: "about",
+            h2 {"ABOUT - SJF CONCEPT"}
+
+            p  { "Welcome to SJF Concept – where quality, precision and creativity meet." }
+
+            p { "We are a passionate company focused on high standards, attention to detail and professional
+            craftsmanship in everything we do. With us you will find a unique range of clothing, laser engraved
+            products, 3D printed items, decals and personalised design – always made with care and an eye for detail." }
+
+            p { "We believe every customer should get something out of the ordinary. Whether you are looking for a
+            personal gift, want to promote your business with custom designed products or simply appreciate good
+            looking, well thought out design – SJF Concept is the place for you." }
+
+            p { "Combining modern technology, creative thinking and craftsmanship, we make products that stand out
+            and last. Our goal is simple: to deliver that little extra every time." }
+
+            p { "Wishing for something special or have a question? Don't hesitate to get in touch: sjfconcept@hotmail.com"}
+
+            div {
+                img {
+                    src:  crate::components::footer::FOOTER_LOGO
+                },
+            }
+        }
+
+
+    }
+}
@@ -0,0 +1,227 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::account::{Account, Address, PastOrder};
+use thiserror::Error;
+
+use crate::components::{WishlistState, use_wishlist};
+use crate::server::auth::AuthApiError;
+
+#[derive(Error, Debug)]
+enum PasskeyError {
+    #[error("Server auth: {0}")]
+    ServerError(#[from] ServerFnError<AuthApiError>),
+    #[error("Javascript error")]
+    JavascriptError,
+}
+
+async fn login(email: String) -> Result<(), PasskeyError> {
+    use crate::server::auth::*;
+    let res = start_customer_authentication(email).await?;
+    let nav = web_sys::window().unwrap().navigator();
+    let options: web_sys::CredentialRequestOptions = res.into();
+    let promise = nav
+        .credentials()
+        .get_with_options(&options)
+        .map_err(|_| PasskeyError::JavascriptError)?;
+    let p: web_sys::PublicKeyCredential = wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map_err(|_| PasskeyError::JavascriptError)?
+        .into();
+    Ok(finish_authentication(p.into()).await?)
+}
+
+async fn register(email: String) -> Result<(), PasskeyError> {
+    use crate::server::auth::*;
+    let r = start_customer_registration(email.clone()).await?;
+    let nav = web_sys::window().unwrap().navigator();
+    let options: web_sys::CredentialCreationOptions = r.into();
+    let promise = nav
+        .credentials()
+        .create_with_options(&options)
+        .map_err(|_| PasskeyError::JavascriptError)?;
+    let p: web_sys::PublicKeyCredential = wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map_err(|_| PasskeyError::JavascriptError)?
+        .into();
+    finish_registration(p.into()).await?;
+    login(email).await
+}
+
+#[component]
+fn CustomerLogin(on_login: EventHandler<()>) -> Element {
+    #[derive(Clone, PartialEq)]
+    enum State {
+        Idle,
+        Pending,
+        Error(&'static str),
+    }
+
+    let mut email = use_signal(String::new);
+    let mut state = use_signal(|| State::Idle);
+    let messages = crate::i18n::use_messages();
+
+    let pending = *state.read() == State::Pending;
+    rsx! {
+        div {
+            class: "account-login",
+            p {
+                "{messages.account_intro}"
+            }
+            label { for: "account-email", "Email" }
+            div {
+                input {
+                    id: "account-email",
+                    r#type: "email",
+                    maxlength: "100",
+                    placeholder: messages.email_placeholder,
+                    oninput: move |e| email.set(e.value()),
+                }
+                button {
+                    disabled: pending,
+                    onclick: move |_| async move {
+                        state.set(State::Pending);
+                        match login(email()).await {
+                            Ok(()) => {
+                                state.set(State::Idle);
+                                on_login.call(());
+                            }
+                            Err(e) => {
+                                warn!("Customer login failed {}", e);
+                                state.set(State::Error(messages.login_failed));
+                            }
+                        }
+                    },
+                    "{messages.log_in}"
+                }
+                button {
+                    disabled: pending,
+                    onclick: move |_| async move {
+                        state.set(State::Pending);
+                        match register(email()).await {
+                            Ok(()) => {
+                                state.set(State::Idle);
+                                on_login.call(());
+                            }
+                            Err(e) => {
+                                warn!("Customer registration failed {}", e);
+                                state.set(State::Error(messages.registration_failed));
+                            }
+                        }
+                    },
+                    "{messages.create_account}"
+                }
+            }
+            match *state.read() {
+                State::Pending => rsx! { span { "{messages.waiting_for_passkey}" } },
+                State::Error(e) => rsx! { span { "{e}" } },
+                State::Idle => rsx! {},
+            }
+        }
+    }
+}
+
+#[component]
+fn OrderHistory(orders: Vec<PastOrder>) -> Element {
+    let messages = crate::i18n::use_messages();
+    rsx! {
+        h3 { "{messages.past_orders}" }
+        if orders.is_empty() {
+            p { "{messages.no_orders}" }
+        }
+        for order in orders {
+            div {
+                key: "{order.reference}",
+                class: "past-order",
+                div {
+                    span { "{order.created.format(\"%Y-%m-%d\")}" }
+                    span { "{order.currency.format(order.total)}" }
+                }
+                ul {
+                    for line in order.lines.iter() {
+                        li { "{line.quantity} {messages.pieces} {line.description} - {order.currency.format(line.total)}" }
+                    }
+                }
+                span { class: "order-reference", "{messages.reference} {order.reference}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn SavedAddress(address: Address) -> Element {
+    let postal = [address.postal_code.clone(), address.city.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    rsx! {
+        div {
+            class: "saved-address",
+            for line in [address.name, address.line1, address.line2].into_iter().flatten() {
+                span { "{line}" }
+            }
+            span { "{postal}" }
+        }
+    }
+}
+
+#[component]
+fn AccountOverview(account: Account, on_logout: EventHandler<()>) -> Element {
+    let messages = crate::i18n::use_messages();
+    rsx! {
+        p {
+            "{messages.logged_in_as} {account.email} "
+            button {
+                onclick: move |_| async move {
+                    if let Err(e) = crate::server::auth::logout().await {
+                        warn!("Logout failed {}", e);
+                    }
+                    on_logout.call(());
+                },
+                "{messages.log_out}"
+            }
+        }
+        OrderHistory { orders: account.orders }
+        h3 { "{messages.saved_addresses}" }
+        if account.addresses.is_empty() {
+            p { "{messages.no_addresses}" }
+        }
+        for address in account.addresses {
+            SavedAddress { address }
+        }
+    }
+}
+
+#[component]
+pub fn AccountPage() -> Element {
+    let mut account = use_resource(crate::server::get_account);
+    let mut wishlist = use_wishlist();
+    let messages = crate::i18n::use_messages();
+
+    rsx! {
+        div {
+            class: "about account",
+            h2 { "{messages.my_account}" }
+            match &*account.read_unchecked() {
+                Some(Ok(Some(a))) => rsx! {
+                    AccountOverview { account: a.clone(), on_logout: move |_| account.restart() }
+                },
+                Some(Ok(None)) => rsx! {
+                    CustomerLogin {
+                        on_login: move |_| {
+                            account.restart();
+                            // Bring the favourites saved in this browser into the account
+                            spawn(async move { wishlist.set(WishlistState::load().await) });
+                        }
+                    }
+                },
+                Some(Err(_)) => rsx! {
+                    p { "{messages.account_failed}" }
+                },
+                None => rsx! {
+                    p { "{messages.loading}" }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use dioxus::logger::tracing::{info, warn};
+use dioxus::logger::tracing::warn;
 use dioxus::prelude::*;
 
 use crate::server::auth::AuthApiError;
@@ -162,7 +162,7 @@ fn Login(user_name: ReadOnlySignal<String>) -> Element {
                         Ok(()) => {
                             state.set(State::LoggedIn);
                             let nav = navigator();
-                            nav.push( NavigationTarget::<crate::Route>::Internal(crate::Route::CategoryList {  }) );
+                            nav.push( NavigationTarget::<crate::Route>::Internal(crate::Route::AdminDashboard {  }) );
                         },
                         Err(e) => {
                             state.set(State::Error(e.to_string()));
@@ -1,15 +1,9 @@
-use std::collections::BTreeMap;
-use std::sync::Arc;
-use std::thread::current;
-
-use dioxus::html::details::open;
-use dioxus::html::FileEngine;
-use dioxus::logger::tracing::{info, warn};
+use dioxus::logger::tracing::warn;
 use dioxus::prelude::*;
 use dioxus::signals::Signal;
+use sjf_api::i18n::Locale;
 
-use crate::components::admin::category;
-use crate::server::{AuthenticatedRequest, Product};
+use crate::server::AuthenticatedRequest;
 use crate::{components, server};
 
 #[derive(PartialEq, Clone, Debug)]
@@ -30,7 +24,7 @@ fn CategoryRemovalButton(
         Removing,
         Error,
         Removed,
-    };
+    }
 
     let mut state = use_signal(|| State::Idle);
 
@@ -43,7 +37,7 @@ fn CategoryRemovalButton(
 
                    state.set(State::Removing);
                    let res = server::category::delete( AuthenticatedRequest { data: sjf_api::category::DeleteReq { id: category().id } }  ).await;
-                   if let  Ok(_) = res
+                   if res.is_ok()
                    {
                        state.set(State::Removed);
                        delete_fn.call(());
@@ -78,14 +72,14 @@ fn CategoryRemovalButton(
 
 #[component]
 fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>) -> Element {
-    let mut categories: Signal<Vec<Category>> = use_signal(|| Vec::new());
+    let mut categories: Signal<Vec<Category>> = use_signal(Vec::new);
     let mut name: Signal<String> = use_signal(move || category.read().name.clone());
     let mut editable: Signal<bool> = use_signal(|| false);
     let mut menu_open: Signal<bool> = use_signal(|| false);
     let mut expanded: Signal<bool> = use_signal(|| false);
 
     let _ = use_resource(move || async move {
-        match server::category::get_children(Some(category.read().id.clone())).await {
+        match server::category::get_children(Some(category.read().id), Locale::Sv).await {
             Ok(rsp) => {
                 let parent_depth = category.read().depth;
                 let parent_id = category.read().id;
@@ -95,7 +89,7 @@ fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>
                         id,
                         name,
                         depth: parent_depth + 1,
-                        parent: Some(parent_id.clone()),
+                        parent: Some(parent_id),
                     }));
             }
             Err(_) => warn!("Failed to load children!"),
@@ -120,7 +114,7 @@ fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>
                         onmounted: move |element| async move { let _ =  element.set_focus(true).await; },
                         onchange: move |evt|  async move {
                                 name.set(evt.value());
-                                server::category::update_name(AuthenticatedRequest { data: (category.read().id, name.read().clone() ) }).await;
+                                let _ = server::category::update_name(AuthenticatedRequest { data: (category.read().id, name.read().clone() ) }).await;
 
                         },
                         value: "{name.read()}"
@@ -145,7 +139,7 @@ fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>
                                     AuthenticatedRequest {
                                         data: server::category::CreateReq {
                                             name: name.clone(),
-                                            parent: parent.clone()
+                                            parent
                                         }
                                     }
                                 ).await;
@@ -186,6 +180,8 @@ fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>
                         }
                     }
                 }
+                CategoryTranslations { category: category.read().id }
+                GoogleCategory { category: category.read().id }
                 components::ProductList { category: category.read().id  }
             }
 
@@ -193,15 +189,78 @@ fn CategoryEntry(category: ReadOnlySignal<Category>, delete_fn: EventHandler<()>
     }
 }
 
+/// Names of the category on the storefronts in other languages, the Swedish
+/// name is used where there is none.
+#[component]
+fn CategoryTranslations(category: ReadOnlySignal<u32>) -> Element {
+    let mut translations = use_resource(move || async move {
+        server::category::translations(category())
+            .await
+            .unwrap_or_default()
+    });
+
+    rsx! {
+        for locale in Locale::ALL.into_iter().filter(|l| *l != Locale::Sv) {
+            label {
+                key: "{locale.code()}",
+                class: "category-translation",
+                "Namn ({locale.name()}) "
+                input {
+                    r#type: "text",
+                    value: translations().and_then(|t| t.get(&locale).cloned()).unwrap_or_default(),
+                    onchange: move |evt| async move {
+                        let req = AuthenticatedRequest { data: (category(), locale, evt.value()) };
+                        match server::category::set_translation(req).await {
+                            Ok(()) => translations.restart(),
+                            Err(e) => warn!("{:#?}", e),
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// The Google product category used in the product feeds, subcategories
+/// without one of their own inherit it.
+#[component]
+fn GoogleCategory(category: ReadOnlySignal<u32>) -> Element {
+    let mut google_category = use_resource(move || async move {
+        server::category::google_category(category())
+            .await
+            .ok()
+            .flatten()
+    });
+
+    rsx! {
+        label {
+            class: "google-category",
+            "Google produktkategori (id) "
+            input {
+                r#type: "number",
+                min: "1",
+                value: google_category().flatten().map(|c| c.to_string()).unwrap_or_default(),
+                onchange: move |evt| async move {
+                    let value = evt.value().trim().parse::<u32>().ok().filter(|c| *c > 0);
+                    match server::category::set_google_category(AuthenticatedRequest { data: (category(), value) }).await {
+                        Ok(()) => google_category.restart(),
+                        Err(e) => warn!("{:#?}", e),
+                    }
+                },
+            }
+        }
+    }
+}
+
 pub const ADMIN_CSS: Asset = asset!("/assets/styling/admin.scss");
 
 #[component]
 pub fn CategoryList() -> Element {
-    let mut categories: Signal<Vec<Category>> = use_signal(|| Vec::new());
+    let mut categories: Signal<Vec<Category>> = use_signal(Vec::new);
 
     let loaded_categories = use_resource(move || async move {
         match crate::server::auth::is_authenticated().await {
-            Ok(true) => match server::category::get_children(None).await {
+            Ok(true) => match server::category::get_children(None, Locale::Sv).await {
                 Ok(rsp) => {
                     categories
                         .write()
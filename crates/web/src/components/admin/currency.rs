@@ -0,0 +1,95 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::currency::Currency;
+
+use super::category::ADMIN_CSS;
+use crate::server::{self, AuthenticatedRequest};
+
+#[component]
+pub fn AdminCurrencies() -> Element {
+    let mut rates = use_resource(move || async move {
+        match server::auth::is_authenticated().await {
+            Ok(true) => server::currency::get_rates().await.map_err(|e| {
+                warn!("Failed to load currency rates {:#?}", e);
+            }),
+            Ok(false) | Err(_) => {
+                let nav = navigator();
+                nav.push(NavigationTarget::<crate::Route>::Internal(
+                    crate::Route::Auth {},
+                ));
+                Err(())
+            }
+        }
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "Valutor"
+            }
+            p {
+                "Priserna sätts i kronor och räknas om till kundens valuta med kurserna nedan. Lämna en kurs tom för att sluta erbjuda valutan."
+            }
+            p {
+                "Ordrar i norska kronor skickas till Norge och säljs utan moms som export. Ordrar i euro och danska kronor säljs med svensk moms tills butiken registrerats för OSS (VAT_OSS), därefter med momsen i landet de skickas till."
+            }
+            match &*rates.read_unchecked() {
+                Some(Ok(list)) => rsx! {
+                    table {
+                        tr {
+                            th {"Valuta"},
+                            th {"Kronor per enhet"},
+                        }
+                        for currency in Currency::ALL.into_iter().filter(|c| *c != Currency::Sek) {
+                            tr {
+                                key: "{currency.code()}",
+                                td { "{currency.code()}" },
+                                td {
+                                    input {
+                                        r#type: "number",
+                                        min: "0",
+                                        step: "0.0001",
+                                        value: list
+                                            .iter()
+                                            .find(|r| r.currency == currency)
+                                            .map(|r| r.sek_per_unit().to_string())
+                                            .unwrap_or_default(),
+                                        onchange: move |evt| async move {
+                                            let sek_micros = evt
+                                                .value()
+                                                .trim()
+                                                .replace(',', ".")
+                                                .parse::<f64>()
+                                                .ok()
+                                                .map(|v| (v * 1_000_000.0).round())
+                                                .filter(|v| *v >= 1.0 && *v <= i32::MAX as f64)
+                                                .map(|v| v as u32);
+                                            let req = AuthenticatedRequest { data: (currency, sek_micros) };
+                                            match server::currency::set_rate(req).await {
+                                                Ok(()) => rates.restart(),
+                                                Err(e) => warn!("{:#?}", e),
+                                            }
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte ladda valutor!" }
+                },
+                None => rsx! {
+                    h2 { "Laddar..." }
+                }
+            }
+            Link {
+                to: crate::Route::AdminDashboard {},
+                "Tillbaka"
+            }
+        }
+    }
+}
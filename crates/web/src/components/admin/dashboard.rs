@@ -0,0 +1,199 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::checkout::RecoveryStats;
+use sjf_api::stock::{StockAlert, StockLevel};
+
+use super::category::ADMIN_CSS;
+use crate::server;
+
+#[component]
+fn LowStockRow(alert: StockAlert) -> Element {
+    let status = match alert.level {
+        StockLevel::SoldOut => "Slutsåld",
+        StockLevel::Low => "Lågt lager",
+    };
+    let threshold = alert
+        .reorder_threshold
+        .map(|x| x.to_string() + "st")
+        .unwrap_or("-".into());
+
+    rsx! {
+        tr {
+            td { "{alert.name}" },
+            td { "{alert.quantity}st" },
+            td { "{threshold}" },
+            td { "{status}" }
+        }
+    }
+}
+
+/// Products listed as most wished on the dashboard.
+const MOST_WISHED: u32 = 10;
+
+/// Days of abandoned checkouts shown on the dashboard.
+const RECOVERY_DAYS: u32 = 30;
+
+fn percent(part: u32, whole: u32) -> String {
+    match whole {
+        0 => "-".into(),
+        _ => format!("{:.0}%", 100.0 * part as f64 / whole as f64),
+    }
+}
+
+#[component]
+fn RecoveryTable(stats: RecoveryStats) -> Element {
+    rsx! {
+        table {
+            tr {
+                th {"Övergivna"},
+                th {"Påminda"},
+                th {"Klickade"},
+                th {"Återvunna"},
+                th {"Konvertering"},
+                th {"Återvunnet värde"}
+            }
+            tr {
+                td { "{stats.abandoned}" },
+                td { "{stats.reminded}" },
+                td { "{stats.clicked} ({percent(stats.clicked, stats.reminded)})" },
+                td { "{stats.recovered}" },
+                td { "{percent(stats.recovered, stats.reminded)}" },
+                td { "{stats.recovered_value}kr" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AdminDashboard() -> Element {
+    let low_stock = use_resource(move || async move {
+        match server::auth::is_authenticated().await {
+            Ok(true) => server::get_low_stock().await.map_err(|e| {
+                warn!("Failed to load low stock {:#?}", e);
+            }),
+            Ok(false) | Err(_) => {
+                let nav = navigator();
+                nav.push(NavigationTarget::<crate::Route>::Internal(
+                    crate::Route::Auth {},
+                ));
+                Err(())
+            }
+        }
+    });
+
+    let most_wished = use_resource(move || async move {
+        server::get_most_wished(MOST_WISHED).await.map_err(|e| {
+            warn!("Failed to load most wished products {:#?}", e);
+        })
+    });
+
+    let recovery = use_resource(move || async move {
+        server::get_recovery_stats(RECOVERY_DAYS)
+            .await
+            .map_err(|e| {
+                warn!("Failed to load recovery stats {:#?}", e);
+            })
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "Lågt lager"
+            }
+            match &*low_stock.read_unchecked() {
+                Some(Ok(alerts)) if alerts.is_empty() => rsx! {
+                    p { "Alla produkter har tillräckligt i lager." }
+                },
+                Some(Ok(alerts)) => rsx! {
+                    table {
+                        tr {
+                            th {"Namn"},
+                            th {"Kvantitet"},
+                            th {"Beställningsgräns"},
+                            th {"Status"}
+                        }
+                        for alert in alerts.iter() {
+                            LowStockRow { key: "{alert.product_id}", alert: alert.clone() }
+                        }
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte ladda lagerstatus!" }
+                },
+                None => rsx! {
+                    h2 { "Laddar..." }
+                }
+            }
+            h2 {
+                "Mest önskade"
+            }
+            match &*most_wished.read_unchecked() {
+                Some(Ok(products)) if products.is_empty() => rsx! {
+                    p { "Inga kunder har sparat favoriter ännu." }
+                },
+                Some(Ok(products)) => rsx! {
+                    table {
+                        tr {
+                            th {"Namn"},
+                            th {"Kundkonton"}
+                        }
+                        for product in products.iter() {
+                            tr {
+                                key: "{product.product_id}",
+                                td { "{product.name}" },
+                                td { "{product.wishes}" }
+                            }
+                        }
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    p { "Kunde inte ladda favoriter!" }
+                },
+                None => rsx! {
+                    p { "Laddar..." }
+                }
+            }
+            h2 {
+                "Övergivna kassor de senaste {RECOVERY_DAYS} dagarna"
+            }
+            match &*recovery.read_unchecked() {
+                Some(Ok(stats)) => rsx! {
+                    RecoveryTable { stats: stats.clone() }
+                },
+                Some(Err(())) => rsx! {
+                    p { "Kunde inte ladda statistik för övergivna kassor!" }
+                },
+                None => rsx! {
+                    p { "Laddar..." }
+                }
+            }
+            Link {
+                to: crate::Route::CategoryList {},
+                "Produkt katalog"
+            }
+            Link {
+                to: crate::Route::AdminProductImport {},
+                "Importera produkter"
+            }
+            Link {
+                to: crate::Route::AdminOrders {},
+                "Ordrar"
+            }
+            Link {
+                to: crate::Route::AdminReviews {},
+                "Recensioner"
+            }
+            Link {
+                to: crate::Route::AdminCurrencies {},
+                "Valutor"
+            }
+            Link {
+                to: crate::Route::AdminOssReport {},
+                "OSS-rapport"
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use dioxus::html::FileEngine;
+use dioxus::logger::tracing::{info, warn};
+use dioxus::prelude::*;
+use sjf_api::import::{
+    COLUMNS, ImportAction, ImportChange, ImportFile, ImportReport, ImportRequest,
+};
+
+use super::category::ADMIN_CSS;
+use crate::server::{self, AuthenticatedRequest};
+
+#[component]
+fn ChangeRow(change: ImportChange) -> Element {
+    let action = match change.action {
+        ImportAction::Create => "Ny",
+        ImportAction::Update(_) => "Ändras",
+        ImportAction::Unchanged(_) => "Oförändrad",
+    };
+    let changes = change.changes.join(", ");
+    rsx! {
+        tr {
+            td { "{change.line}" },
+            td { "{change.sku.clone().unwrap_or_default()}" },
+            td { "{change.name}" },
+            td { "{action}" },
+            td { "{changes}" }
+        }
+    }
+}
+
+#[component]
+fn Report(report: ImportReport) -> Element {
+    let count =
+        |f: fn(&ImportAction) -> bool| report.changes.iter().filter(|c| f(&c.action)).count();
+    let created = count(|a| matches!(a, ImportAction::Create));
+    let updated = count(|a| matches!(a, ImportAction::Update(_)));
+    let unchanged = count(|a| matches!(a, ImportAction::Unchanged(_)));
+
+    rsx! {
+        if report.applied {
+            h3 { "Importen är klar" }
+        }
+        p { "{created} nya, {updated} ändrade och {unchanged} oförändrade produkter." }
+        if !report.errors.is_empty() {
+            h3 { "Fel" }
+            p { "Inget importeras förrän felen är rättade." }
+            table {
+                tr {
+                    th {"Rad"},
+                    th {"Fel"}
+                }
+                for error in report.errors.iter() {
+                    tr {
+                        key: "{error.line}-{error.message}",
+                        td { "{error.line}" },
+                        td { "{error.message}" }
+                    }
+                }
+            }
+        }
+        table {
+            tr {
+                th {"Rad"},
+                th {"SKU"},
+                th {"Namn"},
+                th {"Åtgärd"},
+                th {"Ändringar"}
+            }
+            for change in report.changes.iter() {
+                ChangeRow {
+                    key: "{change.line}",
+                    change: change.clone()
+                }
+            }
+        }
+    }
+}
+
+/// Bulk import of products from CSV or Excel, previewed before anything is stored.
+#[component]
+pub fn AdminProductImport() -> Element {
+    let mut file = use_signal(|| None::<ImportFile>);
+    let mut images = use_signal(BTreeMap::<String, Vec<u8>>::new);
+    let mut report = use_signal(|| None::<Result<ImportReport, ()>>);
+    let mut busy = use_signal(|| false);
+
+    let _auth = use_resource(move || async move {
+        if !matches!(server::auth::is_authenticated().await, Ok(true)) {
+            let nav = navigator();
+            nav.push(NavigationTarget::<crate::Route>::Internal(
+                crate::Route::Auth {},
+            ));
+        }
+    });
+
+    let read_files = move |file_engine: Arc<dyn FileEngine>| async move {
+        images.write().clear();
+        file.set(None);
+        report.set(None);
+        for file_name in file_engine.files() {
+            let name = file_name
+                .rsplit(['/', '\\'])
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+            let lowercase = name.to_lowercase();
+            if lowercase.ends_with(".csv") {
+                let text = file_engine.read_file_to_string(&file_name).await;
+                file.set(text.map(ImportFile::Csv));
+            } else if lowercase.ends_with(".xlsx") {
+                let bytes = file_engine.read_file(&file_name).await;
+                file.set(bytes.map(ImportFile::Xlsx));
+            } else if let Some(contents) = file_engine.read_file(&file_name).await {
+                info!("Read {} {}", name, contents.len());
+                images.write().insert(name, contents);
+            }
+        }
+    };
+
+    let run = move |apply: bool| async move {
+        let Some(import) = file() else {
+            return;
+        };
+        let files = match apply {
+            true => images(),
+            false => images.read().keys().map(|k| (k.clone(), vec![])).collect(),
+        };
+        busy.set(true);
+        let req = AuthenticatedRequest {
+            data: ImportRequest {
+                file: import,
+                images: files,
+                apply,
+            },
+        };
+        let res = server::import_products(req).await.map_err(|e| {
+            warn!("Failed to import products {:#?}", e);
+        });
+        busy.set(false);
+        report.set(Some(res));
+    };
+
+    let columns = COLUMNS.join(", ");
+    let previewed = matches!(&*report.read(), Some(Ok(r)) if r.errors.is_empty() && !r.applied);
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "Importera produkter"
+            }
+            p {
+                "Välj en xlsx- eller CSV-fil med kolumnerna {columns} och de bilder den nämner. Från en xlsx-fil läses det första bladet, CSV-filer sparas som CSV (UTF-8)."
+            }
+            p {
+                "Kategorin skrivs som en sökväg, Kläder/Tröjor. Bilder skiljs åt med | och kan vara filnamn, webbadresser eller /images/<id> för bilder som redan finns. Produkter med samma SKU, eller artikelnummer som artikel-12, uppdateras och övriga skapas."
+            }
+            input {
+                r#type: "file",
+                accept: ".csv,.xlsx,image/*",
+                multiple: true,
+                disabled: busy(),
+                onchange: move |evt| async move {
+                    if let Some(file_engine) = evt.files() {
+                        read_files(file_engine).await;
+                    }
+                }
+            }
+            div {
+                class: "flex-start-container button-row",
+                button {
+                    disabled: busy() || file.read().is_none(),
+                    onclick: move |_| run(false),
+                    "Förhandsgranska"
+                }
+                button {
+                    disabled: busy() || !previewed,
+                    onclick: move |_| run(true),
+                    "Importera"
+                }
+            }
+            match &*report.read() {
+                Some(Ok(r)) => rsx! {
+                    Report { report: r.clone() }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte importera produkterna!" }
+                },
+                None => rsx! {}
+            }
+            a {
+                href: "/api/auth/products/export.csv",
+                download: "produkter.csv",
+                "Exportera alla produkter"
+            }
+            Link {
+                to: crate::Route::AdminDashboard {},
+                "Tillbaka"
+            }
+        }
+    }
+}
@@ -1,3 +1,9 @@
 pub mod auth;
 pub mod category;
+pub mod currency;
+pub mod dashboard;
+pub mod import;
+pub mod orders;
 pub mod product;
+pub mod reviews;
+pub mod tax;
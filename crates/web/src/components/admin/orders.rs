@@ -0,0 +1,125 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::currency::Currency;
+use sjf_api::order::Order;
+
+use super::category::ADMIN_CSS;
+use crate::server::{self, AuthenticatedRequest};
+
+/// Orders listed on the order page.
+const RECENT_ORDERS: u32 = 100;
+
+#[component]
+fn OrderRow(order: Order, on_delivered: EventHandler<()>) -> Element {
+    let lines = order
+        .lines
+        .iter()
+        .map(|l| format!("{} st {}", l.quantity, l.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let total: u32 = order.lines.iter().map(|l| l.quantity * l.price).sum();
+    let rate = order.exchange_rate;
+    let paid = match rate.currency {
+        Currency::Sek => String::new(),
+        currency => format!(
+            " ({})",
+            currency.format(rate.total(order.lines.iter().map(|l| (l.price, l.quantity))))
+        ),
+    };
+    let email = order.email.clone().unwrap_or("-".into());
+    let created = order.created.format("%Y-%m-%d %H:%M").to_string();
+    let id = order.id.clone();
+
+    rsx! {
+        tr {
+            td { "{created}" },
+            td { "{email}" },
+            td { "{lines}" },
+            td { "{total}kr{paid}" },
+            td {
+                match order.delivered {
+                    Some(delivered) => rsx! { "{delivered.format(\"%Y-%m-%d\")}" },
+                    None => rsx! {
+                        button {
+                            onclick: move |_| {
+                                let id = id.clone();
+                                async move {
+                                    match server::mark_delivered(AuthenticatedRequest { data: id }).await {
+                                        Ok(_) => on_delivered.call(()),
+                                        Err(e) => warn!("Failed to mark order delivered {:#?}", e),
+                                    }
+                                }
+                            },
+                            "Markera som levererad"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AdminOrders() -> Element {
+    let mut orders = use_resource(move || async move {
+        match server::auth::is_authenticated().await {
+            Ok(true) => server::get_recent_orders(RECENT_ORDERS).await.map_err(|e| {
+                warn!("Failed to load orders {:#?}", e);
+            }),
+            Ok(false) | Err(_) => {
+                let nav = navigator();
+                nav.push(NavigationTarget::<crate::Route>::Internal(
+                    crate::Route::Auth {},
+                ));
+                Err(())
+            }
+        }
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "Ordrar"
+            }
+            p {
+                "Kunden får en länk för att recensera produkterna en vecka efter att ordern markerats som levererad."
+            }
+            match &*orders.read_unchecked() {
+                Some(Ok(list)) if list.is_empty() => rsx! {
+                    p { "Inga ordrar ännu." }
+                },
+                Some(Ok(list)) => rsx! {
+                    table {
+                        tr {
+                            th {"Datum"},
+                            th {"Email"},
+                            th {"Produkter"},
+                            th {"Summa"},
+                            th {"Levererad"}
+                        }
+                        for order in list.iter() {
+                            OrderRow {
+                                key: "{order.id}",
+                                order: order.clone(),
+                                on_delivered: move |_| orders.restart()
+                            }
+                        }
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte ladda ordrar!" }
+                },
+                None => rsx! {
+                    h2 { "Laddar..." }
+                }
+            }
+            Link {
+                to: crate::Route::AdminDashboard {},
+                "Tillbaka"
+            }
+        }
+    }
+}
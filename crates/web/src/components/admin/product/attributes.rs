@@ -0,0 +1,187 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::attribute::{AttributeDefinition, AttributeKind};
+
+use crate::server::{self, AuthenticatedRequest, Product};
+
+fn kind_name(kind: &AttributeKind) -> String {
+    match kind {
+        AttributeKind::Text => "Text".into(),
+        AttributeKind::Number { unit } if unit.is_empty() => "Tal".into(),
+        AttributeKind::Number { unit } => format!("Tal ({unit})"),
+        AttributeKind::Enum { options } => format!("Val: {}", options.join(", ")),
+    }
+}
+
+/// Attribute definitions of a category, new ones are stored right away and
+/// apply to the subcategories as well.
+#[component]
+pub fn CategoryAttributes(category: ReadOnlySignal<u32>) -> Element {
+    let mut name = use_signal(String::new);
+    let mut kind = use_signal(|| String::from("text"));
+    let mut extra = use_signal(String::new);
+    let mut update_counter = use_signal(|| 0u32);
+
+    let definitions = use_resource(move || async move {
+        update_counter.read();
+        server::get_attribute_definitions(category())
+            .await
+            .map_err(|e| {
+                warn!("Failed to load attributes {:#?}", e);
+            })
+    });
+
+    rsx! {
+        div {
+            class: "attributes",
+            h3 { "Egenskaper" }
+            if let Some(Ok(definitions)) = &*definitions.read() {
+                table {
+                    for definition in definitions.iter().cloned() {
+                        tr {
+                            key: "{definition.id}",
+                            td { "{definition.name}" }
+                            td { "{kind_name(&definition.kind)}" }
+                            td {
+                                if definition.category == category() {
+                                    button {
+                                        onclick: move |_| async move {
+                                            match server::delete_attribute(AuthenticatedRequest { data: definition.id }).await {
+                                                Ok(()) => update_counter += 1,
+                                                Err(e) => warn!("Failed to delete attribute {:#?}", e),
+                                            }
+                                        },
+                                        "Ta bort"
+                                    }
+                                } else {
+                                    "Ärvd"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            form {
+                onsubmit: move |evt| async move {
+                    evt.prevent_default();
+                    let kind = match kind().as_str() {
+                        "number" => AttributeKind::Number { unit: extra() },
+                        "enum" => AttributeKind::Enum {
+                            options: extra().split(',').map(|o| o.trim().to_owned()).collect(),
+                        },
+                        _ => AttributeKind::Text,
+                    };
+                    let definition = AttributeDefinition {
+                        id: 0,
+                        category: category(),
+                        name: name(),
+                        kind,
+                    };
+                    match server::create_attribute(AuthenticatedRequest { data: definition }).await {
+                        Ok(_) => {
+                            name.set(String::new());
+                            extra.set(String::new());
+                            update_counter += 1;
+                        }
+                        Err(e) => warn!("Failed to create attribute {:#?}", e),
+                    }
+                },
+                input {
+                    placeholder: "Namn",
+                    required: true,
+                    value: "{name}",
+                    oninput: move |e| name.set(e.value()),
+                }
+                select {
+                    value: "{kind}",
+                    onchange: move |e| kind.set(e.value()),
+                    option { value: "text", "Text" }
+                    option { value: "number", "Tal" }
+                    option { value: "enum", "Val" }
+                }
+                if kind() == "number" {
+                    input {
+                        placeholder: "Enhet, t.ex. cm",
+                        value: "{extra}",
+                        oninput: move |e| extra.set(e.value()),
+                    }
+                }
+                if kind() == "enum" {
+                    input {
+                        placeholder: "Alternativ, separerade med komma",
+                        required: true,
+                        value: "{extra}",
+                        oninput: move |e| extra.set(e.value()),
+                    }
+                }
+                button { r#type: "submit", "Lägg till egenskap" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ProductAttributes(product: Signal<Product>) -> Element {
+    let category = use_memo(move || product.read().category);
+    let definitions = use_resource(move || async move {
+        server::get_attribute_definitions(category())
+            .await
+            .unwrap_or_default()
+    });
+
+    let mut set_value = move |id: u32, value: String| {
+        let mut product = product.write();
+        if value.trim().is_empty() {
+            product.attributes.remove(&id);
+        } else {
+            product.attributes.insert(id, value);
+        }
+    };
+
+    rsx! {
+        for definition in definitions().unwrap_or_default() {
+            div {
+                key: "{definition.id}",
+                class: "inputsection",
+                label {
+                    for: "attribute{definition.id}",
+                    "{definition.name}"
+                }
+                match definition.kind {
+                    AttributeKind::Enum { options } => rsx! {
+                        select {
+                            id: "attribute{definition.id}",
+                            onchange: move |e| set_value(definition.id, e.value()),
+                            option { value: "", "-" }
+                            for option in options {
+                                option {
+                                    selected: product.read().attributes.get(&definition.id) == Some(&option),
+                                    value: "{option}",
+                                    "{option}"
+                                }
+                            }
+                        }
+                    },
+                    AttributeKind::Number { unit } => rsx! {
+                        input {
+                            id: "attribute{definition.id}",
+                            r#type: "text",
+                            inputmode: "decimal",
+                            value: product.read().attributes.get(&definition.id).cloned().unwrap_or_default(),
+                            oninput: move |e| set_value(definition.id, e.value()),
+                        }
+                        span { "{unit}" }
+                    },
+                    AttributeKind::Text => rsx! {
+                        input {
+                            id: "attribute{definition.id}",
+                            r#type: "text",
+                            value: product.read().attributes.get(&definition.id).cloned().unwrap_or_default(),
+                            oninput: move |e| set_value(definition.id, e.value()),
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
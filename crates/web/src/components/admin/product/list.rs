@@ -1,6 +1,7 @@
 use dioxus::logger::tracing::{info, warn};
 use dioxus::prelude::*;
 
+use crate::components::admin::product::attributes::CategoryAttributes;
 use crate::components::admin::product::product::ProductDetails;
 
 use crate::server::{self, Product};
@@ -14,7 +15,6 @@ pub struct ProductRowProps {
 #[component]
 pub fn ProductRow(props: ProductRowProps) -> Element {
     let product = &*props.product.read();
-    let id = product.id.map(|x| x.to_string()).unwrap_or("".into());
     let price = product.price.to_string();
     let quantity = product
         .quantity
@@ -44,7 +44,7 @@ pub struct ProductTableProps {
 
 #[component]
 pub fn ProductTable(props: ProductTableProps) -> Element {
-    let products = props.products.clone();
+    let products = props.products;
     rsx! {
         table {
             tr {
@@ -69,23 +69,20 @@ pub fn ProductTable(props: ProductTableProps) -> Element {
 #[component]
 pub fn ProductList(category: ReadOnlySignal<u32>) -> Element {
     let mut selected_product: Signal<Option<Product>> = use_signal(|| None);
-    let products: Signal<Vec<Product>> = use_signal(|| vec![]);
     let update_counter: Signal<u32> = use_signal(|| 0);
 
     let products = use_resource(move || async move {
         info!("list update {}", update_counter.read());
-        server::get_products(category.read().clone()).await
+        server::get_products(*category.read()).await
     });
 
-    let mut inspector = use_signal(|| false);
-
     rsx! {
 
         div {
             class: "product_list",
         match &*products.read_unchecked() {
             Some(Ok(products)) => rsx! {
-                if products.len() > 0
+                if !products.is_empty()
                 {
                     ProductTable{
                         onedit: move |product| {
@@ -111,12 +108,13 @@ pub fn ProductList(category: ReadOnlySignal<u32>) -> Element {
 
         button {
             onclick: move |_| {
-                selected_product.set(Some(Product::new(category.read().clone())) );
+                selected_product.set(Some(Product::new(*category.read())) );
 
             },
             "Lägg till produkt"
         }
         ProductDetails {product: selected_product , update_counter,category }
+        CategoryAttributes { category }
 
     }}
 }
@@ -1,4 +1,5 @@
-use dioxus::prelude::*;
+pub mod attributes;
 
 pub mod list;
+#[allow(clippy::module_inception)]
 pub mod product;
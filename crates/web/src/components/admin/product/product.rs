@@ -1,20 +1,18 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::sync::Arc;
 use std::vec;
 
-use dioxus::html::details::open;
-use dioxus::html::{image, FileEngine};
 use dioxus::logger::tracing::{info, warn};
 use dioxus::prelude::*;
 use dioxus::signals::Signal;
 
+use chrono::NaiveDate;
+use sjf_api::i18n::Locale;
+use sjf_api::stock::StockPolicy;
+
 use crate::components::ImageUploadButton;
-use crate::server::category::Delete;
 use crate::server::{AuthenticatedRequest, Product};
 use crate::{components, server};
 
-use super::list::ProductList;
-
 #[component]
 pub fn SaveButton(
     product: Signal<Product>,
@@ -29,7 +27,7 @@ pub fn SaveButton(
     }
 
     let mut saving_state = use_signal(|| State::Idle);
-    let is_unedited = use_memo(move || *product.read() == Product::new(category.read().clone()));
+    let is_unedited = use_memo(move || *product.read() == Product::new(*category.read()));
 
     let changed_since_saved = use_memo(move || {
         let current_product = &*product.read();
@@ -165,7 +163,7 @@ pub fn DeleteButton(
 
                         _ => {
 
-                            let id = product.read().id.clone();
+                            let id = product.read().id;
                             if let Some(id) = id
                             {
                                 let id = id as u32;
@@ -179,7 +177,7 @@ pub fn DeleteButton(
                                 match rsp {
                                     Ok(()) => {
                                         delete_state.set(State::Deleted(id) );
-                                        product.set(Product::new(category.read().clone()));
+                                        product.set(Product::new(*category.read()));
                                         update_counter.with_mut(|c| {(*c)+=1;} );
                                     },
                                     Err(e) => {
@@ -228,6 +226,27 @@ fn ProductName(product: Signal<Product>) -> Element {
     }
 }
 #[component]
+fn ProductSku(product: Signal<Product>) -> Element {
+    rsx! {
+        div {
+            class: "inputsection",
+            label {
+                "SKU"
+            }
+            input {
+                type: "text",
+                maxlength: 64,
+                placeholder: "Ingen",
+                value: product.read().sku.clone().unwrap_or_default(),
+                oninput: move |evt| {
+                    let sku = evt.value().trim().to_string();
+                    product.write().sku = if sku.is_empty() { None } else { Some(sku) };
+                }
+            },
+        }
+    }
+}
+#[component]
 fn ProductInventory(product: Signal<Product>) -> Element {
     rsx! {
                 div {
@@ -239,12 +258,7 @@ fn ProductInventory(product: Signal<Product>) -> Element {
                         disabled: if product.read().quantity.is_none() {true},
                         value: product.read().quantity.map(|x| x.to_string() ).unwrap_or(String::from("")),
                         oninput: move |evt|{
-                            product.write().quantity = Some(
-                            match evt.value().parse::<u16>()
-                            {
-                                Ok(quantity) => quantity,
-                                Err(e) => 0
-                            });
+                            product.write().quantity = Some(evt.value().parse::<i32>().unwrap_or_default().max(0));
 
                         },
                         type: "text"
@@ -262,7 +276,8 @@ fn ProductInventory(product: Signal<Product>) -> Element {
                             }
                             else {
                                 product.with_mut( |product| {
-                                    product.quantity = None
+                                    product.quantity = None;
+                                    product.reorder_threshold = None;
                                 })
                             }
 
@@ -270,6 +285,21 @@ fn ProductInventory(product: Signal<Product>) -> Element {
 
                     },
                 },
+                div {
+                    class: "inputsection",
+                    label {
+                        "Beställningsgräns"
+                    },
+                    input {
+                        disabled: if product.read().quantity.is_none() {true},
+                        placeholder: "Ingen",
+                        value: product.read().reorder_threshold.map(|x| x.to_string() ).unwrap_or(String::from("")),
+                        oninput: move |evt|{
+                            product.write().reorder_threshold = evt.value().parse::<u16>().ok();
+                        },
+                        type: "text"
+                    },
+                },
     }
 }
 
@@ -287,11 +317,7 @@ fn ProductPrice(product: Signal<Product>) -> Element {
                 oninput: move |evt| {
                     product.write().price = {
                         let value = evt.value();
-                        match value.parse::<u16>()
-                        {
-                            Ok(price) => price,
-                            Err(e) => 0
-                        }
+                        value.parse::<u16>().unwrap_or_default()
                     };
 
 
@@ -317,6 +343,114 @@ fn ProductDescription(product: Signal<Product>) -> Element {
     }
 }
 
+/// Name and description on the storefronts in other languages, products
+/// without a translated name are shown in Swedish.
+#[component]
+fn ProductTranslations(product: Signal<Product>) -> Element {
+    rsx! {
+        for locale in Locale::ALL.into_iter().filter(|l| *l != Locale::Sv) {
+            div {
+                key: "{locale.code()}",
+                div {
+                    class: "inputsection",
+                    label {
+                        "Produkt namn ({locale.name()})"
+                    }
+                    input {
+                        type: "text",
+                        maxlength: "100",
+                        value: product.read().translations.get(&locale).map(|t| t.name.clone()).unwrap_or_default(),
+                        oninput: move |evt| {
+                            product.write().translations.entry(locale).or_default().name = evt.value().chars().take(100).collect();
+                        }
+                    },
+                }
+                label {
+                    "Beskrivning ({locale.name()})"
+                }
+                textarea {
+                    value: product.read().translations.get(&locale).map(|t| t.description.clone()).unwrap_or_default(),
+                    oninput: move |evt| {
+                        product.write().translations.entry(locale).or_default().description = evt.value()
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProductStockPolicy(product: Signal<Product>) -> Element {
+    // Pre-orders need a ship date, stay in the pre-order state until one is given
+    let mut pending_preorder = use_signal(|| false);
+    let product_id = use_memo(move || product.read().id);
+    use_effect(move || {
+        product_id.read();
+        pending_preorder.set(false);
+    });
+
+    let policy = product.read().stock_policy.clone();
+    let kind = match policy {
+        StockPolicy::Stop if pending_preorder() => "preorder",
+        _ => policy.as_str(),
+    };
+    let limit = match policy {
+        StockPolicy::Backorder { limit } => limit.to_string(),
+        _ => String::new(),
+    };
+    let ship_date = match policy {
+        StockPolicy::Preorder { ship_date } => ship_date.to_string(),
+        _ => String::new(),
+    };
+
+    rsx! {
+        div {
+            class: "inputsection",
+            label {
+                for: "productstockpolicy",
+                "Vid slut i lager"
+            }
+            select {
+                id: "productstockpolicy",
+                value: "{kind}",
+                onchange: move |e| {
+                    pending_preorder.set(e.value() == "preorder");
+                    product.write().stock_policy = match e.value().as_str() {
+                        "backorder" => StockPolicy::Backorder { limit: 0 },
+                        _ => StockPolicy::Stop,
+                    };
+                },
+                option { value: "stop", "Stoppa försäljningen"}
+                option { value: "backorder", "Tillåt restorder"}
+                option { value: "preorder", "Förbeställning"}
+            }
+            if kind == "backorder" {
+                input {
+                    type: "text",
+                    placeholder: "Max antal restnoterade",
+                    value: "{limit}",
+                    oninput: move |evt| {
+                        product.write().stock_policy = StockPolicy::Backorder {
+                            limit: evt.value().parse().unwrap_or_default(),
+                        };
+                    }
+                }
+            }
+            if kind == "preorder" {
+                input {
+                    type: "date",
+                    value: "{ship_date}",
+                    oninput: move |evt| {
+                        if let Ok(ship_date) = evt.value().parse::<NaiveDate>() {
+                            product.write().stock_policy = StockPolicy::Preorder { ship_date };
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn ProductTax(product: Signal<Product>) -> Element {
     rsx! {
@@ -348,7 +482,7 @@ fn ProductImage(
 ) -> Element {
     rsx! {
         div {
-            onclick: { let image_id = image_id.clone(); move |_| {
+            onclick: { let image_id = image_id; move |_| {
                 product.write().images.as_mut().unwrap().retain(|x| *x !=image_id );
                 thumbnails.write().remove(&image_id);
             }},
@@ -367,22 +501,21 @@ fn ProductImage(
 
 #[component]
 fn ProductImages(product: Signal<Product>) -> Element {
-    let mut thumbnails: Signal<BTreeMap<u32, u32>> = use_signal(move || BTreeMap::new());
+    let mut thumbnails: Signal<BTreeMap<u32, u32>> = use_signal(BTreeMap::new);
 
     let product_id = use_memo(move || product.read().id);
-    let resource = use_resource(move || async move {
-        if let Some(id) = product_id.read().clone() {
-            if let Ok(v) =
+    let _ = use_resource(move || async move {
+        if let Some(id) = *product_id.read()
+            && let Ok(v) =
                 server::get_product_images(AuthenticatedRequest { data: (id as u32) }).await
-            {
-                thumbnails.with_mut(|t| {
-                    v.into_iter()
-                        .filter(|(_, v)| !v.is_empty())
-                        .for_each(|(k, mut v)| {
-                            t.insert(k, v.remove(0));
-                        });
-                });
-            }
+        {
+            thumbnails.with_mut(|t| {
+                v.into_iter()
+                    .filter(|(_, v)| !v.is_empty())
+                    .for_each(|(k, mut v)| {
+                        t.insert(k, v.remove(0));
+                    });
+            });
         }
     });
 
@@ -393,11 +526,11 @@ fn ProductImages(product: Signal<Product>) -> Element {
         let lh = &*thumbnail_ids.read();
         let product = product.read();
         let rh = product.images.as_ref().unwrap_or(&empty);
-        let res = lh != rh;
-        res
+
+        lh != rh
     });
     use_effect(move || {
-        if (thumbnail_ids.read().is_empty()) {
+        if thumbnail_ids.read().is_empty() {
             return;
         }
 
@@ -429,6 +562,90 @@ fn ProductImages(product: Signal<Product>) -> Element {
     }
 }
 
+/// Curated related products, stored as soon as they change since they link
+/// to other products rather than being part of this one.
+#[component]
+fn RelatedProducts(product: Signal<Product>) -> Element {
+    let mut related: Signal<Vec<(u32, String)>> = use_signal(Vec::new);
+    let product_id = use_memo(move || product.read().id.map(|id| id as u32));
+
+    let _ = use_resource(move || async move {
+        let Some(id) = product_id() else {
+            related.set(Vec::new());
+            return;
+        };
+        match server::get_curated_related(id).await {
+            Ok(r) => related.set(r),
+            Err(e) => warn!("Failed to load related products {:#?}", e),
+        }
+    });
+    let names =
+        use_resource(move || async move { server::get_product_names().await.unwrap_or_default() });
+
+    let store = move || async move {
+        let Some(id) = product_id() else {
+            return;
+        };
+        let ids = related.read().iter().map(|(id, _)| *id).collect();
+        if let Err(e) =
+            server::store_curated_related(AuthenticatedRequest { data: (id, ids) }).await
+        {
+            warn!("Failed to store related products {:#?}", e);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "inputsection related",
+            label {
+                for: "productrelated",
+                "Relaterade produkter"
+            }
+            if product_id().is_none() {
+                span { "Spara produkten först" }
+            } else {
+                for (id, name) in related.read().clone() {
+                    div {
+                        key: "{id}",
+                        span { "{name}" }
+                        button {
+                            onclick: move |_| async move {
+                                related.write().retain(|(r, _)| *r != id);
+                                store().await;
+                            },
+                            "Ta bort"
+                        }
+                    }
+                }
+                select {
+                    id: "productrelated",
+                    onchange: move |e| async move {
+                        let Ok(id) = e.value().parse::<u32>() else {
+                            return;
+                        };
+                        let name = names
+                            .read()
+                            .iter()
+                            .flatten()
+                            .find(|(n, _)| *n == id)
+                            .map(|(_, name)| name.clone());
+                        if let Some(name) = name {
+                            related.write().push((id, name));
+                            store().await;
+                        }
+                    },
+                    option { value: "", selected: true, "Lägg till..." }
+                    for (id, name) in names.read().iter().flatten().filter(|(id, _)| {
+                        Some(*id) != product_id() && !related.read().iter().any(|(r, _)| r == id)
+                    }) {
+                        option { key: "{id}", value: "{id}", "{name}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Props)]
 struct FormFieldProps {
     product: Product,
@@ -442,20 +659,25 @@ fn FormFields(props: FormFieldProps) -> Element {
 
     rsx! {
             ProductName {product}
+            ProductSku {product}
             div {
                 ProductPrice {product},
                 ProductTax {product  },
                 ProductInventory {product}
+                ProductStockPolicy {product}
             },
             ProductImages{product},
             ProductDescription {product}
+            ProductTranslations {product}
+            super::attributes::ProductAttributes {product}
+            RelatedProducts {product}
             div {
                 class: "flex-start-container button-row",
                 DeleteButton  {  product: product, update_counter: props.update_counter, category: props.category }
                 button {
                     disabled: product.read().id.is_none(),
                     onclick: move |_| {
-                        product.set(Product::new(props.category.read().clone()))
+                        product.set(Product::new(*props.category.read()))
 
                     },
                     "Ny produkt"
@@ -0,0 +1,146 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::review::{ModeratedReview, ReviewStatus};
+
+use super::category::ADMIN_CSS;
+use crate::server::{self, AuthenticatedRequest};
+
+/// Days of already moderated reviews kept in the list.
+const MODERATED_DAYS: u32 = 30;
+
+#[component]
+fn ModerationRow(review: ModeratedReview, on_change: EventHandler<()>) -> Element {
+    let mut reply = use_signal(|| review.review.reply.clone().unwrap_or_default());
+    let id = review.review.id.clone();
+    let created = review.review.created.format("%Y-%m-%d").to_string();
+    let status = match review.status {
+        ReviewStatus::Pending => "Väntar",
+        ReviewStatus::Approved => "Godkänd",
+        ReviewStatus::Rejected => "Avvisad",
+    };
+
+    let moderate = move |id: String, status: ReviewStatus| async move {
+        match server::moderate_review(AuthenticatedRequest { data: (id, status) }).await {
+            Ok(()) => on_change.call(()),
+            Err(e) => warn!("Failed to moderate review {:#?}", e),
+        }
+    };
+
+    rsx! {
+        tr {
+            td { "{created}" },
+            td { "{review.product_name}" },
+            td { "{review.review.rating}/5" },
+            td {
+                b { "{review.review.author}" }
+                p { "{review.review.text}" }
+                textarea {
+                    placeholder: "Svar",
+                    value: "{reply}",
+                    oninput: move |evt| reply.set(evt.value()),
+                }
+                button {
+                    onclick: {
+                        let id = id.clone();
+                        move |_| {
+                            let id = id.clone();
+                            async move {
+                                match server::reply_review(AuthenticatedRequest { data: (id, reply()) }).await {
+                                    Ok(()) => on_change.call(()),
+                                    Err(e) => warn!("Failed to reply to review {:#?}", e),
+                                }
+                            }
+                        }
+                    },
+                    "Spara svar"
+                }
+            },
+            td { "{status}" },
+            td {
+                if review.status != ReviewStatus::Approved {
+                    button {
+                        onclick: {
+                            let id = id.clone();
+                            move |_| moderate(id.clone(), ReviewStatus::Approved)
+                        },
+                        "Godkänn"
+                    }
+                }
+                if review.status != ReviewStatus::Rejected {
+                    button {
+                        onclick: {
+                            let id = id.clone();
+                            move |_| moderate(id.clone(), ReviewStatus::Rejected)
+                        },
+                        "Avvisa"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AdminReviews() -> Element {
+    let mut reviews = use_resource(move || async move {
+        match server::auth::is_authenticated().await {
+            Ok(true) => server::get_review_moderation(MODERATED_DAYS)
+                .await
+                .map_err(|e| {
+                    warn!("Failed to load reviews {:#?}", e);
+                }),
+            Ok(false) | Err(_) => {
+                let nav = navigator();
+                nav.push(NavigationTarget::<crate::Route>::Internal(
+                    crate::Route::Auth {},
+                ));
+                Err(())
+            }
+        }
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "Recensioner"
+            }
+            match &*reviews.read_unchecked() {
+                Some(Ok(list)) if list.is_empty() => rsx! {
+                    p { "Inga recensioner att granska." }
+                },
+                Some(Ok(list)) => rsx! {
+                    table {
+                        tr {
+                            th {"Datum"},
+                            th {"Produkt"},
+                            th {"Betyg"},
+                            th {"Recension"},
+                            th {"Status"},
+                            th {}
+                        }
+                        for review in list.iter() {
+                            ModerationRow {
+                                key: "{review.review.id}",
+                                review: review.clone(),
+                                on_change: move |_| reviews.restart()
+                            }
+                        }
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte ladda recensioner!" }
+                },
+                None => rsx! {
+                    h2 { "Laddar..." }
+                }
+            }
+            Link {
+                to: crate::Route::AdminDashboard {},
+                "Tillbaka"
+            }
+        }
+    }
+}
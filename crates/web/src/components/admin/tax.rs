@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::currency::Currency;
+use sjf_api::tax::{OssReportRow, format_minor, format_rate};
+
+use super::category::ADMIN_CSS;
+use crate::server;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "server")] {
+        /// The last quarter that has ended, the one due for an OSS return.
+        fn last_quarter() -> (i32, u32) {
+            use chrono::Datelike;
+            let today = chrono::Utc::now().date_naive();
+            match (today.month() - 1) / 3 {
+                0 => (today.year() - 1, 4),
+                quarter => (today.year(), quarter),
+            }
+        }
+    } else {
+        fn last_quarter() -> (i32, u32) {
+            (2025, 1)
+        }
+    }
+}
+
+/// The currencies other than euro that the report has sales in.
+fn currencies(rows: &[OssReportRow]) -> Vec<Currency> {
+    let mut currencies: Vec<Currency> = rows
+        .iter()
+        .map(|r| r.currency)
+        .filter(|c| *c != Currency::Eur)
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+    currencies
+}
+
+#[component]
+fn OssRow(row: OssReportRow) -> Element {
+    rsx! {
+        tr {
+            td { "{row.country}" },
+            td { "{format_rate(row.rate)}" },
+            td { "{row.currency.code()}" },
+            td { "{row.orders}" },
+            td { "{format_minor(row.gross)}" },
+            td { "{format_minor(row.taxable())}" },
+            td { "{format_minor(row.vat())}" }
+        }
+    }
+}
+
+/// Sales declared through the One Stop Shop, per quarter.
+#[component]
+pub fn AdminOssReport() -> Element {
+    let (last_year, last_quarter) = use_server_cached(last_quarter);
+    let mut year = use_signal(|| last_year);
+    let mut quarter = use_signal(|| last_quarter);
+    // ECB rates for the CSV, as entered
+    let mut ecb: Signal<BTreeMap<Currency, String>> = use_signal(BTreeMap::new);
+    let ecb_query = use_memo(move || {
+        ecb.read()
+            .iter()
+            .filter(|(_, rate)| !rate.is_empty())
+            .map(|(currency, rate)| format!("{}={rate}", currency.code()))
+            .collect::<Vec<_>>()
+            .join("&")
+    });
+
+    let report = use_resource(move || async move {
+        match server::auth::is_authenticated().await {
+            Ok(true) => server::get_oss_report(year(), quarter())
+                .await
+                .map_err(|e| {
+                    warn!("Failed to load OSS report {:#?}", e);
+                }),
+            Ok(false) | Err(_) => {
+                let nav = navigator();
+                nav.push(NavigationTarget::<crate::Route>::Internal(
+                    crate::Route::Auth {},
+                ));
+                Err(())
+            }
+        }
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: ADMIN_CSS }
+
+        div {
+            class: "product_list",
+            h2 {
+                "OSS-rapport"
+            }
+            p {
+                "Försäljning till konsumenter i andra EU-länder som beskattats med köparlandets moms. Beloppen är det kunderna betalade, i valutan de betalade med. Deklarationen görs i euro, ange ECB:s kurs för kvartalets sista dag för övriga valutor så räknas de om i CSV-filen."
+            }
+            label {
+                "År "
+                input {
+                    r#type: "number",
+                    value: "{year}",
+                    onchange: move |evt| {
+                        if let Ok(y) = evt.value().trim().parse::<i32>() {
+                            year.set(y);
+                        }
+                    },
+                }
+            }
+            label {
+                " Kvartal "
+                select {
+                    onchange: move |evt| {
+                        if let Ok(q) = evt.value().parse::<u32>() {
+                            quarter.set(q);
+                        }
+                    },
+                    for q in 1..=4u32 {
+                        option {
+                            key: "{q}",
+                            value: "{q}",
+                            selected: q == quarter(),
+                            "Q{q}"
+                        }
+                    }
+                }
+            }
+            match &*report.read_unchecked() {
+                Some(Ok(rows)) if rows.is_empty() => rsx! {
+                    p { "Ingen OSS-försäljning under kvartalet." }
+                },
+                Some(Ok(rows)) => rsx! {
+                    for currency in currencies(rows) {
+                        label {
+                            key: "{currency.code()}",
+                            " ECB-kurs {currency.code()} per euro "
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "0.0001",
+                                onchange: move |evt| {
+                                    let rate = evt.value().trim().replace(',', ".");
+                                    ecb.write().insert(currency, rate);
+                                },
+                            }
+                        }
+                    }
+                    table {
+                        tr {
+                            th {"Land"},
+                            th {"Momssats"},
+                            th {"Valuta"},
+                            th {"Ordrar"},
+                            th {"Försäljning inkl. moms"},
+                            th {"Beskattningsunderlag"},
+                            th {"Moms"}
+                        }
+                        for row in rows.iter() {
+                            OssRow {
+                                key: "{row.country}-{row.rate}-{row.currency.code()}",
+                                row: row.clone()
+                            }
+                        }
+                    }
+                    a {
+                        href: "/api/auth/tax/oss/{year}-Q{quarter}.csv?{ecb_query}",
+                        download: "oss-{year}-Q{quarter}.csv",
+                        "Ladda ner CSV"
+                    }
+                },
+                Some(Err(())) => rsx! {
+                    h2 { "Kunde inte ladda OSS-rapporten!" }
+                },
+                None => rsx! {
+                    h2 { "Laddar..." }
+                }
+            }
+            Link {
+                to: crate::Route::AdminDashboard {},
+                "Tillbaka"
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::components::MenuState;
 use dioxus::logger::tracing::warn;
-use dioxus::prelude::{server_fn::ServerFn, *};
-use serde::Serialize;
+use dioxus::prelude::*;
 use sjf_api::{
+    cart::{CartNotice, SyncCartRequest},
     checkout::CheckoutRequest,
+    currency::Currency,
+    i18n::Locale,
     product::{Product, ProductId},
 };
 
@@ -25,13 +27,21 @@ fn cart_name() -> String {
 pub struct CartState {
     open: MenuState,
     contents: BTreeMap<ProductId, (Product, ProductQuantity)>,
+    notices: Vec<CartNotice>,
+    /// The abandoned checkout this cart was restored from.
+    recovery: Option<String>,
+    /// Language of the names in the cart and of the checkout.
+    locale: Locale,
 }
 
 impl CartState {
-    pub fn new() -> Self {
+    pub fn new(locale: Locale) -> Self {
         Self {
             open: MenuState::Closed,
             contents: Default::default(),
+            notices: vec![],
+            recovery: None,
+            locale,
         }
     }
 
@@ -41,15 +51,13 @@ impl CartState {
         window().map(|w| w.local_storage().unwrap_or_default())?
     }
 
+    fn items(&self) -> Vec<(ProductId, ProductQuantity)> {
+        self.contents.iter().map(|(k, (_, q))| (*k, *q)).collect()
+    }
+
     #[cfg(feature = "web")]
-    pub fn save(&self) {
-        use dioxus::logger::tracing::info;
-        info!("Saving cart");
-        let state: BTreeMap<u32, u32> = self
-            .contents
-            .iter()
-            .map(|(k, (_, q))| (k.clone(), q.clone()))
-            .collect();
+    fn save_local(&self) {
+        let state: BTreeMap<u32, u32> = self.items().into_iter().collect();
 
         Self::get_storage().map(|s| {
             let _ = s.set_item(&cart_name(), &serde_json::to_string(&state)?);
@@ -58,15 +66,34 @@ impl CartState {
     }
 
     #[cfg(feature = "web")]
-    pub async fn load() -> Option<Self> {
+    pub fn save(&self) {
         use dioxus::logger::tracing::info;
+        info!("Saving cart");
+        self.save_local();
+
+        let req = SyncCartRequest {
+            items: self.items(),
+            merge: false,
+            locale: self.locale,
+        };
+        spawn(async move {
+            if let Err(e) = crate::server::sync_cart(req).await {
+                warn!("Failed to store cart on server {:#?}", e);
+            }
+        });
+    }
+
+    #[cfg(feature = "web")]
+    pub async fn load(locale: Locale) -> Option<Self> {
         use web_sys::window;
 
-        if window()
+        let path = window()
             .map(|w| w.location().pathname().unwrap_or_default())
-            .unwrap_or_default()
-            .starts_with(sjf_api::payment::SUCCESS_PATH)
-        {
+            .unwrap_or_default();
+        let paid = Locale::ALL
+            .into_iter()
+            .any(|l| path.starts_with(sjf_api::payment::success_path(l)));
+        if paid {
             let r = Self::get_storage().map(|s| s.remove_item(&cart_name()));
             match r {
                 Some(Ok(())) => (),
@@ -78,54 +105,109 @@ impl CartState {
         let unserialize = || {
             if let Some(storage) = Self::get_storage() {
                 let data = storage.get_item(&cart_name());
-                if let Ok(Some(data)) = data {
-                    if let Ok(data) = serde_json::from_str(&data) {
-                        let data: BTreeMap<u32, u32> = data;
-                        return Some(data);
-                    }
+                if let Ok(Some(data)) = data
+                    && let Ok(data) = serde_json::from_str(&data)
+                {
+                    let data: BTreeMap<u32, u32> = data;
+                    return Some(data);
                 }
             }
-            return None;
+            None
         };
 
-        if let Some(data) = unserialize() {
-            if !data.is_empty() {
-                let product_ids = data.keys().cloned().collect();
-                let rsp = crate::server::get_specified_products(product_ids).await;
-                if let Ok(ps) = rsp {
-                    return Some(Self {
-                        contents: ps
-                            .into_iter()
-                            .filter_map(|p| {
-                                use std::u32;
-
-                                let previous_quantity = data.get(&p.id).unwrap().clone();
-                                let max_quantity = p.stock.clone().unwrap_or(u32::MAX);
-                                let new_quantity = {
-                                    if previous_quantity > max_quantity {
-                                        max_quantity
-                                    } else {
-                                        previous_quantity
-                                    }
-                                };
+        let data = unserialize().unwrap_or_default();
+
+        // The server keeps the cart across browser sessions and re-checks it, the local copy
+        // is only used on its own when the server can't be reached.
+        let req = SyncCartRequest {
+            items: data.iter().map(|(k, q)| (*k, *q)).collect(),
+            merge: !paid,
+            locale,
+        };
+        match crate::server::sync_cart(req).await {
+            Ok(rsp) if rsp.items.is_empty() && rsp.notices.is_empty() => return None,
+            Ok(rsp) => {
+                let open = match rsp.notices.is_empty() {
+                    true => MenuState::Closed,
+                    false => MenuState::Opened,
+                };
+                let cart = Self {
+                    contents: rsp.items.into_iter().map(|(p, q)| (p.id, (p, q))).collect(),
+                    notices: rsp.notices,
+                    open,
+                    recovery: None,
+                    locale,
+                };
+                cart.save_local();
+                return Some(cart);
+            }
+            Err(e) => warn!("Failed to sync cart with server {:#?}", e),
+        }
 
-                                if new_quantity > 0 {
-                                    Some((p.id.clone(), (p, new_quantity)))
+        if !data.is_empty() {
+            let product_ids = data.keys().cloned().collect();
+            let rsp = crate::server::get_specified_products(product_ids, locale).await;
+            if let Ok(ps) = rsp {
+                return Some(Self {
+                    contents: ps
+                        .into_iter()
+                        .filter_map(|p| {
+                            let previous_quantity = *data.get(&p.id).unwrap();
+                            let max_quantity = p.stock.unwrap_or(u32::MAX);
+                            let new_quantity = {
+                                if previous_quantity > max_quantity {
+                                    max_quantity
                                 } else {
-                                    None
+                                    previous_quantity
                                 }
-                            })
-                            .collect(),
-                        open: MenuState::Closed,
-                    });
-                }
+                            };
+
+                            if new_quantity > 0 {
+                                Some((p.id, (p, new_quantity)))
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                    notices: vec![],
+                    open: MenuState::Closed,
+                    recovery: None,
+                    locale,
+                });
             }
         }
-        return None;
+        None
+    }
+
+    /// Adds the contents of an abandoned checkout to the cart and opens it.
+    #[cfg(feature = "web")]
+    pub async fn restore(req: CheckoutRequest) -> Result<Self, ServerFnError> {
+        let rsp = crate::server::sync_cart(SyncCartRequest {
+            items: req.order.into_iter().collect(),
+            merge: true,
+            locale: req.locale,
+        })
+        .await?;
+        let cart = Self {
+            contents: rsp.items.into_iter().map(|(p, q)| (p.id, (p, q))).collect(),
+            notices: rsp.notices,
+            open: MenuState::Opened,
+            recovery: req.recovery,
+            locale: req.locale,
+        };
+        cart.save_local();
+        Ok(cart)
+    }
+
+    pub fn is_restored(&self) -> bool {
+        self.recovery.is_some()
     }
 
     pub fn has_item(&self, id: &ProductId) -> bool {
-        self.contents.get(id).is_some()
+        self.contents.contains_key(id)
+    }
+    pub fn product_ids(&self) -> BTreeSet<ProductId> {
+        self.contents.keys().cloned().collect()
     }
     pub fn is_empty(&self) -> bool {
         self.contents.is_empty()
@@ -134,7 +216,7 @@ impl CartState {
         self.open = MenuState::Closed;
     }
 
-    pub async fn checkout(&self) -> Result<String, ServerFnError> {
+    pub async fn checkout(&self, currency: Currency) -> Result<String, ServerFnError> {
         let req = CheckoutRequest {
             order: self
                 .contents
@@ -142,6 +224,9 @@ impl CartState {
                 .into_iter()
                 .map(|(id, (_, quantity))| (id, quantity))
                 .collect(),
+            recovery: self.recovery.clone(),
+            locale: self.locale,
+            currency,
         };
         let res = crate::server::checkout(req).await;
         if let Ok(payment_url) = &res {
@@ -167,21 +252,13 @@ impl CartState {
         self.save();
     }
 
-    pub fn clear(&mut self) {
-        self.contents.clear();
-        self.save();
-    }
-
     fn inc(&mut self, id: &ProductId) {
-        match self.contents.get_mut(id) {
-            Some((p, q)) => {
-                *q = *q + 1;
-                match p.stock {
-                    Some(stock) if *q >= stock => *q = stock,
-                    _ => {}
-                }
+        if let Some((p, q)) = self.contents.get_mut(id) {
+            *q += 1;
+            match p.stock {
+                Some(stock) if *q >= stock => *q = stock,
+                _ => {}
             }
-            None => {}
         }
         self.save();
     }
@@ -191,7 +268,7 @@ impl CartState {
                 self.contents.remove(id);
             }
             Some((_, q)) => {
-                *q = *q - 1;
+                *q -= 1;
             }
             None => {}
         }
@@ -203,10 +280,11 @@ impl CartState {
     }
 
     fn num_items(&self) -> u32 {
-        self.contents
-            .values()
-            .map(|(_, q)| q)
-            .fold(0, |acc, q| acc + q)
+        self.contents.values().map(|(_, q)| q).sum()
+    }
+
+    fn dismiss_notices(&mut self) {
+        self.notices.clear();
     }
 
     fn toggle(&mut self) {
@@ -222,6 +300,8 @@ pub fn use_cart() -> CartSignal {
 #[component]
 fn CartItem(item_id: ReadOnlySignal<ProductId>) -> Element {
     let mut cart_state = use_cart();
+    let locale = crate::i18n::use_locale();
+    let currency = crate::components::use_currency();
 
     let cart = cart_state.read();
     let (item, quantity) = cart.contents.get(&*item_id.read()).unwrap();
@@ -240,6 +320,10 @@ fn CartItem(item_id: ReadOnlySignal<ProductId>) -> Element {
             }
             div {
                 span { class: "name", "{item.name}" }
+                if let Some(note) = item.delivery_note(*quantity, locale)
+                {
+                    span { class: "delivery-note", "{note}" }
+                }
                 div {
                     class: "additional",
                     div {
@@ -261,7 +345,7 @@ fn CartItem(item_id: ReadOnlySignal<ProductId>) -> Element {
                         }
                     }
                     div {
-                        "{item.price}kr"
+                        "{currency().format(item.price)}"
                     }
                 }
             }
@@ -297,13 +381,16 @@ pub fn CheckoutButton() -> Element {
     enum CheckoutState {
         Idle,
         Pending,
-        Error(ServerFnError),
+        Error,
         Changed,
         Accepted,
     }
 
     let mut state = use_signal(|| CheckoutState::Idle);
     let mut cart_state = use_cart();
+    let currency = crate::components::use_currency();
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
 
     let is_empty = use_memo(move || cart_state.read().is_empty());
 
@@ -319,8 +406,8 @@ pub fn CheckoutButton() -> Element {
                     class: "checkout disabled",
                     match current_state
                     {
-                        Changed => "Varukorgen har uppdaterats",
-                        _ => "Till kassa"
+                        Changed => messages.cart_updated,
+                        _ => messages.to_checkout
                     }
                 }
 
@@ -332,21 +419,22 @@ pub fn CheckoutButton() -> Element {
 
                         state.set(Pending);
                         let cart = &*cart_state.read();
-                        match cart.checkout().await
+                        match cart.checkout(currency().currency).await
                         {
                             Err(e) => {
                                 spawn ( async move {
-                                    if let Some(mut c) = CartState::load().await
+                                    if let Some(mut c) = CartState::load(locale).await
+                                        && (cart_state.read().contents != c.contents
+                                            || !c.notices.is_empty())
                                     {
-                                        if cart_state.read().contents != c.contents
-                                        {
-                                            c.open = MenuState::Opened;
-                                            cart_state.set(c);
-                                            state.set(CheckoutState::Changed);
-                                        }
+                                        c.open = MenuState::Opened;
+                                        c.recovery = cart_state.read().recovery.clone();
+                                        cart_state.set(c);
+                                        state.set(CheckoutState::Changed);
                                     }
                                 });
-                                state.set(Error(e))
+                                warn!("Checkout failed {:#?}", e);
+                                state.set(Error)
                             },
                             Ok(_) => {
                                 state.set(Accepted)
@@ -355,8 +443,8 @@ pub fn CheckoutButton() -> Element {
                     },
                     match current_state
                     {
-                        Changed => "Varukorgen har uppdaterats",
-                        _ => "Till kassa"
+                        Changed => messages.cart_updated,
+                        _ => messages.to_checkout
                     }
                 }
             }
@@ -365,19 +453,19 @@ pub fn CheckoutButton() -> Element {
         Pending => rsx! {
             div {
                 class: "checkout",
-                "Processerar..."
+                "{messages.processing}"
             }
         },
-        Error(s) => rsx! {
+        Error => rsx! {
             div {
                 class:  "checkout",
-                "Misslyckades"
+                "{messages.failed}"
             }
         },
         Accepted => rsx! {
             div {
                 class:  "checkout",
-                "Omdirigerar till stripe..."
+                "{messages.redirecting}"
             }
         },
     }
@@ -385,33 +473,51 @@ pub fn CheckoutButton() -> Element {
 
 #[component]
 pub fn CartContents() -> Element {
-    let cart_state = use_cart();
+    let mut cart_state = use_cart();
+    let currency = crate::components::use_currency();
+    let locale = crate::i18n::use_locale();
     let content_class = use_memo(move || match cart_state.read().open {
         MenuState::Opened => "cart_contents opened",
         _ => "cart_contents",
     });
 
+    let has_notices = use_memo(move || !cart_state.read().notices.is_empty());
+
     let total = use_memo(move || {
         let cart = cart_state.read();
-        cart.contents
-            .values()
-            .map(|(p, q)| q * p.price)
-            .fold(0u32, |acc, c| acc + c)
+        let rate = currency();
+        rate.currency
+            .format(rate.total(cart.contents.values().map(|(p, q)| (p.price, *q))))
     });
 
     rsx! {
             div {
                 class: "{content_class}",
                 div {
+                    if has_notices()
+                    {
+                        div {
+                            class: "notices",
+                            for notice in cart_state.read().notices.iter()
+                            {
+                                p { "{notice.message(locale)}" }
+                            }
+                            button {
+                                onclick: move |_| cart_state.write().dismiss_notices(),
+                                "OK"
+                            }
+                        }
+                    }
                     for id in cart_state.read().contents.keys()
                     {
-                        CartItem {key: id, item_id: id.clone()  }
+                        CartItem {key: id, item_id: *id  }
                     }
 
                     div {
                         class: "total",
-                        "Totalt {total}kr"
+                        "{locale.messages().total} {total}"
                     }
+                    crate::components::CartSuggestions {}
                 }
                 CheckoutButton {}
             }
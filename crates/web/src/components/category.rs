@@ -1,9 +1,181 @@
 use dioxus::prelude::*;
+use sjf_api::product::{GetPreviewsRequest, ListingQuery, PreviewOrder, category_url_in};
 
-use crate::server::{category::get_children, get_previews};
+use crate::server::{category::get_children, get_facets, get_previews};
+
+/// Previews shown per page of a category.
+const PAGE_SIZE: u32 = 24;
 
 #[component]
-pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u32>) -> Element {
+fn ListingControls(
+    category_path: ReadOnlySignal<Vec<String>>,
+    query: ReadOnlySignal<ListingQuery>,
+) -> Element {
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+    let navigate = move |query: ListingQuery| {
+        navigator().push(crate::Route::product_page(
+            locale,
+            category_path(),
+            query.with_page(0),
+        ));
+    };
+    let parse_price = |value: String| value.trim().parse::<u32>().ok();
+
+    rsx! {
+        div {
+            class: "listing-controls",
+            label {
+                "{messages.sort} "
+                select {
+                    onchange: move |e| {
+                        let order = PreviewOrder::from_slug(&e.value()).unwrap_or_default();
+                        navigate(ListingQuery { order, ..query() });
+                    },
+                    for order in PreviewOrder::SELECTABLE {
+                        option {
+                            key: "{order.slug()}",
+                            value: "{order.slug()}",
+                            selected: order == query().order,
+                            "{order.label(locale)}"
+                        }
+                    }
+                }
+            }
+            label {
+                "{messages.price} "
+                input {
+                    r#type: "number",
+                    min: "0",
+                    placeholder: messages.price_from,
+                    value: query().filter.min_price.map(|p| p.to_string()).unwrap_or_default(),
+                    onchange: move |e| {
+                        let mut query = query();
+                        query.filter.min_price = parse_price(e.value());
+                        navigate(query);
+                    },
+                }
+                " - "
+                input {
+                    r#type: "number",
+                    min: "0",
+                    placeholder: messages.price_to,
+                    value: query().filter.max_price.map(|p| p.to_string()).unwrap_or_default(),
+                    onchange: move |e| {
+                        let mut query = query();
+                        query.filter.max_price = parse_price(e.value());
+                        navigate(query);
+                    },
+                }
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: query().filter.in_stock,
+                    onchange: move |e| {
+                        let mut query = query();
+                        query.filter.in_stock = e.checked();
+                        navigate(query);
+                    },
+                }
+                " {messages.in_stock_only}"
+            }
+        }
+    }
+}
+
+/// Checkbox lists for the attributes of the category, counts are for the
+/// current filter with the attribute's own selection left out.
+#[component]
+fn Facets(
+    category_path: ReadOnlySignal<Vec<String>>,
+    id: ReadOnlySignal<u32>,
+    query: ReadOnlySignal<ListingQuery>,
+) -> Element {
+    let locale = crate::i18n::use_locale();
+    let facets =
+        use_resource(
+            move || async move { get_facets(id(), query().filter).await.unwrap_or_default() },
+        );
+
+    rsx! {
+        if let Some(facets) = facets().filter(|f| !f.is_empty()) {
+            div {
+                class: "facets",
+                for facet in facets {
+                    fieldset {
+                        key: "{facet.attribute.id}",
+                        legend { "{facet.attribute.name}" }
+                        for (value, count) in facet.values.iter().cloned() {
+                            label {
+                                key: "{value}",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: query().filter.is_selected(facet.attribute.id, &value),
+                                    onchange: {
+                                        let value = value.clone();
+                                        let attribute = facet.attribute.id;
+                                        move |_| {
+                                            let mut query = query();
+                                            query.filter.toggle(attribute, &value);
+                                            navigator().push(crate::Route::product_page(
+                                                locale,
+                                                category_path(),
+                                                query.with_page(0),
+                                            ));
+                                        }
+                                    },
+                                }
+                                " {facet.attribute.display(&value)} ({count})"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Pagination(
+    category_path: ReadOnlySignal<Vec<String>>,
+    query: ReadOnlySignal<ListingQuery>,
+    total: u32,
+) -> Element {
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+    let pages = total.div_ceil(PAGE_SIZE);
+    let page = query().page;
+    let link = move |page: u32| {
+        crate::Route::product_page(locale, category_path(), query().with_page(page))
+    };
+
+    rsx! {
+        if pages > 1 {
+            div {
+                class: "pagination",
+                if page > 0 {
+                    Link { to: link(page - 1), rel: "prev", "{messages.previous}" }
+                }
+                span { "{locale.page_of(page + 1, pages)}" }
+                if page + 1 < pages {
+                    Link { to: link(page + 1), rel: "next", "{messages.next}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Category(
+    category_path: ReadOnlySignal<Vec<String>>,
+    id: ReadOnlySignal<u32>,
+    query: ReadOnlySignal<ListingQuery>,
+) -> Element {
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+    let labels = super::category_bar::use_category_labels(category_path)?;
+    let labels = move || labels().unwrap_or_else(|| category_path.cloned());
     let children_and_previews: Resource<
         Result<
             (
@@ -12,18 +184,46 @@ pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u
             ),
             ServerFnError,
         >,
-    > = use_server_future(move || {
-        let id = id.clone();
-        async move {
+    > = use_server_future(move || async move {
         let id = Some(*id.read());
-        let cs = get_children(id.clone());
-        let ps = get_previews(id, false, 100, false);
+        let query = query();
+        let cs = get_children(id, locale);
+        let ps = get_previews(GetPreviewsRequest {
+            category: id,
+            recursive: false,
+            limit: PAGE_SIZE,
+            page: query.page,
+            order: query.order,
+            filter: query.filter,
+            locale,
+        });
 
         Ok((cs.await?, ps.await?))
-    }})?;
+    })?;
+
+    let is_filtered = use_memo(move || query().filter != Default::default());
+    let site = super::seo::use_site_url();
+    // Sorted and filtered listings are the same page to search engines
+    let canonical = move || match query().page {
+        0 => category_url_in(locale, &category_path()),
+        page => format!(
+            "{}?{}",
+            category_url_in(locale, &category_path()),
+            ListingQuery::default().with_page(page)
+        ),
+    };
 
     rsx! {
-        document::Title { "SJF Concept - {category_path.last().unwrap() }" }
+        crate::components::PageMeta {
+            title: "SJF Concept - {labels().last().unwrap() }",
+            description: locale.category_description(labels().last().unwrap()),
+            path: canonical(),
+            image: match &*children_and_previews.read() {
+                Some(Ok((_, p))) => p.previews.first().and_then(|p| super::seo::largest_image(&p.images)),
+                _ => None,
+            },
+        }
+        crate::components::JsonLd { data: super::seo::breadcrumbs(&site, locale, &category_path(), &labels(), None) }
         crate::components::CategoryBar { path: category_path }
 
 
@@ -35,14 +235,14 @@ pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u
                     Some(Ok((c,p))) => rsx! {
 
 
-                        match ( (c.children.is_empty(), p.previews.is_empty() ) )
+                        match (c.children.is_empty(), p.previews.is_empty() && !is_filtered() && query().page == 0)
                         {
                             (true,true) => rsx! {
                                     div {
-                                        "Ooops här var det tomt för tillfället, men kika gärna tillbaka senare."
+                                        "{messages.empty_category}"
                                         div {
                                             class: "return-link",
-                                            Link {to: crate::Route::FrontPage {}, "Tillbaka till början" }
+                                            Link {to: crate::Route::front_page(locale), "{messages.back_to_start}" }
                                         }
                                     }
                             },
@@ -56,16 +256,18 @@ pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u
                                             for child in &c.children
                                             {
                                                 Link {
-                                                    to: crate::Route::ProductPage { segments:
+                                                    to: crate::Route::product_page(
+                                                        locale,
                                                         {
                                                             let mut path: Vec<String> = category_path();
                                                             path.push(child.1.clone());
                                                             path
 
-                                                        }
-                                                    },
+                                                        },
+                                                        Default::default()
+                                                    ),
                                                     div {
-                                                        {child.1.clone()}
+                                                        "{c.label(child.0, &child.1)}"
                                                     }
                                                 }
                                             }
@@ -75,14 +277,23 @@ pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u
 
                                 if !previews_empty
                                 {
+                                    ListingControls { category_path, query }
+                                    Facets { category_path, id, query }
+                                    if p.previews.is_empty()
+                                    {
+                                        div {
+                                            class: "listing-empty",
+                                            "{messages.no_matches}"
+                                        }
+                                    }
                                     div {
                                         class: "product-previews",
                                         for preview in &p.previews
                                         {
-                                            crate::components::ProductPreview {preview: preview.clone()}
+                                            crate::components::ProductPreview {key: "{preview.id}", preview: preview.clone()}
                                         }
                                     }
-
+                                    Pagination { category_path, query, total: p.total }
                                 }
 
                             },
@@ -90,7 +301,7 @@ pub fn Category(category_path: ReadOnlySignal<Vec<String>>, id: ReadOnlySignal<u
 
                     },
                     Some(Err(_)) => rsx! {
-                        span { "Kunde inte ladda underkategorier"}
+                        span { "{messages.subcategories_failed}"}
                     }
                 }
             }
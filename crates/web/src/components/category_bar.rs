@@ -1,16 +1,39 @@
 use dioxus::prelude::*;
+use sjf_api::i18n::Locale;
+
+/// The names to show for the segments of a category path, in the language of
+/// the page. The segments themselves stay Swedish.
+pub fn use_category_labels(
+    path: ReadOnlySignal<Vec<String>>,
+) -> Result<Resource<Vec<String>>, RenderError> {
+    let locale = crate::i18n::use_locale();
+    use_server_future(move || async move {
+        let path = path();
+        match locale {
+            Locale::Sv => path,
+            _ => crate::server::category::get_labels(path.clone(), locale)
+                .await
+                .unwrap_or(path),
+        }
+    })
+}
+
 #[component]
 pub fn CategoryBar(path: ReadOnlySignal<Vec<String>>) -> Element {
+    let locale = crate::i18n::use_locale();
+    let labels = use_category_labels(path)?;
+    let labels = labels().unwrap_or_else(|| path.cloned());
+
     rsx! {
         div
         {
             class: "category-bar",
-            for (i,segment) in path().iter().enumerate()
+            for (i,segment) in labels.iter().enumerate()
             {
                 span { a {
 
                     Link {
-                        to: crate::Route::ProductPage { segments: path().clone().into_iter().take(i+1).collect() },
+                        to: crate::Route::product_page(locale, path().into_iter().take(i+1).collect(), Default::default()),
                         "{segment}"
                     }
                 } }
@@ -0,0 +1,55 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::currency::{Currency, ExchangeRate};
+
+use crate::server;
+
+pub type CurrencySignal = Signal<ExchangeRate>;
+
+/// The currency prices are shown in, with its current rate.
+pub fn use_currency() -> CurrencySignal {
+    use_context::<CurrencySignal>()
+}
+
+/// Lets the visitor pick the currency prices are shown and charged in. Hidden
+/// while kr is the only currency offered.
+#[component]
+pub fn CurrencyPicker() -> Element {
+    let messages = crate::i18n::use_messages();
+    let mut selected = use_currency();
+    let rates =
+        use_resource(
+            move || async move { server::currency::get_rates().await.unwrap_or_default() },
+        );
+    let rates = rates().unwrap_or_default();
+
+    if rates.len() < 2 {
+        return rsx! {};
+    }
+
+    rsx! {
+        li {
+            class: "currency",
+            select {
+                aria_label: messages.currency,
+                onchange: move |evt| async move {
+                    let Some(currency) = Currency::from_code(&evt.value()) else {
+                        return;
+                    };
+                    match server::currency::select(currency).await {
+                        Ok(rate) => selected.set(rate),
+                        Err(e) => warn!("Failed to select currency {:#?}", e),
+                    }
+                },
+                for rate in rates {
+                    option {
+                        key: "{rate.currency.code()}",
+                        value: rate.currency.code(),
+                        selected: rate.currency == selected().currency,
+                        "{rate.currency.code()}"
+                    }
+                }
+            }
+        }
+    }
+}
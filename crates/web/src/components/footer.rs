@@ -8,13 +8,15 @@ pub const FOOTER_VISA: Asset = asset!("/assets/visa.svg");
 
 #[component]
 pub fn Footer() -> Element {
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
     rsx! {
         footer {
             ul {
                 li {
                     Link {
-                        to: crate::Route::TermsAndConditions {  },
-                        "Frakt & Villkor"
+                        to: crate::Route::terms(locale),
+                        "{messages.shipping_and_terms}"
                     }
                 },
                 li {
@@ -25,8 +27,14 @@ pub fn Footer() -> Element {
                 }
                 li {
                     Link {
-                        to: crate::Route::CategoryList {},
-                        "Inloggning"
+                        to: crate::Route::account(locale),
+                        "{messages.my_account}"
+                    }
+                }
+                li {
+                    Link {
+                        to: crate::Route::AdminDashboard {},
+                        "{messages.staff_login}"
                     }
                 }
             },
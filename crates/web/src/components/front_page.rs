@@ -1,15 +1,17 @@
-use dioxus::{html::i, logger::tracing::info, prelude::*};
-use sjf_api::product::{Image, Preview};
+use dioxus::{logger::tracing::info, prelude::*};
+use sjf_api::product::{GetPreviewsRequest, Image, Preview, PreviewOrder};
 
 use crate::server;
 
 #[component]
 pub fn ProductPreview(preview: ReadOnlySignal<Preview>) -> Element {
+    let currency = crate::components::use_currency();
     let mut loaded = use_signal(|| false);
+    let locale = crate::i18n::use_locale();
     if preview.read().images.is_empty() {
         rsx! {
             span {
-                "No image",
+                "{locale.messages().no_image}",
             }
         }
     } else {
@@ -18,7 +20,7 @@ pub fn ProductPreview(preview: ReadOnlySignal<Preview>) -> Element {
 
         rsx!(
             Link {
-                to: crate::Route::ProductPage { segments: previewr.product_path() } ,
+                to: crate::Route::product_page(locale, previewr.product_path(), Default::default()) ,
                 class: "product_preview",
                 div {
                     div {
@@ -34,8 +36,9 @@ pub fn ProductPreview(preview: ReadOnlySignal<Preview>) -> Element {
                             "{previewr.name}"
                         }
                         span {
-                            "{previewr.price}kr"
+                            "{currency().format(previewr.price)}"
                         }
+                        crate::components::WishlistToggle { product_id: previewr.id }
                     }
                 }
             }
@@ -45,14 +48,20 @@ pub fn ProductPreview(preview: ReadOnlySignal<Preview>) -> Element {
 
 #[component]
 fn Latest() -> Element {
+    let currency = crate::components::use_currency();
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
     let mut refresh_counter = use_signal(|| 0u32);
 
     use_future(move || async move {
         let mut counter = 0u32;
         while counter < 10 {
-            info!("Waiting for refresh {}",counter);
+            info!("Waiting for refresh {}", counter);
             wasmtimer::tokio::sleep(std::time::Duration::from_secs(30)).await;
-            counter = refresh_counter.with_mut(|v| {*v = *v + 1; *v} );
+            counter = refresh_counter.with_mut(|v| {
+                *v += 1;
+                *v
+            });
         }
         info!("Refresh limited");
     });
@@ -62,34 +71,44 @@ fn Latest() -> Element {
     > = use_server_future(move || {
         let _ = refresh_counter.read();
         async move {
-        let res = server::get_previews(None, true, 12, true).await?;
-
-        use rand::distr::Distribution;
-        let mut rng = rand::rng();
-
-        let random_index: usize = {
-            let len = res.previews.len();
-            let between = rand::distr::Uniform::try_from(0..len).unwrap();
-            between.sample(&mut rng)
-        };
-
-        let highlight: Preview = res.previews[random_index].clone();
-
-        let random_image_index: usize = {
-            let len = highlight.images.len();
-            let between = rand::distr::Uniform::try_from(0..len).unwrap();
-            between.sample(&mut rng)
-        };
-
-        let highlighted_image = highlight.images[random_image_index].clone();
-
-        Ok((res, highlight, highlighted_image))
-    }})?;
+            let res = server::get_previews(GetPreviewsRequest {
+                category: None,
+                recursive: true,
+                limit: 12,
+                page: 0,
+                order: PreviewOrder::Random,
+                filter: Default::default(),
+                locale,
+            })
+            .await?;
+
+            use rand::distr::Distribution;
+            let mut rng = rand::rng();
+
+            let random_index: usize = {
+                let len = res.previews.len();
+                let between = rand::distr::Uniform::try_from(0..len).unwrap();
+                between.sample(&mut rng)
+            };
+
+            let highlight: Preview = res.previews[random_index].clone();
+
+            let random_image_index: usize = {
+                let len = highlight.images.len();
+                let between = rand::distr::Uniform::try_from(0..len).unwrap();
+                between.sample(&mut rng)
+            };
+
+            let highlighted_image = highlight.images[random_image_index].clone();
+
+            Ok((res, highlight, highlighted_image))
+        }
+    })?;
 
     match &*previews.read_unchecked() {
         Some(Ok((rsp, highlight, highlighted_image))) => rsx! {
             h1 {
-                "Populärt!"
+                "{messages.popular}"
             }
             div {
                 class: "product-previews",
@@ -102,7 +121,7 @@ fn Latest() -> Element {
 
             Link {
                 class: "product-highlight",
-                to: crate::Route::ProductPage { segments: highlight.product_path() },
+                to: crate::Route::product_page(locale, highlight.product_path(), Default::default()),
                 div {
                         img {
                             srcset: "{highlighted_image.srcset().unwrap() }"
@@ -115,7 +134,7 @@ fn Latest() -> Element {
                         "{highlight.name.to_uppercase() }"
                     }
                     h2 {
-                        "{highlight.price}kr"
+                        "{currency().format(highlight.price)}"
                     }
                 }
 
@@ -131,7 +150,7 @@ fn Latest() -> Element {
         Some(Err(_)) => rsx! {},
         None => rsx! {
             span {
-                "Loading..."
+                "{messages.loading}"
             }
         },
     }
@@ -139,21 +158,22 @@ fn Latest() -> Element {
 
 #[component]
 fn ProductCategories() -> Element {
-    let mut context = use_context::<crate::components::CategorySignal>();
+    let context = use_context::<crate::components::CategorySignal>();
+    let locale = crate::i18n::use_locale();
 
     rsx! {
         if let Some(ref cs) = context()
         {
             div {
                 class: "category-showcase",
-                h2 { "Utforska"}
+                h2 { "{locale.messages().explore}"}
                 div {
-                    for (_,c) in &cs.children {
+                    for (id,c) in &cs.children {
                         Link {
-                            to: crate::Route::ProductPage { segments:  vec![c.clone()] } ,
+                            to: crate::Route::product_page(locale, vec![c.clone()], Default::default()) ,
                             div {
                                 class: "category",
-                                "{c}"
+                                "{cs.label(*id, c)}"
                             }
 
                         }
@@ -167,8 +187,25 @@ fn ProductCategories() -> Element {
 
 #[component]
 pub fn FrontPage() -> Element {
+    let site = super::seo::use_site_url();
+    let locale = crate::i18n::use_locale();
+
     rsx! {
-        document::Title { "SJF Concept" }
+        crate::components::PageMeta {
+            title: "SJF Concept",
+            description: locale.messages().shop_description,
+            path: crate::Route::front_page(locale).to_string(),
+        }
+        crate::components::JsonLd {
+            data: serde_json::json!({
+                "@context": "https://schema.org/",
+                "@type": "Organization",
+                "name": "SJF Concept",
+                "url": format!("{site}/"),
+                "email": "sjfconcept@hotmail.com",
+                "sameAs": ["https://www.instagram.com/sjfconcept/"],
+            })
+        }
         div {
             class: "front_page",
             Latest {  }
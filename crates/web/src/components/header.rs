@@ -1,9 +1,10 @@
-use dioxus::{logger::tracing::info, prelude::*};
+use dioxus::prelude::*;
 use sjf_api::category::GetChildrenRsp;
 
 use crate::{
-    components::{self, FrontPage, MenuState},
-    server,
+    Route,
+    components::{self, MenuState},
+    i18n, server,
 };
 
 const MAIN_SCSS: Asset = asset!("/assets/styling/main.scss");
@@ -14,7 +15,12 @@ pub type CategorySignal = Signal<Option<GetChildrenRsp>>;
 
 #[component]
 pub fn DynamicMenu() -> Element {
-    let categories = use_server_future(|| async move { server::category::get_children(None).await })?;
+    let locale = i18n::use_locale();
+    let messages = locale.messages();
+    let categories =
+        use_server_future(
+            move || async move { server::category::get_children(None, locale).await },
+        )?;
 
     let mut context = use_context::<CategorySignal>();
 
@@ -31,23 +37,23 @@ pub fn DynamicMenu() -> Element {
             for (id,name) in &rsp.children
             {
                 li {
-                    key: id,
+                    key: "{id}",
                     Link {
-                        to: crate::Route::ProductPage { segments: vec![name.clone()] },
-                        "{name}"
+                        to: Route::product_page(locale, vec![name.clone()], Default::default()),
+                        "{rsp.label(*id, name)}"
                     }
                 }
             }
 
         },
-        Some(Err(ref e)) => rsx! {
+        Some(Err(_)) => rsx! {
             span {
-                "Laddning misslyckades!"
+                "{messages.loading_failed}"
             }
         },
         None => rsx! {
             span {
-                "Laddar!"
+                "{messages.loading}"
             }
         },
     }
@@ -55,6 +61,8 @@ pub fn DynamicMenu() -> Element {
 
 #[component]
 pub fn Header() -> Element {
+    let locale = i18n::use_locale();
+    let messages = locale.messages();
     let mut menu_state = use_signal(|| MenuState::Closed);
     let mut cart_state = crate::components::cart::use_cart();
 
@@ -84,7 +92,7 @@ pub fn Header() -> Element {
                     div {}
                 }
                 Link {
-                    to: crate::Route::FrontPage {},
+                    to: Route::front_page(locale),
                     img {
                         class: "logo",
                         src: HEADER_LOGO
@@ -98,14 +106,22 @@ pub fn Header() -> Element {
                 },
                 class: "menu {menu_class}",
                 li {
-                    Link {to: crate::Route::FrontPage {}, "Hem" }
+                    Link {to: Route::front_page(locale), "{messages.home}" }
                 }
 
                 DynamicMenu {}
 
                 li {
-                    Link {to: crate::Route::About {}, "Om SJF" }
+                    Link {to: Route::wishlist(locale), "{messages.favourites}" }
                 }
+
+                li {
+                    Link {to: Route::about(locale), "{messages.about}" }
+                }
+
+                i18n::LanguageSwitch {}
+
+                components::CurrencyPicker {}
             },
             components::CartContents {}
         }
@@ -2,8 +2,8 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::vec;
 
-use dioxus::html::{image, FileEngine};
-use dioxus::logger::tracing::{info, warn};
+use dioxus::html::FileEngine;
+use dioxus::logger::tracing::info;
 use dioxus::prelude::*;
 use dioxus::signals::Signal;
 
@@ -18,15 +18,14 @@ pub struct ImageUploadButtonProps {
 
 #[component]
 pub fn ImageUploadButton(props: ImageUploadButtonProps) -> Element {
-    let mut thumbnails = props.thumbnails.clone();
+    let mut thumbnails = props.thumbnails;
     let mut is_loading = use_signal(|| false);
 
     let allow_multiple = props.multiple.unwrap_or(false);
 
     let read_files = move |file_engine: Arc<dyn FileEngine>| async move {
         let files = file_engine.files();
-        let mut file_contents: Vec<Vec<u8>> = Vec::new();
-        file_contents.reserve(files.len());
+        let mut file_contents: Vec<Vec<u8>> = Vec::with_capacity(files.len());
 
         for file_name in &files {
             if let Some(contents) = file_engine.read_file(file_name).await {
@@ -46,15 +45,11 @@ pub fn ImageUploadButton(props: ImageUploadButtonProps) -> Element {
             is_loading.set(false);
             info!("Server responded with {:#?}", resp);
 
-            match resp {
-                Ok(mut images) => {
-                    let mut image_ids: Vec<_> = images.iter().map(|(a, _)| a.clone()).collect();
-                    images.into_iter().for_each(|(image, variant)| {
-                        thumbnails.write().insert(image, variant);
-                    });
-                    info!("Image upload completed");
-                }
-                Err(e) => {}
+            if let Ok(images) = resp {
+                images.into_iter().for_each(|(image, variant)| {
+                    thumbnails.write().insert(image, variant);
+                });
+                info!("Image upload completed");
             }
         }
     };
@@ -24,10 +24,18 @@ mod footer;
 pub use footer::*;
 mod cart;
 pub use cart::{use_cart, Cart, CartContents, CartState};
+mod currency;
+pub use currency::{use_currency, CurrencyPicker};
 mod admin;
 pub use admin::auth::Auth;
 pub use admin::category::CategoryList;
+pub use admin::currency::AdminCurrencies;
+pub use admin::dashboard::AdminDashboard;
+pub use admin::import::AdminProductImport;
+pub use admin::orders::AdminOrders;
 pub use admin::product::list::ProductList;
+pub use admin::reviews::AdminReviews;
+pub use admin::tax::AdminOssReport;
 mod close_button;
 pub use close_button::CloseButton;
 mod image_upload;
@@ -43,3 +51,17 @@ mod category_bar;
 pub use category_bar::*;
 mod order;
 pub use order::*;
+mod stock_notification;
+pub use stock_notification::*;
+mod recovery;
+pub use recovery::*;
+mod account;
+pub use account::AccountPage;
+mod wishlist;
+pub use wishlist::{use_wishlist, Wishlist, WishlistState, WishlistToggle};
+mod review;
+pub use review::{ProductReviewList, ProductReviewSummary, ReviewForm};
+mod related;
+pub use related::{CartSuggestions, RelatedProducts};
+mod seo;
+pub use seo::{JsonLd, PageMeta};
@@ -2,19 +2,20 @@ use dioxus::prelude::*;
 
 #[component]
 pub fn OrderCompleted(uuid: ReadOnlySignal<String>) -> Element {
+    let messages = crate::i18n::use_messages();
     rsx! {
         div {
             class: "about",
-            h2 {"Order skapad!"}
+            h2 {"{messages.order_created}"}
 
             p {
-                "Tack för dit köp! Inom kort får du en orderbekräftelse via email!"
+                "{messages.order_thanks}"
             }
 
-            p { "Har du några tankar eller funderingar så kontakta oss på: sjfconcept@hotmail.com"}
+            p { "{messages.contact_us}"}
             p {
                 class:"order-reference",
-                "Referens: {uuid}"
+                "{messages.reference} {uuid}"
             }
 
             div {
@@ -28,19 +29,20 @@ pub fn OrderCompleted(uuid: ReadOnlySignal<String>) -> Element {
 
 #[component]
 pub fn OrderCanceled(uuid: ReadOnlySignal<String>) -> Element {
+    let messages = crate::i18n::use_messages();
     rsx! {
         div {
             class: "about",
-            h2 {"Order avbruten!"}
+            h2 {"{messages.order_canceled}"}
 
             p {
-                "Köpet har avbrutits och du har inte blivit debiterad. Om du skulle ångra dig så finns dina varor kvar i varukorgen redo att beställas."
+                "{messages.order_canceled_text}"
             }
 
-            p { "Har du några tankar eller funderingar så kontakta oss på: sjfconcept@hotmail.com"}
+            p { "{messages.contact_us}"}
             p {
                 class:"order-reference",
-                "Referens: {uuid}"
+                "{messages.reference} {uuid}"
             }
 
             div {
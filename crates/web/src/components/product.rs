@@ -85,6 +85,8 @@ pub fn ProductImages(images: ReadOnlySignal<Vec<sjf_api::product::Image>>) -> El
 #[component]
 pub fn AddToCartButton(product: ReadOnlySignal<sjf_api::product::Product>) -> Element {
     let mut cart = crate::components::cart::use_cart();
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
 
     let added = use_memo(move || {
         let cart = cart.read();
@@ -94,6 +96,13 @@ pub fn AddToCartButton(product: ReadOnlySignal<sjf_api::product::Product>) -> El
     });
 
     let stock = product.read().stock;
+    let stock_note = product.read().delivery_note(1, locale).or_else(|| {
+        product
+            .read()
+            .on_hand()
+            .filter(|s| *s < 10)
+            .map(|s| locale.only_left(s))
+    });
 
     rsx! {
 
@@ -101,8 +110,9 @@ pub fn AddToCartButton(product: ReadOnlySignal<sjf_api::product::Product>) -> El
         {
             div {
                 class: "outofstock",
-                "Slutsåld!"
+                "{messages.sold_out}"
             }
+            crate::components::BackInStockForm { product_id: product.read().id }
         }
         else {
             button {
@@ -112,21 +122,18 @@ pub fn AddToCartButton(product: ReadOnlySignal<sjf_api::product::Product>) -> El
                     );
                 },
                 if added() {
-                    "Tillagd!"
+                    "{messages.added}"
                 }
                 else
                 {
-                    "Lägg i varukorg"
+                    "{messages.add_to_cart}"
                 }
             }
-            if let Some(s) = stock
+            if let Some(note) = stock_note
             {
-                if s < 10
-                {
-                    span {
-                        class: "lowstock",
-                        "Endast {s} kvar i lager"
-                    }
+                span {
+                    class: "lowstock",
+                    "{note}"
                 }
             }
 
@@ -137,8 +144,39 @@ pub fn AddToCartButton(product: ReadOnlySignal<sjf_api::product::Product>) -> El
 
 #[component]
 pub fn Product(product: ReadOnlySignal<sjf_api::product::Product>) -> Element {
+    let currency = crate::components::use_currency();
+    let reviews =
+        use_server_future(
+            move || async move { crate::server::get_reviews(product().id).await.ok() },
+        )?;
+    let site = super::seo::use_site_url();
+    let locale = crate::i18n::use_locale();
+    let category_path = use_memo(move || product().category_name);
+    let labels = super::category_bar::use_category_labels(category_path.into())?;
+    let specs = use_server_future(move || async move {
+        crate::server::get_specs(product().id)
+            .await
+            .unwrap_or_default()
+    })?;
+
     rsx! {
-        document::Title { "SJF Concept - {product().name}" }
+        crate::components::PageMeta {
+            title: "SJF Concept - {product().name}",
+            description: super::seo::summary(&product().description),
+            path: product().preview().product_url_in(locale),
+            image: super::seo::largest_image(&product().images),
+            kind: "product",
+        }
+        crate::components::JsonLd { data: super::seo::product_data(&site, locale, &product(), reviews().flatten().as_ref()) }
+        crate::components::JsonLd {
+            data: super::seo::breadcrumbs(
+                &site,
+                locale,
+                &product().category_name,
+                &labels().unwrap_or_default(),
+                Some((&product().name, product().preview().product_url_in(locale))),
+            )
+        }
         crate::components::CategoryBar { path: product().category_name }
 
 
@@ -152,15 +190,36 @@ pub fn Product(product: ReadOnlySignal<sjf_api::product::Product>) -> Element {
 
                 div {
                     class: "product-details",
-                    h2 { "{product().name}"}
-                    span { class: "price", "{product().price}kr"}
+                    h2 {
+                        "{product().name}"
+                        crate::components::WishlistToggle { product_id: product().id }
+                    }
+                    if let Some(Some(reviews)) = reviews() {
+                        crate::components::ProductReviewSummary { reviews }
+                    }
+                    span { class: "price", "{currency().format(product().price)}"}
                     AddToCartButton { product  }
                     p {
                         "{product().description}"
                     }
+                    if let Some(specs) = specs().filter(|s| !s.is_empty()) {
+                        table {
+                            class: "specs",
+                            for spec in specs {
+                                tr {
+                                    th { "{spec.name}" }
+                                    td { "{spec.value}" }
+                                }
+                            }
+                        }
+                    }
+                    if let Some(Some(reviews)) = reviews() {
+                        crate::components::ProductReviewList { reviews }
+                    }
                 }
 
             }
+            crate::components::RelatedProducts { product_id: product().id }
 
 
         }
@@ -0,0 +1,123 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+
+use crate::components::{CartState, use_cart};
+
+#[component]
+pub fn RestoreCheckout(token: ReadOnlySignal<String>) -> Element {
+    let mut cart_state = use_cart();
+    let messages = crate::i18n::use_messages();
+
+    // Some(true) when something could be put back in the cart, None on failure
+    let restored = use_resource(move || async move {
+        match crate::server::restore_checkout(token()).await {
+            Ok(Some(req)) => match CartState::restore(req).await {
+                Ok(cart) => {
+                    let restored = !cart.is_empty();
+                    cart_state.set(cart);
+                    Some(restored)
+                }
+                Err(e) => {
+                    warn!("Failed to restore cart {:#?}", e);
+                    None
+                }
+            },
+            Ok(None) => Some(false),
+            Err(e) => {
+                warn!("Failed to restore checkout {:#?}", e);
+                None
+            }
+        }
+    });
+
+    rsx! {
+        div {
+            class: "about",
+            match *restored.read() {
+                Some(Some(true)) => rsx! {
+                    h2 {"{messages.welcome_back}"}
+                    p {
+                        "{messages.cart_restored}"
+                    }
+                },
+                Some(Some(false)) => rsx! {
+                    h2 {"{messages.cart_not_restored}"}
+                    p {
+                        "{messages.cart_link_expired}"
+                    }
+                },
+                Some(None) => rsx! {
+                    h2 {"{messages.error_heading}"}
+                    p {
+                        "{messages.cart_restore_failed}"
+                    }
+                },
+                None => rsx! {
+                    h2 {"{messages.restoring_cart}"}
+                }
+            }
+
+            div {
+                img {
+                    src:  crate::components::footer::FOOTER_LOGO
+                },
+            }
+        }
+    }
+}
+
+/// Asks before stopping the reminders, so that following the link in the
+/// email, as link checkers of mail servers do, doesn't stop them.
+#[component]
+pub fn RemindersStopped(token: ReadOnlySignal<String>) -> Element {
+    // Some(true) once stopped, Some(false) on failure
+    let mut stopped = use_signal(|| None);
+    let mut pending = use_signal(|| false);
+    let messages = crate::i18n::use_messages();
+
+    rsx! {
+        div {
+            class: "about",
+            match stopped() {
+                Some(true) => rsx! {
+                    h2 {"{messages.reminders_stopped}"}
+                    p {
+                        "{messages.reminders_stopped_text}"
+                    }
+                },
+                Some(false) => rsx! {
+                    h2 {"{messages.error_heading}"}
+                    p {
+                        "{messages.reminders_stop_failed}"
+                    }
+                },
+                None => rsx! {
+                    h2 {"{messages.stop_reminders_question}"}
+                    p {
+                        "{messages.stop_reminders_text}"
+                    }
+                    button {
+                        disabled: pending(),
+                        onclick: move |_| async move {
+                            pending.set(true);
+                            match crate::server::stop_reminders(token()).await {
+                                Ok(()) => stopped.set(Some(true)),
+                                Err(e) => {
+                                    warn!("Failed to stop reminders {:#?}", e);
+                                    stopped.set(Some(false));
+                                }
+                            }
+                        },
+                        "{messages.stop_reminders}"
+                    }
+                },
+            }
+
+            div {
+                img {
+                    src:  crate::components::footer::FOOTER_LOGO
+                },
+            }
+        }
+    }
+}
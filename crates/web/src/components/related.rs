@@ -0,0 +1,80 @@
+use dioxus::prelude::*;
+use sjf_api::product::Preview;
+
+use crate::components::{ProductPreview, use_cart};
+
+/// Recommendations shown under the product details.
+const RELATED_PRODUCTS: u32 = 8;
+/// Recommendations shown in the cart.
+const CART_SUGGESTIONS: usize = 3;
+/// Cart items whose recommendations are used for the cart suggestions.
+const CART_SOURCES: usize = 3;
+
+#[component]
+pub fn RelatedProducts(product_id: ReadOnlySignal<u32>) -> Element {
+    let locale = crate::i18n::use_locale();
+    let related = use_server_future(move || async move {
+        crate::server::get_related(product_id(), RELATED_PRODUCTS, locale)
+            .await
+            .unwrap_or_default()
+    })?;
+
+    rsx! {
+        if let Some(previews) = related() && !previews.is_empty() {
+            div {
+                class: "related",
+                h3 { "{locale.messages().you_may_also_like}" }
+                div {
+                    class: "product-previews",
+                    for preview in previews {
+                        ProductPreview { key: "{preview.id}", preview }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn CartSuggestions() -> Element {
+    let currency = crate::components::use_currency();
+    let mut cart_state = use_cart();
+    let locale = crate::i18n::use_locale();
+    let in_cart = use_memo(move || cart_state.read().product_ids());
+
+    let suggestions = use_resource(move || async move {
+        let in_cart = in_cart();
+        let mut res: Vec<Preview> = Vec::new();
+        for id in in_cart.iter().take(CART_SOURCES) {
+            let limit = (CART_SUGGESTIONS + in_cart.len()) as u32;
+            for preview in crate::server::get_related(*id, limit, locale)
+                .await
+                .unwrap_or_default()
+            {
+                if !in_cart.contains(&preview.id) && !res.iter().any(|p| p.id == preview.id) {
+                    res.push(preview);
+                }
+            }
+        }
+        res.truncate(CART_SUGGESTIONS);
+        res
+    });
+
+    rsx! {
+        if let Some(previews) = suggestions() && !previews.is_empty() {
+            div {
+                class: "suggestions",
+                span { "{locale.messages().others_also_bought}" }
+                for preview in previews {
+                    Link {
+                        key: "{preview.id}",
+                        to: crate::Route::product_page(locale, preview.product_path(), Default::default()),
+                        onclick: move |_| cart_state.write().close(),
+                        span { "{preview.name}" }
+                        span { "{currency().format(preview.price)}" }
+                    }
+                }
+            }
+        }
+    }
+}
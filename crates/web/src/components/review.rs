@@ -0,0 +1,162 @@
+use dioxus::prelude::*;
+use sjf_api::review::{
+    MAX_AUTHOR_LENGTH, MAX_REVIEW_LENGTH, ProductReviews, SubmitReviewRequest, SubmitReviewStatus,
+};
+
+fn stars(rating: u8) -> String {
+    let rating = rating.min(5) as usize;
+    "★".repeat(rating) + &"☆".repeat(5 - rating)
+}
+
+#[derive(PartialEq, Clone)]
+enum State {
+    Idle,
+    Sending,
+    Done(SubmitReviewStatus),
+    Error,
+}
+
+#[component]
+pub fn ReviewForm(token: ReadOnlySignal<String>) -> Element {
+    let mut rating = use_signal(|| 5u8);
+    let mut text = use_signal(String::new);
+    let mut author = use_signal(String::new);
+    let mut state = use_signal(|| State::Idle);
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+
+    let invitation =
+        use_resource(
+            move || async move { crate::server::get_review_invitation(token()).await.ok() },
+        );
+
+    let message = match &*state.read() {
+        State::Done(SubmitReviewStatus::Submitted) => Some(messages.review_submitted),
+        State::Done(SubmitReviewStatus::AlreadyReviewed) => Some(messages.already_reviewed),
+        State::Done(SubmitReviewStatus::InvalidToken) => Some(messages.invalid_link),
+        State::Done(SubmitReviewStatus::Invalid) => Some(messages.invalid_review),
+        State::Done(SubmitReviewStatus::RateLimited) => Some(messages.too_many_reviews),
+        State::Error => Some(messages.something_went_wrong),
+        State::Idle | State::Sending => None,
+    };
+    let submitted = *state.read() == State::Done(SubmitReviewStatus::Submitted);
+
+    rsx! {
+        div {
+            class: "about review-form",
+            match &*invitation.read() {
+                Some(Some(Some(invitation))) if invitation.already_reviewed => rsx! {
+                    h2 {"{messages.thanks}"}
+                    p {{locale.already_reviewed(&invitation.product_name)}}
+                },
+                Some(Some(Some(invitation))) => rsx! {
+                    h2 {{locale.review_heading(&invitation.product_name)}}
+                    if !submitted {
+                        form {
+                            onsubmit: move |evt| async move {
+                                evt.prevent_default();
+                                state.set(State::Sending);
+                                let req = SubmitReviewRequest {
+                                    token: token(),
+                                    rating: rating(),
+                                    text: text(),
+                                    author: author(),
+                                };
+                                match crate::server::submit_review(req).await {
+                                    Ok(status) => state.set(State::Done(status)),
+                                    Err(_) => state.set(State::Error),
+                                }
+                            },
+                            div {
+                                class: "rating-input",
+                                for value in 1..=5u8 {
+                                    span {
+                                        key: "{value}",
+                                        class: if value <= rating() { "selected" },
+                                        onclick: move |_| rating.set(value),
+                                        if value <= rating() { "★" } else { "☆" }
+                                    }
+                                }
+                            }
+                            label { "{messages.review_name}" }
+                            input {
+                                required: true,
+                                maxlength: "{MAX_AUTHOR_LENGTH}",
+                                value: "{author}",
+                                oninput: move |evt| author.set(evt.value()),
+                            }
+                            label { "{messages.review_text}" }
+                            textarea {
+                                required: true,
+                                maxlength: "{MAX_REVIEW_LENGTH}",
+                                value: "{text}",
+                                oninput: move |evt| text.set(evt.value()),
+                            }
+                            button {
+                                type: "submit",
+                                disabled: *state.read() == State::Sending,
+                                "{messages.send}"
+                            }
+                        }
+                    }
+                    if let Some(message) = message {
+                        p { "{message}" }
+                    }
+                },
+                Some(Some(None)) => rsx! {
+                    h2 {"{messages.invalid_link}"}
+                    p {"{messages.check_link}"}
+                },
+                Some(None) => rsx! {
+                    h2 {"{messages.error_heading}"}
+                    p {"{messages.try_again_later}"}
+                },
+                None => rsx! {
+                    h2 {"{messages.loading}"}
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ProductReviewSummary(reviews: ReadOnlySignal<ProductReviews>) -> Element {
+    let locale = crate::i18n::use_locale();
+    rsx! {
+        if let Some(summary) = reviews().summary {
+            span {
+                class: "rating",
+                title: locale.rating(summary.average),
+                "{stars(summary.average.round() as u8)} ({summary.count})"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ProductReviewList(reviews: ReadOnlySignal<ProductReviews>) -> Element {
+    let messages = crate::i18n::use_messages();
+    rsx! {
+        if !reviews().reviews.is_empty() {
+            div {
+                class: "reviews",
+                h3 { "{messages.reviews}" }
+                for review in reviews().reviews {
+                    div {
+                        key: "{review.id}",
+                        class: "review",
+                        div {
+                            span { class: "rating", "{stars(review.rating)}" }
+                            span { class: "author", "{review.author}" }
+                            span { class: "date", "{review.created.format(\"%Y-%m-%d\")}" }
+                        }
+                        p { "{review.text}" }
+                        if let Some(reply) = review.reply {
+                            p { class: "reply", "{messages.staff_reply} {reply}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use dioxus::prelude::*;
+use serde_json::{Value, json};
+use sjf_api::i18n::Locale;
+use sjf_api::product::{Availability, Image, Product, category_url_in};
+use sjf_api::review::ProductReviews;
+use sjf_api::stock::StockPolicy;
+
+const SITE_NAME: &str = "SJF Concept";
+/// Search engines cut descriptions around this length.
+const DESCRIPTION_LENGTH: usize = 160;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "server")] {
+        fn site_url() -> String {
+            crate::server::site_url().to_owned()
+        }
+    } else {
+        fn site_url() -> String {
+            String::new()
+        }
+    }
+}
+
+/// The absolute URL of the shop, taken from the server during SSR.
+pub fn use_site_url() -> String {
+    use_server_cached(site_url)
+}
+
+/// The URL of the largest variant of the first image.
+pub fn largest_image(images: &[Image]) -> Option<String> {
+    images
+        .first()
+        .and_then(|i| i.sizes.last())
+        .map(|v| v.url.clone())
+}
+
+/// Shortens `text` to a single line that fits in a meta description.
+pub fn summary(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= DESCRIPTION_LENGTH {
+        return text;
+    }
+    let mut short: String = text.chars().take(DESCRIPTION_LENGTH - 1).collect();
+    if let Some(space) = short.rfind(' ') {
+        short.truncate(space);
+    }
+    short + "…"
+}
+
+/// Meta description, canonical URL, links to the page in the other languages
+/// and OpenGraph/Twitter tags of a page. `path` and `image` are relative to the site.
+#[component]
+pub fn PageMeta(
+    title: String,
+    description: String,
+    path: String,
+    image: Option<String>,
+    #[props(default = "website".to_string())] kind: String,
+) -> Element {
+    let site = use_site_url();
+    let route = use_route::<crate::Route>();
+    let locale = route.locale();
+    let url = format!("{site}{path}");
+    let image = image.map(|i| format!("{site}{i}"));
+    let card = match image {
+        Some(_) => "summary_large_image",
+        None => "summary",
+    };
+
+    rsx! {
+        document::Title { "{title}" }
+        document::Meta { name: "description", content: "{description}" }
+        document::Link { rel: "canonical", href: "{url}" }
+        document::Meta { property: "og:site_name", content: SITE_NAME }
+        document::Meta { property: "og:type", content: "{kind}" }
+        document::Meta { property: "og:title", content: "{title}" }
+        document::Meta { property: "og:description", content: "{description}" }
+        document::Meta { property: "og:url", content: "{url}" }
+        for other in Locale::ALL.into_iter().filter(|l| *l != locale) {
+            document::Link {
+                rel: "alternate",
+                hreflang: other.code(),
+                href: "{site}{route.translated(other)}"
+            }
+        }
+        document::Meta { property: "og:locale", content: locale.territory() }
+        document::Meta { name: "twitter:card", content: card }
+        document::Meta { name: "twitter:title", content: "{title}" }
+        document::Meta { name: "twitter:description", content: "{description}" }
+        if let Some(image) = image {
+            document::Meta { property: "og:image", content: "{image}" }
+            document::Meta { name: "twitter:image", content: "{image}" }
+        }
+    }
+}
+
+/// schema.org data embedded in the page for search engines.
+#[component]
+pub fn JsonLd(data: Value) -> Element {
+    rsx! {
+        script {
+            r#type: "application/ld+json",
+            // Keep the text from closing the script element it is embedded in
+            dangerous_inner_html: data.to_string().replace("</", "<\\/")
+        }
+    }
+}
+
+fn availability(product: &Product) -> &'static str {
+    match product.availability() {
+        Availability::InStock => "https://schema.org/InStock",
+        Availability::OutOfStock => "https://schema.org/OutOfStock",
+        Availability::PreOrder => "https://schema.org/PreOrder",
+        Availability::BackOrder => "https://schema.org/BackOrder",
+    }
+}
+
+/// A `Product` with its `Offer`, and the rating when there are reviews.
+pub fn product_data(
+    site: &str,
+    locale: Locale,
+    product: &Product,
+    reviews: Option<&ProductReviews>,
+) -> Value {
+    let url = format!("{site}{}", product.preview().product_url_in(locale));
+    let mut data = json!({
+        "@context": "https://schema.org/",
+        "@type": "Product",
+        "name": product.name,
+        "description": product.description,
+        "sku": product.id.to_string(),
+        "url": url,
+        "image": product
+            .images
+            .iter()
+            .filter_map(|i| i.sizes.last())
+            .map(|v| format!("{site}{}", v.url))
+            .collect::<Vec<_>>(),
+        "brand": { "@type": "Brand", "name": SITE_NAME },
+        "offers": {
+            "@type": "Offer",
+            "url": url,
+            "price": product.price,
+            "priceCurrency": "SEK",
+            "availability": availability(product),
+            "itemCondition": "https://schema.org/NewCondition",
+        },
+    });
+    if let StockPolicy::Preorder { ship_date } = &product.stock_policy {
+        data["offers"]["availabilityStarts"] = ship_date.format("%Y-%m-%d").to_string().into();
+    }
+    if let Some(reviews) = reviews
+        && let Some(summary) = reviews.summary
+    {
+        data["aggregateRating"] = json!({
+            "@type": "AggregateRating",
+            "ratingValue": format!("{:.1}", summary.average),
+            "reviewCount": summary.count,
+        });
+        data["review"] = reviews
+            .reviews
+            .iter()
+            .map(|r| {
+                json!({
+                    "@type": "Review",
+                    "reviewRating": { "@type": "Rating", "ratingValue": r.rating },
+                    "author": { "@type": "Person", "name": r.author },
+                    "datePublished": r.created.format("%Y-%m-%d").to_string(),
+                    "reviewBody": r.text,
+                })
+            })
+            .collect();
+    }
+    data
+}
+
+/// A `BreadcrumbList` of the category path named by `labels`, ending with
+/// `last` when given.
+pub fn breadcrumbs(
+    site: &str,
+    locale: Locale,
+    category_name: &[String],
+    labels: &[String],
+    last: Option<(&str, String)>,
+) -> Value {
+    let mut items: Vec<(String, String)> = (1..=category_name.len())
+        .map(|n| {
+            (
+                labels.get(n - 1).unwrap_or(&category_name[n - 1]).clone(),
+                format!("{site}{}", category_url_in(locale, &category_name[..n])),
+            )
+        })
+        .collect();
+    if let Some((name, path)) = last {
+        items.push((name.to_owned(), format!("{site}{path}")));
+    }
+
+    json!({
+        "@context": "https://schema.org/",
+        "@type": "BreadcrumbList",
+        "itemListElement": items
+            .into_iter()
+            .enumerate()
+            .map(|(n, (name, url))| json!({
+                "@type": "ListItem",
+                "position": n + 1,
+                "name": name,
+                "item": url,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
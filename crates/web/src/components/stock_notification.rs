@@ -0,0 +1,103 @@
+use dioxus::prelude::*;
+use sjf_api::stock::SubscribeStatus;
+
+#[derive(PartialEq, Clone)]
+enum State {
+    Idle,
+    Sending,
+    Done(SubscribeStatus),
+    Error,
+}
+
+#[component]
+pub fn BackInStockForm(product_id: ReadOnlySignal<u32>) -> Element {
+    let mut email = use_signal(String::new);
+    let mut state = use_signal(|| State::Idle);
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+
+    let message = match &*state.read() {
+        State::Done(SubscribeStatus::ConfirmationSent) => Some(messages.confirm_subscription),
+        State::Done(SubscribeStatus::InvalidEmail) => Some(messages.invalid_email),
+        State::Done(SubscribeStatus::RateLimited) => Some(messages.rate_limited),
+        State::Error => Some(messages.something_went_wrong),
+        State::Idle | State::Sending => None,
+    };
+
+    rsx! {
+        form {
+            class: "stocknotify",
+            onsubmit: move |evt| async move {
+                evt.prevent_default();
+                state.set(State::Sending);
+                let res = crate::server::subscribe_back_in_stock(product_id(), email(), locale).await;
+                match res {
+                    Ok(status) => state.set(State::Done(status)),
+                    Err(_) => state.set(State::Error),
+                }
+            },
+            label {
+                "{messages.notify_in_stock}"
+            }
+            div {
+                input {
+                    type: "email",
+                    placeholder: messages.your_email,
+                    required: true,
+                    value: "{email}",
+                    oninput: move |evt| email.set(evt.value()),
+                }
+                button {
+                    type: "submit",
+                    disabled: *state.read() == State::Sending,
+                    "{messages.subscribe}"
+                }
+            }
+            if let Some(message) = message {
+                span { "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn SubscriptionConfirmed(token: ReadOnlySignal<String>) -> Element {
+    let messages = crate::i18n::use_messages();
+    rsx! {
+        div {
+            class: "about",
+            h2 {"{messages.subscription_confirmed}"}
+
+            p {
+                "{messages.subscription_confirmed_text}"
+            }
+
+            div {
+                img {
+                    src:  crate::components::footer::FOOTER_LOGO
+                },
+            }
+        }
+    }
+}
+
+#[component]
+pub fn SubscriptionCanceled(token: ReadOnlySignal<String>) -> Element {
+    let messages = crate::i18n::use_messages();
+    rsx! {
+        div {
+            class: "about",
+            h2 {"{messages.subscription_canceled}"}
+
+            p {
+                "{messages.subscription_canceled_text}"
+            }
+
+            div {
+                img {
+                    src:  crate::components::footer::FOOTER_LOGO
+                },
+            }
+        }
+    }
+}
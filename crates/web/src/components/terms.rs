@@ -1,12 +1,25 @@
-use dioxus::{logger::tracing::info, prelude::*};
+use dioxus::prelude::*;
 
 #[component]
 pub fn TermsAndConditions() -> Element {
+    let english = crate::i18n::use_locale() == sjf_api::i18n::Locale::En;
+    let title = match english {
+        true => "SJF Concept - Terms",
+        false => "SJF Concept - Villkor",
+    };
     rsx! {
-        document::Title { "SJF Concept - Villkor" }
+        document::Title { "{title}" }
         div {
             class: "terms",
 
+            if english {
+                h2 { "Terms and conditions" }
+                p {"Our terms and conditions are only available in Swedish, and the Swedish text below is the one
+                that applies to your purchase. In short: prices are in SEK including VAT, you have a 14 day right
+                of withdrawal on products that are not made to order, and we only use your details to handle your
+                order. If anything is unclear, contact us at sjfconcept@hotmail.com."}
+            }
+
             h2 { "Användarvillkor för SJF Concept"}
 
             p { "Senast uppdaterad: 4/8 2025" }
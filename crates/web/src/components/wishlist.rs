@@ -0,0 +1,179 @@
+use std::collections::BTreeSet;
+
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use sjf_api::product::ProductId;
+use sjf_api::wishlist::SyncWishlistRequest;
+
+use crate::components::ProductPreview;
+
+fn wishlist_name() -> String {
+    format!(
+        "wishlist-{}-{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION_MAJOR")
+    )
+}
+
+#[derive(PartialEq, Default)]
+pub struct WishlistState {
+    items: BTreeSet<ProductId>,
+}
+
+impl WishlistState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "web")]
+    fn get_storage() -> Option<web_sys::Storage> {
+        use web_sys::window;
+        window().map(|w| w.local_storage().unwrap_or_default())?
+    }
+
+    #[cfg(feature = "web")]
+    fn save_local(&self) {
+        Self::get_storage().map(|s| {
+            let _ = s.set_item(&wishlist_name(), &serde_json::to_string(&self.items)?);
+            Ok::<(), serde_json::Error>(())
+        });
+    }
+
+    #[cfg(feature = "web")]
+    fn save(&self) {
+        self.save_local();
+
+        let req = SyncWishlistRequest {
+            items: self.items.iter().cloned().collect(),
+            merge: false,
+        };
+        spawn(async move {
+            if let Err(e) = crate::server::sync_wishlist(req).await {
+                warn!("Failed to store wishlist on server {:#?}", e);
+            }
+        });
+    }
+
+    /// Reads the local wishlist and merges it with the customer account, if logged in.
+    #[cfg(feature = "web")]
+    pub async fn load() -> Self {
+        let items: BTreeSet<ProductId> = Self::get_storage()
+            .and_then(|s| s.get_item(&wishlist_name()).ok().flatten())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        let req = SyncWishlistRequest {
+            items: items.iter().cloned().collect(),
+            merge: true,
+        };
+        match crate::server::sync_wishlist(req).await {
+            Ok(Some(synced)) => {
+                let state = Self {
+                    items: synced.into_iter().collect(),
+                };
+                state.save_local();
+                state
+            }
+            Ok(None) => Self { items },
+            Err(e) => {
+                warn!("Failed to sync wishlist {:#?}", e);
+                Self { items }
+            }
+        }
+    }
+
+    pub fn contains(&self, id: &ProductId) -> bool {
+        self.items.contains(id)
+    }
+
+    pub fn toggle(&mut self, id: ProductId) {
+        if !self.items.remove(&id) {
+            self.items.insert(id);
+        }
+        self.save();
+    }
+
+    /// Forgets products that no longer exist.
+    fn retain(&mut self, existing: &BTreeSet<ProductId>) {
+        self.items.retain(|id| existing.contains(id));
+        self.save();
+    }
+}
+
+type WishlistSignal = Signal<WishlistState>;
+pub fn use_wishlist() -> WishlistSignal {
+    use_context::<WishlistSignal>()
+}
+
+#[component]
+pub fn WishlistToggle(product_id: ReadOnlySignal<ProductId>) -> Element {
+    let mut wishlist = use_wishlist();
+    let messages = crate::i18n::use_messages();
+    let wished = use_memo(move || wishlist.read().contains(&product_id()));
+
+    rsx! {
+        button {
+            class: if wished() { "wish active" } else { "wish" },
+            title: if wished() { messages.remove_favourite } else { messages.save_favourite },
+            onclick: move |evt| {
+                // The toggle sits inside product links
+                evt.prevent_default();
+                evt.stop_propagation();
+                wishlist.write().toggle(product_id());
+            },
+            if wished() { "♥" } else { "♡" }
+        }
+    }
+}
+
+#[component]
+pub fn Wishlist() -> Element {
+    let mut wishlist = use_wishlist();
+    let locale = crate::i18n::use_locale();
+    let messages = locale.messages();
+
+    let products = use_resource(move || async move {
+        let ids: Vec<ProductId> = wishlist.read().items.iter().cloned().collect();
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let products = crate::server::get_specified_products(ids, locale).await?;
+        let existing: BTreeSet<ProductId> = products.iter().map(|p| p.id).collect();
+        if wishlist
+            .peek()
+            .items
+            .iter()
+            .any(|id| !existing.contains(id))
+        {
+            wishlist.write().retain(&existing);
+        }
+        Ok::<_, ServerFnError>(products)
+    });
+
+    rsx! {
+        document::Title { "SJF Concept - {messages.favourites}" }
+        h1 { class: "wishlist-title", "{messages.favourites}" }
+        match &*products.read_unchecked() {
+            Some(Ok(products)) if products.is_empty() => rsx! {
+                p {
+                    class: "wishlist-empty",
+                    "{messages.no_favourites}"
+                }
+            },
+            Some(Ok(products)) => rsx! {
+                div {
+                    class: "product-previews",
+                    for product in products {
+                        ProductPreview { key: "{product.id}", preview: product.preview() }
+                    }
+                }
+            },
+            Some(Err(_)) => rsx! {
+                p { class: "wishlist-empty", "{messages.favourites_failed}" }
+            },
+            None => rsx! {
+                p { class: "wishlist-empty", "{messages.loading}" }
+            }
+        }
+    }
+}
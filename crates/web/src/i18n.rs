@@ -0,0 +1,270 @@
+use dioxus::prelude::*;
+use sjf_api::i18n::{Locale, Messages};
+use sjf_api::product::ListingQuery;
+
+use crate::Route;
+use crate::components::{
+    About, AccountPage, FrontPage, OrderCanceled, OrderCompleted, RemindersStopped,
+    RestoreCheckout, ReviewForm, SubscriptionCanceled, SubscriptionConfirmed, TermsAndConditions,
+    Wishlist,
+};
+
+// The English pages are the Swedish components under English paths, they pick
+// the language up from the route.
+
+#[component]
+pub fn EnFrontPage() -> Element {
+    rsx! { FrontPage {} }
+}
+
+#[component]
+pub fn EnProductPage(
+    segments: ReadOnlySignal<Vec<String>>,
+    query: ReadOnlySignal<ListingQuery>,
+) -> Element {
+    rsx! { crate::ProductPage { segments, query } }
+}
+
+#[component]
+pub fn EnOrderCanceled(uuid: String) -> Element {
+    rsx! { OrderCanceled { uuid } }
+}
+
+#[component]
+pub fn EnOrderCompleted(uuid: String) -> Element {
+    rsx! { OrderCompleted { uuid } }
+}
+
+#[component]
+pub fn EnSubscriptionConfirmed(token: String) -> Element {
+    rsx! { SubscriptionConfirmed { token } }
+}
+
+#[component]
+pub fn EnSubscriptionCanceled(token: String) -> Element {
+    rsx! { SubscriptionCanceled { token } }
+}
+
+#[component]
+pub fn EnRestoreCheckout(token: String) -> Element {
+    rsx! { RestoreCheckout { token } }
+}
+
+#[component]
+pub fn EnRemindersStopped(token: String) -> Element {
+    rsx! { RemindersStopped { token } }
+}
+
+#[component]
+pub fn EnReviewForm(token: String) -> Element {
+    rsx! { ReviewForm { token } }
+}
+
+#[component]
+pub fn EnWishlist() -> Element {
+    rsx! { Wishlist {} }
+}
+
+#[component]
+pub fn EnAccountPage() -> Element {
+    rsx! { AccountPage {} }
+}
+
+#[component]
+pub fn EnAbout() -> Element {
+    rsx! { About {} }
+}
+
+#[component]
+pub fn EnTermsAndConditions() -> Element {
+    rsx! { TermsAndConditions {} }
+}
+
+impl Route {
+    pub fn locale(&self) -> Locale {
+        match self {
+            Route::EnFrontPage {}
+            | Route::EnProductPage { .. }
+            | Route::EnOrderCanceled { .. }
+            | Route::EnOrderCompleted { .. }
+            | Route::EnSubscriptionConfirmed { .. }
+            | Route::EnSubscriptionCanceled { .. }
+            | Route::EnRestoreCheckout { .. }
+            | Route::EnRemindersStopped { .. }
+            | Route::EnReviewForm { .. }
+            | Route::EnWishlist {}
+            | Route::EnAccountPage {}
+            | Route::EnAbout {}
+            | Route::EnTermsAndConditions {} => Locale::En,
+            _ => Locale::Sv,
+        }
+    }
+
+    /// The same page in another language. Pages that only exist in Swedish,
+    /// like the admin pages, stay as they are.
+    pub fn translated(&self, locale: Locale) -> Route {
+        match self.clone() {
+            Route::FrontPage {} | Route::EnFrontPage {} => Route::front_page(locale),
+            Route::ProductPage { segments, query } | Route::EnProductPage { segments, query } => {
+                Route::product_page(locale, segments, query)
+            }
+            Route::OrderCanceled { uuid } | Route::EnOrderCanceled { uuid } => match locale {
+                Locale::Sv => Route::OrderCanceled { uuid },
+                Locale::En => Route::EnOrderCanceled { uuid },
+            },
+            Route::OrderCompleted { uuid } | Route::EnOrderCompleted { uuid } => match locale {
+                Locale::Sv => Route::OrderCompleted { uuid },
+                Locale::En => Route::EnOrderCompleted { uuid },
+            },
+            Route::SubscriptionConfirmed { token } | Route::EnSubscriptionConfirmed { token } => {
+                match locale {
+                    Locale::Sv => Route::SubscriptionConfirmed { token },
+                    Locale::En => Route::EnSubscriptionConfirmed { token },
+                }
+            }
+            Route::SubscriptionCanceled { token } | Route::EnSubscriptionCanceled { token } => {
+                match locale {
+                    Locale::Sv => Route::SubscriptionCanceled { token },
+                    Locale::En => Route::EnSubscriptionCanceled { token },
+                }
+            }
+            Route::RestoreCheckout { token } | Route::EnRestoreCheckout { token } => match locale {
+                Locale::Sv => Route::RestoreCheckout { token },
+                Locale::En => Route::EnRestoreCheckout { token },
+            },
+            Route::RemindersStopped { token } | Route::EnRemindersStopped { token } => match locale
+            {
+                Locale::Sv => Route::RemindersStopped { token },
+                Locale::En => Route::EnRemindersStopped { token },
+            },
+            Route::ReviewForm { token } | Route::EnReviewForm { token } => match locale {
+                Locale::Sv => Route::ReviewForm { token },
+                Locale::En => Route::EnReviewForm { token },
+            },
+            Route::Wishlist {} | Route::EnWishlist {} => Route::wishlist(locale),
+            Route::AccountPage {} | Route::EnAccountPage {} => Route::account(locale),
+            Route::About {} | Route::EnAbout {} => Route::about(locale),
+            Route::TermsAndConditions {} | Route::EnTermsAndConditions {} => Route::terms(locale),
+            route => route,
+        }
+    }
+
+    pub fn front_page(locale: Locale) -> Route {
+        match locale {
+            Locale::Sv => Route::FrontPage {},
+            Locale::En => Route::EnFrontPage {},
+        }
+    }
+
+    pub fn product_page(locale: Locale, segments: Vec<String>, query: ListingQuery) -> Route {
+        match locale {
+            Locale::Sv => Route::ProductPage { segments, query },
+            Locale::En => Route::EnProductPage { segments, query },
+        }
+    }
+
+    pub fn wishlist(locale: Locale) -> Route {
+        match locale {
+            Locale::Sv => Route::Wishlist {},
+            Locale::En => Route::EnWishlist {},
+        }
+    }
+
+    pub fn account(locale: Locale) -> Route {
+        match locale {
+            Locale::Sv => Route::AccountPage {},
+            Locale::En => Route::EnAccountPage {},
+        }
+    }
+
+    pub fn about(locale: Locale) -> Route {
+        match locale {
+            Locale::Sv => Route::About {},
+            Locale::En => Route::EnAbout {},
+        }
+    }
+
+    pub fn terms(locale: Locale) -> Route {
+        match locale {
+            Locale::Sv => Route::TermsAndConditions {},
+            Locale::En => Route::EnTermsAndConditions {},
+        }
+    }
+}
+
+/// Language of the current page.
+pub fn use_locale() -> Locale {
+    use_route::<Route>().locale()
+}
+
+pub fn use_messages() -> &'static Messages {
+    use_locale().messages()
+}
+
+/// Links to the current page in the other languages. A full page load, so
+/// that everything already fetched is fetched again in the new language.
+#[component]
+pub fn LanguageSwitch() -> Element {
+    let route = use_route::<Route>();
+    let current = route.locale();
+
+    rsx! {
+        for locale in Locale::ALL.into_iter().filter(|l| *l != current)
+        {
+            li {
+                key: "{locale.code()}",
+                class: "language",
+                a {
+                    href: "{route.translated(locale)}",
+                    hreflang: locale.code(),
+                    lang: locale.code(),
+                    "{locale.name()}"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pages the links in emails lead to, for every language.
+    fn email_links() -> Vec<(Locale, String)> {
+        Locale::ALL
+            .into_iter()
+            .flat_map(|l| {
+                [
+                    sjf_api::stock::confirm_subscription_path(l),
+                    sjf_api::stock::unsubscribe_path(l),
+                    sjf_api::checkout::recover_checkout_path(l),
+                    sjf_api::checkout::reminder_opt_out_path(l),
+                    sjf_api::review::review_path(l),
+                ]
+                .map(|path| (l, format!("{path}/abc")))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn email_links_open_pages_in_their_language() {
+        for (locale, link) in email_links() {
+            let route: Route = link.parse().unwrap();
+            assert!(!matches!(route, Route::NotFound { .. }), "{link}");
+            assert_eq!(route.locale(), locale, "{link}");
+            assert_eq!(route.to_string(), link);
+        }
+    }
+
+    #[test]
+    fn email_link_pages_switch_language() {
+        for (_, link) in email_links() {
+            let route: Route = link.parse().unwrap();
+            for locale in Locale::ALL {
+                let translated = route.translated(locale);
+                assert_eq!(translated.locale(), locale, "{link}");
+                assert!(translated.translated(route.locale()) == route, "{link}");
+            }
+        }
+    }
+}
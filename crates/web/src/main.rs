@@ -4,8 +4,10 @@ use dioxus::prelude::*;
 //use components::{Echo, Hero};
 
 mod components;
+mod i18n;
 mod server;
-use dioxus::logger::tracing::{error, info};
+#[cfg(feature = "server")]
+use dioxus::logger::tracing::{error, info, warn};
 #[cfg(feature = "server")]
 use sjf_db as db;
 #[cfg(feature = "server")]
@@ -17,7 +19,7 @@ cfg_if::cfg_if! {
         use axum::extract::Path;
         use axum::response::IntoResponse;
         use axum::extract::Request;
-        use axum::http::{StatusCode, Uri};
+        use axum::http::StatusCode;
         use axum::middleware::Next;
         use axum::response::Response;
     }
@@ -25,7 +27,14 @@ cfg_if::cfg_if! {
 //const FAVICON: Asset = asset!("/assets/favicon.ico");
 
 use crate::components::{
-    About, Auth, CartState, CategoryList, OrderCanceled, OrderCompleted, TermsAndConditions,
+    About, AccountPage, AdminCurrencies, AdminDashboard, AdminOrders, AdminOssReport,
+    AdminProductImport, AdminReviews, Auth, CartState, CategoryList, OrderCanceled, OrderCompleted, RemindersStopped, RestoreCheckout,
+    ReviewForm, SubscriptionCanceled, SubscriptionConfirmed, TermsAndConditions, Wishlist, WishlistState,
+};
+use crate::i18n::{
+    EnAbout, EnAccountPage, EnFrontPage, EnOrderCanceled, EnOrderCompleted, EnProductPage,
+    EnRemindersStopped, EnRestoreCheckout, EnReviewForm, EnSubscriptionCanceled,
+    EnSubscriptionConfirmed, EnTermsAndConditions, EnWishlist,
 };
 
 #[derive(Routable, PartialEq, Clone)]
@@ -33,22 +42,86 @@ pub enum Route {
     #[layout(HeaderFooter)]
     #[route("/")]
     FrontPage {},
+    #[route("/admin")]
+    AdminDashboard {},
     #[route("/admin/products")]
     CategoryList {},
+    #[route("/admin/products/import")]
+    AdminProductImport {},
+    #[route("/admin/orders")]
+    AdminOrders {},
+    #[route("/admin/reviews")]
+    AdminReviews {},
+    #[route("/admin/currencies")]
+    AdminCurrencies {},
+    #[route("/admin/oss")]
+    AdminOssReport {},
     #[route("/admin/auth")]
     Auth {},
-    #[route("/produkter/:..segments")]
-    ProductPage { segments: Vec<String> },
+    #[route("/produkter/:..segments?:..query")]
+    ProductPage {
+        segments: Vec<String>,
+        query: sjf_api::product::ListingQuery,
+    },
     #[nest("/order")]
     #[route("/avbruten/:uuid")]
     OrderCanceled { uuid: String },
     #[route("/klar/:uuid")]
     OrderCompleted { uuid: String },
     #[end_nest]
+    #[nest("/bevakning")]
+    #[route("/bekrafta/:token")]
+    SubscriptionConfirmed { token: String },
+    #[route("/avsluta/:token")]
+    SubscriptionCanceled { token: String },
+    #[end_nest]
+    #[nest("/varukorg")]
+    #[route("/aterstall/:token")]
+    RestoreCheckout { token: String },
+    #[route("/inga-paminnelser/:token")]
+    RemindersStopped { token: String },
+    #[end_nest]
+    #[route("/recension/:token")]
+    ReviewForm { token: String },
+    #[route("/favoriter")]
+    Wishlist {},
+    #[route("/mina-sidor")]
+    AccountPage {},
     #[route("/om")]
     About {},
     #[route("/användarvillkor")]
     TermsAndConditions {},
+    #[nest("/en")]
+    #[route("/")]
+    EnFrontPage {},
+    #[route("/products/:..segments?:..query")]
+    EnProductPage {
+        segments: Vec<String>,
+        query: sjf_api::product::ListingQuery,
+    },
+    #[route("/order/canceled/:uuid")]
+    EnOrderCanceled { uuid: String },
+    #[route("/order/done/:uuid")]
+    EnOrderCompleted { uuid: String },
+    #[route("/alerts/confirm/:token")]
+    EnSubscriptionConfirmed { token: String },
+    #[route("/alerts/cancel/:token")]
+    EnSubscriptionCanceled { token: String },
+    #[route("/cart/restore/:token")]
+    EnRestoreCheckout { token: String },
+    #[route("/cart/no-reminders/:token")]
+    EnRemindersStopped { token: String },
+    #[route("/review/:token")]
+    EnReviewForm { token: String },
+    #[route("/favourites")]
+    EnWishlist {},
+    #[route("/my-account")]
+    EnAccountPage {},
+    #[route("/about")]
+    EnAbout {},
+    #[route("/terms")]
+    EnTermsAndConditions {},
+    #[end_nest]
     #[route("/:..segments")]
     NotFound { segments: Vec<String> },
 }
@@ -67,21 +140,39 @@ pub enum Route {
  }
 #[component]
 fn HeaderFooter() -> Element {
+    let locale = i18n::use_locale();
+    let messages = locale.messages();
     use_context_provider(|| Signal::new(None::<sjf_api::category::GetChildrenRsp>));
-    use_context_provider(|| Signal::new(components::CartState::new()));
+    use_context_provider(|| Signal::new(components::CartState::new(locale)));
+    use_context_provider(|| Signal::new(components::WishlistState::new()));
 
     let mut cart_state = components::use_cart();
+    let mut wishlist = components::use_wishlist();
 
     let _ = use_resource(move || async move {
-        if let Some(c) = CartState::load().await {
-            cart_state.set(c);
+        if let Some(c) = CartState::load(locale).await {
+            // A cart restored from a reminder link already contains everything stored
+            cart_state.with_mut(|s| {
+                if !s.is_restored() {
+                    *s = c
+                }
+            });
         }
     });
 
+    let _ = use_resource(move || async move {
+        wishlist.set(WishlistState::load().await);
+    });
+
     let has_items = use_memo(move || !cart_state.read().is_empty());
 
+    let selected_currency = use_server_future(server::currency::get_selected)?;
+    let currency = use_context_provider(|| {
+        Signal::new(selected_currency().and_then(|r| r.ok()).unwrap_or_default())
+    });
 
-    let is_demo_mode = use_server_cached(||  is_demo() );
+
+    let is_demo_mode = use_server_cached(is_demo);
 
     let banner_class = {
         if has_items() {
@@ -92,6 +183,10 @@ fn HeaderFooter() -> Element {
     };
 
     rsx! {
+        document::Meta {
+            http_equiv: "content-language",
+            content: locale.code()
+        }
         document::Link{
             rel: "preconnect",
             href:"https://fonts.googleapis.com"
@@ -110,12 +205,12 @@ fn HeaderFooter() -> Element {
         {
             div {
                 class: "banner visible",
-                "SIDAN ÄR I DEMOLÄGE!"
+                "{messages.demo_mode}"
             }
         }
         div {
             class: "{banner_class}",
-            "Fri frakt över 999kr!"
+            "{messages.free_shipping} {currency().format(sjf_api::checkout::FREE_SHIPPING_OVER)}!"
         }
         div {
             class: "content",
@@ -145,7 +240,7 @@ cfg_if::cfg_if! {
     }
 }
 
-use crate::server::{get_category_and_product, get_product};
+use crate::server::get_category_and_product;
 
 #[cfg(feature = "server")]
 pub async fn handle_image_get(Path(id): Path<(u32, u32)>) -> impl IntoResponse {
@@ -178,53 +273,302 @@ pub async fn handle_robots_get() -> impl IntoResponse {
 
     let robots_txt = match sjf_payment::is_payment_demo()
     {
-        true => include_str!("server/robots/robots_demo.txt"),
-        false=> include_str!("server/robots/robots.txt")
+        true => include_str!("server/robots/robots_demo.txt").to_owned(),
+        false=> format!(
+            "{}\nSitemap: {}/sitemap.xml\n",
+            include_str!("server/robots/robots.txt"),
+            server::site_url()
+        )
     };
 
     (headers, robots_txt)
 
 }
 
+/// Sitemap file `n`, not served in demo mode where robots.txt disallows everything.
+#[cfg(feature = "server")]
+async fn sitemap_response(n: usize) -> Result<impl IntoResponse, StatusCode> {
+    use axum::http::header;
+
+    if sjf_payment::is_payment_demo() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    match server::sitemap::get(n).await {
+        Ok(Some(xml)) => {
+            let headers = [
+                (header::CONTENT_TYPE, "application/xml"),
+                (header::CACHE_CONTROL, "public, max-age=3600"),
+            ];
+            Ok((headers, xml))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to build sitemap {:#?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+pub async fn handle_sitemap_get() -> impl IntoResponse {
+    sitemap_response(0).await
+}
+
+/// Product feeds for Google Merchant Center and Meta catalogues, not served in demo mode.
+#[cfg(feature = "server")]
+pub async fn handle_feed_get(Path(file): Path<String>) -> impl IntoResponse {
+    use axum::http::header;
+
+    if sjf_payment::is_payment_demo() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let (feed, content_type) = match file.as_str() {
+        "google.xml" => (server::feed::google().await, "application/rss+xml"),
+        "meta.csv" => (server::feed::meta().await, "text/csv; charset=utf-8"),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    match feed {
+        Ok(feed) => {
+            let headers = [
+                (header::CONTENT_TYPE, content_type),
+                (header::CACHE_CONTROL, "public, max-age=3600"),
+            ];
+            (headers, feed).into_response()
+        }
+        Err(e) => {
+            error!("Failed to build product feed {:#?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// The quarterly OSS report as CSV, `/api/auth/tax/oss/2025-Q3.csv`, with
+/// ECB rates for the euro columns as `?DKK=7.4609`. Only for staff, as
+/// everything under `api/auth`.
+#[cfg(feature = "server")]
+pub async fn handle_oss_report_get(
+    Path(file): Path<String>,
+    axum::extract::Query(rates): axum::extract::Query<std::collections::BTreeMap<String, String>>,
+) -> impl IntoResponse {
+    use axum::http::header;
+
+    let period = file
+        .strip_suffix(".csv")
+        .and_then(|p| p.split_once("-Q"))
+        .and_then(|(y, q)| Some((y.parse::<i32>().ok()?, q.parse::<u32>().ok()?)))
+        .filter(|(_, q)| (1..=4).contains(q));
+    let Some((year, quarter)) = period else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match db::tax::oss_report(year, quarter).await {
+        Ok(rows) => {
+            let headers = [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment"),
+            ];
+            let ecb = rates
+                .iter()
+                .filter_map(|(code, rate)| sjf_api::tax::parse_ecb_rate(&format!("{code}={rate}")))
+                .collect();
+            (headers, sjf_api::tax::oss_report_csv(&rows, &ecb)).into_response()
+        }
+        Err(e) => {
+            error!("Failed to build OSS report {:#?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Every product as CSV in the format of the product import,
+/// `/api/auth/products/export.csv`.
+#[cfg(feature = "server")]
+pub async fn handle_product_export_get() -> impl IntoResponse {
+    use axum::http::header;
+
+    match db::import::export().await {
+        Ok(csv) => {
+            let headers = [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment"),
+            ];
+            (headers, csv).into_response()
+        }
+        Err(e) => {
+            error!("Failed to export products {:#?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// The parts listed in the sitemap index, `/sitemaps/1.xml` and onwards.
+#[cfg(feature = "server")]
+pub async fn handle_sitemap_part_get(Path(file): Path<String>) -> impl IntoResponse {
+    match file.strip_suffix(".xml").and_then(|n| n.parse::<usize>().ok()) {
+        Some(n) if n > 0 => sitemap_response(n).await.into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(feature = "server")]
+async fn handle_stripe_webhook(headers: axum::http::HeaderMap, body: String) -> StatusCode {
+    let Some(signature) = headers
+        .get("Stripe-Signature")
+        .and_then(|s| s.to_str().ok())
+    else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match sjf_payment::handle_webhook(&body, signature).await {
+        Ok(()) => StatusCode::OK,
+        Err(sjf_payment::PaymentError::WebhookNotConfigured) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Stripe webhook failed {}", e);
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
 #[cfg(feature = "server")]
 async fn order_middleware(request: Request, next: Next) -> Response {
+    use sjf_api::i18n::Locale;
+    use sjf_api::payment::{cancel_path, success_path};
     let path = request.uri().path();
 
-    if let Some(uuid) = path.strip_prefix(&(sjf_payment::SUCCESS_PATH.to_owned() + "/")) {
+    let returned = |page: fn(Locale) -> &'static str| {
+        Locale::ALL
+            .into_iter()
+            .find_map(|l| path.strip_prefix(page(l))?.strip_prefix('/'))
+    };
+
+    if let Some(uuid) = returned(success_path) {
+        let order = db::checkout::get_order(uuid).await;
         let undo = db::checkout::commit_reservation(uuid.into()).await;
-        if let Err(e) = undo {
-            error!("Failed to commit order {} from link {}", uuid, e);
+        match (undo, order) {
+            (Err(e), _) => error!("Failed to commit order {} from link {}", uuid, e),
+            (Ok(()), Ok(items)) => {
+                match db::recovery::mark_recovered(uuid).await {
+                    Ok(true) => info!("Order {} recovered an abandoned checkout", uuid),
+                    Ok(false) => (),
+                    Err(e) => error!("Failed to mark order {} as recovered {}", uuid, e),
+                }
+                sjf_notify::notify_split_order_later(uuid, items).await;
+            }
+            (Ok(()), Err(e)) => error!("Failed to read order {} from link {}", uuid, e),
         }
-    } else if let Some(uuid) = path.strip_prefix(&(sjf_payment::CANCLE_PATH.to_owned() + "/")) {
+    } else if let Some(uuid) = returned(cancel_path) {
         let undo = db::checkout::undo_reservation(uuid.into()).await;
         if let Err(e) = undo {
             error!("Failed to undo order {} from link {}", uuid, e);
         }
+        sjf_notify::send_back_in_stock_notifications_later().await;
+    }
+
+    next.run(request).await
+}
+
+#[cfg(feature = "server")]
+async fn subscription_middleware(request: Request, next: Next) -> Response {
+    use sjf_api::i18n::Locale;
+    use sjf_api::stock::{confirm_subscription_path, unsubscribe_path};
+    let path = request.uri().path();
+
+    let token = |page: fn(Locale) -> &'static str| {
+        Locale::ALL
+            .into_iter()
+            .find_map(|l| path.strip_prefix(page(l))?.strip_prefix('/'))
+    };
+
+    if let Some(token) = token(confirm_subscription_path) {
+        match db::stock::confirm_subscription(token).await {
+            Ok(true) => sjf_notify::send_back_in_stock_notifications_later().await,
+            Ok(false) => info!("Unknown subscription {} confirmed", token),
+            Err(e) => error!("Failed to confirm subscription {} from link {}", token, e),
+        }
+    } else if let Some(token) = token(unsubscribe_path) {
+        match db::stock::unsubscribe(token).await {
+            Ok(true) => info!("Subscription {} canceled", token),
+            Ok(false) => info!("Unknown subscription {} canceled", token),
+            Err(e) => error!("Failed to unsubscribe {} from link {}", token, e),
+        }
     }
 
-    let response = next.run(request).await;
+    next.run(request).await
+}
+
+/// Days the language of the last loaded page is remembered.
+#[cfg(feature = "server")]
+const LOCALE_COOKIE_DAYS: i64 = 365;
+
+/// Sends visitors arriving at the front page from elsewhere to the one in
+/// their language, and remembers the language of the pages they load.
+#[cfg(feature = "server")]
+async fn locale_middleware(request: Request, next: Next) -> Response {
+    use axum::http::header;
+    use sjf_api::i18n::{Locale, LOCALE_COOKIE};
+    use sjf_auth::anonymous;
+
+    let headers = request.headers();
+    let path = request.uri().path();
+    let is_page = request.method() == axum::http::Method::GET
+        && !path.starts_with("/admin")
+        && headers
+            .get(header::ACCEPT)
+            .and_then(|a| a.to_str().ok())
+            .is_some_and(|a| a.contains("text/html"));
+    if !is_page {
+        return next.run(request).await;
+    }
+
+    let remembered = anonymous::read(headers, LOCALE_COOKIE).and_then(|c| Locale::from_code(&c));
+    // Picking a language in the menu links to its front page from one of ours
+    let from_site = headers
+        .get(header::REFERER)
+        .and_then(|r| r.to_str().ok())
+        .zip(headers.get(header::HOST).and_then(|h| h.to_str().ok()))
+        .is_some_and(|(referer, host)| referer.split('/').nth(2) == Some(host));
+
+    if path == "/" && !from_site {
+        let preferred = remembered.unwrap_or_else(|| {
+            headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|h| h.to_str().ok())
+                .map(Locale::from_accept_language)
+                .unwrap_or_default()
+        });
+        if preferred != Locale::Sv {
+            let to = Route::front_page(preferred).to_string();
+            return axum::response::Redirect::temporary(&to).into_response();
+        }
+    }
+
+    let locale = Locale::from_path(path);
+    let mut response = next.run(request).await;
+    if remembered != Some(locale)
+        && let Some(cookie) = anonymous::set(LOCALE_COOKIE, locale.code(), LOCALE_COOKIE_DAYS)
+    {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
     response
 }
+
 #[cfg(feature = "server")]
 async fn accept_fix(mut request: Request, next: Next) -> Response {
     if let Some(accept) = request.headers_mut().get_mut("Accept")
+        && let Ok(str) = accept.to_str()
+        && str.contains("*/*")
     {
-        if let Ok(str) = accept.to_str()
-        {
-            if str.contains("*/*")
-            {
-                use axum::http::HeaderValue;
-
-                let fixed = format!("{}, text/html",str);
-                if let Ok(patched) = HeaderValue::from_str(&fixed)
-                {
-                    *accept = patched;
-                }
-            }
+        use axum::http::HeaderValue;
+
+        let fixed = format!("{}, text/html", str);
+        if let Ok(patched) = HeaderValue::from_str(&fixed) {
+            *accept = patched;
         }
     }
-    let response = next.run(request).await;
-    response
+    next.run(request).await
 }
 
 #[cfg(feature = "server")]
@@ -281,26 +625,70 @@ async fn launch_server() {
     dioxus::logger::init(dioxus::logger::tracing::Level::INFO).expect("failed to init logger");
 
     use crate::metrics::make_prometheus;
-    use axum_prometheus::PrometheusMetricLayerBuilder;
     let (prometheus_layer, metric_handle) = make_prometheus();
 
+    use axum::routing::get;
+    use server::health::{Stage, set_stage};
+
+    // Served from the start, so that /health shows how far startup has come
+    let metric_socket_addr = {
+        let mut tmp = dioxus::cli_config::fullstack_address_or_localhost();
+        tmp.set_port(5533);
+        tmp
+    };
+    let metrics_router = axum::Router::new()
+        .route("/metrics", get(|| async move { metric_handle.render() }))
+        .route("/health", get(server::health::handle_health_get))
+        .into_make_service();
+    let metrics_listener = tokio::net::TcpListener::bind(metric_socket_addr)
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        axum::serve(metrics_listener, metrics_router).await.unwrap();
+    });
+
     if let Ok(dot_env) = res {
         info!("Loaded {}", dot_env.to_string_lossy());
     }
 
+    let config = match sjf_config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    info!("Configuration {:#?}", config);
+    server::init(&config.site);
+
+    let auth_state = match sjf_auth::init(&config.auth) {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Invalid passkey configuration {}", e);
+            std::process::exit(1);
+        }
+    };
+
     info!("Initializing db...");
-    if !db::init().await {
+    set_stage(Stage::Database);
+    if !db::init(&config.database, &config.checkout).await {
         std::process::exit(1);
     }
 
     info!("Initializing stripe");
-    if let Err(e) = sjf_payment::init().await {
+    set_stage(Stage::Payment);
+    if let Err(e) = sjf_payment::init(&config.stripe, &config.site).await {
         error!("{}", e);
         std::process::exit(2);
     }
 
+    info!("Initializing notifications...");
+    set_stage(Stage::Notifications);
+    sjf_notify::init(&config.notify, &config.site);
+
     info!("Initializing object storage...");
-    let res = image::init().await;
+    set_stage(Stage::ObjectStorage);
+    let res = image::init(&config.storage).await;
     if let Err(e) = res {
         use std::error::Error;
 
@@ -315,59 +703,117 @@ async fn launch_server() {
         std::process::exit(2);
     }
 
+    db::jobs::start();
+
     info!("Initializing dioxus...");
     // Connect to the IP and PORT env vars passed by the Dioxus CLI (or your dockerfile)
     let socket_addr = dioxus::cli_config::fullstack_address_or_localhost();
-    let metric_socket_addr = {
-        let mut tmp = socket_addr.clone();
-        tmp.set_port(5533);
-        tmp
-    };
 
     info!("Hosting at {}", socket_addr);
 
-    use axum::routing::get;
     use dioxus::fullstack::prelude::DioxusRouterExt;
 
     let dioxus_router = axum::Router::new()
         .route("/images/:image_id}/:variant_id", get(handle_image_get))
         .route("/robots.txt", get(handle_robots_get) )
+        .route("/sitemap.xml", get(handle_sitemap_get))
+        .route("/sitemaps/:file", get(handle_sitemap_part_get))
+        .route("/feed/:file", get(handle_feed_get))
+        .route("/api/auth/tax/oss/:file", get(handle_oss_report_get))
+        .route("/api/auth/products/export.csv", get(handle_product_export_get))
         .serve_dioxus_application(ServeConfigBuilder::new(), App)
         .layer(prometheus_layer)
         .layer(axum::middleware::from_fn(accept_fix))
+        .layer(axum::middleware::from_fn(locale_middleware))
         .layer(axum::middleware::from_fn(order_middleware))
+        .layer(axum::middleware::from_fn(subscription_middleware))
         .layer(axum::middleware::from_fn(
             sjf_auth::axum::protect_authenticated_routes,
         ))
-        .layer(axum::Extension(sjf_auth::state::AuthState::new()))
+        .layer(axum::Extension(auth_state))
         .layer(sjf_auth::axum::create_auth_layer());
 
-    let custom_router = axum::Router::new().route(
-        "/kubernetes/probes/liveness",
-        get(|| async { StatusCode::NO_CONTENT }),
-    );
+    let custom_router = axum::Router::new()
+        .route(
+            "/kubernetes/probes/liveness",
+            get(|| async { StatusCode::NO_CONTENT }),
+        )
+        .route(
+            "/kubernetes/probes/readiness",
+            get(server::health::handle_readiness_get),
+        )
+        .route(
+            "/stripe/webhook",
+            axum::routing::post(handle_stripe_webhook),
+        );
 
     let router = axum::Router::new()
         .merge(custom_router)
         .merge(dioxus_router)
         .into_make_service();
 
-    let metrics_router = axum::Router::new()
-        .route("/metrics", get(|| async move { metric_handle.render() }))
-        .into_make_service();
-
     let listener = tokio::net::TcpListener::bind(socket_addr).await.unwrap();
-    let metrics_listener = tokio::net::TcpListener::bind(metric_socket_addr)
-        .await
-        .unwrap();
 
-    let f1 = tokio::spawn(async move {
-        axum::serve(listener, router).await.unwrap();
+    let mut server = tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(db::tasks::shutdown_started())
+            .await
     });
-    let f2 = tokio::spawn(async move {
-        axum::serve(metrics_listener, metrics_router).await.unwrap();
-    });
-    let _ = tokio::join!(f1, f2);
+    set_stage(Stage::Ready);
+
+    tokio::select! {
+        _ = shutdown_signal() => {},
+        res = &mut server => {
+            error!("Server stopped unexpectedly {:?}", res);
+            std::process::exit(1);
+        }
+    }
+
+    // Readiness fails from here on. Requests are still served until the load
+    // balancers have noticed and stopped sending new ones
+    info!("Shutting down...");
+    set_stage(Stage::ShuttingDown);
+    let timeout = std::time::Duration::from_secs(config.site.shutdown_timeout_seconds.into());
+    let deadline = tokio::time::Instant::now() + timeout;
+    let drain = std::time::Duration::from_secs(config.site.shutdown_drain_seconds.into());
+    tokio::time::sleep(drain).await;
+    db::tasks::shutdown();
+
+    match tokio::time::timeout_at(deadline, server).await {
+        Ok(Ok(Ok(()))) => info!("Finished in-flight requests"),
+        Ok(res) => error!("Server failed while shutting down {:?}", res),
+        Err(_) => warn!("Requests were still running after {}s", timeout.as_secs()),
+    }
+    if let Err(unfinished) = db::tasks::drain(deadline).await {
+        warn!("Background tasks still running after {}s {:?}", timeout.as_secs(), unfinished);
+    }
+    if tokio::time::timeout_at(deadline, db::close()).await.is_err() {
+        warn!("Database connections were still in use after {}s", timeout.as_secs());
+    }
+    info!("Stopped");
+}
+
+/// Completes on Ctrl-C, or on the SIGTERM Kubernetes sends before stopping the
+/// pod.
+#[cfg(feature = "server")]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let terminate = async {
+        match signal(SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate => {},
+    }
 }
 
 #[component]
@@ -388,34 +834,39 @@ fn NotFound(segments: Vec<String>) -> Element {
         let labels= [("endpoint",path)];
         axum_prometheus::metrics::counter!("not_found_pages", &labels).increment(1);
     }
+    let messages = i18n::use_messages();
     rsx! {
         h2 {
-            "Oops! Sidan hittades inte :/"
+            "{messages.not_found}"
         }
     }
 }
 
 #[component]
-fn ProductPage(segments: ReadOnlySignal<Vec<String>>) -> Element {
-    let error_msg = "Ooops här var det tomt, möjligen kan produkten plockats bort";
+fn ProductPage(
+    segments: ReadOnlySignal<Vec<String>>,
+    query: ReadOnlySignal<sjf_api::product::ListingQuery>,
+) -> Element {
+    let locale = i18n::use_locale();
+    let messages = locale.messages();
+    let error_msg = messages.product_missing;
     let product = use_server_future(
         move || {
-            let segments = segments.clone();
-            async move { get_category_and_product(segments().join("/")).await }
+            async move { get_category_and_product(segments().join("/"), locale).await }
         }
     )?;
 
     match *product.read_unchecked() {
         None => rsx! {
-           div { "Laddar produkt..." }
+           div { "{messages.loading_product}" }
         },
-        Some(Ok((i, Some(ref p)))) => rsx! {
+        Some(Ok((_, Some(ref p)))) => rsx! {
             components::Product {product: p.clone() }
         },
         Some(Ok((i, None))) => rsx! {
-            components::Category { category_path: segments, id: i  }
+            components::Category { category_path: segments, id: i, query }
         },
-        Some(Err(ref e)) => rsx! {
+        Some(Err(_)) => rsx! {
             div { "{error_msg}" }
         },
     }
@@ -1,9 +1,5 @@
-use dioxus::logger::tracing::{info, warn};
-use dioxus::prelude::server_fn::error::NoCustomError;
-use dioxus::prelude::server_fn::ServerFn;
 use dioxus::prelude::*;
 use dioxus::prelude::{server, ServerFnError};
-use webauthn_rs_proto::{AuthenticatorAssertionResponseRaw, RegisterPublicKeyCredential};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -68,9 +64,8 @@ impl From<axum_login::Error<sjf_auth::Backend>> for AuthApiError {
 pub mod def {
 
     use super::*;
-    use axum_login::AuthSession;
-    use sjf_auth::Backend;
-    use web_sys::PublicKeyCredential;
+
+    
 
     pub async fn get_session() -> Result<axum_login::AuthSession<sjf_auth::Backend>, AuthApiError> {
         Ok(extract().await?)
@@ -83,8 +78,21 @@ pub mod def {
     pub async fn get_tower_session() -> Result<sjf_auth::endpoints::TowerSession, AuthApiError> {
         Ok(extract().await?)
     }
+
+    /// The customer account of the current session, if a customer is logged in.
+    pub async fn get_customer() -> Option<sjf_payment::CustomerAccount> {
+        let session = get_session().await.ok()?;
+        session
+            .user
+            .filter(|u| u.role == sjf_auth::Role::Customer)
+            .map(|u| sjf_payment::CustomerAccount {
+                id: u.id,
+                email: u.name,
+            })
+    }
 }
 
+#[cfg(feature = "server")]
 fn to_err<T, E1>(r: Result<T, E1>) -> Result<T, ServerFnError<AuthApiError>>
 where
     E1: Into<AuthApiError>,
@@ -116,6 +124,18 @@ pub async fn start_registration(
     .await;
     to_err(r)
 }
+#[server(endpoint="passkey/customer/registration/start",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn start_customer_registration(
+    email: String,
+) -> Result<webauthn_rs_proto::CreationChallengeResponse, ServerFnError<AuthApiError>> {
+    let r = sjf_auth::endpoints::start_customer_register(
+        def::get_state().await?,
+        def::get_tower_session().await?,
+        email,
+    )
+    .await;
+    to_err(r)
+}
 #[server(endpoint="passkey/registration/finish",input=dioxus::prelude::server_fn::codec::PostUrl)]
 pub async fn finish_registration(
     reg: webauthn_rs_proto::RegisterPublicKeyCredential,
@@ -140,6 +160,18 @@ pub async fn start_authentication(
     .await;
     to_err(r)
 }
+#[server(endpoint="passkey/customer/authentication/start",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn start_customer_authentication(
+    email: String,
+) -> Result<webauthn_rs_proto::RequestChallengeResponse, ServerFnError<AuthApiError>> {
+    let r = sjf_auth::endpoints::start_customer_authentication(
+        def::get_state().await?,
+        def::get_tower_session().await?,
+        email,
+    )
+    .await;
+    to_err(r)
+}
 #[server(endpoint="passkey/authentication/finish",input=dioxus::prelude::server_fn::codec::PostUrl)]
 pub async fn finish_authentication(
     key: webauthn_rs_proto::PublicKeyCredential,
@@ -160,8 +192,17 @@ pub async fn finish_authentication(
     to_err(session.login(&user).await)
 }
 
+#[server(endpoint="passkey/logout",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn logout() -> Result<(), ServerFnError<AuthApiError>> {
+    let mut session = def::get_session().await?;
+    to_err(session.logout().await).map(|_| ())
+}
+
+/// True when an admin is logged in.
 #[server(endpoint="is_authenticated",input=dioxus::prelude::server_fn::codec::GetUrl)]
 pub async fn is_authenticated() -> Result<bool, ServerFnError> {
     let session = def::get_session().await?;
-    Ok(session.user.is_some())
+    Ok(session
+        .user
+        .is_some_and(|u| u.role == sjf_auth::Role::Admin))
 }
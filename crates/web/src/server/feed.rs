@@ -0,0 +1,372 @@
+use chrono::{DateTime, Utc};
+use dioxus::logger::tracing::warn;
+use sjf_api::product::{Availability, Product};
+use sjf_db as db;
+use sjf_db::feed::FeedProduct;
+
+use super::escape_xml;
+
+const BRAND: &str = "SJF Concept";
+/// Longest title and description the feed specifications accept.
+const TITLE_LENGTH: usize = 150;
+const DESCRIPTION_LENGTH: usize = 5000;
+
+/// A product with every field the feeds require, prices in kr including VAT.
+struct Item {
+    id: String,
+    title: String,
+    description: String,
+    link: String,
+    image_link: String,
+    additional_images: Vec<String>,
+    availability: Availability,
+    availability_date: Option<String>,
+    price: u32,
+    google_category: Option<u32>,
+    product_type: String,
+    shipping: u32,
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(length)
+        .collect()
+}
+
+impl Item {
+    /// `None` when the product lacks something the feeds require, such as
+    /// an image or a description.
+    fn new(site: &str, p: FeedProduct) -> Option<Item> {
+        let FeedProduct {
+            product,
+            google_category,
+        } = p;
+        let Product {
+            id,
+            ref name,
+            ref description,
+            ref category_name,
+            price,
+            ref images,
+            ..
+        } = product;
+
+        let mut images = images
+            .iter()
+            .filter_map(|i| i.sizes.last())
+            .map(|v| format!("{site}{}", v.url));
+        let image_link = images.next();
+        let title = truncate(name, TITLE_LENGTH);
+        let description = truncate(description, DESCRIPTION_LENGTH);
+
+        let (Some(image_link), false, false) =
+            (image_link, title.is_empty(), description.is_empty())
+        else {
+            warn!("Product {id} left out of the feeds, it has no image, name or description");
+            return None;
+        };
+
+        Some(Item {
+            id: id.to_string(),
+            title,
+            description,
+            link: format!("{site}{}", product.preview().product_url()),
+            image_link,
+            additional_images: images.take(10).collect(),
+            availability: product.availability(),
+            availability_date: match &product.stock_policy {
+                sjf_api::stock::StockPolicy::Preorder { ship_date } => {
+                    Some(ship_date.format("%Y-%m-%dT00:00Z").to_string())
+                }
+                _ => None,
+            },
+            price,
+            google_category,
+            product_type: category_name.join(" > "),
+            shipping: sjf_api::checkout::shipping_price(1, price),
+        })
+    }
+}
+
+async fn items() -> Result<Vec<Item>, db::Error> {
+    let site = super::site_url();
+    Ok(db::feed::products()
+        .await?
+        .into_iter()
+        .filter_map(|p| Item::new(site, p))
+        .collect())
+}
+
+fn price(kr: u32) -> String {
+    format!("{kr}.00 SEK")
+}
+
+/// RSS 2.0 with the `g:` namespace, for Google Merchant Center.
+pub async fn google() -> Result<String, db::Error> {
+    Ok(google_xml(super::site_url(), items().await?, Utc::now()))
+}
+
+fn google_xml(site: &str, items: Vec<Item>, built: DateTime<Utc>) -> String {
+    let mut xml = format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<rss version="2.0" xmlns:g="http://base.google.com/ns/1.0">"#,
+            "\n<channel>\n<title>{}</title>\n<link>{}/</link>\n",
+            "<description>Produkter från {}</description>\n<lastBuildDate>{}</lastBuildDate>\n"
+        ),
+        BRAND,
+        escape_xml(site),
+        BRAND,
+        built.to_rfc2822()
+    );
+
+    for item in items {
+        let field = |name: &str, value: &str| format!("<g:{name}>{}</g:{name}>", escape_xml(value));
+
+        xml += "<item>";
+        xml += &field("id", &item.id);
+        xml += &format!("<title>{}</title>", escape_xml(&item.title));
+        xml += &format!(
+            "<description>{}</description>",
+            escape_xml(&item.description)
+        );
+        xml += &format!("<link>{}</link>", escape_xml(&item.link));
+        xml += &field("image_link", &item.image_link);
+        for image in &item.additional_images {
+            xml += &field("additional_image_link", image);
+        }
+        xml += &field(
+            "availability",
+            match item.availability {
+                Availability::InStock => "in_stock",
+                Availability::OutOfStock => "out_of_stock",
+                Availability::PreOrder => "preorder",
+                Availability::BackOrder => "backorder",
+            },
+        );
+        if let Some(date) = &item.availability_date {
+            xml += &field("availability_date", date);
+        }
+        xml += &field("price", &price(item.price));
+        xml += &field("brand", BRAND);
+        xml += &field("condition", "new");
+        xml += &field("identifier_exists", "no");
+        if let Some(category) = item.google_category {
+            xml += &field("google_product_category", &category.to_string());
+        }
+        xml += &field("product_type", &item.product_type);
+        xml += &format!(
+            "<g:shipping><g:country>SE</g:country><g:service>Standard</g:service><g:price>{}</g:price></g:shipping>",
+            price(item.shipping)
+        );
+        xml += "</item>\n";
+    }
+
+    xml + "</channel>\n</rss>\n"
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// CSV in the format of Meta commerce catalogues, for Facebook and Instagram Shopping.
+pub async fn meta() -> Result<String, db::Error> {
+    Ok(meta_csv(items().await?))
+}
+
+fn meta_csv(items: Vec<Item>) -> String {
+    let mut csv = String::from(
+        "id,title,description,availability,condition,price,link,image_link,additional_image_link,brand,google_product_category,product_type,shipping\n",
+    );
+
+    for item in items {
+        let availability = match item.availability {
+            Availability::InStock => "in stock",
+            Availability::OutOfStock => "out of stock",
+            Availability::PreOrder => "preorder",
+            Availability::BackOrder => "available for order",
+        };
+        let row = [
+            item.id,
+            item.title,
+            item.description,
+            availability.to_owned(),
+            "new".to_owned(),
+            price(item.price),
+            item.link,
+            item.image_link,
+            item.additional_images.join(","),
+            BRAND.to_owned(),
+            item.google_category
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            item.product_type,
+            format!("SE::Standard:{}", price(item.shipping)),
+        ];
+        csv += &row
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv += "\n";
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sjf_api::product::{Image, ImageVariant};
+    use sjf_api::stock::StockPolicy;
+
+    const SITE: &str = "https://www.sjfconcept.se";
+
+    fn product(id: u32, description: &str, images: usize) -> FeedProduct {
+        FeedProduct {
+            product: Product {
+                id,
+                name: format!("Stol \"Åsa\" & <bord> {id}"),
+                stock: Some(3),
+                stock_policy: StockPolicy::Stop,
+                description: description.into(),
+                category_name: vec!["Möbler".into(), "Stolar".into()],
+                price: 1200,
+                images: (0..images)
+                    .map(|i| Image {
+                        color: "#fff".into(),
+                        sizes: vec![
+                            ImageVariant {
+                                width: 100,
+                                height: 100,
+                                url: format!("/images/{i}/1"),
+                            },
+                            ImageVariant {
+                                width: 800,
+                                height: 800,
+                                url: format!("/images/{i}/2"),
+                            },
+                        ],
+                    })
+                    .collect(),
+            },
+            google_category: Some(443),
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        [
+            product(1, "En stol, med \"citat\" och\nradbrytning", 2),
+            product(2, "Ett bord", 1),
+        ]
+        .into_iter()
+        .filter_map(|p| Item::new(SITE, p))
+        .collect()
+    }
+
+    /// The unescaped text of the first `<name>` element in `xml`.
+    fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+        let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+        let end = start + xml[start..].find(&format!("</{name}>"))?;
+        Some(&xml[start..end])
+    }
+
+    #[test]
+    fn google_items_have_the_required_fields() {
+        let xml = google_xml(SITE, items(), Utc::now());
+        let items = xml.split("<item>").skip(1).collect::<Vec<_>>();
+        assert_eq!(items.len(), 2);
+        for item in items {
+            for name in [
+                "g:id",
+                "title",
+                "description",
+                "link",
+                "g:image_link",
+                "g:availability",
+                "g:price",
+                "g:brand",
+            ] {
+                let value = element(item, name).unwrap_or_default();
+                assert!(!value.is_empty(), "<{name}> missing in {item}");
+            }
+        }
+        let first = xml.split("<item>").nth(1).unwrap();
+        assert_eq!(element(first, "g:id"), Some("1"));
+        assert_eq!(element(first, "g:price"), Some("1200.00 SEK"));
+        assert_eq!(element(first, "g:availability"), Some("in_stock"));
+        assert_eq!(
+            element(first, "g:image_link"),
+            Some("https://www.sjfconcept.se/images/0/2")
+        );
+        assert_eq!(
+            element(first, "title"),
+            Some("Stol &quot;Åsa&quot; &amp; &lt;bord&gt; 1")
+        );
+        assert_eq!(first.matches("<g:additional_image_link>").count(), 1);
+    }
+
+    #[test]
+    fn meta_rows_have_the_required_fields() {
+        let csv = meta_csv(items());
+        let records = sjf_api::import::parse_csv(&csv);
+        let (header, rows) = records.split_first().unwrap();
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            assert_eq!(row.len(), header.len());
+            for name in [
+                "id",
+                "title",
+                "description",
+                "link",
+                "image_link",
+                "availability",
+                "price",
+                "brand",
+            ] {
+                let column = header.iter().position(|h| h == name).unwrap();
+                assert!(!row[column].is_empty(), "{name} missing in {row:?}");
+            }
+        }
+        assert_eq!(rows[0][1], "Stol \"Åsa\" & <bord> 1");
+        // Whitespace, including the newline, is collapsed
+        assert_eq!(rows[0][2], "En stol, med \"citat\" och radbrytning");
+        assert_eq!(rows[0][3], "in stock");
+    }
+
+    #[test]
+    fn products_without_image_or_description_are_left_out() {
+        assert!(Item::new(SITE, product(1, "Beskrivning", 0)).is_none());
+        assert!(Item::new(SITE, product(2, "", 1)).is_none());
+        assert!(Item::new(SITE, product(3, " \n\t ", 1)).is_none());
+        assert!(Item::new(SITE, product(4, "Beskrivning", 1)).is_some());
+    }
+
+    #[test]
+    fn text_is_escaped_and_quoted() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+        assert_eq!(escape_xml("&amp;"), "&amp;amp;");
+        assert_eq!(csv_field("plain"), r#""plain""#);
+        assert_eq!(csv_field(r#"16" skärm, svart"#), r#""16"" skärm, svart""#);
+        assert_eq!(csv_field("två\nrader"), "\"två\nrader\"");
+    }
+
+    #[test]
+    fn text_is_truncated_to_the_feed_limits() {
+        let title = truncate(&"å".repeat(200), TITLE_LENGTH);
+        assert_eq!(title.chars().count(), 150);
+        let description = truncate(&"ord ".repeat(2000), DESCRIPTION_LENGTH);
+        assert_eq!(description.chars().count(), 5000);
+        assert_eq!(truncate("  kort\n\ntext  ", TITLE_LENGTH), "kort text");
+
+        let item = Item::new(SITE, product(1, &"x".repeat(6000), 1)).unwrap();
+        assert_eq!(item.description.chars().count(), DESCRIPTION_LENGTH);
+    }
+}
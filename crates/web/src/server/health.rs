@@ -0,0 +1,264 @@
+//! Health of the shop for Kubernetes and monitoring. The readiness probe
+//! fails until startup has finished, once shutdown has started and while the
+//! database can't be used, as no page can be served then. `/health` on the
+//! metrics port also reports object storage, payment and the background
+//! tasks, which only degrade the shop when they fail.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+/// How long a dependency may take to answer before it counts as failing.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What the server is doing before and after it takes requests, in order.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Configuration,
+    Database,
+    Payment,
+    Notifications,
+    ObjectStorage,
+    Ready,
+    ShuttingDown,
+}
+
+static STAGE: Mutex<Stage> = Mutex::new(Stage::Configuration);
+
+pub fn set_stage(stage: Stage) {
+    *STAGE.lock().unwrap() = stage;
+}
+
+fn stage() -> Stage {
+    *STAGE.lock().unwrap()
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Starting,
+    /// Something other than the database is failing, the shop still serves
+    /// pages.
+    Degraded,
+    Failing,
+    Stopping,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Check {
+    pub name: String,
+    /// Whether the shop can't serve requests while this fails.
+    pub critical: bool,
+    pub status: Status,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, result: Result<(), String>) -> Self {
+        let (status, detail) = match result {
+            Ok(()) => (Status::Ok, None),
+            Err(e) => (Status::Failing, Some(e)),
+        };
+        Check {
+            name: name.into(),
+            critical: false,
+            status,
+            latency_ms: None,
+            detail,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Health {
+    pub status: Status,
+    pub startup: Stage,
+    pub checks: Vec<Check>,
+}
+
+/// Runs `check` with a timeout and measures how long it took.
+async fn timed<E: std::fmt::Debug>(
+    name: &str,
+    check: impl Future<Output = Result<(), E>>,
+) -> Check {
+    let start = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result.map_err(|e| format!("{e:?}")),
+        Err(_) => Err(format!("No answer within {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    Check {
+        latency_ms: Some(start.elapsed().as_millis() as u64),
+        ..Check::new(name, result)
+    }
+}
+
+fn payment() -> Check {
+    let result = match sjf_payment::is_configured() {
+        true => Ok(()),
+        false => Err("Stripe isn't initialized".into()),
+    };
+    Check::new("payment", result)
+}
+
+/// The supervised background loops, failing while waiting to be restarted or
+/// when they stopped making progress. The shop still serves pages meanwhile.
+fn jobs() -> Vec<Check> {
+    let now = chrono::Utc::now();
+    let heartbeats = sjf_db::health::heartbeats();
+    sjf_db::tasks::supervised()
+        .into_iter()
+        .map(|task| {
+            let heartbeat = heartbeats.iter().find(|h| h.job == task.name);
+            let result = match (task.running, heartbeat) {
+                (false, _) => Err("Restarting".into()),
+                (true, Some(h)) if h.is_late(now) => Err("Late".into()),
+                (true, _) => Ok(()),
+            };
+            let mut detail = vec![];
+            if let Some(h) = heartbeat {
+                detail.push(format!(
+                    "Last ran {}s ago, every {}s",
+                    (now - h.last).num_seconds(),
+                    h.interval.as_secs()
+                ));
+            }
+            if let Some(failure) = task.last_failure {
+                detail.push(format!(
+                    "Restarted {} times, last {}",
+                    task.restarts, failure
+                ));
+            }
+            Check {
+                detail: (!detail.is_empty()).then(|| detail.join(", ")),
+                ..Check::new(format!("job/{}", task.name), result)
+            }
+        })
+        .collect()
+}
+
+/// Combines the checks into the status of the shop, only failing checks that
+/// are critical make it fail.
+fn combine(startup: Stage, checks: &[Check]) -> Status {
+    match startup {
+        Stage::Ready => {}
+        Stage::ShuttingDown => return Status::Stopping,
+        _ => return Status::Starting,
+    }
+    let failing = checks.iter().filter(|c| c.status != Status::Ok);
+    match failing.map(|c| c.critical).max() {
+        None => Status::Ok,
+        Some(false) => Status::Degraded,
+        Some(true) => Status::Failing,
+    }
+}
+
+async fn database() -> Check {
+    Check {
+        critical: true,
+        ..timed("database", sjf_db::health::ping()).await
+    }
+}
+
+pub async fn health() -> Health {
+    let startup = stage();
+    if startup != Stage::Ready {
+        return Health {
+            status: combine(startup, &[]),
+            startup,
+            checks: vec![],
+        };
+    }
+
+    let (database, storage) = tokio::join!(database(), timed("object_storage", sjf_image::ping()));
+    let mut checks = vec![database, storage, payment()];
+    checks.extend(jobs());
+
+    Health {
+        status: combine(startup, &checks),
+        startup,
+        checks,
+    }
+}
+
+fn status_code(status: Status) -> StatusCode {
+    match status {
+        Status::Ok | Status::Degraded => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+/// Ready once started and while the database answers.
+pub async fn handle_readiness_get() -> impl IntoResponse {
+    let startup = stage();
+    let checks = match startup {
+        Stage::Ready => vec![database().await],
+        _ => vec![],
+    };
+    match status_code(combine(startup, &checks)) {
+        StatusCode::OK => StatusCode::NO_CONTENT,
+        code => code,
+    }
+}
+
+pub async fn handle_health_get() -> impl IntoResponse {
+    let health = health().await;
+    (status_code(health.status), Json(health))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(critical: bool, status: Status) -> Check {
+        Check {
+            critical,
+            status,
+            ..Check::new("check", Ok(()))
+        }
+    }
+
+    #[test]
+    fn only_the_stage_counts_before_and_after_serving() {
+        let failing = [check(true, Status::Failing)];
+        assert_eq!(combine(Stage::Database, &failing), Status::Starting);
+        assert_eq!(combine(Stage::Configuration, &[]), Status::Starting);
+        assert_eq!(combine(Stage::ShuttingDown, &[]), Status::Stopping);
+        assert_eq!(combine(Stage::Ready, &[]), Status::Ok);
+    }
+
+    #[test]
+    fn failing_dependencies_degrade_unless_critical() {
+        let checks = [check(true, Status::Ok), check(false, Status::Ok)];
+        assert_eq!(combine(Stage::Ready, &checks), Status::Ok);
+        let checks = [check(true, Status::Ok), check(false, Status::Failing)];
+        assert_eq!(combine(Stage::Ready, &checks), Status::Degraded);
+        let checks = [check(false, Status::Failing), check(true, Status::Failing)];
+        assert_eq!(combine(Stage::Ready, &checks), Status::Failing);
+    }
+
+    #[test]
+    fn only_failing_is_unavailable() {
+        assert_eq!(status_code(Status::Ok), StatusCode::OK);
+        assert_eq!(status_code(Status::Degraded), StatusCode::OK);
+        assert_eq!(
+            status_code(Status::Failing),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status_code(Status::Starting),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status_code(Status::Stopping),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
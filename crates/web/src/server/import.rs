@@ -0,0 +1,186 @@
+use std::io::Cursor;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use calamine::{Reader, Xlsx, XlsxError};
+use sjf_api::import::{ImportFile, parse_csv};
+
+/// The records of an import file, for a workbook those of its first sheet.
+pub fn records(file: &ImportFile) -> Result<Vec<Vec<String>>, XlsxError> {
+    match file {
+        ImportFile::Csv(text) => Ok(parse_csv(text)),
+        ImportFile::Xlsx(bytes) => read_xlsx(bytes),
+    }
+}
+
+fn read_xlsx(bytes: &[u8]) -> Result<Vec<Vec<String>>, XlsxError> {
+    let mut workbook = Xlsx::new(Cursor::new(bytes))?;
+    let Some(sheet) = workbook.worksheet_range_at(0) else {
+        return Ok(vec![]);
+    };
+    // Numbers are stored as floats, but print without decimals when whole
+    let records = sheet?
+        .rows()
+        .map(|row| row.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        .filter(|r| r.iter().any(|f| !f.trim().is_empty()))
+        .collect();
+    Ok(records)
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || a >= 240
+        // Shared address space of carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // Benchmarking
+        || (a == 198 && (18..20).contains(&b)))
+}
+
+/// Whether `ip` is reachable from the internet, rather than on the machine
+/// or the network of the shop.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => {
+                let [first, second, ..] = ip.segments();
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    // Documentation
+                    || (first == 0x2001 && second == 0xdb8)
+                    // IPv4 through NAT64
+                    || (first == 0x64 && second == 0xff9b))
+            }
+        },
+    }
+}
+
+/// The address to download an image of an import from. Only web addresses
+/// whose host resolves to public addresses only are allowed, so that import
+/// files can't reach services on the machine or the network of the shop.
+pub async fn public_address(url: &reqwest::Url) -> Result<SocketAddr, String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Not a web address {}", url));
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(format!("No host in {}", url));
+    };
+    // IPv6 hosts are written within brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+    if let Some(internal) = addresses.iter().find(|a| !is_public(a.ip())) {
+        return Err(format!(
+            "{} resolves to the internal address {}",
+            host,
+            internal.ip()
+        ));
+    }
+    addresses
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("{} has no address", host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sjf_api::import::parse_rows;
+
+    #[test]
+    fn workbooks_are_read_like_csv() {
+        let file = ImportFile::Xlsx(include_bytes!("testdata/products.xlsx").to_vec());
+        let records = records(&file).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[1],
+            [
+                "a-1",
+                "Mössa",
+                "Varm & mjuk",
+                "199",
+                "5",
+                "25",
+                "Kläder/Mössor"
+            ]
+        );
+
+        let (rows, errors) = parse_rows(records);
+        assert_eq!(errors, []);
+        assert_eq!(rows[0].price, 199);
+        assert_eq!(rows[0].quantity, Some(5));
+        assert_eq!(rows[1].sku, None);
+        assert_eq!(rows[1].quantity, None);
+        assert_eq!(rows[1].tax_rate, 12);
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        let file = ImportFile::Xlsx(b"sku;name\n1;Hat\n".to_vec());
+        assert!(records(&file).is_err());
+    }
+
+    #[test]
+    fn only_public_addresses_are_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "2606:4700:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn internal_hosts_are_refused() {
+        for url in [
+            "http://127.0.0.1/bild.jpg",
+            "http://localhost:8080/bild.jpg",
+            "https://[::1]/bild.jpg",
+            "http://169.254.169.254/latest/meta-data",
+            "file:///etc/passwd",
+        ] {
+            let url = reqwest::Url::parse(url).unwrap();
+            assert!(public_address(&url).await.is_err(), "{url}");
+        }
+
+        let url = reqwest::Url::parse("https://1.1.1.1/bild.jpg").unwrap();
+        assert_eq!(
+            public_address(&url).await.unwrap(),
+            "1.1.1.1:443".parse().unwrap()
+        );
+    }
+}
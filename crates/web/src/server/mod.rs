@@ -1,21 +1,52 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::str::FromStr;
 
 #[cfg(feature = "server")]
 use axum_prometheus::metrics::counter;
 
+#[cfg(feature = "server")]
 use dioxus::logger::tracing::{error, info, warn};
-use dioxus::prelude::server_fn::error::NoCustomError;
-use dioxus::prelude::server_fn::ServerFn;
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 use sjf_api::checkout::CheckoutRequest;
+use sjf_api::i18n::Locale;
 #[cfg(feature = "server")]
 use sjf_db as db;
 pub mod auth;
+#[cfg(feature = "server")]
+pub mod feed;
+#[cfg(feature = "server")]
+pub mod health;
+#[cfg(feature = "server")]
+pub mod import;
+#[cfg(feature = "server")]
+pub mod sitemap;
 
 use dioxus::prelude::server_fn::codec::Json;
 
+#[cfg(feature = "server")]
+static SITE_URL: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+#[cfg(feature = "server")]
+pub fn init(site: &sjf_config::SiteConfig) {
+    let _ = SITE_URL.set(site.url.clone());
+}
+
+/// The public address of the shop, without a trailing slash.
+#[cfg(feature = "server")]
+pub fn site_url() -> &'static str {
+    SITE_URL.get().map(|s| s.as_str()).unwrap_or_default()
+}
+
+/// Escapes text for use in XML elements and attributes.
+#[cfg(feature = "server")]
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(bound = "T: Serialize, for<'de2> T: Deserialize<'de2>")]
 pub struct AuthenticatedRequest<T>
@@ -32,10 +63,16 @@ pub struct Product {
     pub name: String, //VARCHAR(100)
     pub price: u16,
     pub description: String,
-    pub quantity: Option<u16>,
+    pub quantity: Option<i32>,
     pub images: Option<BTreeSet<u32>>,
     pub tax_rate: u32,
     pub category: u32,
+    pub reorder_threshold: Option<u16>,
+    pub stock_policy: sjf_api::stock::StockPolicy,
+    /// Attribute values by attribute id, as entered.
+    pub attributes: BTreeMap<u32, String>,
+    pub translations: BTreeMap<Locale, sjf_api::product::ProductTranslation>,
+    pub sku: Option<String>,
 }
 
 impl Product {
@@ -49,6 +86,11 @@ impl Product {
             images: None,
             tax_rate: 25,
             category,
+            reorder_threshold: None,
+            stock_policy: Default::default(),
+            attributes: BTreeMap::new(),
+            translations: BTreeMap::new(),
+            sku: None,
         }
     }
 }
@@ -65,20 +107,28 @@ fn error_logger<T>(t: Result<T, db::Error>) -> Result<T, ServerFnError> {
     }
 }
 
+/// Fails for a price or reorder threshold the admin form can't show.
 #[cfg(feature = "server")]
-impl From<db::Product> for Product {
-    fn from(product: db::Product) -> Self {
+impl TryFrom<db::Product> for Product {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(product: db::Product) -> Result<Self, Self::Error> {
         let images = { Some(product.images.into_iter().collect()) };
-        Self {
+        Ok(Self {
             id: Some(product.id),
             name: product.name,
-            price: product.price as u16,
+            price: u16::try_from(product.price)?,
             description: product.description,
-            quantity: product.quantity.map(|x| x as u16),
-            images: images,
+            quantity: product.quantity,
+            images,
             tax_rate: product.tax_rate,
             category: product.category,
-        }
+            reorder_threshold: product.reorder_threshold.map(u16::try_from).transpose()?,
+            stock_policy: product.stock_policy,
+            attributes: product.attributes,
+            translations: product.translations,
+            sku: product.sku,
+        })
     }
 }
 
@@ -88,30 +138,58 @@ impl From<Product> for db::Product {
         db::Product {
             id: product.id.unwrap_or(0),
             name: product.name,
-            price: product.price as i32,
+            price: i32::from(product.price),
             created: Default::default(),
             updated: Default::default(),
             description: product.description,
-            quantity: product.quantity.map(|x| x as i32),
+            quantity: product.quantity,
             images: product.images.unwrap_or_default().into_iter().collect(),
             tax_rate: product.tax_rate,
             category: product.category,
+            reorder_threshold: product.reorder_threshold.map(i32::from),
+            stock_policy: product.stock_policy,
+            attributes: product.attributes,
+            translations: product.translations,
+            sku: product.sku,
         }
     }
 }
 
+/// Translated names and descriptions of the given products, none for Swedish.
+#[cfg(feature = "server")]
+async fn translations(
+    locale: Locale,
+    product_ids: impl Iterator<Item = u32>,
+) -> Result<std::collections::HashMap<u32, sjf_api::product::ProductTranslation>, ServerFnError> {
+    if locale == Locale::Sv {
+        return Ok(Default::default());
+    }
+    let ids: Vec<u32> = product_ids.collect();
+    error_logger(db::translation::product_texts(locale, &ids).await)
+}
+
 #[server(endpoint="auth/product/get", input= dioxus::prelude::server_fn::codec::GetUrl)]
 pub async fn get_products(category: u32) -> Result<Vec<Product>, ServerFnError> {
     use dioxus::prelude::ServerFnError::ServerError;
     let resp = db::get_products(category).await;
 
-    match resp {
-        Ok(products) => Ok(products.into_iter().map(|product| product.into()).collect()),
+    let products = match resp {
+        Ok(products) => products,
         Err(e) => {
             warn!("serverFn get_products failed: {:#?}", e);
-            Err(ServerError("Product get failed".into()))
+            return Err(ServerError("Product get failed".into()));
         }
-    }
+    };
+    products
+        .into_iter()
+        .map(|product| {
+            let id = product.id;
+            Product::try_from(product).map_err(|e| {
+                warn!("serverFn get_products can't show product {}: {}", id, e);
+                ServerError("Product get failed".into())
+            })
+        })
+        .collect()
 }
 
 #[server(endpoint="auth/product/get_images", input=Json)]
@@ -119,7 +197,7 @@ pub async fn get_product_images(
     req: AuthenticatedRequest<u32>,
 ) -> Result<BTreeMap<u32, Vec<u32>>, ServerFnError> {
     use dioxus::prelude::ServerFnError::ServerError;
-    match db::image::get_product_images(req.data.clone()).await {
+    match db::image::get_product_images(req.data).await {
         Ok(v) => Ok(v),
         Err(e) => {
             warn!("get_product_images({}) failed with {:#?}", req.data, e);
@@ -136,10 +214,10 @@ pub async fn delete_product(req: AuthenticatedRequest<u32>) -> Result<(), Server
 pub async fn store_product(req: AuthenticatedRequest<Product>) -> Result<i32, ServerFnError> {
     //IF authenticated
 
-    let product_id = req.data.id.clone();
+    let product_id = req.data.id;
     let product: db::Product = req.data.into();
     use dioxus::prelude::ServerFnError::ServerError;
-    match product_id {
+    let res = match product_id {
         Some(req_id) => match db::update_product(product).await {
             Ok(()) => Ok(req_id),
             Err(e) => {
@@ -154,7 +232,17 @@ pub async fn store_product(req: AuthenticatedRequest<Product>) -> Result<i32, Se
                 Err(ServerError("Store failed".into()))
             }
         },
+    };
+
+    if res.is_ok() {
+        sjf_notify::send_back_in_stock_notifications_later().await;
     }
+    res
+}
+
+#[server(endpoint="auth/stock/low", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_low_stock() -> Result<Vec<sjf_api::stock::StockAlert>, ServerFnError> {
+    error_logger(db::stock::low_stock().await)
 }
 
 #[server(endpoint="auth/images/upload", input=Json)]
@@ -185,34 +273,120 @@ pub async fn upload_images(
 }
 
 use sjf_api::product::{GetPreviewsRequest, GetPreviewsResp};
-#[server(endpoint="get/previews",input=dioxus::prelude::server_fn::codec::GetUrl)]
-pub async fn get_previews(
-    p: Option<u32>,
-    r: bool,
-    limit: u32,
-    random: bool,
-) -> Result<GetPreviewsResp, ServerFnError> {
+#[server(endpoint="get/previews",input=Json)]
+pub async fn get_previews(req: GetPreviewsRequest) -> Result<GetPreviewsResp, ServerFnError> {
+    let locale = req.locale;
     let r = GetPreviewsRequest {
-        recursive: r,
-        category: p,
-        limit: std::cmp::min(100, limit),
-        random,
+        limit: std::cmp::min(100, req.limit),
+        ..req
     };
-    error_logger(db::product::get_previews(r).await)
+    let mut rsp = error_logger(db::product::get_previews(r).await)?;
+    let texts = translations(locale, rsp.previews.iter().map(|p| p.id)).await?;
+    for p in rsp.previews.iter_mut() {
+        if let Some(t) = texts.get(&p.id) {
+            p.translate(t);
+        }
+    }
+    Ok(rsp)
 }
 
-use sjf_api::product::{GetProductRequest, GetProductResponse};
+#[cfg(feature = "server")]
+use sjf_api::product::GetProductRequest;
+use sjf_api::product::GetProductResponse;
 #[server(endpoint="get/product",input=dioxus::prelude::server_fn::codec::GetUrl)]
 pub async fn get_product(p: u32) -> Result<GetProductResponse, ServerFnError> {
     let r = GetProductRequest { product_id: p };
     error_logger(db::product::get_product(r).await)
 }
 
-use sjf_api::product::{GetProductsRequest, GetProductsResponse};
+#[cfg(feature = "server")]
+use sjf_api::product::GetProductsRequest;
+use sjf_api::product::GetProductsResponse;
+#[server(endpoint="get/product/specs",input=dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_specs(product_id: u32) -> Result<Vec<sjf_api::attribute::Spec>, ServerFnError> {
+    error_logger(db::attribute::specs(product_id).await)
+}
+
+#[server(endpoint="get/facets",input=Json)]
+pub async fn get_facets(
+    category: u32,
+    filter: sjf_api::product::PreviewFilter,
+) -> Result<Vec<sjf_api::attribute::Facet>, ServerFnError> {
+    error_logger(db::attribute::facets(category, filter).await)
+}
+
+#[server(endpoint="auth/attributes/get", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_attribute_definitions(
+    category: u32,
+) -> Result<Vec<sjf_api::attribute::AttributeDefinition>, ServerFnError> {
+    error_logger(db::attribute::definitions(category).await)
+}
+
+#[server(endpoint="auth/attributes/create", input=Json)]
+pub async fn create_attribute(
+    req: AuthenticatedRequest<sjf_api::attribute::AttributeDefinition>,
+) -> Result<u32, ServerFnError> {
+    if req.data.name.trim().is_empty() {
+        return Err(ServerFnError::ServerError("Name is required".into()));
+    }
+    error_logger(db::attribute::create(req.data).await)
+}
+
+#[server(endpoint="auth/attributes/delete", input=Json)]
+pub async fn delete_attribute(req: AuthenticatedRequest<u32>) -> Result<(), ServerFnError> {
+    error_logger(db::attribute::delete(req.data).await)
+}
+
+/// Recommendations for a product, curated ones first and then what other
+/// customers bought together with it.
+#[server(endpoint="get/related",input=dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_related(
+    product_id: u32,
+    limit: u32,
+    locale: Locale,
+) -> Result<Vec<sjf_api::product::Preview>, ServerFnError> {
+    let mut related = error_logger(db::related::get_related(product_id, limit.min(24)).await)?;
+    let texts = translations(locale, related.iter().map(|p| p.id)).await?;
+    for p in related.iter_mut() {
+        if let Some(t) = texts.get(&p.id) {
+            p.translate(t);
+        }
+    }
+    Ok(related)
+}
+
+#[server(endpoint="auth/related/get", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_curated_related(product_id: u32) -> Result<Vec<(u32, String)>, ServerFnError> {
+    error_logger(db::related::curated(product_id).await)
+}
+
+#[server(endpoint="auth/related/store", input=Json)]
+pub async fn store_curated_related(
+    req: AuthenticatedRequest<(u32, Vec<u32>)>,
+) -> Result<(), ServerFnError> {
+    let (product_id, related) = req.data;
+    error_logger(db::related::set_curated(product_id, related).await)
+}
+
+#[server(endpoint="auth/product/names", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_product_names() -> Result<Vec<(u32, String)>, ServerFnError> {
+    error_logger(db::product::names().await)
+}
+
 #[server(endpoint="get/specified_products",input=dioxus::prelude::server_fn::codec::GetUrl)]
-pub async fn get_specified_products(p: Vec<u32>) -> Result<GetProductsResponse, ServerFnError> {
+pub async fn get_specified_products(
+    p: Vec<u32>,
+    locale: Locale,
+) -> Result<GetProductsResponse, ServerFnError> {
     let r = GetProductsRequest { product_ids: p };
-    error_logger(db::product::get_specified_products(r).await)
+    let mut products = error_logger(db::product::get_specified_products(r).await)?;
+    let texts = translations(locale, products.iter().map(|p| p.id)).await?;
+    for p in products.iter_mut() {
+        if let Some(t) = texts.get(&p.id) {
+            p.translate(t);
+        }
+    }
+    Ok(products)
 }
 
 pub mod category {
@@ -223,8 +397,20 @@ pub mod category {
     #[server(endpoint="get/category/children",input=dioxus::prelude::server_fn::codec::GetUrl)]
     pub async fn get_children(
         p: Option<u32>,
+        locale: Locale,
     ) -> Result<sjf_api::category::GetChildrenRsp, ServerFnError> {
-        error_logger(db::category::get_children(p).await)
+        let mut rsp = error_logger(db::category::get_children(p).await)?;
+        if locale != Locale::Sv {
+            let ids: Vec<u32> = rsp.children.iter().map(|(id, _)| *id).collect();
+            rsp.labels = error_logger(db::translation::category_names(locale, &ids).await)?;
+        }
+        Ok(rsp)
+    }
+
+    /// The names to show for a category path given by its path segments.
+    #[server(endpoint="get/category/labels",input=Json)]
+    pub async fn get_labels(path: Vec<String>, locale: Locale) -> Result<Vec<String>, ServerFnError> {
+        error_logger(db::translation::path_labels(locale, &path).await)
     }
 
     #[server(endpoint="auth/category/create", input=Json)]
@@ -242,15 +428,113 @@ pub mod category {
     ) -> Result<(), ServerFnError> {
         error_logger(db::category::update_name(req.data.0, req.data.1).await)
     }
+
+    #[server(endpoint="auth/category/translations", input=dioxus::prelude::server_fn::codec::GetUrl)]
+    pub async fn translations(id: u32) -> Result<BTreeMap<Locale, String>, ServerFnError> {
+        let mut res = BTreeMap::new();
+        for locale in Locale::ALL.into_iter().filter(|l| *l != Locale::Sv) {
+            if let Some(name) =
+                error_logger(db::translation::category_names(locale, &[id]).await)?.remove(&id)
+            {
+                res.insert(locale, name);
+            }
+        }
+        Ok(res)
+    }
+
+    #[server(endpoint="auth/category/set_translation", input=Json)]
+    pub async fn set_translation(
+        req: AuthenticatedRequest<(u32, Locale, String)>,
+    ) -> Result<(), ServerFnError> {
+        let (id, locale, name) = req.data;
+        if locale == Locale::Sv {
+            return Err(ServerFnError::ServerError("Swedish is the category name".into()));
+        }
+        error_logger(db::translation::set_category_name(id, locale, &name).await)
+    }
+
+    #[server(endpoint="auth/category/google_category", input=dioxus::prelude::server_fn::codec::GetUrl)]
+    pub async fn google_category(id: u32) -> Result<Option<u32>, ServerFnError> {
+        error_logger(db::category::google_category(id).await)
+    }
+
+    #[server(endpoint="auth/category/set_google_category", input=Json)]
+    pub async fn set_google_category(
+        req: AuthenticatedRequest<(u32, Option<u32>)>,
+    ) -> Result<(), ServerFnError> {
+        error_logger(db::category::set_google_category(req.data.0, req.data.1).await)
+    }
+}
+
+pub mod currency {
+    use super::*;
+    use sjf_api::currency::{Currency, ExchangeRate};
+
+    /// Days the picked currency is remembered.
+    #[cfg(feature = "server")]
+    const CURRENCY_COOKIE_DAYS: i64 = 365;
+
+    /// The currencies prices can be shown in.
+    #[server(endpoint="get/currency/rates",input=dioxus::prelude::server_fn::codec::GetUrl)]
+    pub async fn get_rates() -> Result<Vec<ExchangeRate>, ServerFnError> {
+        error_logger(db::currency::rates().await)
+    }
+
+    /// The currency the visitor picked, kr until they pick another one or
+    /// when the one they picked is no longer offered.
+    #[server(endpoint="get/currency",input=dioxus::prelude::server_fn::codec::GetUrl)]
+    pub async fn get_selected() -> Result<ExchangeRate, ServerFnError> {
+        use sjf_api::currency::CURRENCY_COOKIE;
+        use sjf_auth::anonymous;
+
+        let headers: axum::http::HeaderMap = extract().await.unwrap_or_default();
+        let Some(currency) =
+            anonymous::read(&headers, CURRENCY_COOKIE).and_then(|c| Currency::from_code(&c))
+        else {
+            return Ok(ExchangeRate::SEK);
+        };
+        let rates = error_logger(db::currency::rates().await)?;
+        Ok(rates
+            .into_iter()
+            .find(|r| r.currency == currency)
+            .unwrap_or_default())
+    }
+
+    #[server(endpoint="currency/select",input=dioxus::prelude::server_fn::codec::PostUrl)]
+    pub async fn select(currency: Currency) -> Result<ExchangeRate, ServerFnError> {
+        use sjf_api::currency::CURRENCY_COOKIE;
+        use sjf_auth::anonymous;
+
+        let rates = error_logger(db::currency::rates().await)?;
+        let rate = rates
+            .into_iter()
+            .find(|r| r.currency == currency)
+            .unwrap_or_default();
+        if let Some(cookie) =
+            anonymous::set(CURRENCY_COOKIE, rate.currency.code(), CURRENCY_COOKIE_DAYS)
+        {
+            server_context()
+                .response_parts_mut()
+                .headers
+                .insert(axum::http::header::SET_COOKIE, cookie);
+        }
+        Ok(rate)
+    }
+
+    #[server(endpoint="auth/currency/rate", input=Json)]
+    pub async fn set_rate(
+        req: AuthenticatedRequest<(Currency, Option<u32>)>,
+    ) -> Result<(), ServerFnError> {
+        error_logger(db::currency::set_rate(req.data.0, req.data.1).await)
+    }
 }
 
 #[server(endpoint="get/category_and_product",input=dioxus::prelude::server_fn::codec::GetUrl)]
 pub async fn get_category_and_product(
     path: String,
+    locale: Locale,
 ) -> Result<(u32, Option<sjf_api::product::Product>), ServerFnError> {
-    use dioxus::prelude::ServerFnError::ServerError;
-
-    let serverError = |a: &'static str| -> ServerFnError {
+    let server_error = |a: &'static str| -> ServerFnError {
         dioxus::prelude::ServerFnError::ServerError(a.into())
     };
 
@@ -271,30 +555,351 @@ pub async fn get_category_and_product(
 
     let path = path.strip_prefix('/').unwrap_or(path);
 
-    let path = urlencoding::decode(path).map_err(|_| serverError("Url decoding failed"))?;
+    let path = urlencoding::decode(path).map_err(|_| server_error("Url decoding failed"))?;
 
     let res = get_paths
         .await
-        .map_err(|_| serverError("Database failure"))?;
+        .map_err(|_| server_error("Database failure"))?;
 
-    let category_id = *res.get(&*path).ok_or(serverError("Category not found"))?;
+    let category_id = *res.get(&*path).ok_or(server_error("Category not found"))?;
 
     match article {
         Some(a) => {
-            let a_id: u32 = a.parse().map_err(|_| serverError("Invalid product id"))?;
-            let product = get_product(a_id).await;
+            let a_id: u32 = a.parse().map_err(|_| server_error("Invalid product id"))?;
+            let mut product = get_product(a_id).await?;
+            if let Some(t) = translations(locale, std::iter::once(product.id))
+                .await?
+                .get(&product.id)
+            {
+                product.translate(t);
+            }
 
-            product
-                .map(|p| (category_id, Some(p)))
-                .map_err(|e| e.into())
+            Ok((category_id, Some(product)))
         }
         None => Ok((category_id, None)),
     }
 }
 
-const CHECKOUT_GAUGE: &str = "active_checkout_sessions";
+#[server(endpoint="stock/subscribe",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn subscribe_back_in_stock(
+    product_id: u32,
+    email: String,
+    locale: Locale,
+) -> Result<sjf_api::stock::SubscribeStatus, ServerFnError> {
+    match sjf_notify::subscribe(product_id, &email, locale).await {
+        Ok(status) => Ok(status),
+        Err(e) => {
+            warn!("serverFn subscribe_back_in_stock failed: {}", e);
+            Err(ServerFnError::ServerError("Subscription failed".into()))
+        }
+    }
+}
+
+/// The logged in customer's account, `None` when no customer is logged in.
+#[server(endpoint="account/get",input=dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_account() -> Result<Option<sjf_api::account::Account>, ServerFnError> {
+    let Some(customer) = auth::def::get_customer().await else {
+        return Ok(None);
+    };
+    match sjf_payment::account(&customer).await {
+        Ok(account) => Ok(Some(account)),
+        Err(e) => {
+            warn!("serverFn get_account failed: {}", e);
+            Err(ServerFnError::ServerError("Account lookup failed".into()))
+        }
+    }
+}
+
+/// Syncs the wishlist with the logged in customer's account, `None` when no
+/// customer is logged in and the wishlist only lives in the browser.
+#[server(endpoint="wishlist/sync",input=Json)]
+pub async fn sync_wishlist(
+    req: sjf_api::wishlist::SyncWishlistRequest,
+) -> Result<Option<Vec<u32>>, ServerFnError> {
+    let Some(customer) = auth::def::get_customer().await else {
+        return Ok(None);
+    };
+    error_logger(db::wishlist::sync(&customer.id, req).await).map(Some)
+}
+
+#[server(endpoint="auth/wishlist/top", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_most_wished(
+    limit: u32,
+) -> Result<Vec<sjf_api::wishlist::WishedProduct>, ServerFnError> {
+    error_logger(db::wishlist::most_wished(limit.min(100)).await)
+}
+
+/// Days an anonymous cart is remembered after it was last used.
+#[cfg(feature = "server")]
+const CART_COOKIE_DAYS: i64 = 30;
+
+#[server(endpoint="cart/sync",input=Json)]
+pub async fn sync_cart(
+    req: sjf_api::cart::SyncCartRequest,
+) -> Result<sjf_api::cart::SyncCartResponse, ServerFnError> {
+    use sjf_api::cart::CART_COOKIE;
+    use sjf_auth::anonymous;
+
+    let headers: axum::http::HeaderMap = extract().await.unwrap_or_default();
+    let cart = anonymous::read(&headers, CART_COOKIE).and_then(|id| id.parse().ok());
+
+    let locale = req.locale;
+    let (cart, mut rsp) = error_logger(db::cart::sync(cart, req).await)?;
+
+    let ids = rsp.items.iter().map(|(p, _)| p.id);
+    let texts = translations(locale, ids.chain(rsp.notices.iter().map(|n| n.product_id()))).await?;
+    for (p, _) in rsp.items.iter_mut() {
+        if let Some(t) = texts.get(&p.id) {
+            p.translate(t);
+        }
+    }
+    for n in rsp.notices.iter_mut() {
+        if let Some(t) = texts.get(&n.product_id()) {
+            n.translate(t);
+        }
+    }
+
+    if let Some(cookie) =
+        cart.and_then(|id| anonymous::set(CART_COOKIE, &id.to_string(), CART_COOKIE_DAYS))
+    {
+        server_context()
+            .response_parts_mut()
+            .headers
+            .insert(axum::http::header::SET_COOKIE, cookie);
+    }
+    Ok(rsp)
+}
+
+#[server(endpoint="checkout/restore",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn restore_checkout(token: String) -> Result<Option<CheckoutRequest>, ServerFnError> {
+    error_logger(db::recovery::restore(&token).await)
+}
+
+/// Stops the checkout reminders to the customer behind `token`, once they
+/// have confirmed it on the page the email links to.
+#[server(endpoint="checkout/reminders/stop",input=dioxus::prelude::server_fn::codec::PostUrl)]
+pub async fn stop_reminders(token: String) -> Result<(), ServerFnError> {
+    match error_logger(db::recovery::opt_out(&token).await)? {
+        true => info!("Checkout reminders stopped by {}", token),
+        false => info!("Unknown or repeated reminder opt out {}", token),
+    }
+    Ok(())
+}
+
+#[server(endpoint="auth/checkout/recovery", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_recovery_stats(
+    days: u32,
+) -> Result<sjf_api::checkout::RecoveryStats, ServerFnError> {
+    error_logger(db::recovery::stats(days.min(365)).await)
+}
+
+#[server(endpoint="auth/orders/recent", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_recent_orders(limit: u32) -> Result<Vec<sjf_api::order::Order>, ServerFnError> {
+    error_logger(db::orders::recent(limit.min(500)).await)
+}
+
+/// Imports products from CSV or Excel, or only reports what the import would change
+/// unless `apply` is set. New images are stored once every row and image is
+/// valid, right before the products.
+#[server(endpoint="auth/products/import", input=Json)]
+pub async fn import_products(
+    req: AuthenticatedRequest<sjf_api::import::ImportRequest>,
+) -> Result<sjf_api::import::ImportReport, ServerFnError> {
+    use sjf_api::import::{ImageRef, RowError};
+
+    let sjf_api::import::ImportRequest {
+        file,
+        images: files,
+        apply,
+    } = req.data;
+    let (rows, errors) = match import::records(&file) {
+        Ok(records) => sjf_api::import::parse_rows(records),
+        Err(e) => {
+            warn!("Failed to read import file {}", e);
+            let error = RowError {
+                line: 1,
+                message: "Filen kunde inte läsas, spara den som xlsx eller CSV (UTF-8)".into(),
+            };
+            (vec![], vec![error])
+        }
+    };
+    let names = files.keys().cloned().collect();
+    let mut plan = error_logger(db::import::plan(rows, errors, &names).await)?;
+    if !apply || !plan.report.errors.is_empty() {
+        return Ok(plan.report);
+    }
+
+    // Every image is fetched and encoded before any is stored, so that a
+    // failing one doesn't leave the others behind
+    let mut prepared = vec![];
+    for (image, line) in plan.new_images() {
+        let bytes = match &image {
+            ImageRef::File(name) => Ok(files.get(name).cloned().unwrap_or_default()),
+            ImageRef::Url(url) => download_image(url).await,
+            ImageRef::Existing(_) => continue,
+        };
+        let res = bytes
+            .and_then(|bytes| sjf_image::prepare_image(bytes).map_err(|e| format!("{:?}", e)));
+        match res {
+            Ok(p) => prepared.push((image, p)),
+            Err(e) => {
+                warn!("Failed to import image {}: {}", image.export(), e);
+                plan.report.errors.push(RowError {
+                    line,
+                    message: format!("Bilden {} kunde inte laddas upp", image.export()),
+                });
+            }
+        }
+    }
+    if !plan.report.errors.is_empty() {
+        return Ok(plan.report);
+    }
+
+    let mut uploaded = BTreeMap::new();
+    for (image, p) in prepared {
+        let id = match sjf_image::store_image(p).await {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Failed to store image {}: {:?}", image.export(), e);
+                return Err(ServerFnError::ServerError("Endpoint failure".into()));
+            }
+        };
+        let (image_id, _): (u32, u32) = id.into();
+        uploaded.insert(image, image_id);
+    }
+
+    let report = error_logger(db::import::apply(plan, &uploaded).await)?;
+    info!("Imported {} products", report.changes.len());
+    Ok(report)
+}
+
+/// Downloads an image of an import, at most [`sjf_image::MAX_IMAGE_SIZE`]
+/// bytes, from a public address only.
+#[cfg(feature = "server")]
+async fn download_image(url: &str) -> Result<Vec<u8>, String> {
+    let max = sjf_image::MAX_IMAGE_SIZE;
+    let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    // Connects to the address that was checked, and doesn't follow redirects
+    // that could lead elsewhere
+    let address = import::public_address(&url).await?;
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve(url.host_str().unwrap_or_default(), address)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut rsp = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    if rsp.status().is_redirection() {
+        return Err(format!("Redirected with {}", rsp.status()));
+    }
+
+    let content_type = rsp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with("image/") {
+        return Err(format!("Not an image but {:?}", content_type));
+    }
+    if rsp.content_length().is_some_and(|l| l > max as u64) {
+        return Err(format!("Larger than {} bytes", max));
+    }
+
+    // The length isn't always sent, or may be wrong
+    let mut bytes = vec![];
+    while let Some(chunk) = rsp.chunk().await.map_err(|e| e.to_string())? {
+        if bytes.len() + chunk.len() > max {
+            return Err(format!("Larger than {} bytes", max));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Sales to consumers in other member states taxed through OSS in a quarter.
+#[server(endpoint="auth/tax/oss", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_oss_report(
+    year: i32,
+    quarter: u32,
+) -> Result<Vec<sjf_api::tax::OssReportRow>, ServerFnError> {
+    error_logger(db::tax::oss_report(year, quarter).await)
+}
+
+#[server(endpoint="auth/orders/delivered", input=Json)]
+pub async fn mark_delivered(req: AuthenticatedRequest<String>) -> Result<bool, ServerFnError> {
+    error_logger(db::orders::mark_delivered(&req.data).await)
+}
+
+#[server(endpoint="review/invitation",input=dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_review_invitation(
+    token: String,
+) -> Result<Option<sjf_api::review::ReviewInvitation>, ServerFnError> {
+    error_logger(db::review::invitation(&token).await)
+}
+
+#[server(endpoint="review/submit",input=Json)]
+pub async fn submit_review(
+    req: sjf_api::review::SubmitReviewRequest,
+) -> Result<sjf_api::review::SubmitReviewStatus, ServerFnError> {
+    use sjf_api::review::SubmitReviewStatus;
+
+    let token = req.token.clone();
+    let rating = req.rating;
+    let status = error_logger(db::review::submit(req).await)?;
+    if status == SubmitReviewStatus::Submitted {
+        counter!("review_submitted").increment(1);
+        if let Ok(Some(invitation)) = db::review::invitation(&token).await
+            && let Err(e) = sjf_notify::notify_new_review(&invitation.product_name, rating).await
+        {
+            warn!("Failed to notify staff about review {}", e);
+        }
+    }
+    Ok(status)
+}
+
+/// Reviews shown on a product page.
+#[cfg(feature = "server")]
+const PRODUCT_REVIEWS: u32 = 50;
+
+#[server(endpoint="get/reviews",input=dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_reviews(
+    product_id: u32,
+) -> Result<sjf_api::review::ProductReviews, ServerFnError> {
+    error_logger(db::review::product_reviews(product_id, PRODUCT_REVIEWS).await)
+}
+
+#[server(endpoint="auth/reviews/moderation", input= dioxus::prelude::server_fn::codec::GetUrl)]
+pub async fn get_review_moderation(
+    days: u32,
+) -> Result<Vec<sjf_api::review::ModeratedReview>, ServerFnError> {
+    error_logger(db::review::moderation(days.min(365)).await)
+}
+
+#[server(endpoint="auth/reviews/moderate", input=Json)]
+pub async fn moderate_review(
+    req: AuthenticatedRequest<(String, sjf_api::review::ReviewStatus)>,
+) -> Result<(), ServerFnError> {
+    let (id, status) = req.data;
+    error_logger(db::review::moderate(&id, status).await)
+}
+
+#[server(endpoint="auth/reviews/reply", input=Json)]
+pub async fn reply_review(
+    req: AuthenticatedRequest<(String, String)>,
+) -> Result<(), ServerFnError> {
+    let (id, reply) = req.data;
+    error_logger(db::review::reply(&id, &reply).await)
+}
+
 #[server(endpoint="checkout",input=dioxus::prelude::server_fn::codec::PostUrl)]
 pub async fn checkout(req: CheckoutRequest) -> Result<String, ServerFnError> {
+    let recovery = req.recovery.clone();
+    let locale = req.locale;
     match db::checkout::make_reservation(req).await {
         Err(e) => {
             info!("Checkout failed {}", e);
@@ -304,7 +909,13 @@ pub async fn checkout(req: CheckoutRequest) -> Result<String, ServerFnError> {
             ))
         }
 
-        Ok(uuid) => match sjf_payment::checkout(uuid.clone()).await {
+        Ok(uuid) => match sjf_payment::checkout(
+            uuid.clone(),
+            auth::def::get_customer().await.as_ref(),
+            locale,
+        )
+        .await
+        {
             Err(e) => {
                 counter!("checkout_failure").increment(1);
                 let _ = db::checkout::undo_reservation(uuid).await;
@@ -315,8 +926,54 @@ pub async fn checkout(req: CheckoutRequest) -> Result<String, ServerFnError> {
             }
             Ok(s) => {
                 counter!("checkout_session_created").increment(1);
+                if let Some(token) = recovery
+                    && let Err(e) = db::recovery::link(&token, &uuid).await
+                {
+                    warn!("Failed to link checkout {} to recovery {} {}", uuid, token, e);
+                }
                 Ok(s)
             }
         },
     }
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    fn stored(price: i32, reorder_threshold: Option<i32>) -> db::Product {
+        db::Product {
+            id: 1,
+            name: "Tröja".into(),
+            price,
+            description: String::new(),
+            quantity: Some(3),
+            created: Default::default(),
+            updated: Default::default(),
+            tax_rate: 25,
+            images: vec![],
+            category: 1,
+            reorder_threshold,
+            stock_policy: Default::default(),
+            attributes: Default::default(),
+            translations: Default::default(),
+            sku: None,
+        }
+    }
+
+    #[test]
+    fn products_round_trip_through_the_admin_form() {
+        let product = Product::try_from(stored(499, Some(2))).unwrap();
+        assert_eq!(product.price, 499);
+        assert_eq!(product.reorder_threshold, Some(2));
+        let back = db::Product::from(product);
+        assert_eq!((back.price, back.reorder_threshold), (499, Some(2)));
+    }
+
+    #[test]
+    fn values_the_form_can_not_show_are_refused() {
+        assert!(Product::try_from(stored(499, Some(70_000))).is_err());
+        assert!(Product::try_from(stored(499, Some(-1))).is_err());
+        assert!(Product::try_from(stored(70_000, None)).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sjf_db as db;
+use tokio::sync::Mutex;
+
+use super::escape_xml;
+
+/// Most URLs a single sitemap file may hold, larger catalogues get a sitemap index.
+const SITEMAP_URLS: usize = 50_000;
+
+struct Entry {
+    loc: String,
+    lastmod: Option<DateTime<Utc>>,
+    images: Vec<String>,
+}
+
+struct Cached {
+    generation: i64,
+    /// The sitemap, or the sitemap index followed by its parts.
+    files: Arc<Vec<String>>,
+}
+
+static CACHE: Mutex<Option<Cached>> = Mutex::const_new(None);
+
+fn lastmod(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+async fn entries(site: &str) -> Result<Vec<Entry>, db::Error> {
+    let mut entries = vec![Entry {
+        loc: format!("{site}/"),
+        lastmod: None,
+        images: vec![],
+    }];
+
+    entries.extend(db::sitemap::categories().await?.into_iter().map(|c| Entry {
+        loc: site.to_owned() + &sjf_api::product::category_url(&c.names),
+        lastmod: c.updated,
+        images: vec![],
+    }));
+
+    entries.extend(db::sitemap::products().await?.into_iter().map(|p| {
+        Entry {
+            loc: site.to_owned() + &p.preview.product_url(),
+            lastmod: Some(p.updated),
+            // The largest variant of each photo
+            images: p
+                .preview
+                .images
+                .iter()
+                .filter_map(|i| i.sizes.last())
+                .map(|v| site.to_owned() + &v.url)
+                .collect(),
+        }
+    }));
+
+    Ok(entries)
+}
+
+fn urlset(entries: &[Entry]) -> String {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">"#,
+        "\n"
+    ));
+    for entry in entries {
+        xml += &format!("<url><loc>{}</loc>", escape_xml(&entry.loc));
+        if let Some(time) = entry.lastmod {
+            xml += &format!("<lastmod>{}</lastmod>", lastmod(time));
+        }
+        for image in &entry.images {
+            xml += &format!(
+                "<image:image><image:loc>{}</image:loc></image:image>",
+                escape_xml(image)
+            );
+        }
+        xml += "</url>\n";
+    }
+    xml + "</urlset>\n"
+}
+
+fn index(site: &str, parts: &[&[Entry]]) -> String {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+        "\n"
+    ));
+    for (n, part) in parts.iter().enumerate() {
+        xml += &format!(
+            "<sitemap><loc>{}/sitemaps/{}.xml</loc>",
+            escape_xml(site),
+            n + 1
+        );
+        if let Some(time) = part.iter().filter_map(|e| e.lastmod).max() {
+            xml += &format!("<lastmod>{}</lastmod>", lastmod(time));
+        }
+        xml += "</sitemap>\n";
+    }
+    xml + "</sitemapindex>\n"
+}
+
+fn files(site: &str, entries: &[Entry]) -> Vec<String> {
+    if entries.len() <= SITEMAP_URLS {
+        return vec![urlset(entries)];
+    }
+
+    let parts: Vec<&[Entry]> = entries.chunks(SITEMAP_URLS).collect();
+    let mut files = vec![index(site, &parts)];
+    files.extend(parts.into_iter().map(urlset));
+    files
+}
+
+async fn build() -> Result<Vec<String>, db::Error> {
+    let site = super::site_url();
+    Ok(files(site, &entries(site).await?))
+}
+
+/// File `n` of the sitemap, 0 being `/sitemap.xml`. Built on first use and
+/// again after the materialized views have been refreshed, by any replica or
+/// by `sjf-admin`.
+pub async fn get(n: usize) -> Result<Option<String>, db::Error> {
+    let mut cache = CACHE.lock().await;
+    let generation = db::sitemap::views_generation().await?;
+
+    let files = match &*cache {
+        Some(cached) if cached.generation == generation => cached.files.clone(),
+        _ => {
+            let files = Arc::new(build().await?);
+            *cache = Some(Cached {
+                generation,
+                files: files.clone(),
+            });
+            files
+        }
+    };
+
+    Ok(files.get(n).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE: &str = "https://butik.example";
+
+    fn entry(path: &str, lastmod: Option<&str>, images: &[&str]) -> Entry {
+        Entry {
+            loc: format!("{SITE}{path}"),
+            lastmod: lastmod.map(|t| t.parse().unwrap()),
+            images: images.iter().map(|i| format!("{SITE}{i}")).collect(),
+        }
+    }
+
+    #[test]
+    fn small_catalogues_get_a_single_sitemap() {
+        let entries = [
+            entry("/", None, &[]),
+            entry(
+                "/produkter/Kl%C3%A4der/artikel-1?a&b",
+                Some("2026-03-02T10:15:30.5Z"),
+                &["/images/1-4.jpeg", "/images/2-8.jpeg"],
+            ),
+        ];
+        let files = files(SITE, &entries);
+        assert_eq!(files.len(), 1);
+        let urls: Vec<_> = files[0]
+            .lines()
+            .filter(|l| l.starts_with("<url>"))
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "<url><loc>https://butik.example/</loc></url>",
+                concat!(
+                    "<url><loc>https://butik.example/produkter/Kl%C3%A4der/artikel-1?a&amp;b</loc>",
+                    "<lastmod>2026-03-02T10:15:30Z</lastmod>",
+                    "<image:image><image:loc>https://butik.example/images/1-4.jpeg</image:loc></image:image>",
+                    "<image:image><image:loc>https://butik.example/images/2-8.jpeg</image:loc></image:image>",
+                    "</url>"
+                ),
+            ]
+        );
+        assert!(files[0].ends_with("</urlset>\n"));
+    }
+
+    #[test]
+    fn large_catalogues_get_an_index_of_parts() {
+        let mut entries: Vec<_> = (0..SITEMAP_URLS)
+            .map(|n| entry(&format!("/{n}"), Some("2026-03-01T00:00:00Z"), &[]))
+            .collect();
+        entries[7].lastmod = Some("2026-03-05T08:00:00Z".parse().unwrap());
+        entries.push(entry("/sist", None, &[]));
+
+        let files = files(SITE, &entries);
+        assert_eq!(files.len(), 3);
+        let parts: Vec<_> = files[0]
+            .lines()
+            .filter(|l| l.starts_with("<sitemap>"))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                "<sitemap><loc>https://butik.example/sitemaps/1.xml</loc><lastmod>2026-03-05T08:00:00Z</lastmod></sitemap>",
+                "<sitemap><loc>https://butik.example/sitemaps/2.xml</loc></sitemap>",
+            ]
+        );
+        let count = |file: &str| file.matches("<url>").count();
+        assert_eq!(count(&files[1]), SITEMAP_URLS);
+        assert_eq!(count(&files[2]), 1);
+        assert!(files[2].contains("<loc>https://butik.example/sist</loc>"));
+    }
+}